@@ -0,0 +1,511 @@
+//! Auditor reports for confidential wrapped mints
+//!
+//! [`build_report`] decrypts the auditor ciphertexts of the transfers recorded by the
+//! [`indexer`](crate::indexer), totals the confidential inflows and outflows of every token account,
+//! and checks them against the volumes wrapped and unwrapped through the token wrap program.
+
+use {
+    crate::{
+        serde_utils,
+        types::{ApiIndexedInstruction, ApiIndexedInstructionKind},
+    },
+    anyhow::{anyhow, Context, Result},
+    common::{accounts::decrypt_auditor_transfer_amount, discrete_log::DiscreteLogTable},
+    serde::{Deserialize, Serialize},
+    solana_sdk::{pubkey::Pubkey, signature::Signature},
+    spl_token_2022::solana_zk_sdk::encryption::{
+        elgamal::ElGamalSecretKey, pod::elgamal::PodElGamalCiphertext,
+    },
+    std::{collections::BTreeMap, str::FromStr},
+};
+
+/// A confidential transfer with the amount decrypted by the auditor
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditedTransfer {
+    #[serde(with = "serde_utils::signature_string")]
+    pub signature: Signature,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub source: Pubkey,
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub destination: Pubkey,
+    /// The transfer amount in base units, `None` if the auditor ciphertexts could not be decrypted
+    #[serde(with = "serde_utils::option_string")]
+    pub amount: Option<u64>,
+}
+
+/// Volumes moved by a token account over the audited range, in base units
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct AccountTotals {
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_account: Pubkey,
+    /// Deposits and incoming transfers
+    #[serde(with = "serde_utils::u64_string")]
+    pub inflow: u64,
+    /// Withdrawals and outgoing transfers
+    #[serde(with = "serde_utils::u64_string")]
+    pub outflow: u64,
+    #[serde(with = "serde_utils::u64_string")]
+    pub deposited: u64,
+    #[serde(with = "serde_utils::u64_string")]
+    pub withdrawn: u64,
+    #[serde(with = "serde_utils::u64_string")]
+    pub transferred_in: u64,
+    #[serde(with = "serde_utils::u64_string")]
+    pub transferred_out: u64,
+    #[serde(with = "serde_utils::u64_string")]
+    pub wrapped: u64,
+    #[serde(with = "serde_utils::u64_string")]
+    pub unwrapped: u64,
+}
+
+/// The outcome of a sanity check performed on the report
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// On chain state of the wrapped mint the report is checked against
+#[derive(Debug, Clone, Copy)]
+pub struct MintSupply {
+    /// Supply of the wrapped mint
+    pub supply: u64,
+    /// Balance of the escrow holding the unwrapped tokens
+    pub escrow_balance: u64,
+}
+
+/// Report produced for the auditor of a confidential wrapped mint
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditReport {
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+    #[serde(with = "serde_utils::u64_string")]
+    pub wrapped: u64,
+    #[serde(with = "serde_utils::u64_string")]
+    pub unwrapped: u64,
+    #[serde(with = "serde_utils::u64_string")]
+    pub deposited: u64,
+    #[serde(with = "serde_utils::u64_string")]
+    pub withdrawn: u64,
+    /// Sum of the decrypted transfer amounts
+    #[serde(with = "serde_utils::u64_string")]
+    pub transferred: u64,
+    /// Number of transfers whose amount could not be decrypted
+    pub undecrypted_transfers: u64,
+    pub accounts: Vec<AccountTotals>,
+    pub transfers: Vec<AuditedTransfer>,
+    pub checks: Vec<AuditCheck>,
+}
+
+impl AuditReport {
+    /// Returns true if every sanity check passed
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+}
+
+/// Builds the audit report for the indexed `instructions` of `token_mint`
+///
+/// `from_genesis` must only be set when `instructions` cover the entire history of the mint, as
+/// the balance checks do not hold for a partial history. `supply` should only be provided when
+/// the instructions extend to the current slot.
+///
+/// Fails if a total overflows a u64, decryption is cpu bound and should not be called from an
+/// async context
+pub fn build_report(
+    token_mint: Pubkey,
+    instructions: &[ApiIndexedInstruction],
+    auditor_secret: &ElGamalSecretKey,
+    discrete_log: &DiscreteLogTable,
+    from_genesis: bool,
+    supply: Option<MintSupply>,
+) -> Result<AuditReport> {
+    let mut accounts = BTreeMap::<Pubkey, AccountTotals>::new();
+    let mut report = AuditReport {
+        token_mint,
+        wrapped: 0,
+        unwrapped: 0,
+        deposited: 0,
+        withdrawn: 0,
+        transferred: 0,
+        undecrypted_transfers: 0,
+        accounts: vec![],
+        transfers: vec![],
+        checks: vec![],
+    };
+
+    for instruction in instructions {
+        let token_account = instruction.token_account;
+        let amount = instruction.amount.unwrap_or_default();
+        match instruction.kind {
+            ApiIndexedInstructionKind::Deposit => {
+                add(&mut report.deposited, amount)?;
+                add(
+                    &mut account_totals(&mut accounts, token_account).deposited,
+                    amount,
+                )?;
+            }
+            ApiIndexedInstructionKind::Withdraw => {
+                add(&mut report.withdrawn, amount)?;
+                add(
+                    &mut account_totals(&mut accounts, token_account).withdrawn,
+                    amount,
+                )?;
+            }
+            ApiIndexedInstructionKind::Wrap => {
+                add(&mut report.wrapped, amount)?;
+                add(
+                    &mut account_totals(&mut accounts, token_account).wrapped,
+                    amount,
+                )?;
+            }
+            ApiIndexedInstructionKind::Unwrap => {
+                add(&mut report.unwrapped, amount)?;
+                add(
+                    &mut account_totals(&mut accounts, token_account).unwrapped,
+                    amount,
+                )?;
+            }
+            ApiIndexedInstructionKind::Transfer => {
+                let Some(destination) = instruction.counterparty else {
+                    continue;
+                };
+                let amount = decrypt_transfer(instruction, auditor_secret, discrete_log)
+                    .inspect_err(|err| {
+                        log::warn!(
+                            "failed to decrypt transfer {} {err:#}",
+                            instruction.signature
+                        )
+                    })
+                    .ok();
+                match amount {
+                    Some(amount) => {
+                        add(&mut report.transferred, amount)?;
+                        add(
+                            &mut account_totals(&mut accounts, token_account).transferred_out,
+                            amount,
+                        )?;
+                        add(
+                            &mut account_totals(&mut accounts, destination).transferred_in,
+                            amount,
+                        )?;
+                    }
+                    None => report.undecrypted_transfers += 1,
+                }
+                report.transfers.push(AuditedTransfer {
+                    signature: instruction.signature,
+                    slot: instruction.slot,
+                    block_time: instruction.block_time,
+                    source: token_account,
+                    destination,
+                    amount,
+                });
+            }
+            ApiIndexedInstructionKind::ConfigureAccount
+            | ApiIndexedInstructionKind::ApplyPendingBalance
+            | ApiIndexedInstructionKind::EmptyAccount
+            | ApiIndexedInstructionKind::Mint
+            | ApiIndexedInstructionKind::Burn => {}
+        }
+    }
+
+    report.accounts = accounts
+        .into_values()
+        .map(|mut totals| {
+            totals.inflow = totals.deposited;
+            add(&mut totals.inflow, totals.transferred_in)?;
+            totals.outflow = totals.withdrawn;
+            add(&mut totals.outflow, totals.transferred_out)?;
+            Ok(totals)
+        })
+        .collect::<Result<_>>()?;
+    report.checks = checks(&report, from_genesis, supply);
+    Ok(report)
+}
+
+/// Adds `amount` to `total`, failing instead of overflowing
+fn add(total: &mut u64, amount: u64) -> Result<()> {
+    *total = total
+        .checked_add(amount)
+        .ok_or_else(|| anyhow!("total overflows adding {amount} to {total}"))?;
+    Ok(())
+}
+
+fn account_totals(
+    accounts: &mut BTreeMap<Pubkey, AccountTotals>,
+    token_account: Pubkey,
+) -> &mut AccountTotals {
+    accounts.entry(token_account).or_insert(AccountTotals {
+        token_account,
+        ..Default::default()
+    })
+}
+
+/// Decrypts the amount of a transfer from its auditor ciphertexts
+fn decrypt_transfer(
+    instruction: &ApiIndexedInstruction,
+    auditor_secret: &ElGamalSecretKey,
+    discrete_log: &DiscreteLogTable,
+) -> Result<u64> {
+    let ciphertext = |name: &str| {
+        let ciphertext = instruction
+            .ciphertexts
+            .get(name)
+            .ok_or_else(|| anyhow!("transfer is missing {name}"))?;
+        PodElGamalCiphertext::from_str(ciphertext).with_context(|| format!("invalid {name}"))
+    };
+    decrypt_auditor_transfer_amount(
+        discrete_log,
+        auditor_secret,
+        ciphertext("transfer_amount_auditor_ciphertext_lo")?,
+        ciphertext("transfer_amount_auditor_ciphertext_hi")?,
+    )
+}
+
+/// Sanity checks comparing the decrypted volumes with the wrapped and unwrapped volumes
+fn checks(report: &AuditReport, from_genesis: bool, supply: Option<MintSupply>) -> Vec<AuditCheck> {
+    let mut checks = vec![AuditCheck {
+        name: "transfers_decrypted".to_string(),
+        passed: report.undecrypted_transfers == 0,
+        detail: format!(
+            "{} of {} transfers could not be decrypted with the auditor key",
+            report.undecrypted_transfers,
+            report.transfers.len()
+        ),
+    }];
+
+    if from_genesis {
+        // every token moved out of a confidential balance must have been moved in first
+        let overdrawn = report
+            .accounts
+            .iter()
+            .filter(|totals| totals.outflow > totals.inflow)
+            .map(|totals| totals.token_account.to_string())
+            .collect::<Vec<_>>();
+        checks.push(AuditCheck {
+            name: "account_outflows_within_inflows".to_string(),
+            passed: overdrawn.is_empty(),
+            detail: if overdrawn.is_empty() {
+                "no account moved out more than it received".to_string()
+            } else {
+                format!("outflows exceed inflows for {}", overdrawn.join(", "))
+            },
+        });
+
+        // confidential balances can only hold tokens which are still wrapped
+        let wrapped_supply = report.wrapped as i128 - report.unwrapped as i128;
+        let confidential_supply = report.deposited as i128 - report.withdrawn as i128;
+        checks.push(AuditCheck {
+            name: "confidential_supply_within_wrapped_supply".to_string(),
+            passed: (0..=wrapped_supply).contains(&confidential_supply),
+            detail: format!(
+                "{confidential_supply} held in confidential balances, {wrapped_supply} wrapped"
+            ),
+        });
+
+        if let Some(supply) = supply {
+            checks.push(AuditCheck {
+                name: "wrap_volume_matches_supply".to_string(),
+                passed: wrapped_supply == supply.supply as i128,
+                detail: format!(
+                    "{} wrapped and {} unwrapped, wrapped mint supply is {}",
+                    report.wrapped, report.unwrapped, supply.supply
+                ),
+            });
+        }
+    }
+
+    if let Some(supply) = supply {
+        checks.push(AuditCheck {
+            name: "escrow_backs_supply".to_string(),
+            passed: supply.escrow_balance >= supply.supply,
+            detail: format!(
+                "escrow holds {} for a wrapped mint supply of {}",
+                supply.escrow_balance, supply.supply
+            ),
+        });
+    }
+
+    checks
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*, common::discrete_log::MIN_BABY_STEP_BITS,
+        spl_token_2022::solana_zk_sdk::encryption::elgamal::ElGamalKeypair,
+    };
+
+    fn instruction(
+        kind: ApiIndexedInstructionKind,
+        token_account: Pubkey,
+        counterparty: Option<Pubkey>,
+        amount: Option<u64>,
+    ) -> ApiIndexedInstruction {
+        ApiIndexedInstruction {
+            signature: Signature::new_unique(),
+            instruction_index: 0,
+            slot: 1,
+            block_time: None,
+            kind,
+            token_account,
+            counterparty,
+            amount,
+            ciphertexts: BTreeMap::new(),
+            accounts: vec![],
+        }
+    }
+
+    fn transfer(
+        auditor: &ElGamalKeypair,
+        source: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+    ) -> ApiIndexedInstruction {
+        let mut transfer = instruction(
+            ApiIndexedInstructionKind::Transfer,
+            source,
+            Some(destination),
+            None,
+        );
+        // transfer amounts are split into a 16 bit lo and 32 bit hi part
+        for (name, amount) in [
+            ("transfer_amount_auditor_ciphertext_lo", amount & 0xffff),
+            ("transfer_amount_auditor_ciphertext_hi", amount >> 16),
+        ] {
+            transfer.ciphertexts.insert(
+                name.to_string(),
+                PodElGamalCiphertext::from(auditor.pubkey().encrypt(amount)).to_string(),
+            );
+        }
+        transfer
+    }
+
+    #[test]
+    fn test_build_report() {
+        let discrete_log = DiscreteLogTable::new(MIN_BABY_STEP_BITS).unwrap();
+        let auditor = ElGamalKeypair::new_rand();
+        let token_mint = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+
+        let instructions = [
+            instruction(
+                ApiIndexedInstructionKind::ConfigureAccount,
+                alice,
+                None,
+                None,
+            ),
+            instruction(ApiIndexedInstructionKind::Wrap, alice, None, Some(100_000)),
+            instruction(
+                ApiIndexedInstructionKind::Deposit,
+                alice,
+                None,
+                Some(90_000),
+            ),
+            transfer(&auditor, alice, bob, 70_000),
+            instruction(ApiIndexedInstructionKind::Withdraw, bob, None, Some(20_000)),
+            instruction(ApiIndexedInstructionKind::Unwrap, bob, None, Some(20_000)),
+        ];
+        let supply = MintSupply {
+            supply: 80_000,
+            escrow_balance: 80_000,
+        };
+
+        let report = build_report(
+            token_mint,
+            &instructions,
+            auditor.secret(),
+            &discrete_log,
+            true,
+            Some(supply),
+        )
+        .unwrap();
+        assert_eq!(report.transferred, 70_000);
+        assert_eq!(report.transfers[0].amount, Some(70_000));
+        assert_eq!(report.undecrypted_transfers, 0);
+
+        let alice_totals = report
+            .accounts
+            .iter()
+            .find(|totals| totals.token_account == alice)
+            .unwrap();
+        assert_eq!(alice_totals.inflow, 90_000);
+        assert_eq!(alice_totals.outflow, 70_000);
+        assert_eq!(alice_totals.wrapped, 100_000);
+        let bob_totals = report
+            .accounts
+            .iter()
+            .find(|totals| totals.token_account == bob)
+            .unwrap();
+        assert_eq!(bob_totals.inflow, 70_000);
+        assert_eq!(bob_totals.outflow, 20_000);
+        assert!(report.passed(), "{:#?}", report.checks);
+        assert_eq!(report.checks.len(), 5);
+
+        // another key can not decrypt the transfer, and the supply no longer matches
+        let report = build_report(
+            token_mint,
+            &instructions,
+            ElGamalKeypair::new_rand().secret(),
+            &discrete_log,
+            true,
+            Some(MintSupply {
+                supply: 90_000,
+                ..supply
+            }),
+        )
+        .unwrap();
+        assert_eq!(report.transfers[0].amount, None);
+        assert_eq!(report.undecrypted_transfers, 1);
+        let failed = report
+            .checks
+            .iter()
+            .filter(|check| !check.passed)
+            .map(|check| check.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            failed,
+            vec![
+                "transfers_decrypted",
+                "account_outflows_within_inflows",
+                "wrap_volume_matches_supply",
+                "escrow_backs_supply"
+            ]
+        );
+
+        // balance checks are skipped for a partial history
+        let report = build_report(
+            token_mint,
+            &instructions[3..],
+            auditor.secret(),
+            &discrete_log,
+            false,
+            None,
+        )
+        .unwrap();
+        assert_eq!(report.checks.len(), 1);
+        assert!(report.passed());
+
+        // totals overflowing a u64 fail the report
+        let deposit = instruction(
+            ApiIndexedInstructionKind::Deposit,
+            alice,
+            None,
+            Some(u64::MAX),
+        );
+        let err = build_report(
+            token_mint,
+            &[deposit.clone(), deposit],
+            auditor.secret(),
+            &discrete_log,
+            false,
+            None,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("overflows"), "{err:#}");
+    }
+}
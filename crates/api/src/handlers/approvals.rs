@@ -0,0 +1,308 @@
+use {
+    crate::{
+        router::AppState,
+        types::{
+            ApiError, ApiPendingApproval, ApiPendingApprovalsResponse, ApiTransactionResponse,
+            ApproveAccount, PendingApprovals,
+        },
+    },
+    axum::{
+        extract::{Query, State},
+        response::IntoResponse,
+        Json,
+    },
+    base64::{prelude::BASE64_STANDARD, Engine},
+    http::StatusCode,
+    solana_account_decoder_client_types::UiAccountEncoding,
+    solana_client::{
+        rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+        rpc_filter::{Memcmp, RpcFilterType},
+    },
+    solana_sdk::{program_pack::Pack, pubkey::Pubkey, transaction::Transaction},
+    spl_token_2022::{
+        extension::{
+            confidential_transfer::{
+                instruction::approve_account as approve_account_ix, ConfidentialTransferAccount,
+                ConfidentialTransferMint,
+            },
+            AccountType, BaseStateWithExtensions, StateWithExtensions,
+        },
+        state::{Account, Mint},
+    },
+    std::sync::Arc,
+};
+
+/// Handler which is used by the confidential transfer authority of a mint to approve a
+/// confidential token account, required before it can transact if the mint does not auto approve
+pub async fn approve_account(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ApproveAccount>,
+) -> impl IntoResponse {
+    // lookup both the token mint, and token account
+    let mut accounts = state
+        .rpc
+        .get_multiple_accounts(&[payload.token_mint, payload.token_account])
+        .await
+        .unwrap_or_default();
+
+    // if less than 2 accounts is returned, this means the rpc call failed
+    if accounts.len() < 2 {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query accounts".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure the token mint account exists
+    let Some(token_mint) = std::mem::take(&mut accounts[0]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token mint does not exist".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure token account exists
+    let Some(token_account) = std::mem::take(&mut accounts[1]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token account does not exist".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // only the confidential transfer authority of the mint can approve accounts
+    let mint_authority = match StateWithExtensions::<Mint>::unpack(&token_mint.data) {
+        Ok(mint) => match mint.get_extension::<ConfidentialTransferMint>() {
+            Ok(extension) => Option::<Pubkey>::from(extension.authority),
+            Err(err) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiError {
+                        msg: format!("token mint does not support confidential transfers {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        },
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to unpack token mint {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    if mint_authority != Some(payload.authority) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "authority is not the confidential transfer authority of the token mint"
+                    .to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // unpack token account
+    let token_account = match StateWithExtensions::<Account>::unpack(&token_account.data) {
+        Ok(token_account) => token_account,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to unpack token account {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    if token_account.base.mint != payload.token_mint {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token account does not belong to the token mint".to_string(),
+            }),
+        )
+            .into_response();
+    }
+    match token_account.get_extension::<ConfidentialTransferAccount>() {
+        Ok(extension) if bool::from(extension.approved) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: "token account is already approved".to_string(),
+                }),
+            )
+                .into_response()
+        }
+        Ok(_) => {}
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: format!(
+                        "token account is not configured for confidential transfers {err:#?}"
+                    ),
+                }),
+            )
+                .into_response()
+        }
+    }
+
+    let ix = match approve_account_ix(
+        &spl_token_2022::id(),
+        &payload.token_account,
+        &payload.token_mint,
+        &payload.authority,
+        &[],
+    ) {
+        Ok(ix) => ix,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to create approve account instruction {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let tx = match bincode::serialize(&Transaction::new_with_payer(
+        &[ix],
+        Some(&payload.authority),
+    )) {
+        Ok(tx) => tx,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to serialize transaction {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    (
+        StatusCode::OK,
+        Json(ApiTransactionResponse {
+            transactions: vec![BASE64_STANDARD.encode(tx)],
+        }),
+    )
+        .into_response()
+}
+
+/// Handler which is used to list the confidential token accounts of a mint awaiting approval
+pub async fn pending_approvals(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PendingApprovals>,
+) -> impl IntoResponse {
+    let token_mint = match state.rpc.get_account(&query.token_mint).await {
+        Ok(token_mint) => token_mint,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: format!("failed to query token mint {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    let mint_extension = match StateWithExtensions::<Mint>::unpack(&token_mint.data) {
+        Ok(mint) => match mint.get_extension::<ConfidentialTransferMint>() {
+            Ok(extension) => *extension,
+            Err(err) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiError {
+                        msg: format!("token mint does not support confidential transfers {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        },
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to unpack token mint {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    // confidential token accounts always have extensions, so the account type follows the base account
+    let accounts = match state
+        .rpc
+        .get_program_accounts_with_config(
+            &spl_token_2022::id(),
+            RpcProgramAccountsConfig {
+                filters: Some(vec![
+                    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                        0,
+                        query.token_mint.to_bytes().to_vec(),
+                    )),
+                    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                        Account::LEN,
+                        vec![AccountType::Account as u8],
+                    )),
+                ]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .await
+    {
+        Ok(accounts) => accounts,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to query token accounts {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let token_accounts = accounts
+        .into_iter()
+        .filter_map(|(address, account)| {
+            let token_account = StateWithExtensions::<Account>::unpack(&account.data).ok()?;
+            let extension = token_account
+                .get_extension::<ConfidentialTransferAccount>()
+                .ok()?;
+            (!bool::from(extension.approved)).then(|| ApiPendingApproval {
+                token_account: address,
+                owner: token_account.base.owner,
+                elgamal_pubkey: extension.elgamal_pubkey.to_string(),
+            })
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(ApiPendingApprovalsResponse {
+            token_mint: query.token_mint,
+            auto_approve_new_accounts: mint_extension.auto_approve_new_accounts.into(),
+            confidential_transfer_authority: mint_extension.authority.into(),
+            token_accounts,
+        }),
+    )
+        .into_response()
+}
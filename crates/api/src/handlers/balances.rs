@@ -4,14 +4,14 @@ use {
         types::{ApiBalancesResponse, ApiError, Balances},
     },
     axum::{extract::State, response::IntoResponse, Json},
-    common::key_generator::{derive_ae_key, derive_elgamal_key, KeypairType},
+    common::{
+        accounts::{decrypt_available_balance, decrypt_pending_balance},
+        key_generator::{derive_ae_key, derive_elgamal_key, KeypairType},
+    },
     http::StatusCode,
-    spl_token_2022::{
-        extension::{
-            confidential_transfer::{account_info::combine_balances, ConfidentialTransferAccount},
-            BaseStateWithExtensions, StateWithExtensions,
-        },
-        solana_zk_sdk::encryption::{auth_encryption::AeCiphertext, elgamal::ElGamalCiphertext},
+    spl_token_2022::extension::{
+        confidential_transfer::ConfidentialTransferAccount, BaseStateWithExtensions,
+        StateWithExtensions,
     },
     std::sync::Arc,
 };
@@ -166,81 +166,46 @@ pub async fn balances(
         }
     };
 
-    let Ok(pending_balance_lo) =
-        TryInto::<ElGamalCiphertext>::try_into(confidential_transfer_account.pending_balance_lo)
-    else {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiError {
-                msg: "failed to parse pending_balance_hi".to_string(),
-            }),
-        )
-            .into_response();
-    };
-    let Ok(pending_balance_hi) =
-        TryInto::<ElGamalCiphertext>::try_into(confidential_transfer_account.pending_balance_hi)
-    else {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiError {
-                msg: "failed to parse pending_balance_lo".to_string(),
-            }),
-        )
-            .into_response();
-    };
-
-    let Some(pending_balance_lo) = elgamal_key.secret().decrypt_u32(&pending_balance_lo) else {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiError {
-                msg: "failed to decrypt pending_balance_lo".to_string(),
-            }),
-        )
-            .into_response();
-    };
-
-    let Some(pending_balance_hi) = elgamal_key.secret().decrypt_u32(&pending_balance_hi) else {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiError {
-                msg: "failed to decrypt pending_balance_hi".to_string(),
-            }),
-        )
-            .into_response();
-    };
-
-    let Some(pending_balance) = combine_balances(pending_balance_lo, pending_balance_hi) else {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiError {
-                msg: "failed to combined pending_balance_lo and pending_balance_hi".to_string(),
-            }),
-        )
-            .into_response();
-    };
-
-    let decryptable_available_balance = match TryInto::<AeCiphertext>::try_into(
-        confidential_transfer_account.decryptable_available_balance,
-    ) {
-        Ok(decryptable_available_balance) => decryptable_available_balance,
+    // decrypt the pending balance using the precomputed discrete log table,
+    // this is cpu bound so it is moved off the async runtime
+    let discrete_log = state.discrete_log.clone();
+    let elgamal_secret = elgamal_key.secret().clone();
+    let extension = *confidential_transfer_account;
+    let decrypted = tokio::task::spawn_blocking(move || {
+        decrypt_pending_balance(&discrete_log, &elgamal_secret, &extension)
+    })
+    .await;
+
+    let pending_balance = match decrypted {
+        Ok(Ok(pending_balance)) => pending_balance,
+        Ok(Err(err)) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("{err:#}"),
+                }),
+            )
+                .into_response()
+        }
         Err(err) => {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ApiError {
-                    msg: format!("failed to parse decryptable_available_balance {err:#?}"),
+                    msg: format!("failed to decrypt pending balance {err:#?}"),
                 }),
             )
-                .into_response();
-        }   
-    };
-    
-    let decrypted_available_balance = match ae_key.decrypt(&decryptable_available_balance) {
-        Some(decryptable_balance) => decryptable_balance,
-        None => {
-            log::warn!("failed to decrypt available balance");
-            0
+                .into_response()
         }
     };
+
+    let decrypted_available_balance =
+        match decrypt_available_balance(&ae_key, confidential_transfer_account) {
+            Ok(decryptable_balance) => decryptable_balance,
+            Err(err) => {
+                log::warn!("{err:#}");
+                0
+            }
+        };
     (
         StatusCode::OK,
         Json(ApiBalancesResponse {
@@ -253,6 +218,24 @@ pub async fn balances(
                 token_account.base.amount,
                 decimals,
             ),
+            pending_balance_amount: pending_balance,
+            available_balance_amount: decrypted_available_balance,
+            non_confidential_balance_amount: token_account.base.amount,
+            decimals,
+            pending_balance_credit_counter: confidential_transfer_account
+                .pending_balance_credit_counter
+                .into(),
+            maximum_pending_balance_credit_counter: confidential_transfer_account
+                .maximum_pending_balance_credit_counter
+                .into(),
+            allow_confidential_credits: confidential_transfer_account
+                .allow_confidential_credits
+                .into(),
+            allow_non_confidential_credits: confidential_transfer_account
+                .allow_non_confidential_credits
+                .into(),
+            approved: confidential_transfer_account.approved.into(),
+            elgamal_pubkey: confidential_transfer_account.elgamal_pubkey.to_string(),
         }),
     )
         .into_response()
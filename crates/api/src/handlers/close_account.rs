@@ -0,0 +1,574 @@
+use {
+    super::transactions::encode_transactions,
+    crate::{
+        router::AppState,
+        types::{ApiError, ApiTransactionResponse, CloseAccount},
+    },
+    axum::{extract::State, response::IntoResponse, Json},
+    bytemuck::Zeroable,
+    common::{
+        accounts::{
+            apply_pending_balance_locally, decrypt_available_balance, decrypt_pending_balance,
+            token_account_already_configured, withdraw_locally,
+        },
+        key_generator::{derive_ae_key, derive_elgamal_key, KeypairType},
+        proofs::get_zk_proof_context_state_account_creation_instructions,
+    },
+    http::StatusCode,
+    solana_sdk::{signer::Signer, transaction::Transaction},
+    spl_token_2022::{
+        extension::{
+            confidential_transfer::{
+                account_info::{EmptyAccountAccountInfo, WithdrawAccountInfo},
+                instruction::{
+                    apply_pending_balance, empty_account, withdraw, BatchedRangeProofContext,
+                    CiphertextCommitmentEqualityProofContext, ProofContextState,
+                },
+                ConfidentialTransferAccount, EncryptedBalance,
+            },
+            BaseStateWithExtensions, StateWithExtensions,
+        },
+        solana_zk_sdk::zk_elgamal_proof_program::instruction::{
+            close_context_state, ContextStateInfo,
+        },
+    },
+    spl_token_confidential_transfer_proof_extraction::instruction::{ProofData, ProofLocation},
+    spl_token_confidential_transfer_proof_generation::withdraw::WithdrawProofData,
+    std::sync::Arc,
+};
+
+/// Handler which is used to empty a confidential token account so it can be closed
+///
+/// When `drain` is set the pending balance is applied and the confidential balance withdrawn
+/// first, the transactions must be sent in the order they are returned
+pub async fn close_account(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CloseAccount>,
+) -> impl IntoResponse {
+    // derive the ATA for the authority + token_mint
+    let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &payload.authority,
+        &payload.token_mint,
+        &spl_token_2022::id(),
+    );
+
+    // verify elgamal signature
+    if !payload.elgamal_signature.verify(
+        &payload.authority.to_bytes(),
+        &KeypairType::ElGamal.message_to_sign(user_ata),
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify elgamal signature".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // verify ae signature
+    if !payload.ae_signature.verify(
+        &payload.authority.to_bytes(),
+        &KeypairType::Ae.message_to_sign(user_ata),
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify ae signature".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // derive the elgamal keypair
+    let Ok(elgamal_key) = derive_elgamal_key(payload.elgamal_signature) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to derive elgamal keypair".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // derive the ae keypair
+    let Ok(ae_key) = derive_ae_key(payload.ae_signature) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to derive ae keypair".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // lookup both the token mint, and ata accounts
+    let mut accounts = state
+        .rpc
+        .get_multiple_accounts(&[payload.token_mint, user_ata])
+        .await
+        .unwrap_or_default();
+
+    // if less than 2 accounts is returned, this means the rpc call failed
+    if accounts.len() < 2 {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query accounts".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure the token mint account exists
+    let Some(token_mint) = std::mem::take(&mut accounts[0]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token mint does not exist".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure token account exists
+    let Some(token_account) = std::mem::take(&mut accounts[1]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token account does not exist".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure token account is configured for confidential transfers
+    if !token_account_already_configured(&token_account) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token account is not configured for confidential transfers".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // get the token mint decimals
+    let decimals =
+        match StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&token_mint.data) {
+            Ok(mint) => mint.base.decimals,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to unpack token mint {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+
+    // unpack token account
+    let token_account =
+        match StateWithExtensions::<spl_token_2022::state::Account>::unpack(&token_account.data) {
+            Ok(token_account) => token_account,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to unpack token account {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+
+    // retrieve the confidential transfer account extension
+    let mut extension = match token_account.get_extension::<ConfidentialTransferAccount>() {
+        Ok(confidential_token_account) => *confidential_token_account,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to get confidential transfer account extension {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    // decrypt the pending balance using the precomputed discrete log table,
+    // this is cpu bound so it is moved off the async runtime
+    let discrete_log = state.discrete_log.clone();
+    let elgamal_secret = elgamal_key.secret().clone();
+    let decrypted = tokio::task::spawn_blocking(move || {
+        decrypt_pending_balance(&discrete_log, &elgamal_secret, &extension)
+    })
+    .await;
+    let pending_balance = match decrypted {
+        Ok(Ok(pending_balance)) => pending_balance,
+        Ok(Err(err)) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("{err:#}"),
+                }),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to decrypt pending balance {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    let available_balance = match decrypt_available_balance(&ae_key, &extension) {
+        Ok(available_balance) => available_balance,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("{err:#}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    let Some(confidential_balance) = available_balance.checked_add(pending_balance) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "confidential balance overflows".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // an account can only be emptied once its pending balance is applied, even if it is zero
+    let needs_apply = u64::from(extension.pending_balance_credit_counter) > 0
+        || extension.pending_balance_lo != EncryptedBalance::zeroed()
+        || extension.pending_balance_hi != EncryptedBalance::zeroed();
+    if !payload.drain && (needs_apply || available_balance > 0) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: format!(
+                    "confidential balance of {} is not empty, set drain to apply and withdraw it first",
+                    spl_token_2022::amount_to_ui_amount(confidential_balance, decimals)
+                ),
+            }),
+        )
+            .into_response();
+    }
+
+    // closing requires the token account to hold no tokens, including the drained ones
+    let remaining_balance = token_account.base.amount.saturating_add(if payload.drain {
+        confidential_balance
+    } else {
+        0
+    });
+    if payload.close && remaining_balance > 0 {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: format!(
+                    "non confidential balance of {} must be transferred or unwrapped before closing",
+                    spl_token_2022::amount_to_ui_amount(remaining_balance, decimals)
+                ),
+            }),
+        )
+            .into_response();
+    }
+
+    let mut txs = vec![];
+
+    if needs_apply {
+        let new_decryptable_available_balance = ae_key.encrypt(confidential_balance).into();
+        // can only fail if incorrect token program is provided
+        let apply_ix = apply_pending_balance(
+            &spl_token_2022::id(),
+            &user_ata,
+            extension.pending_balance_credit_counter.into(),
+            &new_decryptable_available_balance,
+            &payload.authority,
+            &[],
+        )
+        .unwrap();
+        txs.push(Transaction::new_with_payer(
+            &[apply_ix],
+            Some(&payload.authority),
+        ));
+
+        // the withdraw and empty proofs are generated against the balance after the apply
+        extension =
+            match apply_pending_balance_locally(&extension, new_decryptable_available_balance) {
+                Ok(extension) => extension,
+                Err(err) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiError {
+                            msg: format!("{err:#}"),
+                        }),
+                    )
+                        .into_response()
+                }
+            };
+    }
+
+    if payload.drain && confidential_balance > 0 {
+        let (Some(equality_proof_keypair), Some(range_proof_keypair)) = (
+            payload.equality_proof_keypair.as_ref(),
+            payload.range_proof_keypair.as_ref(),
+        ) else {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: "equality_proof_keypair and range_proof_keypair are required to drain the confidential balance".to_string(),
+                }),
+            )
+                .into_response();
+        };
+
+        // Confidential Transfer extension information needed to construct a `Withdraw` instruction.
+        let withdraw_account_info = WithdrawAccountInfo::new(&extension);
+
+        // Create a withdraw proof data
+        let WithdrawProofData {
+            equality_proof_data,
+            range_proof_data,
+        } = match withdraw_account_info.generate_proof_data(
+            confidential_balance,
+            &elgamal_key,
+            &ae_key,
+        ) {
+            Ok(proof) => proof,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to generate withdraw proof {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+
+        let range_proof_rent = match state
+            .rpc
+            .get_minimum_balance_for_rent_exemption(std::mem::size_of::<
+                ProofContextState<BatchedRangeProofContext>,
+            >())
+            .await
+        {
+            Ok(rent) => rent,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to get range proof rent {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+
+        let equality_proof_rent = match state
+            .rpc
+            .get_minimum_balance_for_rent_exemption(std::mem::size_of::<
+                ProofContextState<CiphertextCommitmentEqualityProofContext>,
+            >())
+            .await
+        {
+            Ok(rent) => rent,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to get equality proof rent {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+
+        let (range_create_ix, range_verify_ix) =
+            match get_zk_proof_context_state_account_creation_instructions(
+                &payload.authority,
+                &range_proof_keypair.pubkey(),
+                &payload.authority,
+                &range_proof_data,
+                range_proof_rent,
+            ) {
+                Ok(data) => data,
+                Err(err) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiError {
+                            msg: format!("failed to create range proof instructions {err:#?}"),
+                        }),
+                    )
+                        .into_response()
+                }
+            };
+
+        let (equality_create_ix, equality_verify_ix) =
+            match get_zk_proof_context_state_account_creation_instructions(
+                &payload.authority,
+                &equality_proof_keypair.pubkey(),
+                &payload.authority,
+                &equality_proof_data,
+                equality_proof_rent,
+            ) {
+                Ok(data) => data,
+                Err(err) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiError {
+                            msg: format!("failed to create equality proof instructions {err:#?}"),
+                        }),
+                    )
+                        .into_response()
+                }
+            };
+
+        let Ok(new_decryptable_available_balance) =
+            withdraw_account_info.new_decryptable_available_balance(confidential_balance, &ae_key)
+        else {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: "failed to encrypt available balance".to_string(),
+                }),
+            )
+                .into_response();
+        };
+        let new_decryptable_available_balance = new_decryptable_available_balance.into();
+
+        // only way this errors is if incorrect token program is provided
+        let withdraw_ixs = withdraw(
+            &spl_token_2022::id(),
+            &user_ata,
+            &payload.token_mint,
+            confidential_balance,
+            decimals,
+            &new_decryptable_available_balance,
+            &payload.authority,
+            &[],
+            ProofLocation::ContextStateAccount(&equality_proof_keypair.pubkey()),
+            ProofLocation::ContextStateAccount(&range_proof_keypair.pubkey()),
+        )
+        .unwrap();
+
+        let close_proof_ixs = [equality_proof_keypair, range_proof_keypair].map(|keypair| {
+            close_context_state(
+                ContextStateInfo {
+                    context_state_account: &keypair.pubkey(),
+                    context_state_authority: &payload.authority,
+                },
+                &payload.authority,
+            )
+        });
+
+        txs.push(Transaction::new_with_payer(
+            &[equality_create_ix, equality_verify_ix, range_create_ix],
+            Some(&payload.authority),
+        ));
+        txs.push(Transaction::new_with_payer(
+            &[range_verify_ix],
+            Some(&payload.authority),
+        ));
+        txs.push(Transaction::new_with_payer(
+            &withdraw_ixs,
+            Some(&payload.authority),
+        ));
+        txs.push(Transaction::new_with_payer(
+            &close_proof_ixs,
+            Some(&payload.authority),
+        ));
+
+        extension = match withdraw_locally(
+            &extension,
+            confidential_balance,
+            new_decryptable_available_balance,
+        ) {
+            Ok(extension) => extension,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("{err:#}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+    }
+
+    let mut instructions = vec![];
+
+    // accounts which never held a confidential balance are already empty
+    if extension.available_balance != EncryptedBalance::zeroed() {
+        let proof_data =
+            match EmptyAccountAccountInfo::new(&extension).generate_proof_data(&elgamal_key) {
+                Ok(proof_data) => proof_data,
+                Err(err) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiError {
+                            msg: format!("failed to generate zero ciphertext proof {err:#?}"),
+                        }),
+                    )
+                        .into_response()
+                }
+            };
+        // only way this errors is if incorrect token program is provided
+        instructions.append(
+            &mut empty_account(
+                &spl_token_2022::id(),
+                &user_ata,
+                &payload.authority,
+                &[],
+                ProofLocation::InstructionOffset(
+                    1.try_into().unwrap(),
+                    ProofData::InstructionData(&proof_data),
+                ),
+            )
+            .unwrap(),
+        );
+    }
+
+    if payload.close {
+        // the rent is returned to the authority
+        instructions.push(
+            spl_token_2022::instruction::close_account(
+                &spl_token_2022::id(),
+                &user_ata,
+                &payload.authority,
+                &payload.authority,
+                &[],
+            )
+            .unwrap(),
+        );
+    }
+
+    if !instructions.is_empty() {
+        txs.push(Transaction::new_with_payer(
+            &instructions,
+            Some(&payload.authority),
+        ));
+    }
+
+    let txs = match encode_transactions(&txs) {
+        Ok(txs) => txs,
+        Err(response) => return response,
+    };
+    (
+        StatusCode::OK,
+        Json(ApiTransactionResponse { transactions: txs }),
+    )
+        .into_response()
+}
@@ -0,0 +1,166 @@
+use {
+    crate::{
+        router::AppState,
+        types::{ApiError, ApiTransactionResponse, CreateWrappedMint},
+    },
+    axum::{extract::State, response::IntoResponse, Json},
+    base64::{prelude::BASE64_STANDARD, Engine},
+    common::wrapped_mint::{
+        backpointer_len, create_wrapped_mint_instructions, escrow_address, wrapped_mint_len,
+        WrappedMintAccounts, WrappedMintConfig,
+    },
+    http::StatusCode,
+    solana_sdk::transaction::Transaction,
+    spl_token_wrap::{get_wrapped_mint_address, get_wrapped_mint_backpointer_address},
+    std::sync::Arc,
+};
+
+/// Handler which is used to create the confidential wrapped mint of an unwrapped mint
+///
+/// No transactions are returned if the wrapped mint and its escrow already exist
+pub async fn create_wrapped_mint(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreateWrappedMint>,
+) -> impl IntoResponse {
+    let wrapped_mint =
+        get_wrapped_mint_address(&payload.unwrapped_token_mint, &spl_token_2022::id());
+
+    // lookup the unwrapped mint along with the accounts of the wrapped mint
+    let mut accounts = state
+        .rpc
+        .get_multiple_accounts(&[
+            payload.unwrapped_token_mint,
+            wrapped_mint,
+            get_wrapped_mint_backpointer_address(&wrapped_mint),
+        ])
+        .await
+        .unwrap_or_default();
+
+    // if no accounts are returned, this means the rpc call failed
+    if accounts.is_empty() {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query accounts".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // the escrow is owned by the token program of the unwrapped mint
+    let Some(unwrapped_mint) = std::mem::take(&mut accounts[0]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "unwrapped token mint does not exist".to_string(),
+            }),
+        )
+            .into_response();
+    };
+    let escrow = match state
+        .rpc
+        .get_multiple_accounts(&[escrow_address(
+            &payload.unwrapped_token_mint,
+            &unwrapped_mint.owner,
+        )])
+        .await
+    {
+        Ok(mut escrow) if escrow.len() == 1 => std::mem::take(&mut escrow[0]),
+        _ => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: "failed to query escrow account".to_string(),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let Ok(mint_len) = wrapped_mint_len() else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to calculate wrapped mint size".to_string(),
+            }),
+        )
+            .into_response();
+    };
+    let (Ok(mint_rent), Ok(backpointer_rent)) = (
+        state
+            .rpc
+            .get_minimum_balance_for_rent_exemption(mint_len)
+            .await,
+        state
+            .rpc
+            .get_minimum_balance_for_rent_exemption(backpointer_len())
+            .await,
+    ) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query rent".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let config = WrappedMintConfig {
+        auto_approve_new_accounts: payload.auto_approve_new_accounts,
+        auditor_elgamal_pubkey: payload.auditor_elgamal_pubkey,
+        confidential_transfer_authority: payload.confidential_transfer_authority,
+    };
+    let ixs = match create_wrapped_mint_instructions(
+        &payload.payer,
+        &payload.unwrapped_token_mint,
+        &unwrapped_mint.owner,
+        &config,
+        &WrappedMintAccounts {
+            wrapped_mint: std::mem::take(&mut accounts[1]),
+            backpointer: std::mem::take(&mut accounts[2]),
+            escrow,
+            mint_rent,
+            backpointer_rent,
+        },
+    ) {
+        Ok(ixs) => ixs,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: format!("{err:#}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    if ixs.is_empty() {
+        return (
+            StatusCode::OK,
+            Json(ApiTransactionResponse {
+                transactions: vec![],
+            }),
+        )
+            .into_response();
+    }
+
+    let tx = match bincode::serialize(&Transaction::new_with_payer(&ixs, Some(&payload.payer))) {
+        Ok(tx) => tx,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to serialize transaction {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    (
+        StatusCode::OK,
+        Json(ApiTransactionResponse {
+            transactions: vec![BASE64_STANDARD.encode(tx)],
+        }),
+    )
+        .into_response()
+}
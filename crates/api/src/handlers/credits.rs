@@ -0,0 +1,171 @@
+use {
+    crate::{
+        router::AppState,
+        types::{ApiError, ApiTransactionResponse, UpdateCredits},
+    },
+    axum::{extract::State, response::IntoResponse, Json},
+    base64::{prelude::BASE64_STANDARD, Engine},
+    http::StatusCode,
+    solana_sdk::transaction::Transaction,
+    spl_token_2022::{
+        extension::{
+            confidential_transfer::{instruction, ConfidentialTransferAccount},
+            BaseStateWithExtensions, StateWithExtensions,
+        },
+        state::Account,
+    },
+    std::sync::Arc,
+};
+
+/// The type of incoming credits toggled on a token account
+#[derive(Clone, Copy)]
+enum Credits {
+    Confidential,
+    NonConfidential,
+}
+
+impl Credits {
+    fn name(self) -> &'static str {
+        match self {
+            Credits::Confidential => "confidential credits",
+            Credits::NonConfidential => "non confidential credits",
+        }
+    }
+}
+
+/// Handler which is used to allow incoming confidential transfers into a token account
+pub async fn enable_confidential_credits(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<UpdateCredits>,
+) -> impl IntoResponse {
+    update_credits(state, payload, Credits::Confidential, true).await
+}
+
+/// Handler which is used to reject incoming confidential transfers into a token account
+pub async fn disable_confidential_credits(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<UpdateCredits>,
+) -> impl IntoResponse {
+    update_credits(state, payload, Credits::Confidential, false).await
+}
+
+/// Handler which is used to allow incoming non confidential transfers into a token account
+pub async fn enable_non_confidential_credits(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<UpdateCredits>,
+) -> impl IntoResponse {
+    update_credits(state, payload, Credits::NonConfidential, true).await
+}
+
+/// Handler which is used to reject incoming non confidential transfers into a token account
+pub async fn disable_non_confidential_credits(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<UpdateCredits>,
+) -> impl IntoResponse {
+    update_credits(state, payload, Credits::NonConfidential, false).await
+}
+
+async fn update_credits(
+    state: Arc<AppState>,
+    payload: UpdateCredits,
+    credits: Credits,
+    enable: bool,
+) -> axum::response::Response {
+    // derive the ATA for the authority + token_mint
+    let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &payload.authority,
+        &payload.token_mint,
+        &spl_token_2022::id(),
+    );
+
+    let token_account = match state.rpc.get_account(&user_ata).await {
+        Ok(token_account) => token_account,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: format!("failed to query token account {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let token_account = match StateWithExtensions::<Account>::unpack(&token_account.data) {
+        Ok(token_account) => token_account,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to unpack token account {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let extension = match token_account.get_extension::<ConfidentialTransferAccount>() {
+        Ok(extension) => extension,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: format!(
+                        "token account is not configured for confidential transfers {err:#?}"
+                    ),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let enabled = match credits {
+        Credits::Confidential => bool::from(extension.allow_confidential_credits),
+        Credits::NonConfidential => bool::from(extension.allow_non_confidential_credits),
+    };
+    if enabled == enable {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: format!(
+                    "{} are already {}",
+                    credits.name(),
+                    if enable { "enabled" } else { "disabled" }
+                ),
+            }),
+        )
+            .into_response();
+    }
+
+    let ix = match (credits, enable) {
+        (Credits::Confidential, true) => instruction::enable_confidential_credits,
+        (Credits::Confidential, false) => instruction::disable_confidential_credits,
+        (Credits::NonConfidential, true) => instruction::enable_non_confidential_credits,
+        (Credits::NonConfidential, false) => instruction::disable_non_confidential_credits,
+    };
+    // only way this errors is if incorrect token program is provided
+    let ix = ix(&spl_token_2022::id(), &user_ata, &payload.authority, &[]).unwrap();
+
+    let tx = match bincode::serialize(&Transaction::new_with_payer(
+        &[ix],
+        Some(&payload.authority),
+    )) {
+        Ok(tx) => tx,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to serialize transaction {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    (
+        StatusCode::OK,
+        Json(ApiTransactionResponse {
+            transactions: vec![BASE64_STANDARD.encode(tx)],
+        }),
+    )
+        .into_response()
+}
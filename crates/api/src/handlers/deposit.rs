@@ -5,7 +5,7 @@ use {
     },
     axum::{extract::State, response::IntoResponse, Json},
     base64::{prelude::BASE64_STANDARD, Engine},
-    common::accounts::token_account_already_configured,
+    common::{accounts::token_account_already_configured, transactions::with_memo},
     http::StatusCode,
     solana_sdk::transaction::Transaction,
     spl_token_2022::extension::StateWithExtensions,
@@ -104,8 +104,10 @@ pub async fn deposit(
             .into_response();
     }
 
-    let tx = Transaction::new_with_payer(
-        &[
+    let instructions = match with_memo(
+        payload.memo.as_deref(),
+        &payload.authority,
+        vec![
             // deposit can only fail if the incorrect token program is provided
             spl_token_2022::extension::confidential_transfer::instruction::deposit(
                 &spl_token_2022::id(),
@@ -118,8 +120,19 @@ pub async fn deposit(
             )
             .unwrap(),
         ],
-        Some(&payload.authority),
-    );
+    ) {
+        Ok(instructions) => instructions,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: format!("invalid memo {err:#}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    let tx = Transaction::new_with_payer(&instructions, Some(&payload.authority));
 
     let tx = match bincode::serialize(&tx) {
         Ok(tx) => tx,
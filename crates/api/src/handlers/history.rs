@@ -0,0 +1,188 @@
+use {
+    crate::{
+        history::{fetch_events, replay},
+        notifications::Credentials,
+        router::AppState,
+        types::{ApiError, ApiHistoryResponse, History},
+    },
+    axum::{extract::State, response::IntoResponse, Json},
+    common::key_generator::{derive_ae_key, derive_elgamal_key, KeypairType},
+    http::StatusCode,
+    spl_token_2022::extension::StateWithExtensions,
+    std::sync::Arc,
+};
+
+/// Number of transactions returned when the request does not specify a limit
+const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// Maximum number of transactions returned in a single response
+const MAX_HISTORY_LIMIT: usize = 1_000;
+
+/// Handler which is used to display the decrypted transaction history of a confidential token account
+pub async fn history(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<History>,
+) -> impl IntoResponse {
+    // derive the ATA for the authority + token_mint
+    let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &payload.authority,
+        &payload.token_mint,
+        &spl_token_2022::id(),
+    );
+
+    // verify elgamal signature
+    if !payload.elgamal_signature.verify(
+        &payload.authority.to_bytes(),
+        &KeypairType::ElGamal.message_to_sign(user_ata),
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify elgamal signature".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // verify ae signature
+    if !payload.ae_signature.verify(
+        &payload.authority.to_bytes(),
+        &KeypairType::Ae.message_to_sign(user_ata),
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify ae signature".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    let limit = payload.limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+    if limit == 0 || limit > MAX_HISTORY_LIMIT {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: format!("limit must be between 1 and {MAX_HISTORY_LIMIT}"),
+            }),
+        )
+            .into_response();
+    }
+
+    // derive the elgamal keypair
+    let Ok(elgamal_key) = derive_elgamal_key(payload.elgamal_signature) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to derive elgamal keypair".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // derive the ae keypair
+    let Ok(ae_key) = derive_ae_key(payload.ae_signature) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to derive ae keypair".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // lookup the token mint
+    let mut accounts = state
+        .rpc
+        .get_multiple_accounts(&[payload.token_mint])
+        .await
+        .unwrap_or_default();
+
+    // if no accounts are returned, this means the rpc call failed
+    if accounts.is_empty() {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query accounts".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure the token mint account exists
+    let Some(token_mint) = std::mem::take(&mut accounts[0]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token mint does not exist".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // get the token mint decimals
+    let decimals =
+        match StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&token_mint.data) {
+            Ok(mint) => mint.base.decimals,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to unpack token mint {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+
+    // the token account may since have been closed, so its history is fetched regardless
+    let page = match fetch_events(&state.rpc, &user_ata, payload.before, limit).await {
+        Ok(page) => page,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to fetch history {err:#}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    // the running balances are replayed from the events of the page and the older events
+    // revealing its starting balances, decryption is cpu bound so it is moved off the async
+    // runtime
+    let discrete_log = state.discrete_log.clone();
+    let credentials = Credentials {
+        elgamal_secret: elgamal_key.secret().clone(),
+        ae_key,
+    };
+    let events = page.events;
+    let mut entries =
+        match tokio::task::spawn_blocking(move || replay(&events, &credentials, &discrete_log))
+            .await
+        {
+            Ok(mut entries) => entries.split_off(page.lookback),
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to decrypt history {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+    entries.reverse();
+
+    (
+        StatusCode::OK,
+        Json(ApiHistoryResponse {
+            token_account: user_ata,
+            decimals,
+            entries,
+            before: page.before,
+        }),
+    )
+        .into_response()
+}
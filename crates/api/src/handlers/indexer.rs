@@ -0,0 +1,99 @@
+use {
+    crate::{
+        router::AppState,
+        types::{ApiError, ApiIndexedInstructionsResponse, IndexedInstructions},
+    },
+    axum::{
+        extract::{Query, State},
+        response::IntoResponse,
+        Json,
+    },
+    http::StatusCode,
+    std::sync::Arc,
+};
+
+/// Number of instructions returned when the request does not specify a limit
+const DEFAULT_INSTRUCTIONS_LIMIT: usize = 100;
+
+/// Maximum number of instructions returned in a single response
+const MAX_INSTRUCTIONS_LIMIT: usize = 1_000;
+
+/// Handler which is used to display the progress of the indexer
+pub async fn indexer_status(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let Some(indexer) = state.indexer.clone() else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ApiError {
+                msg: "indexer is not enabled".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    match tokio::task::spawn_blocking(move || indexer.status()).await {
+        Ok(Ok(status)) => (StatusCode::OK, Json(status)).into_response(),
+        Ok(Err(err)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: format!("failed to query indexer status {err:#}"),
+            }),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: format!("failed to query indexer status {err:#?}"),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// Handler which is used to list the instructions recorded by the indexer
+pub async fn indexer_instructions(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<IndexedInstructions>,
+) -> impl IntoResponse {
+    let Some(indexer) = state.indexer.clone() else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ApiError {
+                msg: "indexer is not enabled".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let limit = query.limit.unwrap_or(DEFAULT_INSTRUCTIONS_LIMIT);
+    if limit == 0 || limit > MAX_INSTRUCTIONS_LIMIT {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: format!("limit must be between 1 and {MAX_INSTRUCTIONS_LIMIT}"),
+            }),
+        )
+            .into_response();
+    }
+
+    match tokio::task::spawn_blocking(move || indexer.instructions(&query, Some(limit))).await {
+        Ok(Ok(instructions)) => (
+            StatusCode::OK,
+            Json(ApiIndexedInstructionsResponse { instructions }),
+        )
+            .into_response(),
+        Ok(Err(err)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: format!("failed to query indexed instructions {err:#}"),
+            }),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: format!("failed to query indexed instructions {err:#?}"),
+            }),
+        )
+            .into_response(),
+    }
+}
@@ -6,16 +6,15 @@ use {
     axum::{extract::State, response::IntoResponse, Json},
     base64::{prelude::BASE64_STANDARD, Engine},
     common::{
-        accounts::token_account_already_configured,
+        accounts::{
+            token_account_already_configured, DEFAULT_MAXIMUM_PENDING_BALANCE_CREDIT_COUNTER,
+            MAX_PENDING_BALANCE_CREDIT_COUNTER,
+        },
         key_generator::{derive_ae_key, derive_elgamal_key, KeypairType},
+        transactions::configure_account_instructions,
     },
     http::StatusCode,
     solana_sdk::transaction::Transaction,
-    spl_token_2022::extension::{
-        confidential_transfer::instruction::{configure_account, PubkeyValidityProofData},
-        ExtensionType,
-    },
-    spl_token_confidential_transfer_proof_extraction::instruction::{ProofData, ProofLocation},
     std::sync::Arc,
 };
 
@@ -59,6 +58,22 @@ pub async fn initialize(
             .into_response();
     }
 
+    // ensure the requested credit counter keeps the pending balance decryptable
+    let maximum_pending_balance_credit_counter = payload
+        .maximum_pending_balance_credit_counter
+        .unwrap_or(DEFAULT_MAXIMUM_PENDING_BALANCE_CREDIT_COUNTER);
+    if !(1..=MAX_PENDING_BALANCE_CREDIT_COUNTER).contains(&maximum_pending_balance_credit_counter) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: format!(
+                    "maximum_pending_balance_credit_counter must be between 1 and {MAX_PENDING_BALANCE_CREDIT_COUNTER}"
+                ),
+            }),
+        )
+            .into_response();
+    }
+
     // lookup both the token mint, and ata accounts
     let mut accounts = state
         .rpc
@@ -97,7 +112,7 @@ pub async fn initialize(
             return (
                 StatusCode::BAD_REQUEST,
                 Json(ApiError {
-                    msg: "token account already configured for confidential transfers".to_string(),
+                    msg: "token account already configured for confidential transfers, it must be emptied and closed before it can be reconfigured".to_string(),
                 }),
             )
                 .into_response();
@@ -137,63 +152,27 @@ pub async fn initialize(
             .into_response();
     };
 
-    // generate proof data
-    let Ok(proof_data) = PubkeyValidityProofData::new(&elgamal_key) else {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiError {
-                msg: "failed to generate proof data".to_string(),
-            }),
-        )
-            .into_response();
-    };
-
-    // generate the account configuration instructions
-    let Ok(mut configure_instructions) = configure_account(
-        &spl_token_2022::id(),
-        &user_ata,
-        &payload.token_mint,
-        &ae_key.encrypt(0).into(),
-        65536,
+    // create the instructions to initialize the ata, reallocate and configure it for confidential transfers
+    let instructions = match configure_account_instructions(
         &payload.authority,
-        &[],
-        ProofLocation::InstructionOffset(
-            1.try_into().unwrap(),
-            ProofData::InstructionData(&proof_data),
-        ),
-    ) else {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiError {
-                msg: "failed to generate configure instructions".to_string(),
-            }),
-        )
-            .into_response();
+        &payload.token_mint,
+        &elgamal_key,
+        &ae_key,
+        maximum_pending_balance_credit_counter,
+        common::accounts::mint_has_transfer_fee(&token_mint),
+    ) {
+        Ok(instructions) => instructions,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("{err:#}"),
+                }),
+            )
+                .into_response()
+        }
     };
 
-    // create the instructions to initialize the ata, and reallocate for confidential transfers
-    let mut instructions = vec![
-        spl_associated_token_account::instruction::create_associated_token_account_idempotent(
-            &payload.authority,
-            &payload.authority,
-            &payload.token_mint,
-            &spl_token_2022::id(),
-        ),
-        // the only possible error for rellocate is if the token program is not spl_token_2022
-        spl_token_2022::instruction::reallocate(
-            &spl_token_2022::id(),
-            &user_ata,
-            &payload.authority,
-            &payload.authority,
-            &[],
-            &[ExtensionType::ConfidentialTransferAccount],
-        )
-        .unwrap(),
-    ];
-
-    // update the instructions with account configuration
-    instructions.append(&mut configure_instructions);
-
     // create the transaction, bincode serialize it, and return it as a base64 encoded string
 
     let tx = Transaction::new_with_payer(&instructions, Some(&payload.authority));
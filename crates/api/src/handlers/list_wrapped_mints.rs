@@ -0,0 +1,110 @@
+use {
+    crate::{
+        handlers::portfolio::get_multiple_accounts,
+        router::AppState,
+        types::{ApiError, ApiWrappedMint, ApiWrappedMintsResponse},
+    },
+    axum::{extract::State, response::IntoResponse, Json},
+    common::{
+        accounts::is_valid_mint,
+        wrapped_mint::{backpointer_len, WrappedMintConfig},
+    },
+    http::StatusCode,
+    solana_account_decoder_client_types::UiAccountEncoding,
+    solana_client::{
+        rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+        rpc_filter::RpcFilterType,
+    },
+    spl_token_2022::{extension::StateWithExtensions, state::Mint},
+    spl_token_wrap::{
+        get_wrapped_mint_address, get_wrapped_mint_backpointer_address, state::Backpointer,
+    },
+    std::sync::Arc,
+};
+
+/// Handler which is used to list every spl_token_2022 wrapped mint created by the token wrap
+/// program, by scanning its backpointer accounts
+pub async fn list_wrapped_mints(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    // backpointers are the only accounts owned by the token wrap program
+    let backpointers = match state
+        .rpc
+        .get_program_accounts_with_config(
+            &spl_token_wrap::id(),
+            RpcProgramAccountsConfig {
+                filters: Some(vec![RpcFilterType::DataSize(backpointer_len() as u64)]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .await
+    {
+        Ok(accounts) => accounts,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to query backpointers {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    // resolve each backpointer to its (unwrapped, wrapped) mint pair, skipping backpointers of
+    // mints wrapped into other token programs
+    let mut pairs = backpointers
+        .into_iter()
+        .filter_map(|(address, account)| {
+            let backpointer = bytemuck::try_from_bytes::<Backpointer>(&account.data).ok()?;
+            let wrapped_mint =
+                get_wrapped_mint_address(&backpointer.unwrapped_mint, &spl_token_2022::id());
+            (get_wrapped_mint_backpointer_address(&wrapped_mint) == address)
+                .then_some((backpointer.unwrapped_mint, wrapped_mint))
+        })
+        .collect::<Vec<_>>();
+    pairs.sort();
+
+    let wrapped_mint_addresses = pairs
+        .iter()
+        .map(|(_, wrapped_mint)| *wrapped_mint)
+        .collect::<Vec<_>>();
+    let Some(wrapped_mints) = get_multiple_accounts(&state.rpc, &wrapped_mint_addresses).await
+    else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query wrapped mints".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let wrapped_mints = pairs
+        .into_iter()
+        .zip(wrapped_mints)
+        .filter_map(|((unwrapped_token_mint, wrapped_token_mint), account)| {
+            let account = account?;
+            let decimals = StateWithExtensions::<Mint>::unpack(&account.data)
+                .ok()?
+                .base
+                .decimals;
+            let config = WrappedMintConfig::from_mint(&account).ok();
+            Some(ApiWrappedMint {
+                unwrapped_token_mint,
+                wrapped_token_mint,
+                decimals,
+                confidential_transfers: is_valid_mint(&account),
+                has_auditor: config.is_some_and(|config| config.auditor_elgamal_pubkey.is_some()),
+            })
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(ApiWrappedMintsResponse { wrapped_mints }),
+    )
+        .into_response()
+}
@@ -0,0 +1,989 @@
+use {
+    super::transactions::{encode_transactions, proof_context_instructions},
+    crate::{
+        router::AppState,
+        types::{
+            ApiConfidentialSupplyResponse, ApiError, ApiTransactionResponse, ConfidentialBurn,
+            ConfidentialMint, ConfidentialSupply, CreateConfidentialMint,
+        },
+    },
+    axum::{
+        extract::State,
+        response::{IntoResponse, Response},
+        Json,
+    },
+    base64::{prelude::BASE64_STANDARD, Engine},
+    common::{
+        accounts::{decrypt_available_balance, token_account_already_configured},
+        key_generator::{derive_ae_key, derive_elgamal_key, KeypairType},
+        mint_burn::{
+            apply_pending_burn, confidential_mint_len, create_confidential_mint_instructions,
+            decrypt_pending_burn, decrypt_supply, mint_proof_data, ConfidentialMintConfig,
+        },
+        transactions::pack_instructions,
+    },
+    http::StatusCode,
+    solana_sdk::{
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature},
+        signer::Signer,
+        transaction::Transaction,
+    },
+    spl_token_2022::{
+        extension::{
+            confidential_mint_burn::{
+                account_info::BurnAccountInfo,
+                instruction::{
+                    confidential_burn_with_split_proofs, confidential_mint_with_split_proofs,
+                },
+                ConfidentialMintBurn,
+            },
+            confidential_transfer::{ConfidentialTransferAccount, ConfidentialTransferMint},
+            BaseStateWithExtensions, StateWithExtensions,
+        },
+        solana_zk_sdk::{
+            encryption::{
+                auth_encryption::AeKey,
+                elgamal::{ElGamalKeypair, ElGamalPubkey},
+                pod::elgamal::{PodElGamalCiphertext, PodElGamalPubkey},
+            },
+            zk_elgamal_proof_program::instruction::{close_context_state, ContextStateInfo},
+        },
+        state::{Account, Mint},
+    },
+    spl_token_confidential_transfer_proof_extraction::instruction::ProofLocation,
+    spl_token_confidential_transfer_proof_generation::{burn::BurnProofData, mint::MintProofData},
+    std::sync::Arc,
+};
+
+/// Handler which is used to create a natively issued confidential mint, optionally keeping its
+/// supply confidential
+///
+/// The returned transaction must also be signed by the mint keypair
+pub async fn create_confidential_mint(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreateConfidentialMint>,
+) -> impl IntoResponse {
+    // the supply is only kept confidential when both supply keys are provided
+    let supply_keys = match (
+        payload.supply_elgamal_signature,
+        payload.supply_ae_signature,
+    ) {
+        (Some(supply_elgamal_signature), Some(supply_ae_signature)) => {
+            match supply_keys(
+                &payload.authority,
+                &payload.mint,
+                supply_elgamal_signature,
+                supply_ae_signature,
+            ) {
+                Ok(supply_keys) => Some(supply_keys),
+                Err(response) => return response,
+            }
+        }
+        (None, None) => None,
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: "both supply signatures are required".to_string(),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    match state.rpc.get_multiple_accounts(&[payload.mint]).await {
+        Ok(accounts) if accounts.len() == 1 => {
+            if accounts[0].is_some() {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiError {
+                        msg: "mint account already exists".to_string(),
+                    }),
+                )
+                    .into_response();
+            }
+        }
+        _ => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: "failed to query accounts".to_string(),
+                }),
+            )
+                .into_response()
+        }
+    }
+
+    let Ok(mint_len) = confidential_mint_len(supply_keys.is_some()) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to calculate confidential mint size".to_string(),
+            }),
+        )
+            .into_response();
+    };
+    let Ok(rent) = state
+        .rpc
+        .get_minimum_balance_for_rent_exemption(mint_len)
+        .await
+    else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query rent".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let config = ConfidentialMintConfig {
+        auto_approve_new_accounts: payload.auto_approve_new_accounts,
+        auditor_elgamal_pubkey: payload.auditor_elgamal_pubkey,
+        confidential_transfer_authority: payload.confidential_transfer_authority,
+    };
+    let ixs = match create_confidential_mint_instructions(
+        &payload.authority,
+        &payload.mint,
+        payload.decimals,
+        rent,
+        &config,
+        supply_keys
+            .as_ref()
+            .map(|(supply_elgamal_key, supply_ae_key)| {
+                (supply_elgamal_key.pubkey(), supply_ae_key)
+            }),
+    ) {
+        Ok(ixs) => ixs,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: format!("{err:#}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let tx = match bincode::serialize(&Transaction::new_with_payer(&ixs, Some(&payload.authority)))
+    {
+        Ok(tx) => tx,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to serialize transaction {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    (
+        StatusCode::OK,
+        Json(ApiTransactionResponse {
+            transactions: vec![BASE64_STANDARD.encode(tx)],
+        }),
+    )
+        .into_response()
+}
+
+/// Handler which is used by the mint authority of a mint with a confidential supply to mint
+/// tokens into the pending balance of a confidential token account
+///
+/// Any pending burn is applied to the supply before minting
+pub async fn confidential_mint(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ConfidentialMint>,
+) -> impl IntoResponse {
+    let (supply_elgamal_key, supply_ae_key) = match supply_keys(
+        &payload.authority,
+        &payload.token_mint,
+        payload.supply_elgamal_signature,
+        payload.supply_ae_signature,
+    ) {
+        Ok(supply_keys) => supply_keys,
+        Err(response) => return response,
+    };
+
+    let receiving_token_account_key =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            &payload.receiving_token_account,
+            &payload.token_mint,
+            &spl_token_2022::id(),
+        );
+
+    // lookup the token mint and the receiving token account
+    let mut accounts = state
+        .rpc
+        .get_multiple_accounts(&[payload.token_mint, receiving_token_account_key])
+        .await
+        .unwrap_or_default();
+
+    // if fewer accounts are returned, this means the rpc call failed
+    if accounts.len() < 2 {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query accounts".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let Some(token_mint) = std::mem::take(&mut accounts[0]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token mint does not exist".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let Some(receiving_token_account) = std::mem::take(&mut accounts[1]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "receiving token account does not exist".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure the receiving token account is configured for confidential transfers
+    if !token_account_already_configured(&receiving_token_account) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "receiving token account is not configured for confidential transfers"
+                    .to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    let token_mint = match StateWithExtensions::<Mint>::unpack(&token_mint.data) {
+        Ok(token_mint) => token_mint,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to unpack token mint {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    if Option::<Pubkey>::from(token_mint.base.mint_authority) != Some(payload.authority) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "authority is not the mint authority of the token mint".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    let (extension, auditor_pubkey) = match mint_burn_extension(&token_mint, &supply_elgamal_key) {
+        Ok(extension) => extension,
+        Err(response) => return response,
+    };
+
+    let receiving_token_account =
+        match StateWithExtensions::<Account>::unpack(&receiving_token_account.data) {
+            Ok(token_account) => token_account,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to unpack receiving token account {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+    let Ok(destination_pubkey) = receiving_token_account
+        .get_extension::<ConfidentialTransferAccount>()
+        .map_err(|_| ())
+        .and_then(|extension| {
+            TryInto::<ElGamalPubkey>::try_into(extension.elgamal_pubkey).map_err(|_| ())
+        })
+    else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to parse receiving token account elgamal pubkey".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // the mint proofs are generated against the supply once the pending burn is applied, so the
+    // pending burn is applied in the same transaction as the mint
+    let mut mint_ixs = vec![];
+    let extension = if extension.pending_burn != PodElGamalCiphertext::default() {
+        let extension = match apply_pending_burn(&extension) {
+            Ok(extension) => extension,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("{err:#}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+        // can only fail if incorrect token program is provided
+        mint_ixs.push(
+            spl_token_2022::extension::confidential_mint_burn::instruction::apply_pending_burn(
+                &spl_token_2022::id(),
+                &payload.token_mint,
+                &payload.authority,
+                &[],
+            )
+            .unwrap(),
+        );
+        extension
+    } else {
+        extension
+    };
+
+    // decrypting the supply uses the precomputed discrete log table, which is cpu bound so it is
+    // moved off the async runtime
+    let discrete_log = state.discrete_log.clone();
+    let amount = payload.amount;
+    let proof = tokio::task::spawn_blocking(move || {
+        mint_proof_data(
+            &discrete_log,
+            &supply_elgamal_key,
+            &supply_ae_key,
+            &extension,
+            amount,
+            &destination_pubkey,
+            auditor_pubkey.as_ref(),
+        )
+    })
+    .await;
+    let (
+        MintProofData {
+            equality_proof_data,
+            ciphertext_validity_proof_data_with_ciphertext,
+            range_proof_data,
+        },
+        new_decryptable_supply,
+    ) = match proof {
+        Ok(Ok(proof)) => proof,
+        Ok(Err(err)) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("{err:#}"),
+                }),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to generate mint proofs {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let mut proof_ixs = vec![];
+    for proof_ix in [
+        proof_context_instructions(
+            &state,
+            &payload.authority,
+            &payload.range_proof_keypair,
+            &range_proof_data,
+        )
+        .await,
+        proof_context_instructions(
+            &state,
+            &payload.authority,
+            &payload.equality_proof_keypair,
+            &equality_proof_data,
+        )
+        .await,
+        proof_context_instructions(
+            &state,
+            &payload.authority,
+            &payload.ciphertext_validity_proof_keypair,
+            &ciphertext_validity_proof_data_with_ciphertext.proof_data,
+        )
+        .await,
+    ] {
+        match proof_ix {
+            Ok(proof_ix) => proof_ixs.push(proof_ix),
+            Err(response) => return response,
+        }
+    }
+
+    // can only fail if incorrect token program is provided
+    mint_ixs.extend(
+        confidential_mint_with_split_proofs(
+            &spl_token_2022::id(),
+            &receiving_token_account_key,
+            &payload.token_mint,
+            &ciphertext_validity_proof_data_with_ciphertext.ciphertext_lo,
+            &ciphertext_validity_proof_data_with_ciphertext.ciphertext_hi,
+            &payload.authority,
+            &[],
+            ProofLocation::ContextStateAccount(&payload.equality_proof_keypair.pubkey()),
+            ProofLocation::ContextStateAccount(&payload.ciphertext_validity_proof_keypair.pubkey()),
+            ProofLocation::ContextStateAccount(&payload.range_proof_keypair.pubkey()),
+            &new_decryptable_supply,
+        )
+        .unwrap(),
+    );
+
+    proof_transactions_response(
+        &payload.authority,
+        proof_ixs,
+        mint_ixs,
+        [
+            &payload.equality_proof_keypair,
+            &payload.ciphertext_validity_proof_keypair,
+            &payload.range_proof_keypair,
+        ],
+    )
+}
+
+/// Handler which is used to burn tokens from the available balance of a confidential token
+/// account of a mint with a confidential supply
+///
+/// The burned amount is added to the pending burn of the mint, which is applied to the supply
+/// by the next confidential mint
+pub async fn confidential_burn(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ConfidentialBurn>,
+) -> impl IntoResponse {
+    // derive the ATA for the authority + token_mint
+    let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &payload.authority,
+        &payload.token_mint,
+        &spl_token_2022::id(),
+    );
+
+    // verify elgamal signature
+    if !payload.elgamal_signature.verify(
+        &payload.authority.to_bytes(),
+        &KeypairType::ElGamal.message_to_sign(user_ata),
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify elgamal signature".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // verify ae signature
+    if !payload.ae_signature.verify(
+        &payload.authority.to_bytes(),
+        &KeypairType::Ae.message_to_sign(user_ata),
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify ae signature".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // derive the elgamal keypair
+    let Ok(elgamal_key) = derive_elgamal_key(payload.elgamal_signature) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to derive elgamal keypair".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // derive the ae keypair
+    let Ok(ae_key) = derive_ae_key(payload.ae_signature) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to derive ae keypair".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // lookup the token mint and the ata
+    let mut accounts = state
+        .rpc
+        .get_multiple_accounts(&[payload.token_mint, user_ata])
+        .await
+        .unwrap_or_default();
+
+    // if fewer accounts are returned, this means the rpc call failed
+    if accounts.len() < 2 {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query accounts".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let Some(token_mint) = std::mem::take(&mut accounts[0]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token mint does not exist".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let Some(token_account) = std::mem::take(&mut accounts[1]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token account does not exist".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure token account is configured for confidential transfers
+    if !token_account_already_configured(&token_account) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token account is not configured for confidential transfers".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    let token_mint = match StateWithExtensions::<Mint>::unpack(&token_mint.data) {
+        Ok(token_mint) => token_mint,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to unpack token mint {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let Ok(mint_burn_extension) = token_mint.get_extension::<ConfidentialMintBurn>() else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token mint does not have a confidential supply".to_string(),
+            }),
+        )
+            .into_response();
+    };
+    let (Ok(supply_pubkey), Ok(auditor_pubkey)) = (
+        TryInto::<ElGamalPubkey>::try_into(mint_burn_extension.supply_elgamal_pubkey),
+        auditor_pubkey(&token_mint),
+    ) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to parse supply or auditor pubkey".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let token_account = match StateWithExtensions::<Account>::unpack(&token_account.data) {
+        Ok(token_account) => token_account,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to unpack token account {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    // retrieve the confidential transfer account extension
+    let extension = match token_account.get_extension::<ConfidentialTransferAccount>() {
+        Ok(confidential_token_account) => confidential_token_account,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to get confidential transfer account extension {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    match decrypt_available_balance(&ae_key, extension) {
+        Ok(available_balance) if available_balance >= payload.amount => (),
+        Ok(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: "insufficient available balance".to_string(),
+                }),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("{err:#}"),
+                }),
+            )
+                .into_response()
+        }
+    }
+
+    let burn_account = BurnAccountInfo::new(extension);
+    let (
+        Ok(BurnProofData {
+            equality_proof_data,
+            ciphertext_validity_proof_data_with_ciphertext,
+            range_proof_data,
+        }),
+        Ok(new_decryptable_available_balance),
+    ) = (
+        burn_account.generate_split_burn_proof_data(
+            payload.amount,
+            &elgamal_key,
+            &ae_key,
+            &supply_pubkey,
+            auditor_pubkey.as_ref(),
+        ),
+        burn_account.new_decryptable_balance(payload.amount, &ae_key),
+    )
+    else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to generate burn proofs".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let mut proof_ixs = vec![];
+    for proof_ix in [
+        proof_context_instructions(
+            &state,
+            &payload.authority,
+            &payload.range_proof_keypair,
+            &range_proof_data,
+        )
+        .await,
+        proof_context_instructions(
+            &state,
+            &payload.authority,
+            &payload.equality_proof_keypair,
+            &equality_proof_data,
+        )
+        .await,
+        proof_context_instructions(
+            &state,
+            &payload.authority,
+            &payload.ciphertext_validity_proof_keypair,
+            &ciphertext_validity_proof_data_with_ciphertext.proof_data,
+        )
+        .await,
+    ] {
+        match proof_ix {
+            Ok(proof_ix) => proof_ixs.push(proof_ix),
+            Err(response) => return response,
+        }
+    }
+
+    // can only fail if incorrect token program is provided
+    let burn_ixs = confidential_burn_with_split_proofs(
+        &spl_token_2022::id(),
+        &user_ata,
+        &payload.token_mint,
+        &new_decryptable_available_balance.into(),
+        &ciphertext_validity_proof_data_with_ciphertext.ciphertext_lo,
+        &ciphertext_validity_proof_data_with_ciphertext.ciphertext_hi,
+        &payload.authority,
+        &[],
+        ProofLocation::ContextStateAccount(&payload.equality_proof_keypair.pubkey()),
+        ProofLocation::ContextStateAccount(&payload.ciphertext_validity_proof_keypair.pubkey()),
+        ProofLocation::ContextStateAccount(&payload.range_proof_keypair.pubkey()),
+    )
+    .unwrap();
+
+    proof_transactions_response(
+        &payload.authority,
+        proof_ixs,
+        burn_ixs,
+        [
+            &payload.equality_proof_keypair,
+            &payload.ciphertext_validity_proof_keypair,
+            &payload.range_proof_keypair,
+        ],
+    )
+}
+
+/// Handler which is used by the holder of the supply keys of a mint to decrypt its confidential
+/// supply
+pub async fn confidential_supply(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ConfidentialSupply>,
+) -> impl IntoResponse {
+    let (supply_elgamal_key, supply_ae_key) = match supply_keys(
+        &payload.authority,
+        &payload.token_mint,
+        payload.supply_elgamal_signature,
+        payload.supply_ae_signature,
+    ) {
+        Ok(supply_keys) => supply_keys,
+        Err(response) => return response,
+    };
+
+    let token_mint = match state.rpc.get_account(&payload.token_mint).await {
+        Ok(token_mint) => token_mint,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: format!("failed to get token mint {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    let token_mint = match StateWithExtensions::<Mint>::unpack(&token_mint.data) {
+        Ok(token_mint) => token_mint,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to unpack token mint {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    let decimals = token_mint.base.decimals;
+
+    let (extension, _) = match mint_burn_extension(&token_mint, &supply_elgamal_key) {
+        Ok(extension) => extension,
+        Err(response) => return response,
+    };
+
+    // decrypt the supply using the precomputed discrete log table,
+    // this is cpu bound so it is moved off the async runtime
+    let discrete_log = state.discrete_log.clone();
+    let amounts = tokio::task::spawn_blocking(move || {
+        let supply = decrypt_supply(
+            &discrete_log,
+            &supply_elgamal_key,
+            &supply_ae_key,
+            &apply_pending_burn(&extension)?,
+        )?;
+        let pending_burn = decrypt_pending_burn(&discrete_log, &supply_elgamal_key, &extension)?;
+        anyhow::Ok((supply, pending_burn))
+    })
+    .await;
+    let (supply, pending_burn) = match amounts {
+        Ok(Ok(amounts)) => amounts,
+        Ok(Err(err)) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("{err:#}"),
+                }),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to decrypt supply {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    (
+        StatusCode::OK,
+        Json(ApiConfidentialSupplyResponse {
+            supply: spl_token_2022::amount_to_ui_amount(supply, decimals),
+            pending_burn: spl_token_2022::amount_to_ui_amount(pending_burn, decimals),
+            supply_amount: supply,
+            pending_burn_amount: pending_burn,
+            decimals,
+        }),
+    )
+        .into_response()
+}
+
+/// Verifies the supply signatures of `authority`, and derives the supply keys of `token_mint`
+#[allow(clippy::result_large_err)]
+fn supply_keys(
+    authority: &Pubkey,
+    token_mint: &Pubkey,
+    supply_elgamal_signature: Signature,
+    supply_ae_signature: Signature,
+) -> Result<(ElGamalKeypair, AeKey), Response> {
+    // verify supply elgamal signature
+    if !supply_elgamal_signature.verify(
+        &authority.to_bytes(),
+        &KeypairType::SupplyElGamal.message_to_sign(*token_mint),
+    ) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify supply elgamal signature".to_string(),
+            }),
+        )
+            .into_response());
+    }
+
+    // verify supply ae signature
+    if !supply_ae_signature.verify(
+        &authority.to_bytes(),
+        &KeypairType::SupplyAe.message_to_sign(*token_mint),
+    ) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify supply ae signature".to_string(),
+            }),
+        )
+            .into_response());
+    }
+
+    let (Ok(supply_elgamal_key), Ok(supply_ae_key)) = (
+        derive_elgamal_key(supply_elgamal_signature),
+        derive_ae_key(supply_ae_signature),
+    ) else {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to derive supply keys".to_string(),
+            }),
+        )
+            .into_response());
+    };
+    Ok((supply_elgamal_key, supply_ae_key))
+}
+
+/// Returns the confidential mint burn extension of a mint whose supply is encrypted for
+/// `supply_elgamal_key`, along with the auditor pubkey of the mint
+#[allow(clippy::result_large_err)]
+fn mint_burn_extension(
+    token_mint: &StateWithExtensions<'_, Mint>,
+    supply_elgamal_key: &ElGamalKeypair,
+) -> Result<(ConfidentialMintBurn, Option<ElGamalPubkey>), Response> {
+    let Ok(extension) = token_mint.get_extension::<ConfidentialMintBurn>() else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token mint does not have a confidential supply".to_string(),
+            }),
+        )
+            .into_response());
+    };
+
+    // the supply can only be decrypted with the key registered in the mint
+    if PodElGamalPubkey::from(*supply_elgamal_key.pubkey()) != extension.supply_elgamal_pubkey {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "supply elgamal signature does not match the supply elgamal pubkey of the token mint".to_string(),
+            }),
+        )
+            .into_response());
+    }
+
+    let Ok(auditor_pubkey) = auditor_pubkey(token_mint) else {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to parse auditor pubkey".to_string(),
+            }),
+        )
+            .into_response());
+    };
+    Ok((*extension, auditor_pubkey))
+}
+
+/// Returns the auditor pubkey of a confidential mint, if it has one
+fn auditor_pubkey(token_mint: &StateWithExtensions<'_, Mint>) -> Result<Option<ElGamalPubkey>, ()> {
+    let extension = token_mint
+        .get_extension::<ConfidentialTransferMint>()
+        .map_err(|_| ())?;
+    Option::<PodElGamalPubkey>::from(extension.auditor_elgamal_pubkey)
+        .map(TryInto::<ElGamalPubkey>::try_into)
+        .transpose()
+        .map_err(|_| ())
+}
+
+/// Packs the transactions which create and verify the proof accounts, run `ixs` consuming the
+/// proofs, and close the proof accounts
+fn proof_transactions_response(
+    authority: &Pubkey,
+    proof_ixs: Vec<(Instruction, Instruction)>,
+    ixs: Vec<Instruction>,
+    proof_keypairs: [&Keypair; 3],
+) -> Response {
+    let (create_ixs, verify_ixs): (Vec<_>, Vec<_>) = proof_ixs.into_iter().unzip();
+    let close_proof_ixs = proof_keypairs.map(|keypair| {
+        close_context_state(
+            ContextStateInfo {
+                context_state_account: &keypair.pubkey(),
+                context_state_authority: authority,
+            },
+            authority,
+        )
+    });
+
+    let mut groups = vec![create_ixs];
+    groups.extend(verify_ixs.into_iter().map(|verify_ix| vec![verify_ix]));
+    groups.push(ixs);
+    groups.push(close_proof_ixs.to_vec());
+    let txs = match pack_instructions(groups, authority) {
+        Ok(txs) => txs,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to pack transactions {err:#}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let txs = match encode_transactions(&txs) {
+        Ok(txs) => txs,
+        Err(response) => return response,
+    };
+    (
+        StatusCode::OK,
+        Json(ApiTransactionResponse { transactions: txs }),
+    )
+        .into_response()
+}
@@ -1,18 +1,47 @@
 //! handlers for the confidential blink api
 pub mod apply;
+pub mod approvals;
 pub mod balances;
+pub mod close_account;
+pub mod create_wrapped_mint;
+pub mod credits;
 pub mod deposit;
+pub mod history;
+pub mod indexer;
 pub mod initialize;
+pub mod list_wrapped_mints;
+pub mod mint_burn;
+pub mod notifications;
+pub mod portfolio;
+pub mod shield;
+mod transactions;
 pub mod transfer;
+pub mod unshield;
 pub mod unwrap_tokens;
 pub mod withdraw;
+pub mod withheld_fees;
 pub mod wrap_tokens;
+pub mod wrapped_mint_info;
 
 pub use apply::*;
+pub use approvals::*;
 pub use balances::*;
+pub use close_account::*;
+pub use create_wrapped_mint::*;
+pub use credits::*;
 pub use deposit::*;
+pub use history::*;
+pub use indexer::*;
 pub use initialize::*;
+pub use list_wrapped_mints::*;
+pub use mint_burn::*;
+pub use notifications::*;
+pub use portfolio::*;
+pub use shield::*;
 pub use transfer::*;
+pub use unshield::*;
 pub use unwrap_tokens::*;
 pub use withdraw::*;
+pub use withheld_fees::*;
 pub use wrap_tokens::*;
+pub use wrapped_mint_info::*;
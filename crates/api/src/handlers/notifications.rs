@@ -0,0 +1,225 @@
+use {
+    crate::{
+        notifications::{BalanceWatcher, Credentials},
+        router::AppState,
+        types::{ApiError, Notifications},
+    },
+    axum::{
+        extract::State,
+        response::{
+            sse::{Event, KeepAlive, Sse},
+            IntoResponse,
+        },
+        Json,
+    },
+    common::{
+        accounts::token_account_already_configured,
+        key_generator::{derive_ae_key, derive_elgamal_key, KeypairType},
+    },
+    http::StatusCode,
+    std::sync::Arc,
+};
+
+/// Handler which streams balance notifications of the confidential token account as server sent events
+pub async fn notifications(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<Notifications>,
+) -> impl IntoResponse {
+    // derive the ATA for the authority + token_mint
+    let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &payload.authority,
+        &payload.token_mint,
+        &spl_token_2022::id(),
+    );
+
+    // credentials are optional, without them notifications do not include decrypted balances
+    let credentials = match (payload.elgamal_signature, payload.ae_signature) {
+        (Some(elgamal_signature), Some(ae_signature)) => {
+            // verify elgamal signature
+            if !elgamal_signature.verify(
+                &payload.authority.to_bytes(),
+                &KeypairType::ElGamal.message_to_sign(user_ata),
+            ) {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiError {
+                        msg: "failed to verify elgamal signature".to_string(),
+                    }),
+                )
+                    .into_response();
+            }
+
+            // verify ae signature
+            if !ae_signature.verify(
+                &payload.authority.to_bytes(),
+                &KeypairType::Ae.message_to_sign(user_ata),
+            ) {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiError {
+                        msg: "failed to verify ae signature".to_string(),
+                    }),
+                )
+                    .into_response();
+            }
+
+            // derive the elgamal keypair
+            let Ok(elgamal_key) = derive_elgamal_key(elgamal_signature) else {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: "failed to derive elgamal keypair".to_string(),
+                    }),
+                )
+                    .into_response();
+            };
+
+            // derive the ae keypair
+            let Ok(ae_key) = derive_ae_key(ae_signature) else {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: "failed to derive ae keypair".to_string(),
+                    }),
+                )
+                    .into_response();
+            };
+
+            Some(Credentials {
+                elgamal_secret: elgamal_key.secret().clone(),
+                ae_key,
+            })
+        }
+        (None, None) => None,
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: "elgamal_signature and ae_signature must be provided together".to_string(),
+                }),
+            )
+                .into_response();
+        }
+    };
+
+    // lookup the ata account
+    let mut accounts = state
+        .rpc
+        .get_multiple_accounts(&[user_ata])
+        .await
+        .unwrap_or_default();
+
+    // if no accounts are returned, this means the rpc call failed
+    if accounts.is_empty() {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query accounts".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure token account exists
+    let Some(token_account) = std::mem::take(&mut accounts[0]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token account does not exist".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure token account is configured for confidential transfers
+    if !token_account_already_configured(&token_account) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token account is not configured for confidential transfers".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // the number of open subscriptions is limited, as they all share one websocket
+    let Some(permit) = state.subscriptions.try_reserve() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiError {
+                msg: "too many notification subscriptions".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let client = match state.subscriptions.client().await {
+        Ok(client) => client,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("{err:#}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    // the subscription is closed once the client disconnects and the stream is dropped
+    let notifications = match BalanceWatcher::new(
+        client.clone(),
+        user_ata,
+        credentials,
+        state.discrete_log.clone(),
+    )
+    .subscribe(token_account)
+    .await
+    {
+        Ok(notifications) => notifications,
+        Err(err) => {
+            // the websocket may have been closed, reconnect on the next subscription
+            state.subscriptions.reset(&client).await;
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to subscribe to token account {err:#}"),
+                }),
+            )
+                .into_response();
+        }
+    };
+
+    // the permit is held by the stream, releasing the subscription once it is dropped
+    let events = futures::stream::unfold(Some((notifications, permit)), move |subscription| {
+        let state = state.clone();
+        let client = client.clone();
+        async move {
+            let (mut notifications, permit) = subscription?;
+            match notifications.recv().await {
+                Some(notification) => Some((
+                    Event::default()
+                        .event(notification.kind.as_str())
+                        .json_data(&notification),
+                    Some((notifications, permit)),
+                )),
+                None => {
+                    // the websocket was closed, reconnect on the next subscription and tell
+                    // the client to subscribe again
+                    state.subscriptions.reset(&client).await;
+                    Some((
+                        Event::default().event("error").json_data(ApiError {
+                            msg: "subscription closed, reconnect to resume notifications"
+                                .to_string(),
+                        }),
+                        None,
+                    ))
+                }
+            }
+        }
+    });
+
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
@@ -0,0 +1,348 @@
+use {
+    crate::{
+        router::AppState,
+        types::{ApiError, ApiPortfolioResponse, ApiPortfolioToken, Portfolio},
+    },
+    anyhow::{anyhow, Result},
+    axum::{extract::State, response::IntoResponse, Json},
+    common::{
+        accounts::{
+            decrypt_available_balance, decrypt_pending_balance, unwrapped_mint_from_backpointer,
+        },
+        key_generator::{derive_ae_key, derive_elgamal_key, KeypairType},
+    },
+    http::StatusCode,
+    solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::TokenAccountsFilter},
+    solana_sdk::{account::Account, pubkey::Pubkey},
+    spl_token_2022::extension::{
+        confidential_transfer::ConfidentialTransferAccount, BaseStateWithExtensions,
+        StateWithExtensions,
+    },
+    spl_token_wrap::get_wrapped_mint_backpointer_address,
+    std::{collections::BTreeMap, str::FromStr, sync::Arc},
+};
+
+/// Maximum number of accounts which can be requested with a single `getMultipleAccounts` call
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// A confidential token account owned by the authority
+struct ConfidentialTokenAccount {
+    address: Pubkey,
+    mint: Pubkey,
+    non_confidential_balance: u64,
+    extension: ConfidentialTransferAccount,
+}
+
+/// Handler which is used to display the balances of every confidential token account owned by the authority
+pub async fn portfolio(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<Portfolio>,
+) -> impl IntoResponse {
+    // verify elgamal signature
+    if !payload.elgamal_signature.verify(
+        &payload.authority.to_bytes(),
+        &KeypairType::ElGamal.message_to_sign(payload.authority),
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify elgamal signature".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // verify ae signature
+    if !payload.ae_signature.verify(
+        &payload.authority.to_bytes(),
+        &KeypairType::Ae.message_to_sign(payload.authority),
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify ae signature".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // derive the elgamal keypair
+    let Ok(elgamal_key) = derive_elgamal_key(payload.elgamal_signature) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to derive elgamal keypair".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // derive the ae keypair
+    let Ok(ae_key) = derive_ae_key(payload.ae_signature) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to derive ae keypair".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // find all token accounts owned by the authority
+    let keyed_accounts = match state
+        .rpc
+        .get_token_accounts_by_owner(
+            &payload.authority,
+            TokenAccountsFilter::ProgramId(spl_token_2022::id()),
+        )
+        .await
+    {
+        Ok(keyed_accounts) => keyed_accounts,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to query token accounts {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let mut addresses = Vec::with_capacity(keyed_accounts.len());
+    for keyed_account in keyed_accounts {
+        match Pubkey::from_str(&keyed_account.pubkey) {
+            Ok(address) => addresses.push(address),
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to parse token account address {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        }
+    }
+
+    // the token accounts are returned json parsed, so fetch the raw account data
+    let Some(accounts) = get_multiple_accounts(&state.rpc, &addresses).await else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query accounts".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // keep only the token accounts which are configured for confidential transfers
+    let token_accounts = addresses
+        .into_iter()
+        .zip(accounts)
+        .filter_map(|(address, account)| {
+            let account = account?;
+            let token_account =
+                StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+                    .ok()?;
+            let extension = *token_account
+                .get_extension::<ConfidentialTransferAccount>()
+                .ok()?;
+            Some(ConfidentialTokenAccount {
+                address,
+                mint: token_account.base.mint,
+                non_confidential_balance: token_account.base.amount,
+                extension,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // decrypt the balances of every token account, this is cpu bound so it is moved off the async runtime
+    let discrete_log = state.discrete_log.clone();
+    let elgamal_secret = elgamal_key.secret().clone();
+    let decrypted = tokio::task::spawn_blocking(move || -> Result<_> {
+        let mut tokens: BTreeMap<Pubkey, ApiPortfolioToken> = BTreeMap::new();
+        for token_account in token_accounts {
+            // a balance which can't be decrypted is counted as zero, and its account flagged
+            let mut decrypted = true;
+            let pending_balance =
+                decrypt_pending_balance(&discrete_log, &elgamal_secret, &token_account.extension)
+                    .unwrap_or_else(|err| {
+                        log::warn!("{} {err:#}", token_account.address);
+                        decrypted = false;
+                        0
+                    });
+            let available_balance = decrypt_available_balance(&ae_key, &token_account.extension)
+                .unwrap_or_else(|err| {
+                    log::warn!("{} {err:#}", token_account.address);
+                    decrypted = false;
+                    0
+                });
+
+            let token = tokens
+                .entry(token_account.mint)
+                .or_insert_with(|| ApiPortfolioToken {
+                    token_mint: token_account.mint,
+                    unwrapped_token_mint: None,
+                    token_accounts: vec![],
+                    decimals: 0,
+                    pending_balance: 0.0,
+                    available_balance: 0.0,
+                    non_confidential_balance: 0.0,
+                    total_balance: 0.0,
+                    pending_balance_amount: 0,
+                    available_balance_amount: 0,
+                    non_confidential_balance_amount: 0,
+                    total_balance_amount: 0,
+                    unapproved_token_accounts: vec![],
+                    undecryptable_token_accounts: vec![],
+                });
+            token.token_accounts.push(token_account.address.to_string());
+            if !bool::from(token_account.extension.approved) {
+                token
+                    .unapproved_token_accounts
+                    .push(token_account.address.to_string());
+            }
+            if !decrypted {
+                token
+                    .undecryptable_token_accounts
+                    .push(token_account.address.to_string());
+            }
+            add(&mut token.pending_balance_amount, pending_balance)?;
+            add(&mut token.available_balance_amount, available_balance)?;
+            add(
+                &mut token.non_confidential_balance_amount,
+                token_account.non_confidential_balance,
+            )?;
+        }
+        Ok(tokens)
+    })
+    .await;
+
+    let mut tokens = match decrypted {
+        Ok(Ok(tokens)) => tokens,
+        Ok(Err(err)) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to total balances {err:#}"),
+                }),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to decrypt balances {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    // lookup the token mint, and the token wrap backpointer of every mint
+    let mint_addresses = tokens
+        .keys()
+        .flat_map(|mint| [*mint, get_wrapped_mint_backpointer_address(mint)])
+        .collect::<Vec<_>>();
+    let Some(mint_accounts) = get_multiple_accounts(&state.rpc, &mint_addresses).await else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query token mints".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    for (token, accounts) in tokens.values_mut().zip(mint_accounts.chunks(2)) {
+        let Some(token_mint) = &accounts[0] else {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("token mint {} does not exist", token.token_mint),
+                }),
+            )
+                .into_response();
+        };
+        token.decimals =
+            match StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&token_mint.data) {
+                Ok(mint) => mint.base.decimals,
+                Err(err) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiError {
+                            msg: format!("failed to unpack token mint {err:#?}"),
+                        }),
+                    )
+                        .into_response()
+                }
+            };
+        token.unwrapped_token_mint = accounts[1].as_ref().and_then(|backpointer| {
+            unwrapped_mint_from_backpointer(&token.token_mint, backpointer)
+        });
+
+        let mut total_balance_amount = token.pending_balance_amount;
+        let total = add(&mut total_balance_amount, token.available_balance_amount).and_then(|()| {
+            add(
+                &mut total_balance_amount,
+                token.non_confidential_balance_amount,
+            )
+        });
+        if let Err(err) = total {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to total balances {err:#}"),
+                }),
+            )
+                .into_response();
+        }
+        token.total_balance_amount = total_balance_amount;
+        token.pending_balance =
+            spl_token_2022::amount_to_ui_amount(token.pending_balance_amount, token.decimals);
+        token.available_balance =
+            spl_token_2022::amount_to_ui_amount(token.available_balance_amount, token.decimals);
+        token.non_confidential_balance = spl_token_2022::amount_to_ui_amount(
+            token.non_confidential_balance_amount,
+            token.decimals,
+        );
+        token.total_balance =
+            spl_token_2022::amount_to_ui_amount(token.total_balance_amount, token.decimals);
+    }
+
+    (
+        StatusCode::OK,
+        Json(ApiPortfolioResponse {
+            tokens: tokens.into_values().collect(),
+        }),
+    )
+        .into_response()
+}
+
+/// Adds `amount` to a balance total, failing instead of overflowing
+fn add(total: &mut u64, amount: u64) -> Result<()> {
+    *total = total
+        .checked_add(amount)
+        .ok_or_else(|| anyhow!("total overflows adding {amount} to {total}"))?;
+    Ok(())
+}
+
+/// Fetches the given accounts, splitting the request to stay within the rpc limits
+///
+/// Returns `None` if any of the rpc calls fail
+pub(crate) async fn get_multiple_accounts(
+    rpc: &RpcClient,
+    addresses: &[Pubkey],
+) -> Option<Vec<Option<Account>>> {
+    let mut accounts = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let mut chunk_accounts = rpc.get_multiple_accounts(chunk).await.ok()?;
+        if chunk_accounts.len() < chunk.len() {
+            return None;
+        }
+        accounts.append(&mut chunk_accounts);
+    }
+    Some(accounts)
+}
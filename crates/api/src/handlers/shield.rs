@@ -0,0 +1,421 @@
+use {
+    super::transactions::encode_transactions,
+    crate::{
+        handlers::wrap_tokens::{
+            add_transfer_hook_accounts, validate_wrap_request, wrap_instruction,
+        },
+        router::AppState,
+        types::{ApiError, ApiTransactionResponse, Shield, WrapTokens},
+    },
+    axum::{extract::State, response::IntoResponse, Json},
+    common::{
+        accounts::{
+            decrypt_available_balance, decrypt_pending_balance, token_account_already_configured,
+            DEFAULT_MAXIMUM_PENDING_BALANCE_CREDIT_COUNTER,
+        },
+        key_generator::{derive_ae_key, derive_elgamal_key, KeypairType},
+        transactions::{configure_account_instructions, pack_instructions},
+        wrapped_mint::escrow_address,
+    },
+    http::StatusCode,
+    solana_sdk::transaction::Transaction,
+    spl_token_2022::{
+        extension::{
+            confidential_transfer::{
+                instruction::{apply_pending_balance, deposit},
+                ConfidentialTransferAccount, ConfidentialTransferMint,
+            },
+            BaseStateWithExtensions, StateWithExtensions,
+        },
+        state::{Account, Mint},
+    },
+    spl_token_wrap::get_wrapped_mint_address,
+    std::sync::Arc,
+};
+
+/// Handler which is used to wrap unwrapped tokens and move them into the confidential balance
+///
+/// The wrapped mint ATA is created and configured if needed, the wrap and deposit are packed
+/// into as few transactions as possible and are followed by the apply transaction
+pub async fn shield(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<Shield>,
+) -> impl IntoResponse {
+    let wrapped_mint =
+        get_wrapped_mint_address(&payload.unwrapped_token_mint, &spl_token_2022::id());
+
+    // derive the ATA for the authority + wrapped mint
+    let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &payload.authority,
+        &wrapped_mint,
+        &spl_token_2022::id(),
+    );
+
+    // verify elgamal signature
+    if !payload.elgamal_signature.verify(
+        &payload.authority.to_bytes(),
+        &KeypairType::ElGamal.message_to_sign(user_ata),
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify elgamal signature".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // verify ae signature
+    if !payload.ae_signature.verify(
+        &payload.authority.to_bytes(),
+        &KeypairType::Ae.message_to_sign(user_ata),
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify ae signature".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // derive the elgamal keypair
+    let Ok(elgamal_key) = derive_elgamal_key(payload.elgamal_signature) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to derive elgamal keypair".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // derive the ae keypair
+    let Ok(ae_key) = derive_ae_key(payload.ae_signature) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to derive ae keypair".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // lookup the wrapped mint and ata accounts
+    let mut accounts = state
+        .rpc
+        .get_multiple_accounts(&[wrapped_mint, user_ata])
+        .await
+        .unwrap_or_default();
+
+    // if less than 2 accounts is returned, this means the rpc call failed
+    if accounts.len() < 2 {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query accounts".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure the wrapped mint account exists
+    let Some(token_mint) = std::mem::take(&mut accounts[0]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "wrapped mint does not exist".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure the wrapped mint is valid for confidential transfers
+    if !common::accounts::is_valid_mint(&token_mint) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "wrapped mint does not support confidential transfers".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // get the wrapped mint decimals, and whether new accounts are approved automatically
+    let (decimals, auto_approve_new_accounts) =
+        match StateWithExtensions::<Mint>::unpack(&token_mint.data) {
+            Ok(mint) => match mint.get_extension::<ConfidentialTransferMint>() {
+                Ok(extension) => (
+                    mint.base.decimals,
+                    bool::from(extension.auto_approve_new_accounts),
+                ),
+                Err(err) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiError {
+                            msg: format!(
+                                "failed to get confidential transfer mint extension {err:#?}"
+                            ),
+                        }),
+                    )
+                        .into_response()
+                }
+            },
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to unpack wrapped mint {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+
+    // validate the wrap of the unwrapped tokens held by the unwrapped mint ATA, only the amount
+    // left after the transfer fee of the unwrapped mint is wrapped, and deposited
+    let unwrapped_user_ata =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            &payload.authority,
+            &payload.unwrapped_token_mint,
+            &payload.unwrapped_token_program,
+        );
+    let wrap_request = WrapTokens {
+        authority: payload.authority,
+        unwrapped_token_mint: payload.unwrapped_token_mint,
+        wrapped_token_mint: wrapped_mint,
+        unwrapped_token_program: payload.unwrapped_token_program,
+        amount: payload.amount,
+    };
+    let transfer = match validate_wrap_request(&state, &wrap_request, unwrapped_user_ata, 0).await {
+        Ok(transfer) => transfer,
+        Err(response) => return response,
+    };
+
+    let mut groups = vec![];
+
+    // the confidential balance, and credit counter before the deposit
+    let (confidential_balance, pending_balance_credit_counter) = match std::mem::take(
+        &mut accounts[1],
+    ) {
+        Some(token_account) if token_account_already_configured(&token_account) => {
+            let token_account = match StateWithExtensions::<Account>::unpack(&token_account.data) {
+                Ok(token_account) => token_account,
+                Err(err) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiError {
+                            msg: format!("failed to unpack token account {err:#?}"),
+                        }),
+                    )
+                        .into_response()
+                }
+            };
+            let extension = match token_account.get_extension::<ConfidentialTransferAccount>() {
+                Ok(extension) => *extension,
+                Err(err) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiError {
+                            msg: format!(
+                                "failed to get confidential transfer account extension {err:#?}"
+                            ),
+                        }),
+                    )
+                        .into_response()
+                }
+            };
+            if !bool::from(extension.approved) {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiError {
+                        msg: "token account must be approved before tokens can be shielded"
+                            .to_string(),
+                    }),
+                )
+                    .into_response();
+            }
+            let pending_balance_credit_counter =
+                u64::from(extension.pending_balance_credit_counter);
+            if pending_balance_credit_counter
+                >= u64::from(extension.maximum_pending_balance_credit_counter)
+            {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiError {
+                        msg: "maximum pending balance credit counter reached, apply the pending balance first".to_string(),
+                    }),
+                )
+                    .into_response();
+            }
+
+            // decrypt the pending balance using the precomputed discrete log table,
+            // this is cpu bound so it is moved off the async runtime
+            let discrete_log = state.discrete_log.clone();
+            let elgamal_secret = elgamal_key.secret().clone();
+            let decrypted = tokio::task::spawn_blocking(move || {
+                decrypt_pending_balance(&discrete_log, &elgamal_secret, &extension)
+            })
+            .await;
+            let pending_balance = match decrypted {
+                Ok(Ok(pending_balance)) => pending_balance,
+                Ok(Err(err)) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiError {
+                            msg: format!("{err:#}"),
+                        }),
+                    )
+                        .into_response()
+                }
+                Err(err) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiError {
+                            msg: format!("failed to decrypt pending balance {err:#?}"),
+                        }),
+                    )
+                        .into_response()
+                }
+            };
+            let available_balance = match decrypt_available_balance(&ae_key, &extension) {
+                Ok(available_balance) => available_balance,
+                Err(err) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiError {
+                            msg: format!("{err:#}"),
+                        }),
+                    )
+                        .into_response()
+                }
+            };
+            (
+                available_balance.checked_add(pending_balance),
+                pending_balance_credit_counter,
+            )
+        }
+        _ => {
+            // accounts created here can't deposit until they are approved
+            if !auto_approve_new_accounts {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiError {
+                        msg: "wrapped mint requires token accounts to be approved, initialize the token account and request approval before shielding".to_string(),
+                    }),
+                )
+                    .into_response();
+            }
+            match configure_account_instructions(
+                &payload.authority,
+                &wrapped_mint,
+                &elgamal_key,
+                &ae_key,
+                DEFAULT_MAXIMUM_PENDING_BALANCE_CREDIT_COUNTER,
+                // wrapped mints are created without a transfer fee
+                false,
+            ) {
+                Ok(instructions) => groups.push(instructions),
+                Err(err) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiError {
+                            msg: format!("{err:#}"),
+                        }),
+                    )
+                        .into_response()
+                }
+            }
+            (Some(0), 0)
+        }
+    };
+
+    let Some(new_confidential_balance) =
+        confidential_balance.and_then(|balance| balance.checked_add(transfer.received_amount))
+    else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "confidential balance overflows".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let mut wrap_ix = wrap_instruction(
+        &payload.authority,
+        &payload.unwrapped_token_mint,
+        &payload.unwrapped_token_program,
+        &wrapped_mint,
+        payload.amount,
+    );
+    if transfer.transfer_hook_program.is_some() {
+        if let Err(response) = add_transfer_hook_accounts(
+            &state,
+            &mut wrap_ix,
+            &unwrapped_user_ata,
+            &payload.unwrapped_token_mint,
+            &escrow_address(
+                &payload.unwrapped_token_mint,
+                &payload.unwrapped_token_program,
+            ),
+            &payload.authority,
+            payload.amount,
+        )
+        .await
+        {
+            return response;
+        }
+    }
+    groups.push(vec![wrap_ix]);
+    // deposit can only fail if the incorrect token program is provided
+    groups.push(vec![deposit(
+        &spl_token_2022::id(),
+        &user_ata,
+        &wrapped_mint,
+        transfer.received_amount,
+        decimals,
+        &payload.authority,
+        &[],
+    )
+    .unwrap()]);
+
+    let mut txs = match pack_instructions(groups, &payload.authority) {
+        Ok(txs) => txs,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to pack transactions {err:#}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    // the deposit increments the credit counter, which the apply must account for
+    // can only fail if incorrect token program is provided
+    let apply_ix = apply_pending_balance(
+        &spl_token_2022::id(),
+        &user_ata,
+        pending_balance_credit_counter + 1,
+        &ae_key.encrypt(new_confidential_balance).into(),
+        &payload.authority,
+        &[],
+    )
+    .unwrap();
+    txs.push(Transaction::new_with_payer(
+        &[apply_ix],
+        Some(&payload.authority),
+    ));
+
+    let txs = match encode_transactions(&txs) {
+        Ok(txs) => txs,
+        Err(response) => return response,
+    };
+    (
+        StatusCode::OK,
+        Json(ApiTransactionResponse { transactions: txs }),
+    )
+        .into_response()
+}
@@ -0,0 +1,75 @@
+//! Transaction building helpers shared by the handlers
+use {
+    crate::{router::AppState, types::ApiError},
+    axum::{
+        response::{IntoResponse, Response},
+        Json,
+    },
+    bytemuck::Pod,
+    common::proofs::get_zk_proof_context_state_account_creation_instructions,
+    http::StatusCode,
+    solana_sdk::{
+        instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer,
+        transaction::Transaction,
+    },
+    spl_token_2022::extension::confidential_transfer::instruction::{
+        ProofContextState, ZkProofData,
+    },
+};
+
+/// Encodes the transactions returned to the client, see
+/// [`common::transactions::encode_transactions`]
+#[allow(clippy::result_large_err)]
+pub(crate) fn encode_transactions(txs: &[Transaction]) -> Result<Vec<String>, Response> {
+    common::transactions::encode_transactions(txs).map_err(|err| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: format!("failed to encode transactions {err:#}"),
+            }),
+        )
+            .into_response()
+    })
+}
+
+/// Returns the instructions which create the context state account of `keypair`, and verify
+/// `proof_data` into it
+pub(crate) async fn proof_context_instructions<ZK: Pod + ZkProofData<U>, U: Pod>(
+    state: &AppState,
+    authority: &Pubkey,
+    keypair: &Keypair,
+    proof_data: &ZK,
+) -> Result<(Instruction, Instruction), Response> {
+    let rent = match state
+        .rpc
+        .get_minimum_balance_for_rent_exemption(std::mem::size_of::<ProofContextState<U>>())
+        .await
+    {
+        Ok(rent) => rent,
+        Err(err) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to get proof rent {err:#?}"),
+                }),
+            )
+                .into_response())
+        }
+    };
+    get_zk_proof_context_state_account_creation_instructions(
+        authority,
+        &keypair.pubkey(),
+        authority,
+        proof_data,
+        rent,
+    )
+    .map_err(|err| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: format!("failed to create proof instructions {err:#?}"),
+            }),
+        )
+            .into_response()
+    })
+}
@@ -1,14 +1,19 @@
 use {
+    super::transactions::{encode_transactions, proof_context_instructions},
     crate::{
         router::AppState,
-        types::{ApiError, ApiTransactionResponse, Transfer},
+        types::{ApiError, ApiTransferResponse, Transfer},
+    },
+    axum::{
+        extract::State,
+        response::{IntoResponse, Response},
+        Json,
     },
-    axum::{extract::State, response::IntoResponse, Json},
-    base64::{prelude::BASE64_STANDARD, Engine},
     common::{
-        accounts::token_account_already_configured,
+        accounts::{requires_incoming_transfer_memos, token_account_already_configured},
         key_generator::{derive_ae_key, derive_elgamal_key, KeypairType},
         proofs::get_zk_proof_context_state_account_creation_instructions,
+        transactions::{pack_instructions, with_memo},
     },
     http::StatusCode,
     solana_sdk::{signer::Signer, transaction::Transaction},
@@ -17,24 +22,37 @@ use {
             confidential_transfer::{
                 account_info::TransferAccountInfo,
                 instruction::{
-                    BatchedGroupedCiphertext3HandlesValidityProofContext, BatchedRangeProofContext,
-                    CiphertextCommitmentEqualityProofContext, ProofContextState,
+                    transfer_with_fee, BatchedGroupedCiphertext3HandlesValidityProofContext,
+                    BatchedRangeProofContext, CiphertextCommitmentEqualityProofContext,
+                    ProofContextState,
                 },
                 ConfidentialTransferAccount, ConfidentialTransferMint,
             },
+            confidential_transfer_fee::ConfidentialTransferFeeConfig,
+            transfer_fee::TransferFeeConfig,
             BaseStateWithExtensions, StateWithExtensions,
         },
         solana_zk_sdk::{
-            encryption::{elgamal::ElGamalPubkey, pod::elgamal::PodElGamalPubkey},
+            encryption::{
+                auth_encryption::AeKey,
+                elgamal::{ElGamalKeypair, ElGamalPubkey},
+                pod::elgamal::PodElGamalPubkey,
+            },
             zk_elgamal_proof_program::instruction::{close_context_state, ContextStateInfo},
         },
+        state::Mint,
     },
     spl_token_confidential_transfer_proof_extraction::instruction::ProofLocation,
-    spl_token_confidential_transfer_proof_generation::transfer::TransferProofData,
+    spl_token_confidential_transfer_proof_generation::{
+        transfer::TransferProofData, transfer_with_fee::TransferWithFeeProofData,
+    },
     std::sync::Arc,
 };
 
 /// Handler which is used to transfer confidential balance
+///
+/// Mints with a transfer fee use the transfer with fee proofs, and the fee is reported in the
+/// response
 pub async fn transfer(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<Transfer>,
@@ -179,6 +197,17 @@ pub async fn transfer(
             .into_response();
     }
 
+    // the token program rejects transfers to these accounts unless a memo precedes them
+    if payload.memo.is_none() && requires_incoming_transfer_memos(&receiving_token_account) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "receiving token account requires a memo for incoming transfers".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
     // ensure the token mint is valid for confidential transfers
     if !common::accounts::is_valid_mint(&token_mint) {
         return (
@@ -191,19 +220,18 @@ pub async fn transfer(
     }
 
     // get the token mint decimals
-    let mint_account =
-        match StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&token_mint.data) {
-            Ok(mint) => mint,
-            Err(err) => {
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ApiError {
-                        msg: format!("failed to unpack token mint {err:#?}"),
-                    }),
-                )
-                    .into_response()
-            }
-        };
+    let mint_account = match StateWithExtensions::<Mint>::unpack(&token_mint.data) {
+        Ok(mint) => mint,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to unpack token mint {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
 
     let mint_extension = match mint_account.get_extension::<ConfidentialTransferMint>() {
         Ok(mint) => mint,
@@ -289,35 +317,42 @@ pub async fn transfer(
     };
 
     let sender_transfer_account = TransferAccountInfo::new(sender_confidential_transfer_account);
-    // bit awkward since split proof generation takes a type Option<&>
-    let transfer_proof_data = if let Some(auditor_pubkey) =
+    let Ok(auditor_pubkey) =
         Option::<PodElGamalPubkey>::from(mint_extension.auditor_elgamal_pubkey)
-    {
-        let Ok(auditor_pubkey) = TryInto::<ElGamalPubkey>::try_into(auditor_pubkey) else {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ApiError {
-                    msg: "failed to parse auditor pubkey".to_string(),
-                }),
-            )
-                .into_response();
-        };
-        sender_transfer_account.generate_split_transfer_proof_data(
-            payload.amount,
-            &elgamal_key,
-            &ae_key,
-            &destination_pubkey,
-            Some(&auditor_pubkey),
+            .map(TryInto::<ElGamalPubkey>::try_into)
+            .transpose()
+    else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to parse auditor pubkey".to_string(),
+            }),
         )
-    } else {
-        sender_transfer_account.generate_split_transfer_proof_data(
-            payload.amount,
+            .into_response();
+    };
+
+    // the token program rejects the plain transfer instruction for mints with a transfer fee
+    if mint_account.get_extension::<TransferFeeConfig>().is_ok() {
+        return transfer_with_fee_response(
+            &state,
+            &payload,
+            &mint_account,
+            &sender_transfer_account,
             &elgamal_key,
             &ae_key,
             &destination_pubkey,
-            None,
+            auditor_pubkey.as_ref(),
         )
-    };
+        .await;
+    }
+
+    let transfer_proof_data = sender_transfer_account.generate_split_transfer_proof_data(
+        payload.amount,
+        &elgamal_key,
+        &ae_key,
+        &destination_pubkey,
+        auditor_pubkey.as_ref(),
+    );
 
     let Ok(TransferProofData {
         equality_proof_data,
@@ -517,6 +552,19 @@ pub async fn transfer(
         )
         .unwrap();
 
+        let instructions =
+            match with_memo(payload.memo.as_deref(), &payload.authority, instructions) {
+                Ok(instructions) => instructions,
+                Err(err) => {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(ApiError {
+                            msg: format!("invalid memo {err:#}"),
+                        }),
+                    )
+                        .into_response()
+                }
+            };
         Transaction::new_with_payer(&instructions, Some(&payload.authority))
     };
 
@@ -559,23 +607,271 @@ pub async fn transfer(
             Some(&payload.authority),
         )
     };
-    let txs = [tx1, tx2, tx3, tx4, tx5]
-        .into_iter()
-        .filter_map(|tx| Some(BASE64_STANDARD.encode(bincode::serialize(&tx).ok()?)))
-        .collect::<Vec<_>>();
+    let txs = match encode_transactions(&[tx1, tx2, tx3, tx4, tx5]) {
+        Ok(txs) => txs,
+        Err(response) => return response,
+    };
+    (
+        StatusCode::OK,
+        Json(ApiTransferResponse {
+            transactions: txs,
+            transfer_fee_amount: 0,
+            received_amount: payload.amount,
+        }),
+    )
+        .into_response()
+}
 
-    if txs.len() != 5 {
+/// Builds the transactions of a confidential transfer for a mint with a transfer fee
+///
+/// Besides the proofs of a plain transfer, the fee is proven with a fee sigma (percentage with
+/// cap) proof and a fee ciphertext validity proof, each stored in its own context state account
+#[allow(clippy::too_many_arguments)]
+async fn transfer_with_fee_response(
+    state: &AppState,
+    payload: &Transfer,
+    mint: &StateWithExtensions<'_, Mint>,
+    sender_transfer_account: &TransferAccountInfo,
+    elgamal_key: &ElGamalKeypair,
+    ae_key: &AeKey,
+    destination_pubkey: &ElGamalPubkey,
+    auditor_pubkey: Option<&ElGamalPubkey>,
+) -> Response {
+    let (Some(fee_sigma_proof_keypair), Some(fee_ciphertext_validity_proof_keypair)) = (
+        &payload.fee_sigma_proof_keypair,
+        &payload.fee_ciphertext_validity_proof_keypair,
+    ) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "fee_sigma_proof_keypair and fee_ciphertext_validity_proof_keypair are required for mints with a transfer fee".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let (Ok(transfer_fee_config), Ok(confidential_transfer_fee_config)) = (
+        mint.get_extension::<TransferFeeConfig>(),
+        mint.get_extension::<ConfidentialTransferFeeConfig>(),
+    ) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token mint does not support confidential transfers with a fee".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // the withheld fee is encrypted for the withdraw withheld authority
+    let Ok(withdraw_withheld_authority_pubkey) = TryInto::<ElGamalPubkey>::try_into(
+        confidential_transfer_fee_config.withdraw_withheld_authority_elgamal_pubkey,
+    ) else {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ApiError {
-                msg: "failed to encode transactions".to_string(),
+                msg: "failed to parse withdraw withheld authority pubkey".to_string(),
             }),
         )
             .into_response();
+    };
+
+    // the fee proof must use the fee of the epoch the transfer is processed in
+    let epoch = match state.rpc.get_epoch_info().await {
+        Ok(epoch_info) => epoch_info.epoch,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to get epoch info {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    let transfer_fee = transfer_fee_config.get_epoch_fee(epoch);
+    let Some(transfer_fee_amount) = transfer_fee.calculate_fee(payload.amount) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to calculate transfer fee".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let Ok(TransferWithFeeProofData {
+        equality_proof_data,
+        transfer_amount_ciphertext_validity_proof_data_with_ciphertext,
+        percentage_with_cap_proof_data,
+        fee_ciphertext_validity_proof_data,
+        range_proof_data,
+    }) = sender_transfer_account.generate_split_transfer_with_fee_proof_data(
+        payload.amount,
+        elgamal_key,
+        ae_key,
+        destination_pubkey,
+        auditor_pubkey,
+        &withdraw_withheld_authority_pubkey,
+        transfer_fee.transfer_fee_basis_points.into(),
+        transfer_fee.maximum_fee.into(),
+    )
+    else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to generate split transfer with fee proof".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let mut create_ixs = vec![];
+    let mut verify_ixs = vec![];
+    for proof_ixs in [
+        proof_context_instructions(
+            state,
+            &payload.authority,
+            &payload.range_proof_keypair,
+            &range_proof_data,
+        )
+        .await,
+        proof_context_instructions(
+            state,
+            &payload.authority,
+            &payload.equality_proof_keypair,
+            &equality_proof_data,
+        )
+        .await,
+        proof_context_instructions(
+            state,
+            &payload.authority,
+            &payload.ciphertext_validity_proof_keypair,
+            &transfer_amount_ciphertext_validity_proof_data_with_ciphertext.proof_data,
+        )
+        .await,
+        proof_context_instructions(
+            state,
+            &payload.authority,
+            fee_sigma_proof_keypair,
+            &percentage_with_cap_proof_data,
+        )
+        .await,
+        proof_context_instructions(
+            state,
+            &payload.authority,
+            fee_ciphertext_validity_proof_keypair,
+            &fee_ciphertext_validity_proof_data,
+        )
+        .await,
+    ] {
+        match proof_ixs {
+            Ok((create_ix, verify_ix)) => {
+                create_ixs.push(create_ix);
+                verify_ixs.push(verify_ix);
+            }
+            Err(response) => return response,
+        }
     }
+
+    let Ok(new_decryptable_available_balance) =
+        sender_transfer_account.new_decryptable_available_balance(payload.amount, ae_key)
+    else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to encrypt available balance".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // can only fail if incorrect token program is provided
+    let transfer_ixs = transfer_with_fee(
+        &spl_token_2022::id(),
+        &spl_associated_token_account::get_associated_token_address_with_program_id(
+            &payload.authority,
+            &payload.token_mint,
+            &spl_token_2022::id(),
+        ),
+        &payload.token_mint,
+        &spl_associated_token_account::get_associated_token_address_with_program_id(
+            &payload.receiving_token_account,
+            &payload.token_mint,
+            &spl_token_2022::id(),
+        ),
+        &new_decryptable_available_balance.into(),
+        &transfer_amount_ciphertext_validity_proof_data_with_ciphertext.ciphertext_lo,
+        &transfer_amount_ciphertext_validity_proof_data_with_ciphertext.ciphertext_hi,
+        &payload.authority,
+        &[],
+        ProofLocation::ContextStateAccount(&payload.equality_proof_keypair.pubkey()),
+        ProofLocation::ContextStateAccount(&payload.ciphertext_validity_proof_keypair.pubkey()),
+        ProofLocation::ContextStateAccount(&fee_sigma_proof_keypair.pubkey()),
+        ProofLocation::ContextStateAccount(&fee_ciphertext_validity_proof_keypair.pubkey()),
+        ProofLocation::ContextStateAccount(&payload.range_proof_keypair.pubkey()),
+    )
+    .unwrap();
+
+    let close_proof_ixs = [
+        &payload.equality_proof_keypair,
+        &payload.ciphertext_validity_proof_keypair,
+        fee_sigma_proof_keypair,
+        fee_ciphertext_validity_proof_keypair,
+        &payload.range_proof_keypair,
+    ]
+    .map(|keypair| {
+        close_context_state(
+            ContextStateInfo {
+                context_state_account: &keypair.pubkey(),
+                context_state_authority: &payload.authority,
+            },
+            &payload.authority,
+        )
+    });
+
+    // the proof accounts are created first, each proof is verified into its account, and the
+    // accounts are closed once the transfer consumed them
+    let mut groups = vec![create_ixs];
+    groups.extend(verify_ixs.into_iter().map(|verify_ix| vec![verify_ix]));
+    let transfer_ixs = match with_memo(payload.memo.as_deref(), &payload.authority, transfer_ixs) {
+        Ok(instructions) => instructions,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: format!("invalid memo {err:#}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    groups.push(transfer_ixs);
+    groups.push(close_proof_ixs.to_vec());
+    let txs = match pack_instructions(groups, &payload.authority) {
+        Ok(txs) => txs,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to pack transactions {err:#}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let txs = match encode_transactions(&txs) {
+        Ok(txs) => txs,
+        Err(response) => return response,
+    };
     (
         StatusCode::OK,
-        Json(ApiTransactionResponse { transactions: txs }),
+        Json(ApiTransferResponse {
+            transactions: txs,
+            transfer_fee_amount,
+            received_amount: payload.amount.saturating_sub(transfer_fee_amount),
+        }),
     )
         .into_response()
 }
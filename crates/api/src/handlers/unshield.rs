@@ -0,0 +1,521 @@
+use {
+    super::transactions::encode_transactions,
+    crate::{
+        handlers::{
+            unwrap_tokens::unwrap_instructions,
+            wrap_tokens::{add_transfer_hook_accounts, validate_wrap_request},
+        },
+        router::AppState,
+        types::{ApiError, ApiTransactionResponse, Unshield, WrapTokens},
+    },
+    axum::{extract::State, response::IntoResponse, Json},
+    common::{
+        accounts::{
+            apply_pending_balance_locally, decrypt_available_balance, decrypt_pending_balance,
+            token_account_already_configured,
+        },
+        key_generator::{derive_ae_key, derive_elgamal_key, KeypairType},
+        proofs::get_zk_proof_context_state_account_creation_instructions,
+        transactions::pack_instructions,
+        wrapped_mint::escrow_address,
+    },
+    http::StatusCode,
+    solana_sdk::{signer::Signer, transaction::Transaction},
+    spl_token_2022::{
+        extension::{
+            confidential_transfer::{
+                account_info::WithdrawAccountInfo,
+                instruction::{
+                    apply_pending_balance, withdraw, BatchedRangeProofContext,
+                    CiphertextCommitmentEqualityProofContext, ProofContextState,
+                },
+                ConfidentialTransferAccount,
+            },
+            BaseStateWithExtensions, StateWithExtensions,
+        },
+        solana_zk_sdk::zk_elgamal_proof_program::instruction::{
+            close_context_state, ContextStateInfo,
+        },
+    },
+    spl_token_confidential_transfer_proof_extraction::instruction::ProofLocation,
+    spl_token_confidential_transfer_proof_generation::withdraw::WithdrawProofData,
+    spl_token_wrap::{get_wrapped_mint_address, get_wrapped_mint_authority},
+    std::sync::Arc,
+};
+
+/// Handler which is used to withdraw tokens from the confidential balance and unwrap them
+///
+/// The pending balance is applied first if the available balance is insufficient, and the
+/// unwrap is packed into the withdraw transaction where size allows
+pub async fn unshield(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<Unshield>,
+) -> impl IntoResponse {
+    let wrapped_mint =
+        get_wrapped_mint_address(&payload.unwrapped_token_mint, &spl_token_2022::id());
+
+    // derive the ATA for the authority + wrapped mint
+    let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &payload.authority,
+        &wrapped_mint,
+        &spl_token_2022::id(),
+    );
+
+    // verify elgamal signature
+    if !payload.elgamal_signature.verify(
+        &payload.authority.to_bytes(),
+        &KeypairType::ElGamal.message_to_sign(user_ata),
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify elgamal signature".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // verify ae signature
+    if !payload.ae_signature.verify(
+        &payload.authority.to_bytes(),
+        &KeypairType::Ae.message_to_sign(user_ata),
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify ae signature".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // derive the elgamal keypair
+    let Ok(elgamal_key) = derive_elgamal_key(payload.elgamal_signature) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to derive elgamal keypair".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // derive the ae keypair
+    let Ok(ae_key) = derive_ae_key(payload.ae_signature) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to derive ae keypair".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // lookup the wrapped mint and ata accounts
+    let mut accounts = state
+        .rpc
+        .get_multiple_accounts(&[wrapped_mint, user_ata])
+        .await
+        .unwrap_or_default();
+
+    // if less than 2 accounts is returned, this means the rpc call failed
+    if accounts.len() < 2 {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query accounts".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure the wrapped mint account exists
+    let Some(token_mint) = std::mem::take(&mut accounts[0]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "wrapped mint does not exist".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure token account exists
+    let Some(token_account) = std::mem::take(&mut accounts[1]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token account does not exist".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // validate the unwrap from the wrapped mint ATA, whose tokens are withdrawn from the
+    // confidential balance beforehand, rejecting unwrapped mints whose tokens can't be
+    // transferred out of the escrow
+    let unwrap_request = WrapTokens {
+        authority: payload.authority,
+        unwrapped_token_mint: payload.unwrapped_token_mint,
+        wrapped_token_mint: wrapped_mint,
+        unwrapped_token_program: payload.unwrapped_token_program,
+        amount: payload.amount,
+    };
+    let transfer =
+        match validate_wrap_request(&state, &unwrap_request, user_ata, payload.amount).await {
+            Ok(transfer) => transfer,
+            Err(response) => return response,
+        };
+
+    // ensure token account is configured for confidential transfers
+    if !token_account_already_configured(&token_account) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token account is not configured for confidential transfers".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // get the wrapped mint decimals
+    let decimals =
+        match StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&token_mint.data) {
+            Ok(mint) => mint.base.decimals,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to unpack wrapped mint {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+
+    // unpack token account
+    let token_account =
+        match StateWithExtensions::<spl_token_2022::state::Account>::unpack(&token_account.data) {
+            Ok(token_account) => token_account,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to unpack token account {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+
+    // retrieve the confidential transfer account extension
+    let mut extension = match token_account.get_extension::<ConfidentialTransferAccount>() {
+        Ok(confidential_token_account) => *confidential_token_account,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to get confidential transfer account extension {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let available_balance = match decrypt_available_balance(&ae_key, &extension) {
+        Ok(available_balance) => available_balance,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("{err:#}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let mut txs = vec![];
+
+    // apply the pending balance only when the available balance can't cover the amount
+    if available_balance < payload.amount {
+        // decrypt the pending balance using the precomputed discrete log table,
+        // this is cpu bound so it is moved off the async runtime
+        let discrete_log = state.discrete_log.clone();
+        let elgamal_secret = elgamal_key.secret().clone();
+        let decrypted = tokio::task::spawn_blocking(move || {
+            decrypt_pending_balance(&discrete_log, &elgamal_secret, &extension)
+        })
+        .await;
+        let pending_balance = match decrypted {
+            Ok(Ok(pending_balance)) => pending_balance,
+            Ok(Err(err)) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("{err:#}"),
+                    }),
+                )
+                    .into_response()
+            }
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to decrypt pending balance {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+
+        let new_available_balance = available_balance.saturating_add(pending_balance);
+        if new_available_balance < payload.amount {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: format!(
+                        "insufficient confidential balance of {}",
+                        spl_token_2022::amount_to_ui_amount(new_available_balance, decimals)
+                    ),
+                }),
+            )
+                .into_response();
+        }
+
+        let new_decryptable_available_balance = ae_key.encrypt(new_available_balance).into();
+        // can only fail if incorrect token program is provided
+        let apply_ix = apply_pending_balance(
+            &spl_token_2022::id(),
+            &user_ata,
+            extension.pending_balance_credit_counter.into(),
+            &new_decryptable_available_balance,
+            &payload.authority,
+            &[],
+        )
+        .unwrap();
+        txs.push(Transaction::new_with_payer(
+            &[apply_ix],
+            Some(&payload.authority),
+        ));
+
+        // the withdraw proof is generated against the balance after the apply
+        extension =
+            match apply_pending_balance_locally(&extension, new_decryptable_available_balance) {
+                Ok(extension) => extension,
+                Err(err) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiError {
+                            msg: format!("{err:#}"),
+                        }),
+                    )
+                        .into_response()
+                }
+            };
+    }
+
+    // Confidential Transfer extension information needed to construct a `Withdraw` instruction.
+    let withdraw_account_info = WithdrawAccountInfo::new(&extension);
+
+    // Create a withdraw proof data
+    let WithdrawProofData {
+        equality_proof_data,
+        range_proof_data,
+    } = match withdraw_account_info.generate_proof_data(payload.amount, &elgamal_key, &ae_key) {
+        Ok(proof) => proof,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to generate withdraw proof {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let range_proof_rent = match state
+        .rpc
+        .get_minimum_balance_for_rent_exemption(std::mem::size_of::<
+            ProofContextState<BatchedRangeProofContext>,
+        >())
+        .await
+    {
+        Ok(rent) => rent,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to get range proof rent {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let equality_proof_rent = match state
+        .rpc
+        .get_minimum_balance_for_rent_exemption(std::mem::size_of::<
+            ProofContextState<CiphertextCommitmentEqualityProofContext>,
+        >())
+        .await
+    {
+        Ok(rent) => rent,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to get equality proof rent {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let (range_create_ix, range_verify_ix) =
+        match get_zk_proof_context_state_account_creation_instructions(
+            &payload.authority,
+            &payload.range_proof_keypair.pubkey(),
+            &payload.authority,
+            &range_proof_data,
+            range_proof_rent,
+        ) {
+            Ok(data) => data,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to create range proof instructions {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+
+    let (equality_create_ix, equality_verify_ix) =
+        match get_zk_proof_context_state_account_creation_instructions(
+            &payload.authority,
+            &payload.equality_proof_keypair.pubkey(),
+            &payload.authority,
+            &equality_proof_data,
+            equality_proof_rent,
+        ) {
+            Ok(data) => data,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to create equality proof instructions {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+
+    let Ok(new_decryptable_available_balance) =
+        withdraw_account_info.new_decryptable_available_balance(payload.amount, &ae_key)
+    else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to encrypt available balance".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // only way this errors is if incorrect token program is provided
+    let withdraw_ixs = withdraw(
+        &spl_token_2022::id(),
+        &user_ata,
+        &wrapped_mint,
+        payload.amount,
+        decimals,
+        &new_decryptable_available_balance.into(),
+        &payload.authority,
+        &[],
+        ProofLocation::ContextStateAccount(&payload.equality_proof_keypair.pubkey()),
+        ProofLocation::ContextStateAccount(&payload.range_proof_keypair.pubkey()),
+    )
+    .unwrap();
+
+    let close_proof_ixs = [
+        &payload.equality_proof_keypair,
+        &payload.range_proof_keypair,
+    ]
+    .map(|keypair| {
+        close_context_state(
+            ContextStateInfo {
+                context_state_account: &keypair.pubkey(),
+                context_state_authority: &payload.authority,
+            },
+            &payload.authority,
+        )
+    });
+
+    txs.push(Transaction::new_with_payer(
+        &[equality_create_ix, equality_verify_ix, range_create_ix],
+        Some(&payload.authority),
+    ));
+    txs.push(Transaction::new_with_payer(
+        &[range_verify_ix],
+        Some(&payload.authority),
+    ));
+
+    // the unwrap must follow the withdraw, as it spends the withdrawn non confidential balance
+    let mut unwrap_ixs = unwrap_instructions(
+        &payload.authority,
+        &payload.unwrapped_token_mint,
+        &payload.unwrapped_token_program,
+        &wrapped_mint,
+        payload.amount,
+    );
+    if transfer.transfer_hook_program.is_some() {
+        if let Err(response) = add_transfer_hook_accounts(
+            &state,
+            &mut unwrap_ixs[1],
+            &escrow_address(
+                &payload.unwrapped_token_mint,
+                &payload.unwrapped_token_program,
+            ),
+            &payload.unwrapped_token_mint,
+            &spl_associated_token_account::get_associated_token_address_with_program_id(
+                &payload.authority,
+                &payload.unwrapped_token_mint,
+                &payload.unwrapped_token_program,
+            ),
+            &get_wrapped_mint_authority(&wrapped_mint),
+            payload.amount,
+        )
+        .await
+        {
+            return response;
+        }
+    }
+    match pack_instructions(
+        vec![withdraw_ixs, unwrap_ixs, close_proof_ixs.to_vec()],
+        &payload.authority,
+    ) {
+        Ok(mut packed) => txs.append(&mut packed),
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to pack transactions {err:#}"),
+                }),
+            )
+                .into_response()
+        }
+    }
+
+    let txs = match encode_transactions(&txs) {
+        Ok(txs) => txs,
+        Err(response) => return response,
+    };
+    (
+        StatusCode::OK,
+        Json(ApiTransactionResponse { transactions: txs }),
+    )
+        .into_response()
+}
@@ -1,65 +1,91 @@
 use {
     crate::{
+        handlers::wrap_tokens::{
+            add_transfer_hook_accounts, resolve_wrap_request, validate_wrap_request,
+        },
         router::AppState,
-        types::{ApiError, ApiTransactionResponse, WrapTokens},
+        types::{ApiError, ApiWrapTokensResponse, SimpleWrapTokens, WrapTokens},
+    },
+    axum::{
+        extract::State,
+        response::{IntoResponse, Response},
+        Json,
     },
-    axum::{extract::State, response::IntoResponse, Json},
     base64::{prelude::BASE64_STANDARD, Engine},
+    common::wrapped_mint::escrow_address,
     http::StatusCode,
-    solana_sdk::transaction::Transaction,
+    solana_sdk::{instruction::Instruction, pubkey::Pubkey, transaction::Transaction},
     spl_token_wrap::get_wrapped_mint_authority,
     std::sync::Arc,
 };
 
+/// Handler which is used to unwrap tokens from the non confidential balance
+///
+/// The response includes the amount of unwrapped tokens received after any transfer fee of the
+/// unwrapped mint
 pub async fn unwrap_tokens(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(payload): Json<WrapTokens>,
 ) -> impl IntoResponse {
-    let unwrapped_user_ata =
-        spl_associated_token_account::get_associated_token_address_with_program_id(
-            &payload.authority,
-            &payload.unwrapped_token_mint,
-            &payload.unwrapped_token_program,
-        );
+    unwrap_tokens_response(&state, &payload).await
+}
+
+/// Handler which is used to unwrap tokens given only one of the mints, and a ui amount
+pub async fn simple_unwrap_tokens(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<SimpleWrapTokens>,
+) -> impl IntoResponse {
+    match resolve_wrap_request(&state, &payload).await {
+        Ok(payload) => unwrap_tokens_response(&state, &payload).await,
+        Err(response) => response,
+    }
+}
+
+async fn unwrap_tokens_response(state: &AppState, payload: &WrapTokens) -> Response {
     let wrapped_user_ata =
         spl_associated_token_account::get_associated_token_address_with_program_id(
             &payload.authority,
             &payload.wrapped_token_mint,
             &spl_token_2022::id(),
         );
-    let wrapped_mint_authority = get_wrapped_mint_authority(&payload.wrapped_token_mint);
+    let transfer = match validate_wrap_request(state, payload, wrapped_user_ata, 0).await {
+        Ok(transfer) => transfer,
+        Err(response) => return response,
+    };
 
-    let mut ixs = vec![
-        spl_associated_token_account::instruction::create_associated_token_account_idempotent(
-            &payload.authority,
-            &payload.authority,
+    let mut ixs = unwrap_instructions(
+        &payload.authority,
+        &payload.unwrapped_token_mint,
+        &payload.unwrapped_token_program,
+        &payload.wrapped_token_mint,
+        payload.amount,
+    );
+    if transfer.transfer_hook_program.is_some() {
+        // the escrow transfers the unwrapped tokens, signed for by the wrapped mint authority
+        if let Err(response) = add_transfer_hook_accounts(
+            state,
+            &mut ixs[1],
+            &escrow_address(
+                &payload.unwrapped_token_mint,
+                &payload.unwrapped_token_program,
+            ),
             &payload.unwrapped_token_mint,
-            &payload.unwrapped_token_program
-        ),
-        spl_token_wrap::instruction::unwrap(
-            &spl_token_wrap::id(),
             &spl_associated_token_account::get_associated_token_address_with_program_id(
-                &wrapped_mint_authority,
+                &payload.authority,
                 &payload.unwrapped_token_mint,
                 &payload.unwrapped_token_program,
             ),
-            &unwrapped_user_ata,
-            &wrapped_mint_authority,
-            &payload.unwrapped_token_mint,
-            &spl_token_2022::id(),
-            &payload.unwrapped_token_program,
-            &wrapped_user_ata,
-            &payload.wrapped_token_mint,
-            &payload.authority,
-            &[],
+            &get_wrapped_mint_authority(&payload.wrapped_token_mint),
             payload.amount,
         )
-    ];
+        .await
+        {
+            return response;
+        }
+    }
 
-    let tx = match bincode::serialize(&Transaction::new_with_payer(
-        &ixs,
-        Some(&payload.authority),
-    )) {
+    let tx = match bincode::serialize(&Transaction::new_with_payer(&ixs, Some(&payload.authority)))
+    {
         Ok(tx) => tx,
         Err(err) => {
             return (
@@ -73,9 +99,62 @@ pub async fn unwrap_tokens(
     };
     (
         StatusCode::OK,
-        Json(ApiTransactionResponse {
+        Json(ApiWrapTokensResponse {
             transactions: vec![BASE64_STANDARD.encode(tx)],
+            transfer_fee_amount: transfer.fee,
+            received_amount: transfer.received_amount,
         }),
     )
         .into_response()
 }
+
+/// Returns the instructions which create the unwrapped mint ATA of `authority` if needed,
+/// and unwrap `amount` tokens from the wrapped mint ATA of `authority` into it
+pub(crate) fn unwrap_instructions(
+    authority: &Pubkey,
+    unwrapped_token_mint: &Pubkey,
+    unwrapped_token_program: &Pubkey,
+    wrapped_token_mint: &Pubkey,
+    amount: u64,
+) -> Vec<Instruction> {
+    let unwrapped_user_ata =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            authority,
+            unwrapped_token_mint,
+            unwrapped_token_program,
+        );
+    let wrapped_user_ata =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            authority,
+            wrapped_token_mint,
+            &spl_token_2022::id(),
+        );
+    let wrapped_mint_authority = get_wrapped_mint_authority(wrapped_token_mint);
+
+    vec![
+        spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            authority,
+            authority,
+            unwrapped_token_mint,
+            unwrapped_token_program,
+        ),
+        spl_token_wrap::instruction::unwrap(
+            &spl_token_wrap::id(),
+            &spl_associated_token_account::get_associated_token_address_with_program_id(
+                &wrapped_mint_authority,
+                unwrapped_token_mint,
+                unwrapped_token_program,
+            ),
+            &unwrapped_user_ata,
+            &wrapped_mint_authority,
+            unwrapped_token_mint,
+            &spl_token_2022::id(),
+            unwrapped_token_program,
+            &wrapped_user_ata,
+            wrapped_token_mint,
+            authority,
+            &[],
+            amount,
+        ),
+    ]
+}
@@ -1,14 +1,15 @@
 use {
+    super::transactions::encode_transactions,
     crate::{
         router::AppState,
         types::{ApiError, ApiTransactionResponse, Withdraw},
     },
     axum::{extract::State, response::IntoResponse, Json},
-    base64::{prelude::BASE64_STANDARD, Engine},
     common::{
         accounts::token_account_already_configured,
         key_generator::{derive_ae_key, derive_elgamal_key, KeypairType},
         proofs::get_zk_proof_context_state_account_creation_instructions,
+        transactions::with_memo,
     },
     http::StatusCode,
     solana_sdk::{signer::Signer, transaction::Transaction},
@@ -338,6 +339,19 @@ pub async fn withdraw(
             ProofLocation::ContextStateAccount(&payload.range_proof_keypair.pubkey()),
         )
         .unwrap();
+        let instructions =
+            match with_memo(payload.memo.as_deref(), &payload.authority, instructions) {
+                Ok(instructions) => instructions,
+                Err(err) => {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(ApiError {
+                            msg: format!("invalid memo {err:#}"),
+                        }),
+                    )
+                        .into_response()
+                }
+            };
         Transaction::new_with_payer(&instructions, Some(&payload.authority))
     };
 
@@ -367,20 +381,10 @@ pub async fn withdraw(
             Some(&payload.authority),
         )
     };
-    let txs = [tx1, tx2, tx3, tx4]
-        .into_iter()
-        .filter_map(|tx| Some(BASE64_STANDARD.encode(bincode::serialize(&tx).ok()?)))
-        .collect::<Vec<_>>();
-
-    if txs.len() != 4 {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiError {
-                msg: "failed to encode transactions".to_string(),
-            }),
-        )
-            .into_response();
-    }
+    let txs = match encode_transactions(&[tx1, tx2, tx3, tx4]) {
+        Ok(txs) => txs,
+        Err(response) => return response,
+    };
     (
         StatusCode::OK,
         Json(ApiTransactionResponse { transactions: txs }),
@@ -0,0 +1,760 @@
+use {
+    super::transactions::encode_transactions,
+    crate::{
+        router::AppState,
+        types::{
+            ApiError, ApiTransactionResponse, ApiWithdrawWithheldFeesResponse, HarvestWithheldFees,
+            UpdateHarvestToMint, WithdrawWithheldFees,
+        },
+    },
+    axum::{extract::State, response::IntoResponse, Json},
+    base64::{prelude::BASE64_STANDARD, Engine},
+    common::{
+        accounts::{
+            aggregate_withheld_amounts, decrypt_available_balance,
+            token_account_already_configured, withdraw_withheld_proof_data,
+        },
+        key_generator::{derive_ae_key, derive_elgamal_key, KeypairType},
+        proofs::get_zk_proof_context_state_account_creation_instructions,
+        transactions::pack_instructions,
+    },
+    http::StatusCode,
+    solana_account_decoder_client_types::UiAccountEncoding,
+    solana_client::{
+        rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+        rpc_filter::{Memcmp, RpcFilterType},
+    },
+    solana_sdk::{program_pack::Pack, pubkey::Pubkey, signer::Signer, transaction::Transaction},
+    spl_token_2022::{
+        extension::{
+            confidential_transfer::{
+                instruction::{CiphertextCiphertextEqualityProofContext, ProofContextState},
+                ConfidentialTransferAccount,
+            },
+            confidential_transfer_fee::{
+                instruction, ConfidentialTransferFeeAmount, ConfidentialTransferFeeConfig,
+            },
+            transfer_fee::TransferFeeConfig,
+            AccountType, BaseStateWithExtensions, StateWithExtensions,
+        },
+        solana_zk_sdk::{
+            encryption::pod::elgamal::PodElGamalPubkey,
+            zk_elgamal_proof_program::instruction::{close_context_state, ContextStateInfo},
+        },
+        state::{Account, Mint},
+    },
+    spl_token_confidential_transfer_proof_extraction::instruction::ProofLocation,
+    std::sync::Arc,
+};
+
+/// The number of token accounts harvested by a single instruction
+const MAX_HARVEST_SOURCES: usize = 20;
+
+/// Handler which is used to harvest the withheld confidential transfer fees of token accounts
+/// to the mint, where the withdraw withheld authority can withdraw them
+pub async fn harvest_withheld_fees(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<HarvestWithheldFees>,
+) -> impl IntoResponse {
+    let token_mint = match state.rpc.get_account(&payload.token_mint).await {
+        Ok(token_mint) => token_mint,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: format!("failed to query token mint {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let token_mint = match StateWithExtensions::<Mint>::unpack(&token_mint.data) {
+        Ok(token_mint) => token_mint,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to unpack token mint {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let Ok(extension) = token_mint.get_extension::<ConfidentialTransferFeeConfig>() else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token mint does not support confidential transfer fees".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    if !bool::from(extension.harvest_to_mint_enabled) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "harvesting withheld fees to the mint is disabled".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    let sources = if payload.sources.is_empty() {
+        // confidential token accounts always have extensions, so the account type follows the base account
+        let accounts = match state
+            .rpc
+            .get_program_accounts_with_config(
+                &spl_token_2022::id(),
+                RpcProgramAccountsConfig {
+                    filters: Some(vec![
+                        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                            0,
+                            payload.token_mint.to_bytes().to_vec(),
+                        )),
+                        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                            Account::LEN,
+                            vec![AccountType::Account as u8],
+                        )),
+                    ]),
+                    account_config: RpcAccountInfoConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .await
+        {
+            Ok(accounts) => accounts,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to query token accounts {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+        accounts
+            .into_iter()
+            .filter(|(_, account)| {
+                StateWithExtensions::<Account>::unpack(&account.data).is_ok_and(|token_account| {
+                    token_account
+                        .get_extension::<ConfidentialTransferFeeAmount>()
+                        .is_ok()
+                })
+            })
+            .map(|(address, _)| address)
+            .collect::<Vec<_>>()
+    } else {
+        payload.sources
+    };
+
+    if sources.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "no token accounts to harvest withheld fees from".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // only way this errors is if incorrect token program is provided
+    let groups = sources
+        .chunks(MAX_HARVEST_SOURCES)
+        .map(|sources| {
+            vec![instruction::harvest_withheld_tokens_to_mint(
+                &spl_token_2022::id(),
+                &payload.token_mint,
+                &sources.iter().collect::<Vec<_>>(),
+            )
+            .unwrap()]
+        })
+        .collect::<Vec<_>>();
+    let txs = match pack_instructions(groups, &payload.payer) {
+        Ok(txs) => txs,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to pack transactions {err:#}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let txs = match encode_transactions(&txs) {
+        Ok(txs) => txs,
+        Err(response) => return response,
+    };
+    (
+        StatusCode::OK,
+        Json(ApiTransactionResponse { transactions: txs }),
+    )
+        .into_response()
+}
+
+/// Handler which is used by the withdraw withheld authority of a mint to withdraw withheld
+/// confidential transfer fees, either from the mint or from token accounts, into the available
+/// balance of its own confidential token account
+pub async fn withdraw_withheld_fees(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<WithdrawWithheldFees>,
+) -> impl IntoResponse {
+    // derive the ATA for the authority + token_mint
+    let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &payload.authority,
+        &payload.token_mint,
+        &spl_token_2022::id(),
+    );
+
+    // verify withdraw withheld signature
+    if !payload.withdraw_withheld_signature.verify(
+        &payload.authority.to_bytes(),
+        &KeypairType::WithdrawWithheld.message_to_sign(user_ata),
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify withdraw withheld signature".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // verify elgamal signature
+    if !payload.elgamal_signature.verify(
+        &payload.authority.to_bytes(),
+        &KeypairType::ElGamal.message_to_sign(user_ata),
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify elgamal signature".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // verify ae signature
+    if !payload.ae_signature.verify(
+        &payload.authority.to_bytes(),
+        &KeypairType::Ae.message_to_sign(user_ata),
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "failed to verify ae signature".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // derive the withdraw withheld authority elgamal keypair
+    let Ok(withdraw_withheld_key) = derive_elgamal_key(payload.withdraw_withheld_signature) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to derive withdraw withheld elgamal keypair".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // derive the ae keypair
+    let Ok(ae_key) = derive_ae_key(payload.ae_signature) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to derive ae keypair".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // lookup the token mint, the ata, and any source token accounts
+    let mut addresses = vec![payload.token_mint, user_ata];
+    addresses.extend(payload.sources.iter().copied());
+    let mut accounts = state
+        .rpc
+        .get_multiple_accounts(&addresses)
+        .await
+        .unwrap_or_default();
+
+    // if fewer accounts are returned than requested, this means the rpc call failed
+    if accounts.len() < addresses.len() {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query accounts".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure the token mint account exists
+    let Some(token_mint) = std::mem::take(&mut accounts[0]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token mint does not exist".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure token account exists
+    let Some(token_account) = std::mem::take(&mut accounts[1]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token account does not exist".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    // ensure token account is configured for confidential transfers
+    if !token_account_already_configured(&token_account) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token account is not configured for confidential transfers".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    let mut source_accounts = Vec::with_capacity(payload.sources.len());
+    for (source, account) in payload.sources.iter().zip(accounts.drain(2..)) {
+        let Some(account) = account else {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: format!("source token account {source} does not exist"),
+                }),
+            )
+                .into_response();
+        };
+        source_accounts.push(account);
+    }
+
+    let token_mint = match StateWithExtensions::<Mint>::unpack(&token_mint.data) {
+        Ok(token_mint) => token_mint,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to unpack token mint {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let (Ok(transfer_fee_config), Ok(confidential_transfer_fee_config)) = (
+        token_mint.get_extension::<TransferFeeConfig>(),
+        token_mint.get_extension::<ConfidentialTransferFeeConfig>(),
+    ) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token mint does not support confidential transfer fees".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    if Option::<Pubkey>::from(transfer_fee_config.withdraw_withheld_authority)
+        != Some(payload.authority)
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "authority is not the withdraw withheld authority of the token mint"
+                    .to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // the withheld fees can only be decrypted with the key registered in the mint
+    if PodElGamalPubkey::from(*withdraw_withheld_key.pubkey())
+        != confidential_transfer_fee_config.withdraw_withheld_authority_elgamal_pubkey
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "withdraw withheld signature does not match the withdraw withheld authority elgamal pubkey of the token mint".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    let token_account = match StateWithExtensions::<Account>::unpack(&token_account.data) {
+        Ok(token_account) => token_account,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to unpack token account {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    // retrieve the confidential transfer account extension
+    let extension = match token_account.get_extension::<ConfidentialTransferAccount>() {
+        Ok(confidential_token_account) => *confidential_token_account,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to get confidential transfer account extension {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let Ok(destination_pubkey) = extension.elgamal_pubkey.try_into() else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to parse token account elgamal pubkey".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let withheld_amount = if payload.sources.is_empty() {
+        confidential_transfer_fee_config.withheld_amount
+    } else {
+        match aggregate_withheld_amounts(&source_accounts) {
+            Ok(withheld_amount) => withheld_amount,
+            Err(err) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiError {
+                        msg: format!("{err:#}"),
+                    }),
+                )
+                    .into_response()
+            }
+        }
+    };
+
+    // decrypt the withheld amount using the precomputed discrete log table,
+    // this is cpu bound so it is moved off the async runtime
+    let discrete_log = state.discrete_log.clone();
+    let proof = tokio::task::spawn_blocking(move || {
+        withdraw_withheld_proof_data(
+            &discrete_log,
+            &withdraw_withheld_key,
+            &destination_pubkey,
+            withheld_amount,
+        )
+    })
+    .await;
+    let (amount, equality_proof_data) = match proof {
+        Ok(Ok(proof)) => proof,
+        Ok(Err(err)) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("{err:#}"),
+                }),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to generate ciphertext equality proof {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    if amount == 0 {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "no withheld fees to withdraw".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    // the withheld fees are added to the available balance of the destination
+    let available_balance = match decrypt_available_balance(&ae_key, &extension) {
+        Ok(available_balance) => available_balance,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("{err:#}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    let Some(new_available_balance) = available_balance.checked_add(amount) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "available balance overflows".to_string(),
+            }),
+        )
+            .into_response();
+    };
+    let new_decryptable_available_balance = ae_key.encrypt(new_available_balance).into();
+
+    let equality_proof_rent = match state
+        .rpc
+        .get_minimum_balance_for_rent_exemption(std::mem::size_of::<
+            ProofContextState<CiphertextCiphertextEqualityProofContext>,
+        >())
+        .await
+    {
+        Ok(rent) => rent,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to get equality proof rent {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    // Equality Proof Instructions---------------------------------------------------------------------------
+    let (equality_create_ix, equality_verify_ix) =
+        match get_zk_proof_context_state_account_creation_instructions(
+            &payload.authority,
+            &payload.equality_proof_keypair.pubkey(),
+            &payload.authority,
+            &equality_proof_data,
+            equality_proof_rent,
+        ) {
+            Ok(data) => data,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to create equality proof instructions {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+
+    let proof_location =
+        ProofLocation::ContextStateAccount(&payload.equality_proof_keypair.pubkey());
+    let withdraw_ixs = if payload.sources.is_empty() {
+        instruction::withdraw_withheld_tokens_from_mint(
+            &spl_token_2022::id(),
+            &payload.token_mint,
+            &user_ata,
+            &new_decryptable_available_balance,
+            &payload.authority,
+            &[],
+            proof_location,
+        )
+    } else {
+        instruction::withdraw_withheld_tokens_from_accounts(
+            &spl_token_2022::id(),
+            &payload.token_mint,
+            &user_ata,
+            &new_decryptable_available_balance,
+            &payload.authority,
+            &[],
+            &payload.sources.iter().collect::<Vec<_>>(),
+            proof_location,
+        )
+    };
+    // the program id is always correct, so this only fails with too many source accounts
+    let withdraw_ixs = match withdraw_ixs {
+        Ok(withdraw_ixs) => withdraw_ixs,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: format!("failed to create withdraw withheld instruction {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    // Close the equality proof account
+    let close_equality_proof_ix = close_context_state(
+        ContextStateInfo {
+            context_state_account: &payload.equality_proof_keypair.pubkey(),
+            context_state_authority: &payload.authority,
+        },
+        &payload.authority,
+    );
+
+    let groups = vec![
+        vec![equality_create_ix, equality_verify_ix],
+        withdraw_ixs,
+        vec![close_equality_proof_ix],
+    ];
+    let txs = match pack_instructions(groups, &payload.authority) {
+        Ok(txs) => txs,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to pack transactions {err:#}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let txs = match encode_transactions(&txs) {
+        Ok(txs) => txs,
+        Err(response) => return response,
+    };
+    (
+        StatusCode::OK,
+        Json(ApiWithdrawWithheldFeesResponse {
+            transactions: txs,
+            amount,
+        }),
+    )
+        .into_response()
+}
+
+/// Handler which is used by the confidential transfer fee authority of a mint to allow
+/// harvesting withheld fees to the mint
+pub async fn enable_harvest_to_mint(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<UpdateHarvestToMint>,
+) -> impl IntoResponse {
+    update_harvest_to_mint(state, payload, true).await
+}
+
+/// Handler which is used by the confidential transfer fee authority of a mint to reject
+/// harvesting withheld fees to the mint
+pub async fn disable_harvest_to_mint(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<UpdateHarvestToMint>,
+) -> impl IntoResponse {
+    update_harvest_to_mint(state, payload, false).await
+}
+
+async fn update_harvest_to_mint(
+    state: Arc<AppState>,
+    payload: UpdateHarvestToMint,
+    enable: bool,
+) -> axum::response::Response {
+    let token_mint = match state.rpc.get_account(&payload.token_mint).await {
+        Ok(token_mint) => token_mint,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: format!("failed to query token mint {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let token_mint = match StateWithExtensions::<Mint>::unpack(&token_mint.data) {
+        Ok(token_mint) => token_mint,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to unpack token mint {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let Ok(extension) = token_mint.get_extension::<ConfidentialTransferFeeConfig>() else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "token mint does not support confidential transfer fees".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    if Option::<Pubkey>::from(extension.authority) != Some(payload.authority) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "authority is not the confidential transfer fee authority of the token mint"
+                    .to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    if bool::from(extension.harvest_to_mint_enabled) == enable {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: format!(
+                    "harvest to mint is already {}",
+                    if enable { "enabled" } else { "disabled" }
+                ),
+            }),
+        )
+            .into_response();
+    }
+
+    let ix = if enable {
+        instruction::enable_harvest_to_mint
+    } else {
+        instruction::disable_harvest_to_mint
+    };
+    // only way this errors is if incorrect token program is provided
+    let ix = ix(
+        &spl_token_2022::id(),
+        &payload.token_mint,
+        &payload.authority,
+        &[],
+    )
+    .unwrap();
+
+    let tx = match bincode::serialize(&Transaction::new_with_payer(
+        &[ix],
+        Some(&payload.authority),
+    )) {
+        Ok(tx) => tx,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to serialize transaction {err:#?}"),
+                }),
+            )
+                .into_response()
+        }
+    };
+    (
+        StatusCode::OK,
+        Json(ApiTransactionResponse {
+            transactions: vec![BASE64_STANDARD.encode(tx)],
+        }),
+    )
+        .into_response()
+}
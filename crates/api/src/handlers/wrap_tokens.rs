@@ -1,56 +1,108 @@
 use {
     crate::{
         router::AppState,
-        types::{ApiError, ApiTransactionResponse, WrapTokens},
+        types::{ApiError, ApiWrapTokensResponse, SimpleWrapTokens, WrapTokens},
+    },
+    axum::{
+        extract::State,
+        response::{IntoResponse, Response},
+        Json,
     },
-    axum::{extract::State, response::IntoResponse, Json},
     base64::{prelude::BASE64_STANDARD, Engine},
+    common::{
+        accounts::unwrapped_mint_from_backpointer,
+        wrapped_mint::{
+            escrow_address, unwrapped_transfer, validate_wrap_accounts, UnwrappedTransfer,
+            WrapAccounts,
+        },
+    },
     http::StatusCode,
-    solana_sdk::transaction::Transaction,
-    spl_token_wrap::get_wrapped_mint_authority,
+    solana_sdk::{
+        account::Account, instruction::Instruction, pubkey::Pubkey, transaction::Transaction,
+    },
+    spl_token_2022::{
+        extension::StateWithExtensions,
+        offchain::{add_extra_account_metas, AccountFetchError},
+        state::Mint,
+    },
+    spl_token_wrap::{
+        get_wrapped_mint_address, get_wrapped_mint_authority, get_wrapped_mint_backpointer_address,
+    },
     std::sync::Arc,
 };
 
+/// Handler which is used to wrap unwrapped tokens into the non confidential balance
+///
+/// The wrapped mint ATA is created if needed, and the response includes the amount of wrapped
+/// tokens minted after any transfer fee of the unwrapped mint
 pub async fn wrap_tokens(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(payload): Json<WrapTokens>,
 ) -> impl IntoResponse {
+    wrap_tokens_response(&state, &payload).await
+}
+
+/// Handler which is used to wrap tokens given only one of the mints, and a ui amount
+pub async fn simple_wrap_tokens(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<SimpleWrapTokens>,
+) -> impl IntoResponse {
+    match resolve_wrap_request(&state, &payload).await {
+        Ok(payload) => wrap_tokens_response(&state, &payload).await,
+        Err(response) => response,
+    }
+}
+
+async fn wrap_tokens_response(state: &AppState, payload: &WrapTokens) -> Response {
     let unwrapped_user_ata =
         spl_associated_token_account::get_associated_token_address_with_program_id(
             &payload.authority,
             &payload.unwrapped_token_mint,
             &payload.unwrapped_token_program,
         );
-    let wrapped_user_ata =
-        spl_associated_token_account::get_associated_token_address_with_program_id(
+    let transfer = match validate_wrap_request(state, payload, unwrapped_user_ata, 0).await {
+        Ok(transfer) => transfer,
+        Err(response) => return response,
+    };
+
+    let mut wrap_ix = wrap_instruction(
+        &payload.authority,
+        &payload.unwrapped_token_mint,
+        &payload.unwrapped_token_program,
+        &payload.wrapped_token_mint,
+        payload.amount,
+    );
+    if transfer.transfer_hook_program.is_some() {
+        if let Err(response) = add_transfer_hook_accounts(
+            state,
+            &mut wrap_ix,
+            &unwrapped_user_ata,
+            &payload.unwrapped_token_mint,
+            &escrow_address(
+                &payload.unwrapped_token_mint,
+                &payload.unwrapped_token_program,
+            ),
+            &payload.authority,
+            payload.amount,
+        )
+        .await
+        {
+            return response;
+        }
+    }
+
+    let ixs = [
+        spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            &payload.authority,
             &payload.authority,
             &payload.wrapped_token_mint,
             &spl_token_2022::id(),
-        );
-    let wrapped_mint_authority = get_wrapped_mint_authority(&payload.wrapped_token_mint);
-    let ix = spl_token_wrap::instruction::wrap(
-        &spl_token_wrap::id(),
-        &wrapped_user_ata,
-        &payload.wrapped_token_mint,
-        &wrapped_mint_authority,
-        &payload.unwrapped_token_program,
-        &spl_token_2022::id(),
-        &unwrapped_user_ata,
-        &payload.unwrapped_token_mint,
-        &spl_associated_token_account::get_associated_token_address_with_program_id(
-            &wrapped_mint_authority,
-            &payload.unwrapped_token_mint,
-            &payload.unwrapped_token_program,
         ),
-        &payload.authority,
-        &[],
-        payload.amount,
-    );
+        wrap_ix,
+    ];
 
-    let tx = match bincode::serialize(&Transaction::new_with_payer(
-        &[ix],
-        Some(&payload.authority),
-    )) {
+    let tx = match bincode::serialize(&Transaction::new_with_payer(&ixs, Some(&payload.authority)))
+    {
         Ok(tx) => tx,
         Err(err) => {
             return (
@@ -64,9 +116,326 @@ pub async fn wrap_tokens(
     };
     (
         StatusCode::OK,
-        Json(ApiTransactionResponse {
+        Json(ApiWrapTokensResponse {
             transactions: vec![BASE64_STANDARD.encode(tx)],
+            transfer_fee_amount: transfer.fee,
+            received_amount: transfer.received_amount,
         }),
     )
         .into_response()
 }
+
+/// Resolves the unwrapped mint, wrapped mint, and token program of a simplified wrap or unwrap
+/// request, converting its ui amount using the decimals of the mint
+pub(crate) async fn resolve_wrap_request(
+    state: &AppState,
+    payload: &SimpleWrapTokens,
+) -> Result<WrapTokens, Response> {
+    let unwrapped_mint = resolve_unwrapped_mint(state, &payload.token_mint).await?;
+
+    // parsed exactly rather than through a float, which rounds amounts down
+    let amount = match spl_token_2022::try_ui_amount_into_amount(
+        payload.ui_amount.clone(),
+        unwrapped_mint.decimals,
+    ) {
+        Ok(amount) => amount,
+        Err(_) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: format!(
+                        "invalid ui_amount {}, expected a decimal number with at most {} decimals",
+                        payload.ui_amount, unwrapped_mint.decimals
+                    ),
+                }),
+            )
+                .into_response())
+        }
+    };
+    if amount == 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: "ui_amount must be greater than 0".to_string(),
+            }),
+        )
+            .into_response());
+    }
+
+    Ok(WrapTokens {
+        authority: payload.authority,
+        unwrapped_token_mint: unwrapped_mint.mint,
+        wrapped_token_mint: get_wrapped_mint_address(&unwrapped_mint.mint, &spl_token_2022::id()),
+        unwrapped_token_program: unwrapped_mint.token_program,
+        amount,
+    })
+}
+
+/// The unwrapped side of a mint which may be either unwrapped or wrapped
+pub(crate) struct UnwrappedMint {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub decimals: u8,
+}
+
+/// Resolves the unwrapped mint of `token_mint`, which is treated as a wrapped mint if it has a
+/// token wrap backpointer, and as the unwrapped mint otherwise
+pub(crate) async fn resolve_unwrapped_mint(
+    state: &AppState,
+    token_mint: &Pubkey,
+) -> Result<UnwrappedMint, Response> {
+    // lookup the token mint, and the backpointer it would have if it is a wrapped mint
+    let mut accounts = state
+        .rpc
+        .get_multiple_accounts(&[
+            *token_mint,
+            get_wrapped_mint_backpointer_address(token_mint),
+        ])
+        .await
+        .unwrap_or_default();
+
+    // if less than 2 accounts is returned, this means the rpc call failed
+    if accounts.len() < 2 {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query accounts".to_string(),
+            }),
+        )
+            .into_response());
+    }
+
+    let Some(mint_account) = std::mem::take(&mut accounts[0]) else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: format!("token mint {token_mint} does not exist"),
+            }),
+        )
+            .into_response());
+    };
+
+    // wrapped mints have the same decimals as their unwrapped mint
+    let decimals = match StateWithExtensions::<Mint>::unpack(&mint_account.data) {
+        Ok(mint) => mint.base.decimals,
+        Err(err) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    msg: format!("failed to unpack token mint {err:#?}"),
+                }),
+            )
+                .into_response())
+        }
+    };
+
+    let backpointer = accounts[1]
+        .as_ref()
+        .and_then(|backpointer| unwrapped_mint_from_backpointer(token_mint, backpointer));
+    let Some(mint) = backpointer else {
+        return Ok(UnwrappedMint {
+            mint: *token_mint,
+            token_program: mint_account.owner,
+            decimals,
+        });
+    };
+    match state.rpc.get_account(&mint).await {
+        Ok(unwrapped_mint) => Ok(UnwrappedMint {
+            mint,
+            token_program: unwrapped_mint.owner,
+            decimals,
+        }),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: format!("failed to query unwrapped mint {err:#?}"),
+            }),
+        )
+            .into_response()),
+    }
+}
+
+/// Ensures the wrap or unwrap described by `payload` won't fail on chain, taking the tokens
+/// from the `source` token account, and returns the effect of the unwrapped mint extensions
+///
+/// `withdrawn` tokens are moved into `source` by the same request before they are taken, so
+/// they don't need to be there yet
+pub(crate) async fn validate_wrap_request(
+    state: &AppState,
+    payload: &WrapTokens,
+    source: Pubkey,
+    withdrawn: u64,
+) -> Result<UnwrappedTransfer, Response> {
+    let mut accounts = state
+        .rpc
+        .get_multiple_accounts(&[
+            payload.unwrapped_token_mint,
+            get_wrapped_mint_backpointer_address(&payload.wrapped_token_mint),
+            escrow_address(
+                &payload.unwrapped_token_mint,
+                &payload.unwrapped_token_program,
+            ),
+            source,
+        ])
+        .await
+        .unwrap_or_default();
+
+    // if less than 4 accounts is returned, this means the rpc call failed
+    if accounts.len() < 4 {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query accounts".to_string(),
+            }),
+        )
+            .into_response());
+    }
+
+    let accounts = WrapAccounts {
+        unwrapped_mint: std::mem::take(&mut accounts[0]),
+        backpointer: std::mem::take(&mut accounts[1]),
+        escrow: std::mem::take(&mut accounts[2]),
+        source: std::mem::take(&mut accounts[3]),
+    };
+    if let Err(err) = validate_wrap_accounts(
+        &payload.unwrapped_token_mint,
+        &payload.unwrapped_token_program,
+        &payload.wrapped_token_mint,
+        payload.amount.saturating_sub(withdrawn),
+        &accounts,
+    ) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: format!("{err:#}"),
+            }),
+        )
+            .into_response());
+    }
+
+    // the unwrapped mint exists once validated
+    check_unwrapped_transfer(
+        state,
+        accounts.unwrapped_mint.as_ref().unwrap(),
+        payload.amount,
+    )
+    .await
+}
+
+/// Ensures the extensions of `unwrapped_mint` allow moving `amount` tokens to or from the escrow,
+/// returning the effect they have on the transfer
+pub(crate) async fn check_unwrapped_transfer(
+    state: &AppState,
+    unwrapped_mint: &Account,
+    amount: u64,
+) -> Result<UnwrappedTransfer, Response> {
+    // transfer fees are scheduled by epoch
+    let epoch = match state.rpc.get_epoch_info().await {
+        Ok(epoch_info) => epoch_info.epoch,
+        Err(err) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    msg: format!("failed to get epoch info {err:#?}"),
+                }),
+            )
+                .into_response())
+        }
+    };
+    unwrapped_transfer(unwrapped_mint, amount, epoch).map_err(|err| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: format!("{err:#}"),
+            }),
+        )
+            .into_response()
+    })
+}
+
+/// Appends the extra accounts required by the transfer hook of the unwrapped mint to a wrap or
+/// unwrap instruction, which moves `amount` tokens from `source` to `destination`
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn add_transfer_hook_accounts(
+    state: &AppState,
+    ix: &mut Instruction,
+    source: &Pubkey,
+    unwrapped_token_mint: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+) -> Result<(), Response> {
+    let rpc = state.rpc.clone();
+    let fetch_account_data = move |address: Pubkey| {
+        let rpc = rpc.clone();
+        async move {
+            rpc.get_multiple_accounts(&[address])
+                .await
+                .map(|mut accounts| accounts.pop().flatten().map(|account| account.data))
+                .map_err(|err| AccountFetchError::from(err.to_string()))
+        }
+    };
+    add_extra_account_metas(
+        ix,
+        source,
+        unwrapped_token_mint,
+        destination,
+        authority,
+        amount,
+        fetch_account_data,
+    )
+    .await
+    .map_err(|err| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: format!(
+                    "failed to resolve transfer hook accounts of the unwrapped mint {err:#}"
+                ),
+            }),
+        )
+            .into_response()
+    })
+}
+
+/// Returns the instruction which wraps `amount` unwrapped tokens from the ATA of `authority`,
+/// into the wrapped mint ATA of `authority`
+pub(crate) fn wrap_instruction(
+    authority: &Pubkey,
+    unwrapped_token_mint: &Pubkey,
+    unwrapped_token_program: &Pubkey,
+    wrapped_token_mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let unwrapped_user_ata =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            authority,
+            unwrapped_token_mint,
+            unwrapped_token_program,
+        );
+    let wrapped_user_ata =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            authority,
+            wrapped_token_mint,
+            &spl_token_2022::id(),
+        );
+    let wrapped_mint_authority = get_wrapped_mint_authority(wrapped_token_mint);
+    spl_token_wrap::instruction::wrap(
+        &spl_token_wrap::id(),
+        &wrapped_user_ata,
+        wrapped_token_mint,
+        &wrapped_mint_authority,
+        unwrapped_token_program,
+        &spl_token_2022::id(),
+        &unwrapped_user_ata,
+        unwrapped_token_mint,
+        &spl_associated_token_account::get_associated_token_address_with_program_id(
+            &wrapped_mint_authority,
+            unwrapped_token_mint,
+            unwrapped_token_program,
+        ),
+        authority,
+        &[],
+        amount,
+    )
+}
@@ -0,0 +1,139 @@
+use {
+    crate::{
+        handlers::wrap_tokens::resolve_unwrapped_mint,
+        router::AppState,
+        types::{ApiError, ApiWrappedMintResponse, WrappedMintInfo},
+    },
+    axum::{
+        extract::{Query, State},
+        response::IntoResponse,
+        Json,
+    },
+    common::{accounts::unwrapped_mint_from_backpointer, wrapped_mint::escrow_address},
+    http::StatusCode,
+    spl_token_2022::{
+        extension::StateWithExtensions,
+        state::{Account, Mint},
+    },
+    spl_token_wrap::{
+        get_wrapped_mint_address, get_wrapped_mint_authority, get_wrapped_mint_backpointer_address,
+    },
+    std::sync::Arc,
+};
+
+/// Handler which is used to look up a wrapped mint, and compare its supply to the escrow balance
+///
+/// Either the unwrapped or the wrapped mint may be given
+pub async fn wrapped_mint_info(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<WrappedMintInfo>,
+) -> impl IntoResponse {
+    let unwrapped_mint = match resolve_unwrapped_mint(&state, &query.token_mint).await {
+        Ok(unwrapped_mint) => unwrapped_mint,
+        Err(response) => return response,
+    };
+    let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint.mint, &spl_token_2022::id());
+    let backpointer = get_wrapped_mint_backpointer_address(&wrapped_mint);
+    let escrow = escrow_address(&unwrapped_mint.mint, &unwrapped_mint.token_program);
+
+    // lookup the wrapped mint, backpointer, and escrow accounts
+    let mut accounts = state
+        .rpc
+        .get_multiple_accounts(&[wrapped_mint, backpointer, escrow])
+        .await
+        .unwrap_or_default();
+
+    // if less than 3 accounts is returned, this means the rpc call failed
+    if accounts.len() < 3 {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                msg: "failed to query accounts".to_string(),
+            }),
+        )
+            .into_response();
+    };
+
+    let Some(wrapped_mint_account) = std::mem::take(&mut accounts[0]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                msg: format!(
+                    "wrapped mint {wrapped_mint} of {} does not exist",
+                    unwrapped_mint.mint
+                ),
+            }),
+        )
+            .into_response();
+    };
+
+    let wrapped_supply_amount =
+        match StateWithExtensions::<Mint>::unpack(&wrapped_mint_account.data) {
+            Ok(mint) => mint.base.supply,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to unpack wrapped mint {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+
+    // a missing escrow can't back any supply
+    let escrow_account = std::mem::take(&mut accounts[2]);
+    let escrow_balance_amount = match &escrow_account {
+        Some(escrow) => match StateWithExtensions::<Account>::unpack(&escrow.data) {
+            Ok(escrow) => escrow.base.amount,
+            Err(err) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError {
+                        msg: format!("failed to unpack escrow {err:#?}"),
+                    }),
+                )
+                    .into_response()
+            }
+        },
+        None => 0,
+    };
+
+    // tokens sent to the escrow directly are not backing any wrapped tokens, but don't put the
+    // backing at risk either
+    let escrow_surplus_amount = escrow_balance_amount.saturating_sub(wrapped_supply_amount);
+
+    (
+        StatusCode::OK,
+        Json(ApiWrappedMintResponse {
+            unwrapped_token_mint: unwrapped_mint.mint,
+            unwrapped_token_program: unwrapped_mint.token_program,
+            wrapped_token_mint: wrapped_mint,
+            backpointer,
+            backpointer_unwrapped_token_mint: accounts[1].as_ref().and_then(|backpointer| {
+                unwrapped_mint_from_backpointer(&wrapped_mint, backpointer)
+            }),
+            wrapped_mint_authority: get_wrapped_mint_authority(&wrapped_mint),
+            escrow,
+            escrow_exists: escrow_account.is_some(),
+            escrow_balance: spl_token_2022::amount_to_ui_amount(
+                escrow_balance_amount,
+                unwrapped_mint.decimals,
+            ),
+            escrow_balance_amount,
+            wrapped_supply: spl_token_2022::amount_to_ui_amount(
+                wrapped_supply_amount,
+                unwrapped_mint.decimals,
+            ),
+            wrapped_supply_amount,
+            decimals: unwrapped_mint.decimals,
+            supply_mismatch: escrow_balance_amount < wrapped_supply_amount,
+            escrow_surplus: spl_token_2022::amount_to_ui_amount(
+                escrow_surplus_amount,
+                unwrapped_mint.decimals,
+            ),
+            escrow_surplus_amount,
+        }),
+    )
+        .into_response()
+}
@@ -0,0 +1,1706 @@
+//! Decrypted transaction history of confidential token accounts
+//!
+//! The confidential transfer, mint and burn instructions of every transaction touching a token
+//! account are decoded with [`decode_instructions`], then [`replay`] walks them oldest first to decrypt the
+//! amounts and compute the running balances after each instruction.
+
+use {
+    crate::{
+        notifications::Credentials,
+        types::{ApiHistoryEntry, ApiHistoryEntryKind},
+    },
+    anyhow::{anyhow, Context, Result},
+    common::{
+        accounts::{decrypt_transfer_amount, decrypt_transfer_fee},
+        discrete_log::DiscreteLogTable,
+    },
+    futures::{StreamExt, TryStreamExt},
+    solana_client::{
+        nonblocking::rpc_client::RpcClient, rpc_client::GetConfirmedSignaturesForAddress2Config,
+        rpc_config::RpcTransactionConfig,
+    },
+    solana_sdk::{
+        commitment_config::CommitmentConfig, instruction::CompiledInstruction, pubkey::Pubkey,
+        signature::Signature,
+    },
+    solana_transaction_status_client_types::{
+        UiLoadedAddresses, UiTransactionEncoding, UiTransactionTokenBalance,
+    },
+    spl_token_2022::{
+        extension::{
+            confidential_mint_burn::instruction::{
+                BurnInstructionData, ConfidentialMintBurnInstruction, MintInstructionData,
+            },
+            confidential_transfer::instruction::{
+                ApplyPendingBalanceData, BatchedGroupedCiphertext2HandlesValidityProofContext,
+                BatchedGroupedCiphertext2HandlesValidityProofData,
+                BatchedGroupedCiphertext3HandlesValidityProofContext,
+                BatchedGroupedCiphertext3HandlesValidityProofData, ConfidentialTransferInstruction,
+                DepositInstructionData, TransferInstructionData, TransferWithFeeInstructionData,
+                WithdrawInstructionData,
+            },
+        },
+        instruction::{decode_instruction_data, decode_instruction_type, TokenInstruction},
+        solana_zk_sdk::{
+            encryption::{
+                auth_encryption::{AeCiphertext, AeKey},
+                pod::{
+                    auth_encryption::PodAeCiphertext,
+                    elgamal::PodElGamalCiphertext,
+                    grouped_elgamal::{
+                        PodGroupedElGamalCiphertext2Handles, PodGroupedElGamalCiphertext3Handles,
+                    },
+                },
+            },
+            zk_elgamal_proof_program::{self, instruction::ProofInstruction},
+        },
+    },
+    std::str::FromStr,
+};
+
+/// Maximum number of signatures returned by a single `getSignaturesForAddress` call
+const MAX_SIGNATURES: usize = 1_000;
+
+/// Maximum number of transactions older than a page fetched to recover its starting balances
+const MAX_LOOKBACK: usize = 100;
+
+/// Number of transactions older than a page fetched at a time
+const LOOKBACK_BATCH: usize = 10;
+
+/// Number of transactions fetched concurrently
+const CONCURRENT_REQUESTS: usize = 8;
+
+/// Index of the destination decryption handle in transfer amount ciphertexts
+const DESTINATION_HANDLE: usize = 1;
+
+/// Index of the destination decryption handle in transfer fee ciphertexts
+const FEE_DESTINATION_HANDLE: usize = 0;
+
+/// Index of the destination decryption handle in mint amount ciphertexts
+const MINT_DESTINATION_HANDLE: usize = 0;
+
+/// The grouped ciphertexts of the lo and hi bits of a transfer amount
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TransferAmountCiphertexts {
+    pub lo: PodGroupedElGamalCiphertext3Handles,
+    pub hi: PodGroupedElGamalCiphertext3Handles,
+}
+
+/// The grouped ciphertexts of the lo and hi bits of a transfer fee
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FeeCiphertexts {
+    pub lo: PodGroupedElGamalCiphertext2Handles,
+    pub hi: PodGroupedElGamalCiphertext2Handles,
+}
+
+/// Where the ciphertexts of an incoming transfer amount, or of its fee, can be found
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransferAmount<C = TransferAmountCiphertexts> {
+    /// The ciphertexts checked by the ciphertext validity proof
+    Ciphertexts(Box<C>),
+    /// The ciphertext validity proof was verified into a context state account beforehand
+    ContextStateAccount(Pubkey),
+    /// The ciphertext validity proof could not be located, for example because it was read
+    /// from a record account
+    Unknown,
+}
+
+/// A confidential transfer, mint or burn instruction which changed the balances of a token
+/// account
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfidentialInstruction {
+    ConfigureAccount,
+    Deposit {
+        amount: u64,
+    },
+    Withdraw {
+        amount: u64,
+        new_decryptable_available_balance: PodAeCiphertext,
+    },
+    ApplyPendingBalance {
+        new_decryptable_available_balance: PodAeCiphertext,
+    },
+    TransferOut {
+        destination: Pubkey,
+        new_source_decryptable_available_balance: PodAeCiphertext,
+    },
+    TransferIn {
+        source: Pubkey,
+        /// The transfer amount, including the fee
+        amount: TransferAmount,
+        /// The fee withheld from the amount, `None` for transfers without a fee
+        fee: Option<TransferAmount<FeeCiphertexts>>,
+    },
+    Mint {
+        amount: TransferAmount,
+    },
+    Burn {
+        new_decryptable_available_balance: PodAeCiphertext,
+    },
+    EmptyAccount,
+}
+
+/// A confidential transfer instruction along with the transaction it was included in
+#[derive(Clone, Debug)]
+pub struct HistoryEvent {
+    pub signature: Signature,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub instruction: ConfidentialInstruction,
+    /// The non confidential balance after the transaction, if reported by the rpc node
+    pub non_confidential_balance: Option<u64>,
+}
+
+/// A successful transaction with its account keys resolved
+pub(crate) struct ConfirmedTransaction {
+    pub(crate) signature: Signature,
+    pub(crate) slot: u64,
+    pub(crate) block_time: Option<i64>,
+    pub(crate) account_keys: Vec<Pubkey>,
+    pub(crate) instructions: Vec<CompiledInstruction>,
+    pub(crate) post_token_balances: Vec<UiTransactionTokenBalance>,
+}
+
+/// Decodes the confidential transfer, mint and burn instructions of a transaction which affect
+/// `token_account`
+///
+/// A transfer from a token account to itself yields both a `TransferOut` and a `TransferIn`
+pub fn decode_instructions(
+    token_account: &Pubkey,
+    account_keys: &[Pubkey],
+    instructions: &[CompiledInstruction],
+) -> Vec<ConfidentialInstruction> {
+    let mut decoded = vec![];
+    for (index, instruction) in instructions.iter().enumerate() {
+        if account_keys.get(instruction.program_id_index as usize) != Some(&spl_token_2022::id()) {
+            continue;
+        }
+        match TokenInstruction::unpack(&instruction.data) {
+            Ok(TokenInstruction::ConfidentialTransferExtension) => {}
+            Ok(TokenInstruction::ConfidentialMintBurnExtension) => {
+                match decode_mint_burn(account_keys, instructions, index) {
+                    Some(DecodedMintBurn::Mint {
+                        token_account: destination,
+                        amount,
+                        ..
+                    }) if destination == *token_account => {
+                        decoded.push(ConfidentialInstruction::Mint { amount });
+                    }
+                    Some(DecodedMintBurn::Burn {
+                        token_account: source,
+                        data,
+                        ..
+                    }) if source == *token_account => {
+                        decoded.push(ConfidentialInstruction::Burn {
+                            new_decryptable_available_balance: data
+                                .new_decryptable_available_balance,
+                        });
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            _ => continue,
+        }
+        let data = &instruction.data[1..];
+        let account = |position: usize| {
+            instruction
+                .accounts
+                .get(position)
+                .and_then(|index| account_keys.get(*index as usize))
+                .copied()
+        };
+        let Ok(instruction_type) = decode_instruction_type::<ConfidentialTransferInstruction>(data)
+        else {
+            continue;
+        };
+
+        match instruction_type {
+            ConfidentialTransferInstruction::ConfigureAccount
+            | ConfidentialTransferInstruction::ConfigureAccountWithRegistry
+                if account(0) == Some(*token_account) =>
+            {
+                decoded.push(ConfidentialInstruction::ConfigureAccount);
+            }
+            ConfidentialTransferInstruction::Deposit if account(0) == Some(*token_account) => {
+                let Ok(deposit) = decode_instruction_data::<DepositInstructionData>(data) else {
+                    continue;
+                };
+                decoded.push(ConfidentialInstruction::Deposit {
+                    amount: deposit.amount.into(),
+                });
+            }
+            ConfidentialTransferInstruction::Withdraw if account(0) == Some(*token_account) => {
+                let Ok(withdraw) = decode_instruction_data::<WithdrawInstructionData>(data) else {
+                    continue;
+                };
+                decoded.push(ConfidentialInstruction::Withdraw {
+                    amount: withdraw.amount.into(),
+                    new_decryptable_available_balance: withdraw.new_decryptable_available_balance,
+                });
+            }
+            ConfidentialTransferInstruction::ApplyPendingBalance
+                if account(0) == Some(*token_account) =>
+            {
+                let Ok(apply) = decode_instruction_data::<ApplyPendingBalanceData>(data) else {
+                    continue;
+                };
+                decoded.push(ConfidentialInstruction::ApplyPendingBalance {
+                    new_decryptable_available_balance: apply.new_decryptable_available_balance,
+                });
+            }
+            ConfidentialTransferInstruction::Transfer
+            | ConfidentialTransferInstruction::TransferWithFee => {
+                let Some(transfer) =
+                    decode_transfer(account_keys, instructions, index, instruction_type)
+                else {
+                    continue;
+                };
+                if transfer.source == *token_account {
+                    decoded.push(ConfidentialInstruction::TransferOut {
+                        destination: transfer.destination,
+                        new_source_decryptable_available_balance: transfer
+                            .new_source_decryptable_available_balance,
+                    });
+                }
+                if transfer.destination == *token_account {
+                    decoded.push(ConfidentialInstruction::TransferIn {
+                        source: transfer.source,
+                        amount: transfer.amount,
+                        fee: transfer.fee,
+                    });
+                }
+            }
+            ConfidentialTransferInstruction::EmptyAccount if account(0) == Some(*token_account) => {
+                decoded.push(ConfidentialInstruction::EmptyAccount);
+            }
+            _ => {}
+        }
+    }
+    decoded
+}
+
+/// A `Transfer` or `TransferWithFee` instruction
+pub(crate) struct DecodedTransfer {
+    pub(crate) source: Pubkey,
+    pub(crate) destination: Pubkey,
+    pub(crate) new_source_decryptable_available_balance: PodAeCiphertext,
+    pub(crate) transfer_amount_auditor_ciphertext_lo: PodElGamalCiphertext,
+    pub(crate) transfer_amount_auditor_ciphertext_hi: PodElGamalCiphertext,
+    /// The transfer amount, including the fee
+    pub(crate) amount: TransferAmount,
+    /// The fee withheld from the destination, `None` for transfers without a fee, and
+    /// transfers to the source itself which are exempt from fees
+    pub(crate) fee: Option<TransferAmount<FeeCiphertexts>>,
+}
+
+/// Decodes the transfer instruction at `index`, locating the ciphertext validity proofs of its
+/// amount and fee
+pub(crate) fn decode_transfer(
+    account_keys: &[Pubkey],
+    instructions: &[CompiledInstruction],
+    index: usize,
+    instruction_type: ConfidentialTransferInstruction,
+) -> Option<DecodedTransfer> {
+    let instruction = instructions.get(index)?;
+    let data = instruction.data.get(1..)?;
+    let account = |position: usize| {
+        instruction
+            .accounts
+            .get(position)
+            .and_then(|index| account_keys.get(*index as usize))
+            .copied()
+    };
+    let source = account(0)?;
+    let destination = account(2)?;
+    // the source, mint and destination are followed by the instructions sysvar if any proof is
+    // verified in the same transaction
+    let first_proof_account =
+        |offsets: &[i8]| 3 + usize::from(offsets.iter().any(|offset| *offset != 0));
+
+    match instruction_type {
+        ConfidentialTransferInstruction::Transfer => {
+            let transfer = decode_instruction_data::<TransferInstructionData>(data).ok()?;
+            let offsets = [
+                transfer.equality_proof_instruction_offset,
+                transfer.ciphertext_validity_proof_instruction_offset,
+                transfer.range_proof_instruction_offset,
+            ];
+            let first_proof_account = first_proof_account(&offsets);
+            Some(DecodedTransfer {
+                source,
+                destination,
+                new_source_decryptable_available_balance: transfer
+                    .new_source_decryptable_available_balance,
+                transfer_amount_auditor_ciphertext_lo: transfer
+                    .transfer_amount_auditor_ciphertext_lo,
+                transfer_amount_auditor_ciphertext_hi: transfer
+                    .transfer_amount_auditor_ciphertext_hi,
+                amount: locate_proof(
+                    account_keys,
+                    instructions,
+                    index,
+                    first_proof_account,
+                    &offsets,
+                    1,
+                ),
+                fee: None,
+            })
+        }
+        ConfidentialTransferInstruction::TransferWithFee => {
+            let transfer = decode_instruction_data::<TransferWithFeeInstructionData>(data).ok()?;
+            let offsets = [
+                transfer.equality_proof_instruction_offset,
+                transfer.transfer_amount_ciphertext_validity_proof_instruction_offset,
+                transfer.fee_sigma_proof_instruction_offset,
+                transfer.fee_ciphertext_validity_proof_instruction_offset,
+                transfer.range_proof_instruction_offset,
+            ];
+            let first_proof_account = first_proof_account(&offsets);
+            Some(DecodedTransfer {
+                source,
+                destination,
+                new_source_decryptable_available_balance: transfer
+                    .new_source_decryptable_available_balance,
+                transfer_amount_auditor_ciphertext_lo: transfer
+                    .transfer_amount_auditor_ciphertext_lo,
+                transfer_amount_auditor_ciphertext_hi: transfer
+                    .transfer_amount_auditor_ciphertext_hi,
+                amount: locate_proof(
+                    account_keys,
+                    instructions,
+                    index,
+                    first_proof_account,
+                    &offsets,
+                    1,
+                ),
+                fee: (source != destination).then(|| {
+                    locate_proof(
+                        account_keys,
+                        instructions,
+                        index,
+                        first_proof_account,
+                        &offsets,
+                        3,
+                    )
+                }),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// A `Mint` or `Burn` instruction of the confidential mint burn extension
+pub(crate) enum DecodedMintBurn {
+    Mint {
+        token_account: Pubkey,
+        mint: Pubkey,
+        data: MintInstructionData,
+        amount: TransferAmount,
+    },
+    Burn {
+        token_account: Pubkey,
+        mint: Pubkey,
+        data: BurnInstructionData,
+        amount: TransferAmount,
+    },
+}
+
+/// Decodes the confidential mint or burn instruction at `index`, locating the ciphertext
+/// validity proof of its amount
+pub(crate) fn decode_mint_burn(
+    account_keys: &[Pubkey],
+    instructions: &[CompiledInstruction],
+    index: usize,
+) -> Option<DecodedMintBurn> {
+    let instruction = instructions.get(index)?;
+    let data = instruction.data.get(1..)?;
+    let account = |position: usize| {
+        instruction
+            .accounts
+            .get(position)
+            .and_then(|index| account_keys.get(*index as usize))
+            .copied()
+    };
+    let token_account = account(0)?;
+    let mint = account(1)?;
+    // the token account and mint are followed by the instructions sysvar only if the equality
+    // proof is verified in the same transaction
+    let first_proof_account = |offsets: &[i8; 3]| 2 + usize::from(offsets[0] != 0);
+
+    match decode_instruction_type::<ConfidentialMintBurnInstruction>(data).ok()? {
+        ConfidentialMintBurnInstruction::Mint => {
+            let data = *decode_instruction_data::<MintInstructionData>(data).ok()?;
+            let offsets = [
+                data.equality_proof_instruction_offset,
+                data.ciphertext_validity_proof_instruction_offset,
+                data.range_proof_instruction_offset,
+            ];
+            Some(DecodedMintBurn::Mint {
+                token_account,
+                mint,
+                amount: locate_proof(
+                    account_keys,
+                    instructions,
+                    index,
+                    first_proof_account(&offsets),
+                    &offsets,
+                    1,
+                ),
+                data,
+            })
+        }
+        ConfidentialMintBurnInstruction::Burn => {
+            let data = *decode_instruction_data::<BurnInstructionData>(data).ok()?;
+            let offsets = [
+                data.equality_proof_instruction_offset,
+                data.ciphertext_validity_proof_instruction_offset,
+                data.range_proof_instruction_offset,
+            ];
+            Some(DecodedMintBurn::Burn {
+                token_account,
+                mint,
+                amount: locate_proof(
+                    account_keys,
+                    instructions,
+                    index,
+                    first_proof_account(&offsets),
+                    &offsets,
+                    1,
+                ),
+                data,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Locates the ciphertext validity proof at position `proof` of the proof instruction `offsets`
+/// of the instruction at `index`, which are in the order the token program reads them
+///
+/// The proof accounts of the instruction start at position `first_proof_account`
+fn locate_proof<C: VerifiedCiphertexts>(
+    account_keys: &[Pubkey],
+    instructions: &[CompiledInstruction],
+    index: usize,
+    first_proof_account: usize,
+    offsets: &[i8],
+    proof: usize,
+) -> TransferAmount<C> {
+    let proof_instruction = |offset: i8| {
+        index
+            .checked_add_signed(offset as isize)
+            .and_then(|index| instructions.get(index))
+    };
+
+    let offset = offsets[proof];
+    if offset != 0 {
+        return proof_instruction(offset)
+            .and_then(|instruction| C::verified(account_keys, instruction))
+            .map(|ciphertexts| TransferAmount::Ciphertexts(Box::new(ciphertexts)))
+            .unwrap_or(TransferAmount::Unknown);
+    }
+
+    // every earlier proof has an account which is either a context state account or the record
+    // account an instruction reads the proof from
+    let position = first_proof_account
+        + offsets[..proof]
+            .iter()
+            .filter(|offset| {
+                **offset == 0
+                    || proof_instruction(**offset).is_some_and(|proof| !proof.accounts.is_empty())
+            })
+            .count();
+
+    instructions
+        .get(index)
+        .and_then(|instruction| instruction.accounts.get(position))
+        .and_then(|index| account_keys.get(*index as usize))
+        .map(|context_state_account| TransferAmount::ContextStateAccount(*context_state_account))
+        .unwrap_or(TransferAmount::Unknown)
+}
+
+/// Ciphertexts checked by a ciphertext validity proof
+pub trait VerifiedCiphertexts: Sized {
+    /// Returns the ciphertexts if `instruction` verifies their proof with the proof data
+    /// included in the instruction
+    fn verified(account_keys: &[Pubkey], instruction: &CompiledInstruction) -> Option<Self>;
+}
+
+impl VerifiedCiphertexts for TransferAmountCiphertexts {
+    fn verified(account_keys: &[Pubkey], instruction: &CompiledInstruction) -> Option<Self> {
+        if !is_proof_instruction(
+            account_keys,
+            instruction,
+            ProofInstruction::VerifyBatchedGroupedCiphertext3HandlesValidity,
+        ) {
+            return None;
+        }
+        let proof_data = ProofInstruction::proof_data::<
+            BatchedGroupedCiphertext3HandlesValidityProofData,
+            BatchedGroupedCiphertext3HandlesValidityProofContext,
+        >(&instruction.data)?;
+        Some(Self {
+            lo: proof_data.context.grouped_ciphertext_lo,
+            hi: proof_data.context.grouped_ciphertext_hi,
+        })
+    }
+}
+
+impl VerifiedCiphertexts for FeeCiphertexts {
+    fn verified(account_keys: &[Pubkey], instruction: &CompiledInstruction) -> Option<Self> {
+        if !is_proof_instruction(
+            account_keys,
+            instruction,
+            ProofInstruction::VerifyBatchedGroupedCiphertext2HandlesValidity,
+        ) {
+            return None;
+        }
+        let proof_data = ProofInstruction::proof_data::<
+            BatchedGroupedCiphertext2HandlesValidityProofData,
+            BatchedGroupedCiphertext2HandlesValidityProofContext,
+        >(&instruction.data)?;
+        Some(Self {
+            lo: proof_data.context.grouped_ciphertext_lo,
+            hi: proof_data.context.grouped_ciphertext_hi,
+        })
+    }
+}
+
+/// Checks that `instruction` is a zk elgamal proof program instruction of type `proof`
+fn is_proof_instruction(
+    account_keys: &[Pubkey],
+    instruction: &CompiledInstruction,
+    proof: ProofInstruction,
+) -> bool {
+    account_keys.get(instruction.program_id_index as usize) == Some(&zk_elgamal_proof_program::id())
+        && ProofInstruction::instruction_type(&instruction.data) == Some(proof)
+}
+
+/// Finds the ciphertexts verified into a context state account by one of `instructions`
+pub fn context_state_ciphertexts<C: VerifiedCiphertexts>(
+    context_state_account: &Pubkey,
+    account_keys: &[Pubkey],
+    instructions: &[CompiledInstruction],
+) -> Option<TransferAmount<C>> {
+    instructions.iter().find_map(|instruction| {
+        // with the proof data included, the context state account is the first account
+        let account = instruction
+            .accounts
+            .first()
+            .and_then(|index| account_keys.get(*index as usize))?;
+        if account != context_state_account {
+            return None;
+        }
+        let ciphertexts = C::verified(account_keys, instruction)?;
+        Some(TransferAmount::Ciphertexts(Box::new(ciphertexts)))
+    })
+}
+
+/// Replays the events of a token account oldest first, decrypting amounts and computing the
+/// running balances after each event
+///
+/// Decryption is cpu bound and should not be called from an async context
+pub fn replay(
+    events: &[HistoryEvent],
+    credentials: &Credentials,
+    discrete_log: &DiscreteLogTable,
+) -> Vec<ApiHistoryEntry> {
+    // balances are unknown until the account is configured, or an instruction reveals them
+    let mut pending_balance: Option<u64> = None;
+    let mut available_balance: Option<u64> = None;
+    let mut non_confidential_balance: Option<u64> = None;
+
+    let mut entries = Vec::with_capacity(events.len());
+    for event in events {
+        if event.non_confidential_balance.is_some() {
+            non_confidential_balance = event.non_confidential_balance;
+        }
+
+        let (kind, amount, counterparty) = match event.instruction.clone() {
+            ConfidentialInstruction::ConfigureAccount => {
+                pending_balance = Some(0);
+                available_balance = Some(0);
+                (ApiHistoryEntryKind::ConfigureAccount, None, None)
+            }
+            ConfidentialInstruction::Deposit { amount } => {
+                pending_balance = pending_balance.and_then(|balance| balance.checked_add(amount));
+                (ApiHistoryEntryKind::Deposit, Some(amount), None)
+            }
+            ConfidentialInstruction::Withdraw {
+                amount,
+                new_decryptable_available_balance,
+            } => {
+                available_balance =
+                    decrypt_ae_balance(&credentials.ae_key, new_decryptable_available_balance);
+                (ApiHistoryEntryKind::Withdraw, Some(amount), None)
+            }
+            ConfidentialInstruction::ApplyPendingBalance {
+                new_decryptable_available_balance,
+            } => {
+                let new_available_balance =
+                    decrypt_ae_balance(&credentials.ae_key, new_decryptable_available_balance);
+                let amount = new_available_balance
+                    .zip(available_balance)
+                    .and_then(|(new, previous)| new.checked_sub(previous));
+                // credits racing the apply are rare, so without a known pending balance
+                // assume all of it was applied
+                pending_balance = match (pending_balance, amount) {
+                    (Some(pending_balance), Some(amount)) => {
+                        Some(pending_balance.saturating_sub(amount))
+                    }
+                    _ => Some(0),
+                };
+                available_balance = new_available_balance;
+                (ApiHistoryEntryKind::ApplyPendingBalance, amount, None)
+            }
+            ConfidentialInstruction::TransferOut {
+                destination,
+                new_source_decryptable_available_balance,
+            } => {
+                let new_available_balance = decrypt_ae_balance(
+                    &credentials.ae_key,
+                    new_source_decryptable_available_balance,
+                );
+                let amount = available_balance
+                    .zip(new_available_balance)
+                    .and_then(|(previous, new)| previous.checked_sub(new));
+                available_balance = new_available_balance;
+                (ApiHistoryEntryKind::TransferOut, amount, Some(destination))
+            }
+            ConfidentialInstruction::TransferIn {
+                source,
+                amount,
+                fee,
+            } => {
+                let amount = match amount {
+                    TransferAmount::Ciphertexts(ciphertexts) => decrypt_transfer_amount(
+                        discrete_log,
+                        &credentials.elgamal_secret,
+                        ciphertexts.lo,
+                        ciphertexts.hi,
+                        DESTINATION_HANDLE,
+                    )
+                    .inspect_err(|err| {
+                        log::warn!("failed to decrypt transfer {} {err:#}", event.signature)
+                    })
+                    .ok(),
+                    _ => None,
+                };
+                // the fee is withheld from the amount credited to the pending balance
+                let fee = match fee {
+                    None => Some(0),
+                    Some(TransferAmount::Ciphertexts(ciphertexts)) => decrypt_transfer_fee(
+                        discrete_log,
+                        &credentials.elgamal_secret,
+                        ciphertexts.lo,
+                        ciphertexts.hi,
+                        FEE_DESTINATION_HANDLE,
+                    )
+                    .inspect_err(|err| {
+                        log::warn!("failed to decrypt transfer fee {} {err:#}", event.signature)
+                    })
+                    .ok(),
+                    Some(_) => None,
+                };
+                let amount = amount
+                    .zip(fee)
+                    .and_then(|(amount, fee)| amount.checked_sub(fee));
+                pending_balance = pending_balance
+                    .zip(amount)
+                    .and_then(|(balance, amount)| balance.checked_add(amount));
+                (ApiHistoryEntryKind::TransferIn, amount, Some(source))
+            }
+            ConfidentialInstruction::Mint { amount } => {
+                let amount = match amount {
+                    TransferAmount::Ciphertexts(ciphertexts) => decrypt_transfer_amount(
+                        discrete_log,
+                        &credentials.elgamal_secret,
+                        ciphertexts.lo,
+                        ciphertexts.hi,
+                        MINT_DESTINATION_HANDLE,
+                    )
+                    .inspect_err(|err| {
+                        log::warn!("failed to decrypt mint {} {err:#}", event.signature)
+                    })
+                    .ok(),
+                    _ => None,
+                };
+                pending_balance = pending_balance
+                    .zip(amount)
+                    .and_then(|(balance, amount)| balance.checked_add(amount));
+                (ApiHistoryEntryKind::Mint, amount, None)
+            }
+            ConfidentialInstruction::Burn {
+                new_decryptable_available_balance,
+            } => {
+                let new_available_balance =
+                    decrypt_ae_balance(&credentials.ae_key, new_decryptable_available_balance);
+                let amount = available_balance
+                    .zip(new_available_balance)
+                    .and_then(|(previous, new)| previous.checked_sub(new));
+                available_balance = new_available_balance;
+                (ApiHistoryEntryKind::Burn, amount, None)
+            }
+            ConfidentialInstruction::EmptyAccount => {
+                available_balance = Some(0);
+                (ApiHistoryEntryKind::EmptyAccount, None, None)
+            }
+        };
+
+        entries.push(ApiHistoryEntry {
+            signature: event.signature,
+            slot: event.slot,
+            block_time: event.block_time,
+            kind,
+            amount,
+            counterparty,
+            pending_balance_amount: pending_balance,
+            available_balance_amount: available_balance,
+            non_confidential_balance_amount: non_confidential_balance,
+        });
+    }
+    entries
+}
+
+fn decrypt_ae_balance(ae_key: &AeKey, ciphertext: PodAeCiphertext) -> Option<u64> {
+    let ciphertext = TryInto::<AeCiphertext>::try_into(ciphertext).ok()?;
+    ae_key.decrypt(&ciphertext)
+}
+
+/// A page of the history of a token account
+pub struct HistoryPage {
+    /// The events of the page, oldest first
+    pub events: Vec<HistoryEvent>,
+    /// Number of leading `events` which are older than the page, and were only fetched to
+    /// recover the balances at the start of the page
+    pub lookback: usize,
+    /// Cursor of the next page, `None` once the oldest transaction was reached
+    pub before: Option<Signature>,
+}
+
+/// Fetches the confidential transfer instructions of at most `limit` transactions of
+/// `token_account` older than `before`
+///
+/// Signatures are requested from the rpc node in batches no larger than what is left of the
+/// page, so only the transactions of the page are fetched, and pages never split the
+/// instructions of a transaction. Up to [`MAX_LOOKBACK`] older transactions are fetched until
+/// one reveals both balances, so the running balances of the page can be replayed
+pub async fn fetch_events(
+    rpc: &RpcClient,
+    token_account: &Pubkey,
+    before: Option<Signature>,
+    limit: usize,
+) -> Result<HistoryPage> {
+    // the events of every transaction, newest transaction first
+    let mut page: Vec<Vec<HistoryEvent>> = vec![];
+    let mut cursor = before;
+    let mut exhausted = false;
+    while !exhausted && page.len() < limit {
+        let batch;
+        (batch, cursor, exhausted) =
+            fetch_transaction_events(rpc, token_account, cursor, limit - page.len()).await?;
+        page.extend(batch);
+    }
+    let next_before = if exhausted { None } else { cursor };
+
+    let mut lookback: Vec<Vec<HistoryEvent>> = vec![];
+    let mut fetched = 0;
+    while !exhausted && fetched < MAX_LOOKBACK && !lookback.iter().flatten().any(reveals_balances) {
+        let batch = LOOKBACK_BATCH.min(MAX_LOOKBACK - fetched);
+        let transactions;
+        (transactions, cursor, exhausted) =
+            fetch_transaction_events(rpc, token_account, cursor, batch).await?;
+        fetched += batch;
+        lookback.extend(transactions);
+    }
+    // only the events since the newest transaction revealing the balances are replayed
+    if let Some(position) = lookback
+        .iter()
+        .position(|events| events.iter().any(reveals_balances))
+    {
+        lookback.truncate(position + 1);
+    }
+    let lookback = lookback.into_iter().rev().flatten().collect::<Vec<_>>();
+
+    let mut events = lookback;
+    let lookback = events.len();
+    events.extend(page.into_iter().rev().flatten());
+
+    // incoming transfers and mints verified with context state accounts carry their ciphertexts
+    // in the transaction which created the context state account
+    for event in events.iter_mut() {
+        match &mut event.instruction {
+            ConfidentialInstruction::TransferIn { amount, fee, .. } => {
+                resolve_context_state(rpc, amount).await?;
+                if let Some(fee) = fee {
+                    resolve_context_state(rpc, fee).await?;
+                }
+            }
+            ConfidentialInstruction::Mint { amount } => resolve_context_state(rpc, amount).await?,
+            _ => {}
+        }
+    }
+
+    Ok(HistoryPage {
+        events,
+        lookback,
+        before: next_before,
+    })
+}
+
+/// Replaces a context state account with the ciphertexts verified into it, found in the
+/// transaction which created the account
+async fn resolve_context_state<C: VerifiedCiphertexts>(
+    rpc: &RpcClient,
+    amount: &mut TransferAmount<C>,
+) -> Result<()> {
+    let TransferAmount::ContextStateAccount(context_state_account) = *amount else {
+        return Ok(());
+    };
+    *amount = get_transactions(rpc, &context_state_account, None)
+        .await?
+        .iter()
+        .find_map(|transaction| {
+            context_state_ciphertexts(
+                &context_state_account,
+                &transaction.account_keys,
+                &transaction.instructions,
+            )
+        })
+        .unwrap_or(TransferAmount::Unknown);
+    Ok(())
+}
+
+/// Whether the balances after `event` are known without any earlier event
+fn reveals_balances(event: &HistoryEvent) -> bool {
+    matches!(
+        event.instruction,
+        ConfidentialInstruction::ConfigureAccount
+            | ConfidentialInstruction::ApplyPendingBalance { .. }
+    )
+}
+
+/// Fetches the events of the next `limit` transactions of `token_account` older than `before`,
+/// newest transaction first, skipping transactions without confidential transfer instructions
+///
+/// Returns the events, the signature to continue from, and whether the oldest transaction
+/// was reached
+async fn fetch_transaction_events(
+    rpc: &RpcClient,
+    token_account: &Pubkey,
+    before: Option<Signature>,
+    limit: usize,
+) -> Result<(Vec<Vec<HistoryEvent>>, Option<Signature>, bool)> {
+    let limit = limit.min(MAX_SIGNATURES);
+    let signatures = rpc
+        .get_signatures_for_address_with_config(
+            token_account,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some(limit),
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )
+        .await
+        .with_context(|| format!("failed to get signatures for {token_account}"))?;
+    let exhausted = signatures.len() < limit;
+    let cursor = match signatures.last() {
+        Some(last) => Some(
+            Signature::from_str(&last.signature)
+                .with_context(|| format!("invalid signature {}", last.signature))?,
+        ),
+        None => before,
+    };
+
+    let signatures = signatures
+        .into_iter()
+        .filter(|signature| signature.err.is_none())
+        .map(|signature| signature.signature)
+        .collect();
+    let events = fetch_transactions(rpc, signatures)
+        .await?
+        .iter()
+        .map(|transaction| transaction_events(token_account, transaction))
+        .filter(|events| !events.is_empty())
+        .collect();
+    Ok((events, cursor, exhausted))
+}
+
+/// Decodes the confidential transfer instructions of `transaction` affecting `token_account`
+fn transaction_events(
+    token_account: &Pubkey,
+    transaction: &ConfirmedTransaction,
+) -> Vec<HistoryEvent> {
+    let non_confidential_balance = transaction
+        .account_keys
+        .iter()
+        .position(|key| key == token_account)
+        .and_then(|position| {
+            transaction
+                .post_token_balances
+                .iter()
+                .find(|balance| balance.account_index as usize == position)
+        })
+        .and_then(|balance| balance.ui_token_amount.amount.parse().ok());
+
+    decode_instructions(
+        token_account,
+        &transaction.account_keys,
+        &transaction.instructions,
+    )
+    .into_iter()
+    .map(|instruction| HistoryEvent {
+        signature: transaction.signature,
+        slot: transaction.slot,
+        block_time: transaction.block_time,
+        instruction,
+        non_confidential_balance,
+    })
+    .collect()
+}
+
+/// Fetches every successful transaction which referenced `address` after `until`, oldest first
+pub(crate) async fn get_transactions(
+    rpc: &RpcClient,
+    address: &Pubkey,
+    until: Option<Signature>,
+) -> Result<Vec<ConfirmedTransaction>> {
+    let mut signatures = vec![];
+    let mut before = None;
+    loop {
+        let page = rpc
+            .get_signatures_for_address_with_config(
+                address,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until,
+                    limit: Some(MAX_SIGNATURES),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )
+            .await
+            .with_context(|| format!("failed to get signatures for {address}"))?;
+        let exhausted = page.len() < MAX_SIGNATURES;
+        if let Some(last) = page.last() {
+            before = Some(
+                Signature::from_str(&last.signature)
+                    .with_context(|| format!("invalid signature {}", last.signature))?,
+            );
+        }
+        signatures.extend(
+            page.into_iter()
+                .filter(|signature| signature.err.is_none())
+                .map(|signature| signature.signature),
+        );
+        if exhausted {
+            break;
+        }
+    }
+    signatures.reverse();
+
+    fetch_transactions(rpc, signatures).await
+}
+
+/// Fetches the successful transactions of `signatures`, keeping their order
+async fn fetch_transactions(
+    rpc: &RpcClient,
+    signatures: Vec<String>,
+) -> Result<Vec<ConfirmedTransaction>> {
+    futures::stream::iter(signatures)
+        .map(|signature| get_transaction(rpc, signature))
+        .buffered(CONCURRENT_REQUESTS)
+        .try_filter_map(|transaction| async move { Ok(transaction) })
+        .try_collect()
+        .await
+}
+
+/// Fetches a transaction, returning `None` if it failed
+async fn get_transaction(
+    rpc: &RpcClient,
+    signature: String,
+) -> Result<Option<ConfirmedTransaction>> {
+    let signature = Signature::from_str(&signature)
+        .with_context(|| format!("invalid signature {signature}"))?;
+    let confirmed = rpc
+        .get_transaction_with_config(
+            &signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await
+        .with_context(|| format!("failed to get transaction {signature}"))?;
+
+    let meta = confirmed
+        .transaction
+        .meta
+        .ok_or_else(|| anyhow!("transaction {signature} is missing its status"))?;
+    if meta.err.is_some() {
+        return Ok(None);
+    }
+    let transaction = confirmed
+        .transaction
+        .transaction
+        .decode()
+        .ok_or_else(|| anyhow!("failed to decode transaction {signature}"))?;
+
+    // accounts loaded from lookup tables follow the static account keys
+    let mut account_keys = transaction.message.static_account_keys().to_vec();
+    if let Some(loaded_addresses) = Option::<UiLoadedAddresses>::from(meta.loaded_addresses) {
+        for address in loaded_addresses
+            .writable
+            .iter()
+            .chain(loaded_addresses.readonly.iter())
+        {
+            account_keys.push(
+                Pubkey::from_str(address)
+                    .with_context(|| format!("invalid loaded address {address}"))?,
+            );
+        }
+    }
+
+    Ok(Some(ConfirmedTransaction {
+        signature,
+        slot: confirmed.slot,
+        block_time: confirmed.block_time,
+        account_keys,
+        instructions: transaction.message.instructions().to_vec(),
+        post_token_balances: Option::from(meta.post_token_balances).unwrap_or_default(),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        common::discrete_log::MIN_BABY_STEP_BITS,
+        solana_account_decoder_client_types::token::UiTokenAmount,
+        solana_sdk::{instruction::Instruction, message::Message},
+        solana_transaction_status_client_types::option_serializer::OptionSerializer,
+        spl_token_2022::{
+            extension::{
+                confidential_mint_burn::instruction::{
+                    confidential_burn_with_split_proofs, confidential_mint_with_split_proofs,
+                },
+                confidential_transfer::instruction::{
+                    deposit, inner_apply_pending_balance, inner_transfer, inner_transfer_with_fee,
+                },
+            },
+            solana_zk_sdk::{
+                encryption::{
+                    elgamal::ElGamalKeypair, grouped_elgamal::GroupedElGamal,
+                    pedersen::PedersenOpening, pod::elgamal::PodElGamalCiphertext,
+                },
+                zk_elgamal_proof_program::instruction::ContextStateInfo,
+            },
+        },
+        spl_token_confidential_transfer_proof_extraction::instruction::ProofLocation,
+    };
+
+    fn compile(
+        instructions: &[Instruction],
+        payer: &Pubkey,
+    ) -> (Vec<Pubkey>, Vec<CompiledInstruction>) {
+        let message = Message::new(instructions, Some(payer));
+        (message.account_keys, message.instructions)
+    }
+
+    fn event(signature: Signature, instruction: ConfidentialInstruction) -> HistoryEvent {
+        HistoryEvent {
+            signature,
+            slot: 1,
+            block_time: None,
+            instruction,
+            non_confidential_balance: None,
+        }
+    }
+
+    #[test]
+    fn test_decode_and_replay() {
+        let authority = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let source = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let equality_proof_account = Pubkey::new_unique();
+        let ciphertext_validity_proof_account = Pubkey::new_unique();
+        let range_proof_account = Pubkey::new_unique();
+
+        let source_keys = Credentials {
+            elgamal_secret: ElGamalKeypair::new_rand().secret().clone(),
+            ae_key: AeKey::new_rand(),
+        };
+        let destination_elgamal = ElGamalKeypair::new_rand();
+        let destination_keys = Credentials {
+            elgamal_secret: destination_elgamal.secret().clone(),
+            ae_key: AeKey::new_rand(),
+        };
+        let discrete_log = DiscreteLogTable::new(MIN_BABY_STEP_BITS).unwrap();
+
+        // deposit 100, apply it, then transfer 70 to the destination
+        let (account_keys, instructions) = compile(
+            &[
+                deposit(
+                    &spl_token_2022::id(),
+                    &source,
+                    &mint,
+                    100,
+                    6,
+                    &authority,
+                    &[],
+                )
+                .unwrap(),
+                inner_apply_pending_balance(
+                    &spl_token_2022::id(),
+                    &source,
+                    1,
+                    &source_keys.ae_key.encrypt(100).into(),
+                    &authority,
+                    &[],
+                )
+                .unwrap(),
+                inner_transfer(
+                    &spl_token_2022::id(),
+                    &source,
+                    &mint,
+                    &destination,
+                    &source_keys.ae_key.encrypt(30).into(),
+                    &PodElGamalCiphertext::default(),
+                    &PodElGamalCiphertext::default(),
+                    &authority,
+                    &[],
+                    ProofLocation::ContextStateAccount(&equality_proof_account),
+                    ProofLocation::ContextStateAccount(&ciphertext_validity_proof_account),
+                    ProofLocation::ContextStateAccount(&range_proof_account),
+                )
+                .unwrap(),
+            ],
+            &authority,
+        );
+
+        let decoded = decode_instructions(&source, &account_keys, &instructions);
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[0], ConfidentialInstruction::Deposit { amount: 100 });
+        assert!(matches!(
+            decoded[2],
+            ConfidentialInstruction::TransferOut { destination: d, .. } if d == destination
+        ));
+
+        let destination_decoded = decode_instructions(&destination, &account_keys, &instructions);
+        assert_eq!(
+            destination_decoded,
+            vec![ConfidentialInstruction::TransferIn {
+                source,
+                amount: TransferAmount::ContextStateAccount(ciphertext_validity_proof_account),
+                fee: None,
+            }]
+        );
+
+        // not touching the account at all
+        assert!(
+            decode_instructions(&Pubkey::new_unique(), &account_keys, &instructions).is_empty()
+        );
+
+        let signatures = [Signature::new_unique(), Signature::new_unique()];
+        let mut events = vec![event(
+            signatures[0],
+            ConfidentialInstruction::ConfigureAccount,
+        )];
+        events.extend(
+            decoded
+                .into_iter()
+                .map(|instruction| event(signatures[1], instruction)),
+        );
+        let entries = replay(&events, &source_keys, &discrete_log);
+        let balances = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.kind,
+                    entry.amount,
+                    entry.pending_balance_amount,
+                    entry.available_balance_amount,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            balances,
+            vec![
+                (
+                    ApiHistoryEntryKind::ConfigureAccount,
+                    None,
+                    Some(0),
+                    Some(0)
+                ),
+                (ApiHistoryEntryKind::Deposit, Some(100), Some(100), Some(0)),
+                (
+                    ApiHistoryEntryKind::ApplyPendingBalance,
+                    Some(100),
+                    Some(0),
+                    Some(100)
+                ),
+                (
+                    ApiHistoryEntryKind::TransferOut,
+                    Some(70),
+                    Some(0),
+                    Some(30)
+                ),
+            ]
+        );
+        assert_eq!(entries[3].counterparty, Some(destination));
+
+        // the transfer amount is recovered from the transaction verifying the context state account
+        let opening_lo = PedersenOpening::new_rand();
+        let opening_hi = PedersenOpening::new_rand();
+        let auditor = ElGamalKeypair::new_rand();
+        let source_elgamal = ElGamalKeypair::new_rand();
+        let pubkeys = [
+            source_elgamal.pubkey(),
+            destination_elgamal.pubkey(),
+            auditor.pubkey(),
+        ];
+        let grouped_ciphertext_lo = GroupedElGamal::encrypt_with(pubkeys, 70u64, &opening_lo);
+        let grouped_ciphertext_hi = GroupedElGamal::encrypt_with(pubkeys, 0u64, &opening_hi);
+        let proof_data = BatchedGroupedCiphertext3HandlesValidityProofData::new(
+            source_elgamal.pubkey(),
+            destination_elgamal.pubkey(),
+            auditor.pubkey(),
+            &grouped_ciphertext_lo,
+            &grouped_ciphertext_hi,
+            70,
+            0,
+            &opening_lo,
+            &opening_hi,
+        )
+        .unwrap();
+        let (account_keys, instructions) = compile(
+            &[
+                ProofInstruction::VerifyBatchedGroupedCiphertext3HandlesValidity
+                    .encode_verify_proof(
+                        Some(ContextStateInfo {
+                            context_state_account: &ciphertext_validity_proof_account,
+                            context_state_authority: &authority,
+                        }),
+                        &proof_data,
+                    ),
+            ],
+            &authority,
+        );
+        let amount = context_state_ciphertexts(
+            &ciphertext_validity_proof_account,
+            &account_keys,
+            &instructions,
+        )
+        .unwrap();
+        assert!(context_state_ciphertexts::<TransferAmountCiphertexts>(
+            &range_proof_account,
+            &account_keys,
+            &instructions
+        )
+        .is_none());
+
+        let events = vec![
+            event(signatures[0], ConfidentialInstruction::ConfigureAccount),
+            event(
+                signatures[1],
+                ConfidentialInstruction::TransferIn {
+                    source,
+                    amount,
+                    fee: None,
+                },
+            ),
+        ];
+        let entries = replay(&events, &destination_keys, &discrete_log);
+        assert_eq!(entries[1].kind, ApiHistoryEntryKind::TransferIn);
+        assert_eq!(entries[1].amount, Some(70));
+        assert_eq!(entries[1].counterparty, Some(source));
+        assert_eq!(entries[1].pending_balance_amount, Some(70));
+        assert_eq!(entries[1].available_balance_amount, Some(0));
+
+        // without a located proof the amount and pending balance are unknown
+        let events = vec![
+            event(signatures[0], ConfidentialInstruction::ConfigureAccount),
+            event(
+                signatures[1],
+                ConfidentialInstruction::TransferIn {
+                    source,
+                    amount: TransferAmount::Unknown,
+                    fee: None,
+                },
+            ),
+        ];
+        let entries = replay(&events, &destination_keys, &discrete_log);
+        assert_eq!(entries[1].amount, None);
+        assert_eq!(entries[1].pending_balance_amount, None);
+    }
+
+    #[test]
+    fn test_decode_and_replay_transfer_with_fee() {
+        let authority = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let source = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let proof_accounts = [(); 5].map(|_| Pubkey::new_unique());
+        let transfer_with_fee = |destination: &Pubkey| {
+            inner_transfer_with_fee(
+                &spl_token_2022::id(),
+                &source,
+                &mint,
+                destination,
+                &AeKey::new_rand().encrypt(0).into(),
+                &PodElGamalCiphertext::default(),
+                &PodElGamalCiphertext::default(),
+                &authority,
+                &[],
+                ProofLocation::ContextStateAccount(&proof_accounts[0]),
+                ProofLocation::ContextStateAccount(&proof_accounts[1]),
+                ProofLocation::ContextStateAccount(&proof_accounts[2]),
+                ProofLocation::ContextStateAccount(&proof_accounts[3]),
+                ProofLocation::ContextStateAccount(&proof_accounts[4]),
+            )
+            .unwrap()
+        };
+
+        // the amount and fee ciphertext validity proofs follow the equality and fee sigma proofs
+        let (account_keys, instructions) = compile(&[transfer_with_fee(&destination)], &authority);
+        assert_eq!(
+            decode_instructions(&destination, &account_keys, &instructions),
+            vec![ConfidentialInstruction::TransferIn {
+                source,
+                amount: TransferAmount::ContextStateAccount(proof_accounts[1]),
+                fee: Some(TransferAmount::ContextStateAccount(proof_accounts[3])),
+            }]
+        );
+        assert!(matches!(
+            decode_instructions(&source, &account_keys, &instructions)[..],
+            [ConfidentialInstruction::TransferOut { destination: d, .. }] if d == destination
+        ));
+
+        // transfers to the source itself are exempt from fees
+        let (account_keys, instructions) = compile(&[transfer_with_fee(&source)], &authority);
+        assert!(matches!(
+            decode_instructions(&source, &account_keys, &instructions)[..],
+            [
+                ConfidentialInstruction::TransferOut { .. },
+                ConfidentialInstruction::TransferIn { fee: None, .. }
+            ]
+        ));
+
+        // a transfer of 100 with a fee of 1 credits 99 to the destination
+        let destination_elgamal = ElGamalKeypair::new_rand();
+        let destination_keys = Credentials {
+            elgamal_secret: destination_elgamal.secret().clone(),
+            ae_key: AeKey::new_rand(),
+        };
+        let source_elgamal = ElGamalKeypair::new_rand();
+        let auditor = ElGamalKeypair::new_rand();
+        let withdraw_withheld_authority = ElGamalKeypair::new_rand();
+        let openings = [(); 4].map(|_| PedersenOpening::new_rand());
+        let pubkeys = [
+            source_elgamal.pubkey(),
+            destination_elgamal.pubkey(),
+            auditor.pubkey(),
+        ];
+        let amount_lo = GroupedElGamal::encrypt_with(pubkeys, 100u64, &openings[0]);
+        let amount_hi = GroupedElGamal::encrypt_with(pubkeys, 0u64, &openings[1]);
+        let amount_proof = BatchedGroupedCiphertext3HandlesValidityProofData::new(
+            source_elgamal.pubkey(),
+            destination_elgamal.pubkey(),
+            auditor.pubkey(),
+            &amount_lo,
+            &amount_hi,
+            100,
+            0,
+            &openings[0],
+            &openings[1],
+        )
+        .unwrap();
+        let fee_pubkeys = [
+            destination_elgamal.pubkey(),
+            withdraw_withheld_authority.pubkey(),
+        ];
+        let fee_lo = GroupedElGamal::encrypt_with(fee_pubkeys, 1u64, &openings[2]);
+        let fee_hi = GroupedElGamal::encrypt_with(fee_pubkeys, 0u64, &openings[3]);
+        let fee_proof = BatchedGroupedCiphertext2HandlesValidityProofData::new(
+            destination_elgamal.pubkey(),
+            withdraw_withheld_authority.pubkey(),
+            &fee_lo,
+            &fee_hi,
+            1,
+            0,
+            &openings[2],
+            &openings[3],
+        )
+        .unwrap();
+        let (account_keys, instructions) = compile(
+            &[
+                ProofInstruction::VerifyBatchedGroupedCiphertext3HandlesValidity
+                    .encode_verify_proof(
+                        Some(ContextStateInfo {
+                            context_state_account: &proof_accounts[1],
+                            context_state_authority: &authority,
+                        }),
+                        &amount_proof,
+                    ),
+                ProofInstruction::VerifyBatchedGroupedCiphertext2HandlesValidity
+                    .encode_verify_proof(
+                        Some(ContextStateInfo {
+                            context_state_account: &proof_accounts[3],
+                            context_state_authority: &authority,
+                        }),
+                        &fee_proof,
+                    ),
+            ],
+            &authority,
+        );
+        let amount = context_state_ciphertexts(&proof_accounts[1], &account_keys, &instructions);
+        let fee = context_state_ciphertexts(&proof_accounts[3], &account_keys, &instructions);
+        assert!(matches!(amount, Some(TransferAmount::Ciphertexts(_))));
+        assert!(matches!(fee, Some(TransferAmount::Ciphertexts(_))));
+        // the proofs are not mistaken for one another
+        assert!(context_state_ciphertexts::<FeeCiphertexts>(
+            &proof_accounts[1],
+            &account_keys,
+            &instructions
+        )
+        .is_none());
+
+        let discrete_log = DiscreteLogTable::new(MIN_BABY_STEP_BITS).unwrap();
+        let signatures = [Signature::new_unique(), Signature::new_unique()];
+        let events = vec![
+            event(signatures[0], ConfidentialInstruction::ConfigureAccount),
+            event(
+                signatures[1],
+                ConfidentialInstruction::TransferIn {
+                    source,
+                    amount: amount.unwrap(),
+                    fee: fee.clone(),
+                },
+            ),
+        ];
+        let entries = replay(&events, &destination_keys, &discrete_log);
+        assert_eq!(entries[1].kind, ApiHistoryEntryKind::TransferIn);
+        assert_eq!(entries[1].amount, Some(99));
+        assert_eq!(entries[1].pending_balance_amount, Some(99));
+
+        // with an unknown fee the credited amount is unknown
+        let events = vec![
+            event(signatures[0], ConfidentialInstruction::ConfigureAccount),
+            event(
+                signatures[1],
+                ConfidentialInstruction::TransferIn {
+                    source,
+                    amount: TransferAmount::Ciphertexts(Box::new(TransferAmountCiphertexts {
+                        lo: amount_lo.into(),
+                        hi: amount_hi.into(),
+                    })),
+                    fee: Some(TransferAmount::Unknown),
+                },
+            ),
+        ];
+        let entries = replay(&events, &destination_keys, &discrete_log);
+        assert_eq!(entries[1].amount, None);
+        assert_eq!(entries[1].pending_balance_amount, None);
+    }
+
+    #[test]
+    fn test_decode_and_replay_mint_burn() {
+        let authority = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let token_account = Pubkey::new_unique();
+        let proof_accounts = [(); 6].map(|_| Pubkey::new_unique());
+        let elgamal = ElGamalKeypair::new_rand();
+        let credentials = Credentials {
+            elgamal_secret: elgamal.secret().clone(),
+            ae_key: AeKey::new_rand(),
+        };
+
+        // mint 100 confidential tokens, apply them, then burn 60
+        let mut mint_burn = confidential_mint_with_split_proofs(
+            &spl_token_2022::id(),
+            &token_account,
+            &mint,
+            &PodElGamalCiphertext::default(),
+            &PodElGamalCiphertext::default(),
+            &authority,
+            &[],
+            ProofLocation::ContextStateAccount(&proof_accounts[0]),
+            ProofLocation::ContextStateAccount(&proof_accounts[1]),
+            ProofLocation::ContextStateAccount(&proof_accounts[2]),
+            &AeKey::new_rand().encrypt(100).into(),
+        )
+        .unwrap();
+        mint_burn.push(
+            inner_apply_pending_balance(
+                &spl_token_2022::id(),
+                &token_account,
+                1,
+                &credentials.ae_key.encrypt(100).into(),
+                &authority,
+                &[],
+            )
+            .unwrap(),
+        );
+        mint_burn.extend(
+            confidential_burn_with_split_proofs(
+                &spl_token_2022::id(),
+                &token_account,
+                &mint,
+                &credentials.ae_key.encrypt(40).into(),
+                &PodElGamalCiphertext::default(),
+                &PodElGamalCiphertext::default(),
+                &authority,
+                &[],
+                ProofLocation::ContextStateAccount(&proof_accounts[3]),
+                ProofLocation::ContextStateAccount(&proof_accounts[4]),
+                ProofLocation::ContextStateAccount(&proof_accounts[5]),
+            )
+            .unwrap(),
+        );
+        let (account_keys, instructions) = compile(&mint_burn, &authority);
+
+        let decoded = decode_instructions(&token_account, &account_keys, &instructions);
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(
+            decoded[0],
+            ConfidentialInstruction::Mint {
+                amount: TransferAmount::ContextStateAccount(proof_accounts[1]),
+            }
+        );
+        assert!(matches!(decoded[2], ConfidentialInstruction::Burn { .. }));
+        assert!(
+            decode_instructions(&Pubkey::new_unique(), &account_keys, &instructions).is_empty()
+        );
+
+        // the minted amount is decrypted from the ciphertext validity proof
+        let opening_lo = PedersenOpening::new_rand();
+        let opening_hi = PedersenOpening::new_rand();
+        let supply = ElGamalKeypair::new_rand();
+        let auditor = ElGamalKeypair::new_rand();
+        let pubkeys = [elgamal.pubkey(), supply.pubkey(), auditor.pubkey()];
+        let mint_amount = ConfidentialInstruction::Mint {
+            amount: TransferAmount::Ciphertexts(Box::new(TransferAmountCiphertexts {
+                lo: GroupedElGamal::encrypt_with(pubkeys, 100u64, &opening_lo).into(),
+                hi: GroupedElGamal::encrypt_with(pubkeys, 0u64, &opening_hi).into(),
+            })),
+        };
+
+        let discrete_log = DiscreteLogTable::new(MIN_BABY_STEP_BITS).unwrap();
+        let signatures = [Signature::new_unique(), Signature::new_unique()];
+        let mut events = vec![
+            event(signatures[0], ConfidentialInstruction::ConfigureAccount),
+            event(signatures[1], mint_amount),
+        ];
+        events.extend(
+            decoded[1..]
+                .iter()
+                .map(|instruction| event(signatures[1], instruction.clone())),
+        );
+        let balances = replay(&events, &credentials, &discrete_log)
+            .iter()
+            .map(|entry| {
+                (
+                    entry.kind,
+                    entry.amount,
+                    entry.pending_balance_amount,
+                    entry.available_balance_amount,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            balances,
+            vec![
+                (
+                    ApiHistoryEntryKind::ConfigureAccount,
+                    None,
+                    Some(0),
+                    Some(0)
+                ),
+                (ApiHistoryEntryKind::Mint, Some(100), Some(100), Some(0)),
+                (
+                    ApiHistoryEntryKind::ApplyPendingBalance,
+                    Some(100),
+                    Some(0),
+                    Some(100)
+                ),
+                (ApiHistoryEntryKind::Burn, Some(60), Some(0), Some(40)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_transaction_events() {
+        let authority = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let token_account = Pubkey::new_unique();
+        let (account_keys, instructions) = compile(
+            &[
+                deposit(
+                    &spl_token_2022::id(),
+                    &token_account,
+                    &mint,
+                    100,
+                    6,
+                    &authority,
+                    &[],
+                )
+                .unwrap(),
+                // instructions of other token accounts are skipped
+                deposit(
+                    &spl_token_2022::id(),
+                    &Pubkey::new_unique(),
+                    &mint,
+                    200,
+                    6,
+                    &authority,
+                    &[],
+                )
+                .unwrap(),
+            ],
+            &authority,
+        );
+        let position = account_keys
+            .iter()
+            .position(|key| key == &token_account)
+            .unwrap();
+        let transaction = ConfirmedTransaction {
+            signature: Signature::new_unique(),
+            slot: 7,
+            block_time: Some(1_700_000_000),
+            account_keys,
+            instructions,
+            post_token_balances: vec![UiTransactionTokenBalance {
+                account_index: position as u8,
+                mint: mint.to_string(),
+                ui_token_amount: UiTokenAmount {
+                    ui_amount: None,
+                    decimals: 6,
+                    amount: "900".to_string(),
+                    ui_amount_string: "0.0009".to_string(),
+                },
+                owner: OptionSerializer::None,
+                program_id: OptionSerializer::None,
+            }],
+        };
+
+        let events = transaction_events(&token_account, &transaction);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].signature, transaction.signature);
+        assert_eq!(events[0].slot, 7);
+        assert_eq!(
+            events[0].instruction,
+            ConfidentialInstruction::Deposit { amount: 100 }
+        );
+        assert_eq!(events[0].non_confidential_balance, Some(900));
+
+        // transactions without confidential transfer instructions produce no events
+        let transaction = ConfirmedTransaction {
+            instructions: vec![],
+            ..transaction
+        };
+        assert!(transaction_events(&token_account, &transaction).is_empty());
+    }
+}
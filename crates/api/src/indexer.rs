@@ -0,0 +1,1111 @@
+//! Local indexer for a confidential wrapped mint
+//!
+//! The [`Indexer`] follows every transaction referencing the wrapped mint, along with the
+//! transactions of each token account configured for it, since applying a pending balance or
+//! emptying an account does not reference the mint. The confidential transfer, confidential
+//! mint and burn, and token wrap instructions are decoded with [`decode_instructions`] and
+//! recorded in an [`IndexerStore`] backed by SQLite, which the api queries through the
+//! `/indexer` endpoints.
+
+use {
+    crate::{
+        history::{
+            context_state_ciphertexts, decode_mint_burn, decode_transfer, get_transactions,
+            ConfirmedTransaction, DecodedMintBurn, TransferAmount, VerifiedCiphertexts,
+        },
+        types::{
+            ApiIndexedInstruction, ApiIndexedInstructionKind, ApiIndexerStatusResponse,
+            IndexedInstructions,
+        },
+    },
+    anyhow::{anyhow, Context, Result},
+    rusqlite::{params, Connection, OptionalExtension},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{pubkey::Pubkey, signature::Signature},
+    spl_token_2022::{
+        extension::confidential_transfer::instruction::{
+            ApplyPendingBalanceData, ConfidentialTransferInstruction,
+            ConfigureAccountInstructionData, DepositInstructionData, WithdrawInstructionData,
+        },
+        instruction::{decode_instruction_data, decode_instruction_type, TokenInstruction},
+    },
+    spl_token_wrap::instruction::TokenWrapInstruction,
+    std::{
+        collections::{BTreeMap, HashSet},
+        path::Path,
+        str::FromStr,
+        sync::{Arc, Mutex},
+        time::Duration,
+    },
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS instructions (
+    signature TEXT NOT NULL,
+    instruction_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    kind TEXT NOT NULL,
+    token_account TEXT NOT NULL,
+    counterparty TEXT,
+    amount TEXT,
+    ciphertexts TEXT NOT NULL,
+    accounts TEXT NOT NULL,
+    PRIMARY KEY (signature, instruction_index)
+);
+CREATE INDEX IF NOT EXISTS instructions_token_account ON instructions (token_account);
+CREATE INDEX IF NOT EXISTS instructions_counterparty ON instructions (counterparty);
+CREATE INDEX IF NOT EXISTS instructions_slot ON instructions (slot);
+CREATE TABLE IF NOT EXISTS cursors (
+    address TEXT PRIMARY KEY,
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL
+);
+";
+
+/// SQLite database holding the instructions recorded for a single confidential mint
+pub struct IndexerStore {
+    connection: Mutex<Connection>,
+    token_mint: Pubkey,
+}
+
+impl IndexerStore {
+    /// Opens or creates the database at `path`, failing if it was created for another mint
+    pub fn open(path: impl AsRef<Path>, token_mint: Pubkey) -> Result<Self> {
+        Self::new(Self::connect(path.as_ref())?, Some(token_mint))
+    }
+
+    /// Opens a database previously created by the indexer, typically to serve queries while
+    /// another process runs the indexer
+    pub fn open_existing(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(Self::connect(path.as_ref())?, None)
+    }
+
+    /// Creates a database which only lives in memory
+    pub fn open_in_memory(token_mint: Pubkey) -> Result<Self> {
+        Self::new(Connection::open_in_memory()?, Some(token_mint))
+    }
+
+    fn connect(path: &Path) -> Result<Connection> {
+        let connection = Connection::open(path)
+            .with_context(|| format!("failed to open indexer database {}", path.display()))?;
+        // the indexer and the api may use the database from separate processes
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.busy_timeout(Duration::from_secs(5))?;
+        Ok(connection)
+    }
+
+    fn new(connection: Connection, token_mint: Option<Pubkey>) -> Result<Self> {
+        connection
+            .execute_batch(SCHEMA)
+            .with_context(|| "failed to create indexer schema")?;
+        if let Some(token_mint) = token_mint {
+            connection.execute(
+                "INSERT OR IGNORE INTO meta (key, value) VALUES ('token_mint', ?1)",
+                params![token_mint.to_string()],
+            )?;
+        }
+        let indexed_mint: String = connection
+            .query_row(
+                "SELECT value FROM meta WHERE key = 'token_mint'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or_else(|| anyhow!("indexer database has not been created by the indexer"))?;
+        let indexed_mint = Pubkey::from_str(&indexed_mint)
+            .with_context(|| format!("invalid indexed token mint {indexed_mint}"))?;
+        if let Some(token_mint) = token_mint {
+            if indexed_mint != token_mint {
+                return Err(anyhow!(
+                    "indexer database was created for token mint {indexed_mint}"
+                ));
+            }
+        }
+        Ok(Self {
+            connection: Mutex::new(connection),
+            token_mint: indexed_mint,
+        })
+    }
+
+    /// The confidential token mint being indexed
+    pub fn token_mint(&self) -> Pubkey {
+        self.token_mint
+    }
+
+    fn connection(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.connection
+            .lock()
+            .map_err(|_| anyhow!("indexer database lock poisoned"))
+    }
+
+    /// Records the instructions of a transaction and advances the cursor of `address` to it
+    pub fn record(
+        &self,
+        address: &Pubkey,
+        signature: &Signature,
+        slot: u64,
+        instructions: &[ApiIndexedInstruction],
+    ) -> Result<()> {
+        let mut connection = self.connection()?;
+        let transaction = connection.transaction()?;
+        for instruction in instructions {
+            transaction.execute(
+                "INSERT OR IGNORE INTO instructions (
+                    signature, instruction_index, slot, block_time, kind, token_account,
+                    counterparty, amount, ciphertexts, accounts
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    instruction.signature.to_string(),
+                    instruction.instruction_index,
+                    instruction.slot,
+                    instruction.block_time,
+                    instruction.kind.as_str(),
+                    instruction.token_account.to_string(),
+                    instruction.counterparty.map(|key| key.to_string()),
+                    instruction.amount.map(|amount| amount.to_string()),
+                    serde_json::to_string(&instruction.ciphertexts)?,
+                    serde_json::to_string(&instruction.accounts)?,
+                ],
+            )?;
+        }
+        transaction.execute(
+            "INSERT OR REPLACE INTO cursors (address, signature, slot) VALUES (?1, ?2, ?3)",
+            params![address.to_string(), signature.to_string(), slot],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Returns the most recent transaction indexed for `address`
+    pub fn cursor(&self, address: &Pubkey) -> Result<Option<Signature>> {
+        let signature: Option<String> = self
+            .connection()?
+            .query_row(
+                "SELECT signature FROM cursors WHERE address = ?1",
+                params![address.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        signature
+            .map(|signature| {
+                Signature::from_str(&signature)
+                    .with_context(|| format!("invalid cursor signature {signature}"))
+            })
+            .transpose()
+    }
+
+    /// Returns every token account configured for the mint
+    pub fn token_accounts(&self) -> Result<HashSet<Pubkey>> {
+        let connection = self.connection()?;
+        let mut statement = connection.prepare(
+            "SELECT DISTINCT token_account FROM instructions WHERE kind = 'configure_account'",
+        )?;
+        let token_accounts = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .map(|token_account| {
+                let token_account = token_account?;
+                Pubkey::from_str(&token_account)
+                    .with_context(|| format!("invalid token account {token_account}"))
+            })
+            .collect();
+        token_accounts
+    }
+
+    /// Returns up to `limit` of the recorded instructions matching `query`, oldest first
+    pub fn instructions(
+        &self,
+        query: &IndexedInstructions,
+        limit: Option<usize>,
+    ) -> Result<Vec<ApiIndexedInstruction>> {
+        let connection = self.connection()?;
+        let mut statement = connection.prepare(
+            "SELECT signature, instruction_index, slot, block_time, kind, token_account,
+                counterparty, amount, ciphertexts, accounts
+            FROM instructions
+            WHERE (?1 IS NULL OR token_account = ?1 OR counterparty = ?1)
+                AND (?2 IS NULL OR kind = ?2)
+                AND (?3 IS NULL OR slot >= ?3)
+                AND (?4 IS NULL OR slot <= ?4)
+                AND (?5 IS NULL OR block_time >= ?5)
+                AND (?6 IS NULL OR block_time <= ?6)
+            ORDER BY slot, signature, instruction_index
+            LIMIT ?7",
+        )?;
+        let rows = statement.query_map(
+            params![
+                query.token_account.map(|key| key.to_string()),
+                query.kind.map(|kind| kind.as_str()),
+                query.min_slot,
+                query.max_slot,
+                query.min_block_time,
+                query.max_block_time,
+                // a negative limit returns every row
+                limit.map_or(-1, |limit| limit as i64),
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, u32>(1)?,
+                    row.get::<_, u64>(2)?,
+                    row.get::<_, Option<i64>>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                    row.get::<_, Option<String>>(7)?,
+                    row.get::<_, String>(8)?,
+                    row.get::<_, String>(9)?,
+                ))
+            },
+        )?;
+
+        let mut instructions = vec![];
+        for row in rows {
+            let (
+                signature,
+                instruction_index,
+                slot,
+                block_time,
+                kind,
+                token_account,
+                counterparty,
+                amount,
+                ciphertexts,
+                accounts,
+            ) = row?;
+            instructions.push(ApiIndexedInstruction {
+                signature: Signature::from_str(&signature)?,
+                instruction_index,
+                slot,
+                block_time,
+                kind: kind.parse()?,
+                token_account: Pubkey::from_str(&token_account)?,
+                counterparty: counterparty.as_deref().map(Pubkey::from_str).transpose()?,
+                amount: amount.as_deref().map(u64::from_str).transpose()?,
+                ciphertexts: serde_json::from_str(&ciphertexts)?,
+                accounts: serde_json::from_str(&accounts)?,
+            });
+        }
+        Ok(instructions)
+    }
+
+    /// Summarizes the progress of the indexer
+    pub fn status(&self) -> Result<ApiIndexerStatusResponse> {
+        let connection = self.connection()?;
+        let last_indexed_slot =
+            connection.query_row("SELECT MAX(slot) FROM cursors", [], |row| row.get(0))?;
+        let instruction_count =
+            connection.query_row("SELECT COUNT(*) FROM instructions", [], |row| row.get(0))?;
+        let token_account_count = connection.query_row(
+            "SELECT COUNT(DISTINCT token_account) FROM instructions
+            WHERE kind = 'configure_account'",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(ApiIndexerStatusResponse {
+            token_mint: self.token_mint,
+            last_indexed_slot,
+            instruction_count,
+            token_account_count,
+        })
+    }
+}
+
+/// Decodes the confidential transfer, confidential mint and burn, and token wrap instructions of
+/// a transaction which involve `token_mint`
+///
+/// Instructions which do not reference the mint are only decoded when they operate on one of
+/// `token_accounts`
+pub(crate) fn decode_instructions(
+    token_mint: &Pubkey,
+    token_accounts: &HashSet<Pubkey>,
+    transaction: &ConfirmedTransaction,
+) -> Vec<ApiIndexedInstruction> {
+    let account_keys = &transaction.account_keys;
+    let mut decoded = vec![];
+    for (index, instruction) in transaction.instructions.iter().enumerate() {
+        let account = |position: usize| {
+            instruction
+                .accounts
+                .get(position)
+                .and_then(|index| account_keys.get(*index as usize))
+                .copied()
+        };
+        let Some(program_id) = account_keys.get(instruction.program_id_index as usize) else {
+            continue;
+        };
+        let mut ciphertexts = BTreeMap::new();
+
+        let decoded_instruction = if *program_id == spl_token_2022::id()
+            && matches!(
+                TokenInstruction::unpack(&instruction.data),
+                Ok(TokenInstruction::ConfidentialMintBurnExtension)
+            ) {
+            match decode_mint_burn(account_keys, &transaction.instructions, index) {
+                Some(DecodedMintBurn::Mint {
+                    token_account,
+                    mint,
+                    data,
+                    amount,
+                }) if mint == *token_mint => {
+                    ciphertexts.insert(
+                        "new_decryptable_supply",
+                        data.new_decryptable_supply.to_string(),
+                    );
+                    ciphertexts.insert(
+                        "mint_amount_auditor_ciphertext_lo",
+                        data.mint_amount_auditor_ciphertext_lo.to_string(),
+                    );
+                    ciphertexts.insert(
+                        "mint_amount_auditor_ciphertext_hi",
+                        data.mint_amount_auditor_ciphertext_hi.to_string(),
+                    );
+                    if let TransferAmount::Ciphertexts(amount) =
+                        resolve_in_transaction(transaction, amount)
+                    {
+                        ciphertexts.insert("mint_amount_lo", amount.lo.to_string());
+                        ciphertexts.insert("mint_amount_hi", amount.hi.to_string());
+                    }
+                    (ApiIndexedInstructionKind::Mint, token_account, None, None)
+                }
+                Some(DecodedMintBurn::Burn {
+                    token_account,
+                    mint,
+                    data,
+                    amount,
+                }) if mint == *token_mint => {
+                    ciphertexts.insert(
+                        "new_decryptable_available_balance",
+                        data.new_decryptable_available_balance.to_string(),
+                    );
+                    ciphertexts.insert(
+                        "burn_amount_auditor_ciphertext_lo",
+                        data.burn_amount_auditor_ciphertext_lo.to_string(),
+                    );
+                    ciphertexts.insert(
+                        "burn_amount_auditor_ciphertext_hi",
+                        data.burn_amount_auditor_ciphertext_hi.to_string(),
+                    );
+                    if let TransferAmount::Ciphertexts(amount) =
+                        resolve_in_transaction(transaction, amount)
+                    {
+                        ciphertexts.insert("burn_amount_lo", amount.lo.to_string());
+                        ciphertexts.insert("burn_amount_hi", amount.hi.to_string());
+                    }
+                    (ApiIndexedInstructionKind::Burn, token_account, None, None)
+                }
+                _ => continue,
+            }
+        } else if *program_id == spl_token_2022::id() {
+            if !matches!(
+                TokenInstruction::unpack(&instruction.data),
+                Ok(TokenInstruction::ConfidentialTransferExtension)
+            ) {
+                continue;
+            }
+            let data = &instruction.data[1..];
+            let Ok(instruction_type) =
+                decode_instruction_type::<ConfidentialTransferInstruction>(data)
+            else {
+                continue;
+            };
+            let Some(token_account) = account(0) else {
+                continue;
+            };
+            // the mint follows the token account in every instruction which references it
+            let references_mint = account(1) == Some(*token_mint);
+
+            match instruction_type {
+                ConfidentialTransferInstruction::ConfigureAccount if references_mint => {
+                    let Ok(configure) =
+                        decode_instruction_data::<ConfigureAccountInstructionData>(data)
+                    else {
+                        continue;
+                    };
+                    ciphertexts.insert(
+                        "decryptable_zero_balance",
+                        configure.decryptable_zero_balance.to_string(),
+                    );
+                    (
+                        ApiIndexedInstructionKind::ConfigureAccount,
+                        token_account,
+                        None,
+                        None,
+                    )
+                }
+                ConfidentialTransferInstruction::ConfigureAccountWithRegistry
+                    if references_mint =>
+                {
+                    (
+                        ApiIndexedInstructionKind::ConfigureAccount,
+                        token_account,
+                        None,
+                        None,
+                    )
+                }
+                ConfidentialTransferInstruction::Deposit if references_mint => {
+                    let Ok(deposit) = decode_instruction_data::<DepositInstructionData>(data)
+                    else {
+                        continue;
+                    };
+                    (
+                        ApiIndexedInstructionKind::Deposit,
+                        token_account,
+                        None,
+                        Some(deposit.amount.into()),
+                    )
+                }
+                ConfidentialTransferInstruction::Withdraw if references_mint => {
+                    let Ok(withdraw) = decode_instruction_data::<WithdrawInstructionData>(data)
+                    else {
+                        continue;
+                    };
+                    ciphertexts.insert(
+                        "new_decryptable_available_balance",
+                        withdraw.new_decryptable_available_balance.to_string(),
+                    );
+                    (
+                        ApiIndexedInstructionKind::Withdraw,
+                        token_account,
+                        None,
+                        Some(withdraw.amount.into()),
+                    )
+                }
+                ConfidentialTransferInstruction::ApplyPendingBalance
+                    if token_accounts.contains(&token_account) =>
+                {
+                    let Ok(apply) = decode_instruction_data::<ApplyPendingBalanceData>(data) else {
+                        continue;
+                    };
+                    ciphertexts.insert(
+                        "new_decryptable_available_balance",
+                        apply.new_decryptable_available_balance.to_string(),
+                    );
+                    (
+                        ApiIndexedInstructionKind::ApplyPendingBalance,
+                        token_account,
+                        None,
+                        None,
+                    )
+                }
+                ConfidentialTransferInstruction::Transfer
+                | ConfidentialTransferInstruction::TransferWithFee
+                    if references_mint =>
+                {
+                    let Some(transfer) = decode_transfer(
+                        account_keys,
+                        &transaction.instructions,
+                        index,
+                        instruction_type,
+                    ) else {
+                        continue;
+                    };
+                    ciphertexts.insert(
+                        "new_source_decryptable_available_balance",
+                        transfer
+                            .new_source_decryptable_available_balance
+                            .to_string(),
+                    );
+                    ciphertexts.insert(
+                        "transfer_amount_auditor_ciphertext_lo",
+                        transfer.transfer_amount_auditor_ciphertext_lo.to_string(),
+                    );
+                    ciphertexts.insert(
+                        "transfer_amount_auditor_ciphertext_hi",
+                        transfer.transfer_amount_auditor_ciphertext_hi.to_string(),
+                    );
+                    let amount = resolve_in_transaction(transaction, transfer.amount);
+                    if let TransferAmount::Ciphertexts(amount) = amount {
+                        ciphertexts.insert("transfer_amount_lo", amount.lo.to_string());
+                        ciphertexts.insert("transfer_amount_hi", amount.hi.to_string());
+                    }
+                    let fee = transfer
+                        .fee
+                        .map(|fee| resolve_in_transaction(transaction, fee));
+                    if let Some(TransferAmount::Ciphertexts(fee)) = fee {
+                        ciphertexts.insert("fee_lo", fee.lo.to_string());
+                        ciphertexts.insert("fee_hi", fee.hi.to_string());
+                    }
+                    (
+                        ApiIndexedInstructionKind::Transfer,
+                        token_account,
+                        Some(transfer.destination),
+                        None,
+                    )
+                }
+                ConfidentialTransferInstruction::EmptyAccount
+                    if token_accounts.contains(&token_account) =>
+                {
+                    (
+                        ApiIndexedInstructionKind::EmptyAccount,
+                        token_account,
+                        None,
+                        None,
+                    )
+                }
+                _ => continue,
+            }
+        } else if *program_id == spl_token_wrap::id() {
+            match TokenWrapInstruction::unpack(&instruction.data) {
+                // the recipient wrapped token account is followed by the wrapped mint
+                Ok(TokenWrapInstruction::Wrap { amount }) if account(1) == Some(*token_mint) => {
+                    let Some(token_account) = account(0) else {
+                        continue;
+                    };
+                    (
+                        ApiIndexedInstructionKind::Wrap,
+                        token_account,
+                        None,
+                        Some(amount),
+                    )
+                }
+                // the source wrapped token account is followed by the wrapped mint
+                Ok(TokenWrapInstruction::Unwrap { amount }) if account(7) == Some(*token_mint) => {
+                    let Some(token_account) = account(6) else {
+                        continue;
+                    };
+                    (
+                        ApiIndexedInstructionKind::Unwrap,
+                        token_account,
+                        None,
+                        Some(amount),
+                    )
+                }
+                _ => continue,
+            }
+        } else {
+            continue;
+        };
+
+        let (kind, token_account, counterparty, amount) = decoded_instruction;
+        decoded.push(ApiIndexedInstruction {
+            signature: transaction.signature,
+            instruction_index: index as u32,
+            slot: transaction.slot,
+            block_time: transaction.block_time,
+            kind,
+            token_account,
+            counterparty,
+            amount,
+            ciphertexts: ciphertexts
+                .into_iter()
+                .map(|(name, ciphertext)| (name.to_string(), ciphertext))
+                .collect(),
+            accounts: instruction
+                .accounts
+                .iter()
+                .filter_map(|index| account_keys.get(*index as usize))
+                .map(|account| account.to_string())
+                .collect(),
+        });
+    }
+    decoded
+}
+
+/// Replaces a context state account created by `transaction` itself with the ciphertexts
+/// verified into it
+fn resolve_in_transaction<C: VerifiedCiphertexts>(
+    transaction: &ConfirmedTransaction,
+    amount: TransferAmount<C>,
+) -> TransferAmount<C> {
+    match amount {
+        TransferAmount::ContextStateAccount(context_state_account) => context_state_ciphertexts(
+            &context_state_account,
+            &transaction.account_keys,
+            &transaction.instructions,
+        )
+        .unwrap_or(TransferAmount::Unknown),
+        amount => amount,
+    }
+}
+
+/// Background service which records the instructions of a confidential mint
+pub struct Indexer {
+    rpc: Arc<RpcClient>,
+    store: Arc<IndexerStore>,
+    poll_interval: Duration,
+}
+
+impl Indexer {
+    pub fn new(rpc: Arc<RpcClient>, store: Arc<IndexerStore>, poll_interval: Duration) -> Self {
+        Self {
+            rpc,
+            store,
+            poll_interval,
+        }
+    }
+
+    /// Indexes every transaction confirmed since the last pass, returning the number of
+    /// instructions recorded
+    pub async fn index_once(&self) -> Result<usize> {
+        let token_mint = self.store.token_mint();
+        let mut token_accounts = self.with_store(|store| store.token_accounts()).await?;
+        let mut recorded = self.index_address(&token_mint, &mut token_accounts).await?;
+        // accounts configured by the pass over the mint are picked up here as well
+        for token_account in token_accounts.clone() {
+            recorded += self
+                .index_address(&token_account, &mut token_accounts)
+                .await?;
+        }
+        Ok(recorded)
+    }
+
+    async fn index_address(
+        &self,
+        address: &Pubkey,
+        token_accounts: &mut HashSet<Pubkey>,
+    ) -> Result<usize> {
+        let until = {
+            let address = *address;
+            self.with_store(move |store| store.cursor(&address)).await?
+        };
+        let transactions = get_transactions(&self.rpc, address, until).await?;
+        let mut recorded = 0;
+        for transaction in transactions {
+            let instructions =
+                decode_instructions(&self.store.token_mint(), token_accounts, &transaction);
+            // later instructions of an account configured in this pass must be decoded too
+            token_accounts.extend(
+                instructions
+                    .iter()
+                    .filter(|instruction| {
+                        instruction.kind == ApiIndexedInstructionKind::ConfigureAccount
+                    })
+                    .map(|instruction| instruction.token_account),
+            );
+            recorded += instructions.len();
+            let address = *address;
+            self.with_store(move |store| {
+                store.record(
+                    &address,
+                    &transaction.signature,
+                    transaction.slot,
+                    &instructions,
+                )
+            })
+            .await?;
+        }
+        Ok(recorded)
+    }
+
+    /// Runs a blocking database call off the async runtime
+    async fn with_store<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&IndexerStore) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let store = self.store.clone();
+        tokio::task::spawn_blocking(move || f(&store))
+            .await
+            .with_context(|| "failed to join indexer database task")?
+    }
+
+    /// Indexes the mint every `poll_interval` until interrupted
+    pub async fn run(&self) -> Result<()> {
+        log::info!("indexing confidential mint {}", self.store.token_mint());
+        loop {
+            match self.index_once().await {
+                Ok(0) => {}
+                Ok(recorded) => log::info!("indexed {recorded} instructions"),
+                Err(err) => log::error!("failed to index {err:#}"),
+            }
+
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {
+                    log::info!("stopping indexer");
+                    return Ok(());
+                }
+                _ = tokio::time::sleep(self.poll_interval) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        solana_sdk::{instruction::Instruction, message::Message},
+        spl_token_2022::{
+            extension::{
+                confidential_mint_burn::instruction::{
+                    confidential_burn_with_split_proofs, confidential_mint_with_split_proofs,
+                },
+                confidential_transfer::instruction::{
+                    deposit, inner_apply_pending_balance, inner_transfer, inner_transfer_with_fee,
+                    BatchedGroupedCiphertext2HandlesValidityProofData,
+                },
+            },
+            solana_zk_sdk::{
+                encryption::{
+                    auth_encryption::AeKey,
+                    elgamal::ElGamalKeypair,
+                    grouped_elgamal::GroupedElGamal,
+                    pedersen::PedersenOpening,
+                    pod::{
+                        elgamal::PodElGamalCiphertext,
+                        grouped_elgamal::PodGroupedElGamalCiphertext2Handles,
+                    },
+                },
+                zk_elgamal_proof_program::instruction::{ContextStateInfo, ProofInstruction},
+            },
+        },
+        spl_token_confidential_transfer_proof_extraction::instruction::ProofLocation,
+    };
+
+    fn transaction(
+        instructions: &[Instruction],
+        payer: &Pubkey,
+        slot: u64,
+    ) -> ConfirmedTransaction {
+        let message = Message::new(instructions, Some(payer));
+        ConfirmedTransaction {
+            signature: Signature::new_unique(),
+            slot,
+            block_time: None,
+            account_keys: message.account_keys,
+            instructions: message.instructions,
+            post_token_balances: vec![],
+        }
+    }
+
+    #[test]
+    fn test_decode_instructions() {
+        let authority = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let source = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let ae_key = AeKey::new_rand();
+
+        let transaction = transaction(
+            &[
+                deposit(
+                    &spl_token_2022::id(),
+                    &source,
+                    &mint,
+                    100,
+                    6,
+                    &authority,
+                    &[],
+                )
+                .unwrap(),
+                inner_apply_pending_balance(
+                    &spl_token_2022::id(),
+                    &source,
+                    1,
+                    &ae_key.encrypt(100).into(),
+                    &authority,
+                    &[],
+                )
+                .unwrap(),
+                inner_transfer(
+                    &spl_token_2022::id(),
+                    &source,
+                    &mint,
+                    &destination,
+                    &ae_key.encrypt(30).into(),
+                    &PodElGamalCiphertext::default(),
+                    &PodElGamalCiphertext::default(),
+                    &authority,
+                    &[],
+                    ProofLocation::ContextStateAccount(&Pubkey::new_unique()),
+                    ProofLocation::ContextStateAccount(&Pubkey::new_unique()),
+                    ProofLocation::ContextStateAccount(&Pubkey::new_unique()),
+                )
+                .unwrap(),
+            ],
+            &authority,
+            1,
+        );
+
+        // the apply does not reference the mint, so it is skipped for unknown token accounts
+        let decoded = decode_instructions(&mint, &HashSet::new(), &transaction);
+        let kinds = decoded
+            .iter()
+            .map(|instruction| (instruction.instruction_index, instruction.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (0, ApiIndexedInstructionKind::Deposit),
+                (2, ApiIndexedInstructionKind::Transfer)
+            ]
+        );
+        assert_eq!(decoded[0].amount, Some(100));
+        assert_eq!(decoded[1].token_account, source);
+        assert_eq!(decoded[1].counterparty, Some(destination));
+        assert_eq!(
+            decoded[1].ciphertexts["transfer_amount_auditor_ciphertext_lo"],
+            PodElGamalCiphertext::default().to_string()
+        );
+
+        let decoded = decode_instructions(&mint, &HashSet::from([source]), &transaction);
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(
+            decoded[1].kind,
+            ApiIndexedInstructionKind::ApplyPendingBalance
+        );
+        assert!(decoded[1]
+            .ciphertexts
+            .contains_key("new_decryptable_available_balance"));
+
+        // instructions of other mints are ignored
+        assert!(decode_instructions(
+            &Pubkey::new_unique(),
+            &HashSet::from([destination]),
+            &transaction
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_decode_transfer_with_fee() {
+        let authority = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let source = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let fee_proof_account = Pubkey::new_unique();
+
+        // the fee ciphertext validity proof is verified into a context state account by the same
+        // transaction
+        let destination_elgamal = ElGamalKeypair::new_rand();
+        let withdraw_withheld_authority = ElGamalKeypair::new_rand();
+        let pubkeys = [
+            destination_elgamal.pubkey(),
+            withdraw_withheld_authority.pubkey(),
+        ];
+        let (opening_lo, opening_hi) = (PedersenOpening::new_rand(), PedersenOpening::new_rand());
+        let fee_lo = GroupedElGamal::encrypt_with(pubkeys, 1u64, &opening_lo);
+        let fee_hi = GroupedElGamal::encrypt_with(pubkeys, 0u64, &opening_hi);
+        let proof_data = BatchedGroupedCiphertext2HandlesValidityProofData::new(
+            destination_elgamal.pubkey(),
+            withdraw_withheld_authority.pubkey(),
+            &fee_lo,
+            &fee_hi,
+            1,
+            0,
+            &opening_lo,
+            &opening_hi,
+        )
+        .unwrap();
+        let transaction = transaction(
+            &[
+                ProofInstruction::VerifyBatchedGroupedCiphertext2HandlesValidity
+                    .encode_verify_proof(
+                        Some(ContextStateInfo {
+                            context_state_account: &fee_proof_account,
+                            context_state_authority: &authority,
+                        }),
+                        &proof_data,
+                    ),
+                inner_transfer_with_fee(
+                    &spl_token_2022::id(),
+                    &source,
+                    &mint,
+                    &destination,
+                    &AeKey::new_rand().encrypt(0).into(),
+                    &PodElGamalCiphertext::default(),
+                    &PodElGamalCiphertext::default(),
+                    &authority,
+                    &[],
+                    ProofLocation::ContextStateAccount(&Pubkey::new_unique()),
+                    ProofLocation::ContextStateAccount(&Pubkey::new_unique()),
+                    ProofLocation::ContextStateAccount(&Pubkey::new_unique()),
+                    ProofLocation::ContextStateAccount(&fee_proof_account),
+                    ProofLocation::ContextStateAccount(&Pubkey::new_unique()),
+                )
+                .unwrap(),
+            ],
+            &authority,
+            1,
+        );
+
+        let decoded = decode_instructions(&mint, &HashSet::new(), &transaction);
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].instruction_index, 1);
+        assert_eq!(decoded[0].kind, ApiIndexedInstructionKind::Transfer);
+        assert_eq!(decoded[0].token_account, source);
+        assert_eq!(decoded[0].counterparty, Some(destination));
+        assert_eq!(
+            decoded[0].ciphertexts["transfer_amount_auditor_ciphertext_hi"],
+            PodElGamalCiphertext::default().to_string()
+        );
+        assert_eq!(
+            decoded[0].ciphertexts["fee_lo"],
+            PodGroupedElGamalCiphertext2Handles::from(fee_lo).to_string()
+        );
+        assert!(decoded[0].ciphertexts.contains_key("fee_hi"));
+        // the amount proof is verified in an earlier transaction
+        assert!(!decoded[0].ciphertexts.contains_key("transfer_amount_lo"));
+    }
+
+    #[test]
+    fn test_decode_mint_burn() {
+        let authority = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let token_account = Pubkey::new_unique();
+
+        let mut instructions = confidential_mint_with_split_proofs(
+            &spl_token_2022::id(),
+            &token_account,
+            &mint,
+            &PodElGamalCiphertext::default(),
+            &PodElGamalCiphertext::default(),
+            &authority,
+            &[],
+            ProofLocation::ContextStateAccount(&Pubkey::new_unique()),
+            ProofLocation::ContextStateAccount(&Pubkey::new_unique()),
+            ProofLocation::ContextStateAccount(&Pubkey::new_unique()),
+            &AeKey::new_rand().encrypt(100).into(),
+        )
+        .unwrap();
+        instructions.extend(
+            confidential_burn_with_split_proofs(
+                &spl_token_2022::id(),
+                &token_account,
+                &mint,
+                &AeKey::new_rand().encrypt(40).into(),
+                &PodElGamalCiphertext::default(),
+                &PodElGamalCiphertext::default(),
+                &authority,
+                &[],
+                ProofLocation::ContextStateAccount(&Pubkey::new_unique()),
+                ProofLocation::ContextStateAccount(&Pubkey::new_unique()),
+                ProofLocation::ContextStateAccount(&Pubkey::new_unique()),
+            )
+            .unwrap(),
+        );
+        let transaction = transaction(&instructions, &authority, 1);
+
+        let decoded = decode_instructions(&mint, &HashSet::new(), &transaction);
+        let kinds = decoded
+            .iter()
+            .map(|instruction| (instruction.kind, instruction.token_account))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (ApiIndexedInstructionKind::Mint, token_account),
+                (ApiIndexedInstructionKind::Burn, token_account)
+            ]
+        );
+        assert!(decoded[0]
+            .ciphertexts
+            .contains_key("mint_amount_auditor_ciphertext_lo"));
+        assert!(decoded[1]
+            .ciphertexts
+            .contains_key("new_decryptable_available_balance"));
+
+        // mints and burns of other mints are ignored
+        assert!(decode_instructions(
+            &Pubkey::new_unique(),
+            &HashSet::from([token_account]),
+            &transaction
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_store() {
+        let mint = Pubkey::new_unique();
+        let store = IndexerStore::open_in_memory(mint).unwrap();
+        let token_account = Pubkey::new_unique();
+        let counterparty = Pubkey::new_unique();
+
+        let instruction = |slot: u64, kind: ApiIndexedInstructionKind| ApiIndexedInstruction {
+            signature: Signature::new_unique(),
+            instruction_index: 0,
+            slot,
+            block_time: Some(1_700_000_000),
+            kind,
+            token_account,
+            counterparty: (kind == ApiIndexedInstructionKind::Transfer).then_some(counterparty),
+            amount: (kind == ApiIndexedInstructionKind::Wrap).then_some(42),
+            ciphertexts: BTreeMap::from([("ciphertext".to_string(), "AAAA".to_string())]),
+            accounts: vec![token_account.to_string(), mint.to_string()],
+        };
+        let instructions = [
+            instruction(1, ApiIndexedInstructionKind::ConfigureAccount),
+            instruction(2, ApiIndexedInstructionKind::Wrap),
+            instruction(3, ApiIndexedInstructionKind::Transfer),
+        ];
+
+        assert_eq!(store.cursor(&mint).unwrap(), None);
+        for instruction in &instructions {
+            store
+                .record(
+                    &mint,
+                    &instruction.signature,
+                    instruction.slot,
+                    std::slice::from_ref(instruction),
+                )
+                .unwrap();
+        }
+        // recording a transaction again does not duplicate its instructions
+        store
+            .record(&mint, &instructions[2].signature, 3, &instructions[2..])
+            .unwrap();
+        assert_eq!(
+            store.cursor(&mint).unwrap(),
+            Some(instructions[2].signature)
+        );
+        assert_eq!(
+            store.token_accounts().unwrap(),
+            HashSet::from([token_account])
+        );
+
+        let all = store
+            .instructions(&IndexedInstructions::default(), None)
+            .unwrap();
+        assert_eq!(all, instructions.to_vec());
+
+        let query = IndexedInstructions {
+            token_account: Some(counterparty),
+            ..Default::default()
+        };
+        assert_eq!(
+            store.instructions(&query, Some(10)).unwrap(),
+            instructions[2..]
+        );
+
+        let query = IndexedInstructions {
+            kind: Some(ApiIndexedInstructionKind::Wrap),
+            ..Default::default()
+        };
+        assert_eq!(
+            store.instructions(&query, Some(10)).unwrap(),
+            instructions[1..2]
+        );
+
+        let query = IndexedInstructions {
+            min_slot: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            store.instructions(&query, Some(1)).unwrap(),
+            instructions[1..2]
+        );
+
+        let status = store.status().unwrap();
+        assert_eq!(status.token_mint, mint);
+        assert_eq!(status.last_indexed_slot, Some(3));
+        assert_eq!(status.instruction_count, 3);
+        assert_eq!(status.token_account_count, 1);
+    }
+
+    #[test]
+    fn test_open_other_mint() {
+        let path = std::env::temp_dir().join(format!("indexer-{}.db", Pubkey::new_unique()));
+        let mint = Pubkey::new_unique();
+        IndexerStore::open(&path, mint).unwrap();
+        assert_eq!(
+            IndexerStore::open_existing(&path).unwrap().token_mint(),
+            mint
+        );
+        assert!(IndexerStore::open(&path, Pubkey::new_unique()).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
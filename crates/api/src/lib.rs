@@ -1,4 +1,8 @@
+pub mod audit;
 pub mod handlers;
+pub mod history;
+pub mod indexer;
+pub mod notifications;
 pub mod router;
 pub mod serde_utils;
 pub mod types;
@@ -8,13 +12,39 @@ mod tests;
 
 use {
     anyhow::{Context, Result},
+    common::discrete_log::DiscreteLogTable,
     solana_client::nonblocking::rpc_client::RpcClient,
     std::sync::Arc,
 };
 
-pub async fn start_api(listen_url: &str, rpc_url: String) -> Result<()> {
+pub async fn start_api(
+    listen_url: &str,
+    rpc_url: String,
+    discrete_log_table: String,
+    baby_step_bits: u32,
+    indexer_database: Option<String>,
+) -> Result<()> {
     let rpc = RpcClient::new(rpc_url);
-    let router = router::new(Arc::new(rpc));
+
+    // building the table can take a few seconds, so do it off the async runtime
+    log::info!("loading discrete log table from {discrete_log_table}");
+    let discrete_log = tokio::task::spawn_blocking(move || {
+        DiscreteLogTable::load_or_build(discrete_log_table, baby_step_bits)
+    })
+    .await
+    .with_context(|| "failed to join discrete log table task")??;
+
+    let indexer = match indexer_database {
+        Some(indexer_database) => {
+            log::info!("serving indexed instructions from {indexer_database}");
+            Some(Arc::new(indexer::IndexerStore::open_existing(
+                indexer_database,
+            )?))
+        }
+        None => None,
+    };
+
+    let router = router::new(Arc::new(rpc), Arc::new(discrete_log), indexer);
     Ok(axum::serve(
         tokio::net::TcpListener::bind(listen_url)
             .await
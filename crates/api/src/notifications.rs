@@ -0,0 +1,532 @@
+//! Notifications for changes to the balances of confidential token accounts
+//!
+//! [`BalanceWatcher`] subscribes to a token account over the rpc websocket and emits an
+//! [`ApiBalanceNotification`] whenever the pending balance is credited or any balance changes.
+//! Notifications are streamed to clients as server sent events, or posted to a webhook with
+//! [`WebhookDispatcher`].
+
+use {
+    crate::types::{ApiBalanceNotification, ApiBalanceNotificationKind},
+    anyhow::{anyhow, Context, Result},
+    common::{
+        accounts::{decrypt_available_balance, decrypt_pending_balance},
+        discrete_log::DiscreteLogTable,
+    },
+    futures::StreamExt,
+    solana_account_decoder_client_types::UiAccountEncoding,
+    solana_client::{nonblocking::pubsub_client::PubsubClient, rpc_config::RpcAccountInfoConfig},
+    solana_sdk::{account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey},
+    spl_token_2022::{
+        extension::{
+            confidential_transfer::ConfidentialTransferAccount, BaseStateWithExtensions,
+            StateWithExtensions,
+        },
+        solana_zk_sdk::encryption::{auth_encryption::AeKey, elgamal::ElGamalSecretKey},
+        state::Account as TokenAccount,
+    },
+    std::{sync::Arc, time::Duration},
+    tokio::sync::{mpsc, oneshot, Mutex, OwnedSemaphorePermit, Semaphore},
+    url::Url,
+};
+
+/// Number of notifications buffered before the subscription waits on the receiver
+const NOTIFICATION_BUFFER: usize = 32;
+
+/// Maximum number of notification subscriptions the API keeps open at once
+pub const MAX_SUBSCRIPTIONS: usize = 256;
+
+/// Timeout of a single webhook delivery attempt
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Keys used to decrypt the balances included in notifications
+pub struct Credentials {
+    pub elgamal_secret: ElGamalSecretKey,
+    pub ae_key: AeKey,
+}
+
+/// The balances of a confidential token account at a point in time
+#[derive(Clone, Debug, PartialEq)]
+pub struct BalanceSnapshot {
+    pub token_mint: Pubkey,
+    pub pending_balance_credit_counter: u64,
+    pub non_confidential_balance: u64,
+    /// Only present if the snapshot was taken with credentials
+    pub pending_balance: Option<u64>,
+    /// Only present if the snapshot was taken with credentials
+    pub available_balance: Option<u64>,
+    /// Used to detect changes to the encrypted balances when no credentials are available
+    pub extension: ConfidentialTransferAccount,
+}
+
+impl BalanceSnapshot {
+    /// Unpacks a token account, decrypting its balances if `credentials` are provided
+    ///
+    /// Decryption is cpu bound and should not be called from an async context
+    pub fn new(
+        account: &Account,
+        credentials: Option<&Credentials>,
+        discrete_log: &DiscreteLogTable,
+    ) -> Result<Self> {
+        let token_account = StateWithExtensions::<TokenAccount>::unpack(&account.data)
+            .map_err(|e| anyhow!("failed to unpack token account {e:#?}"))?;
+        let extension = *token_account
+            .get_extension::<ConfidentialTransferAccount>()
+            .map_err(|e| anyhow!("failed to get confidential transfer account extension {e:#?}"))?;
+
+        let (pending_balance, available_balance) = match credentials {
+            Some(credentials) => (
+                Some(decrypt_pending_balance(
+                    discrete_log,
+                    &credentials.elgamal_secret,
+                    &extension,
+                )?),
+                Some(decrypt_available_balance(&credentials.ae_key, &extension)?),
+            ),
+            None => (None, None),
+        };
+
+        Ok(Self {
+            token_mint: token_account.base.mint,
+            pending_balance_credit_counter: extension.pending_balance_credit_counter.into(),
+            non_confidential_balance: token_account.base.amount,
+            pending_balance,
+            available_balance,
+            extension,
+        })
+    }
+
+    /// Returns a notification describing the changes since `previous`, or `None` if nothing changed
+    pub fn notification(
+        &self,
+        previous: &Self,
+        token_account: Pubkey,
+        slot: u64,
+    ) -> Option<ApiBalanceNotification> {
+        if self.extension == previous.extension
+            && self.non_confidential_balance == previous.non_confidential_balance
+        {
+            return None;
+        }
+
+        // the counter is reset when the pending balance is applied
+        let new_credits =
+            if self.pending_balance_credit_counter >= previous.pending_balance_credit_counter {
+                self.pending_balance_credit_counter - previous.pending_balance_credit_counter
+            } else {
+                self.pending_balance_credit_counter
+            };
+
+        Some(ApiBalanceNotification {
+            kind: if new_credits > 0 {
+                ApiBalanceNotificationKind::Credit
+            } else {
+                ApiBalanceNotificationKind::BalanceChange
+            },
+            token_account,
+            token_mint: self.token_mint,
+            slot,
+            pending_balance_credit_counter: self.pending_balance_credit_counter,
+            new_credits,
+            non_confidential_balance_amount: self.non_confidential_balance,
+            non_confidential_balance_delta: self.non_confidential_balance as i128
+                - previous.non_confidential_balance as i128,
+            pending_balance_amount: self.pending_balance,
+            pending_balance_delta: delta(self.pending_balance, previous.pending_balance),
+            available_balance_amount: self.available_balance,
+            available_balance_delta: delta(self.available_balance, previous.available_balance),
+        })
+    }
+}
+
+fn delta(current: Option<u64>, previous: Option<u64>) -> Option<i128> {
+    Some(current? as i128 - previous? as i128)
+}
+
+/// Subscribes to a confidential token account over the rpc websocket
+pub struct BalanceWatcher {
+    client: Arc<PubsubClient>,
+    token_account: Pubkey,
+    credentials: Option<Arc<Credentials>>,
+    discrete_log: Arc<DiscreteLogTable>,
+}
+
+impl BalanceWatcher {
+    pub fn new(
+        client: Arc<PubsubClient>,
+        token_account: Pubkey,
+        credentials: Option<Credentials>,
+        discrete_log: Arc<DiscreteLogTable>,
+    ) -> Self {
+        Self {
+            client,
+            token_account,
+            credentials: credentials.map(Arc::new),
+            discrete_log,
+        }
+    }
+
+    /// Subscribes to the token account, comparing updates against the `initial` account state
+    ///
+    /// Notifications are sent on the returned channel until the receiver is dropped or the
+    /// websocket subscription ends
+    pub async fn subscribe(
+        self,
+        initial: Account,
+    ) -> Result<mpsc::Receiver<ApiBalanceNotification>> {
+        let mut previous = self.snapshot(initial).await?;
+
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let (tx, rx) = mpsc::channel(NOTIFICATION_BUFFER);
+
+        tokio::spawn(async move {
+            let subscription = self
+                .client
+                .account_subscribe(
+                    &self.token_account,
+                    Some(RpcAccountInfoConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                        ..Default::default()
+                    }),
+                )
+                .await;
+            let (mut updates, unsubscribe) = match subscription {
+                Ok(subscription) => {
+                    let _ = ready_tx.send(Ok(()));
+                    subscription
+                }
+                Err(err) => {
+                    let _ = ready_tx.send(Err(anyhow!("failed to subscribe {err:#?}")));
+                    return;
+                }
+            };
+
+            loop {
+                let update = tokio::select! {
+                    _ = tx.closed() => break,
+                    update = updates.next() => update,
+                };
+                let Some(update) = update else {
+                    log::warn!("subscription to {} closed", self.token_account);
+                    break;
+                };
+                let Some(account) = update.value.decode::<Account>() else {
+                    log::warn!("failed to decode account update for {}", self.token_account);
+                    continue;
+                };
+                let snapshot = match self.snapshot(account).await {
+                    Ok(snapshot) => snapshot,
+                    Err(err) => {
+                        log::warn!("failed to read balances of {} {err:#}", self.token_account);
+                        continue;
+                    }
+                };
+                if let Some(notification) =
+                    snapshot.notification(&previous, self.token_account, update.context.slot)
+                {
+                    if tx.send(notification).await.is_err() {
+                        break;
+                    }
+                }
+                previous = snapshot;
+            }
+            unsubscribe().await;
+        });
+
+        ready_rx
+            .await
+            .with_context(|| "subscription task exited")??;
+        Ok(rx)
+    }
+
+    async fn snapshot(&self, account: Account) -> Result<BalanceSnapshot> {
+        let credentials = self.credentials.clone();
+        let discrete_log = self.discrete_log.clone();
+        tokio::task::spawn_blocking(move || {
+            BalanceSnapshot::new(&account, credentials.as_deref(), &discrete_log)
+        })
+        .await
+        .with_context(|| "failed to join decryption task")?
+    }
+}
+
+/// Shares a single rpc websocket between the notification subscriptions of the API, and limits
+/// how many subscriptions can be open at once
+pub struct SubscriptionPool {
+    rpc_url: String,
+    client: Mutex<Option<Arc<PubsubClient>>>,
+    permits: Arc<Semaphore>,
+}
+
+impl SubscriptionPool {
+    pub fn new(rpc_url: String, max_subscriptions: usize) -> Self {
+        Self {
+            rpc_url,
+            client: Mutex::new(None),
+            permits: Arc::new(Semaphore::new(max_subscriptions)),
+        }
+    }
+
+    /// Reserves a subscription, returning `None` if the limit is reached
+    ///
+    /// The subscription is released once the permit is dropped
+    pub fn try_reserve(&self) -> Option<OwnedSemaphorePermit> {
+        self.permits.clone().try_acquire_owned().ok()
+    }
+
+    /// Returns the shared websocket client, connecting to the rpc websocket if needed
+    pub async fn client(&self) -> Result<Arc<PubsubClient>> {
+        let mut client = self.client.lock().await;
+        if let Some(client) = client.as_ref() {
+            return Ok(client.clone());
+        }
+        let ws_url = websocket_url(&self.rpc_url)?;
+        let connected = Arc::new(
+            PubsubClient::new(&ws_url)
+                .await
+                .with_context(|| format!("failed to connect to {ws_url}"))?,
+        );
+        *client = Some(connected.clone());
+        Ok(connected)
+    }
+
+    /// Drops `failed` as the shared client so the next subscription reconnects, ie: after the
+    /// websocket was closed
+    pub async fn reset(&self, failed: &Arc<PubsubClient>) {
+        let mut client = self.client.lock().await;
+        if client
+            .as_ref()
+            .is_some_and(|client| Arc::ptr_eq(client, failed))
+        {
+            *client = None;
+        }
+    }
+}
+
+/// Posts notifications to a webhook, retrying failed deliveries with exponential backoff
+pub struct WebhookDispatcher {
+    client: reqwest::Client,
+    url: String,
+    max_retries: u32,
+    retry_delay: Duration,
+}
+
+impl WebhookDispatcher {
+    pub fn new(url: String, max_retries: u32, retry_delay: Duration) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            max_retries,
+            retry_delay,
+        }
+    }
+
+    /// Delivers a single notification, returning an error once all retries have failed
+    ///
+    /// Any response other than a 2xx status code is treated as a failed delivery
+    pub async fn dispatch(&self, notification: &ApiBalanceNotification) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            let err = match self
+                .client
+                .post(&self.url)
+                .timeout(WEBHOOK_TIMEOUT)
+                .json(notification)
+                .send()
+                .await
+            {
+                Ok(res) if res.status().is_success() => return Ok(()),
+                Ok(res) => anyhow!("webhook responded with {}", res.status()),
+                Err(err) => anyhow!("failed to send webhook {err:#?}"),
+            };
+            if attempt >= self.max_retries {
+                return Err(err.context(format!("giving up after {} attempts", attempt + 1)));
+            }
+            log::warn!("webhook delivery failed, retrying {err:#}");
+            tokio::time::sleep(
+                self.retry_delay
+                    .saturating_mul(2u32.saturating_pow(attempt)),
+            )
+            .await;
+            attempt += 1;
+        }
+    }
+
+    /// Delivers every notification received on `notifications` until the channel is closed
+    pub async fn run(&self, mut notifications: mpsc::Receiver<ApiBalanceNotification>) {
+        while let Some(notification) = notifications.recv().await {
+            if let Err(err) = self.dispatch(&notification).await {
+                log::error!("failed to deliver notification {err:#}");
+            }
+        }
+    }
+}
+
+/// Derives the websocket url of an rpc node following the solana cli convention,
+/// `http` becomes `ws`, `https` becomes `wss` and an explicit port is incremented by one
+pub fn websocket_url(rpc_url: &str) -> Result<String> {
+    let mut url = Url::parse(rpc_url).with_context(|| format!("invalid rpc url {rpc_url}"))?;
+    let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+    url.set_scheme(scheme)
+        .map_err(|_| anyhow!("invalid rpc url {rpc_url}"))?;
+    if let Some(port) = url.port() {
+        let port = port
+            .checked_add(1)
+            .ok_or_else(|| anyhow!("invalid rpc port {port}"))?;
+        url.set_port(Some(port))
+            .map_err(|_| anyhow!("invalid rpc url {rpc_url}"))?;
+    }
+    Ok(url.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        axum::{extract::State, routing::post, Json, Router},
+        http::StatusCode,
+        std::sync::atomic::{AtomicU32, Ordering},
+    };
+
+    fn snapshot(
+        counter: u64,
+        non_confidential: u64,
+        pending: u64,
+        available: u64,
+    ) -> BalanceSnapshot {
+        let extension = ConfidentialTransferAccount {
+            pending_balance_credit_counter: counter.into(),
+            // stand in for the ciphertexts changing along with the balances
+            actual_pending_balance_credit_counter: (pending + available).into(),
+            ..Default::default()
+        };
+        BalanceSnapshot {
+            token_mint: Pubkey::default(),
+            pending_balance_credit_counter: counter,
+            non_confidential_balance: non_confidential,
+            pending_balance: Some(pending),
+            available_balance: Some(available),
+            extension,
+        }
+    }
+
+    #[test]
+    fn test_websocket_url() {
+        assert_eq!(
+            websocket_url("http://localhost:8899").unwrap(),
+            "ws://localhost:8900/"
+        );
+        assert_eq!(
+            websocket_url("https://api.devnet.solana.com/").unwrap(),
+            "wss://api.devnet.solana.com/"
+        );
+        assert!(websocket_url("not a url").is_err());
+    }
+
+    #[test]
+    fn test_notification() {
+        let token_account = Pubkey::new_unique();
+        let initial = snapshot(0, 1_000, 0, 0);
+
+        // nothing changed
+        assert_eq!(initial.notification(&initial, token_account, 1), None);
+
+        // deposit into the pending balance
+        let deposited = snapshot(1, 900, 100, 0);
+        let notification = deposited.notification(&initial, token_account, 2).unwrap();
+        assert_eq!(notification.kind, ApiBalanceNotificationKind::Credit);
+        assert_eq!(notification.slot, 2);
+        assert_eq!(notification.new_credits, 1);
+        assert_eq!(notification.non_confidential_balance_delta, -100);
+        assert_eq!(notification.pending_balance_delta, Some(100));
+        assert_eq!(notification.available_balance_delta, Some(0));
+
+        // apply the pending balance, resetting the counter
+        let applied = snapshot(0, 900, 0, 100);
+        let notification = applied.notification(&deposited, token_account, 3).unwrap();
+        assert_eq!(notification.kind, ApiBalanceNotificationKind::BalanceChange);
+        assert_eq!(notification.new_credits, 0);
+        assert_eq!(notification.pending_balance_delta, Some(-100));
+        assert_eq!(notification.available_balance_delta, Some(100));
+
+        // apply followed by two credits between notifications, the counter goes from 3 to 2
+        let credited_three_times = snapshot(3, 700, 300, 0);
+        let credited = snapshot(2, 700, 50, 300);
+        let notification = credited
+            .notification(&credited_three_times, token_account, 4)
+            .unwrap();
+        assert_eq!(notification.kind, ApiBalanceNotificationKind::Credit);
+        assert_eq!(notification.new_credits, 2);
+
+        // deltas are omitted without credentials
+        let mut without_credentials = credited.clone();
+        without_credentials.pending_balance = None;
+        without_credentials.available_balance = None;
+        let notification = without_credentials
+            .notification(&applied, token_account, 5)
+            .unwrap();
+        assert_eq!(notification.pending_balance_amount, None);
+        assert_eq!(notification.pending_balance_delta, None);
+        assert_eq!(notification.available_balance_delta, None);
+    }
+
+    /// Starts a local webhook which fails the first `failures` requests
+    async fn webhook_stand_in(failures: u32) -> (String, Arc<AtomicU32>) {
+        let requests = Arc::new(AtomicU32::new(0));
+        let router = Router::new()
+            .route(
+                "/webhook",
+                post(
+                    move |State(requests): State<Arc<AtomicU32>>,
+                          Json(_): Json<ApiBalanceNotification>| async move {
+                        if requests.fetch_add(1, Ordering::SeqCst) < failures {
+                            StatusCode::INTERNAL_SERVER_ERROR
+                        } else {
+                            StatusCode::OK
+                        }
+                    },
+                ),
+            )
+            .with_state(requests.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        (format!("http://{addr}/webhook"), requests)
+    }
+
+    #[test]
+    fn test_subscription_pool_limit() {
+        let pool = SubscriptionPool::new("http://localhost:8899".to_string(), 2);
+        let first = pool.try_reserve().unwrap();
+        let _second = pool.try_reserve().unwrap();
+        assert!(pool.try_reserve().is_none());
+
+        // closing a subscription frees its slot
+        drop(first);
+        assert!(pool.try_reserve().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_retry() {
+        let (url, requests) = webhook_stand_in(2).await;
+        let notification = snapshot(1, 900, 100, 0)
+            .notification(&snapshot(0, 1_000, 0, 0), Pubkey::new_unique(), 1)
+            .unwrap();
+
+        let dispatcher = WebhookDispatcher::new(url, 3, Duration::from_millis(10));
+        dispatcher.dispatch(&notification).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_gives_up() {
+        let (url, requests) = webhook_stand_in(u32::MAX).await;
+        let notification = snapshot(1, 900, 100, 0)
+            .notification(&snapshot(0, 1_000, 0, 0), Pubkey::new_unique(), 1)
+            .unwrap();
+
+        let dispatcher = WebhookDispatcher::new(url, 2, Duration::from_millis(10));
+        assert!(dispatcher.dispatch(&notification).await.is_err());
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+}
@@ -1,6 +1,14 @@
 use {
-    crate::handlers,
-    axum::{routing::post, Router},
+    crate::{
+        handlers,
+        indexer::IndexerStore,
+        notifications::{SubscriptionPool, MAX_SUBSCRIPTIONS},
+    },
+    axum::{
+        routing::{get, post},
+        Router,
+    },
+    common::discrete_log::DiscreteLogTable,
     solana_client::nonblocking::rpc_client::RpcClient,
     std::sync::Arc,
     tower_http::{
@@ -13,9 +21,19 @@ use {
 
 pub struct AppState {
     pub rpc: Arc<RpcClient>,
+    /// Precomputed table used to decrypt pending balances
+    pub discrete_log: Arc<DiscreteLogTable>,
+    /// Database populated by the indexer, if enabled
+    pub indexer: Option<Arc<IndexerStore>>,
+    /// Websocket shared by the balance notification subscriptions
+    pub subscriptions: Arc<SubscriptionPool>,
 }
 
-pub fn new(rpc: Arc<RpcClient>) -> Router {
+pub fn new(
+    rpc: Arc<RpcClient>,
+    discrete_log: Arc<DiscreteLogTable>,
+    indexer: Option<Arc<IndexerStore>>,
+) -> Router {
     Router::new()
         .route(
             "/confidential-balances/transfer-amount-auditor",
@@ -27,7 +45,19 @@ pub fn new(rpc: Arc<RpcClient>) -> Router {
         )
         .route(
             "/confidential-balances/create-confidential-mint",
-            post(|| async { "hello" }),
+            post(handlers::create_confidential_mint),
+        )
+        .route(
+            "/confidential-balances/mint",
+            post(handlers::confidential_mint),
+        )
+        .route(
+            "/confidential-balances/burn",
+            post(handlers::confidential_burn),
+        )
+        .route(
+            "/confidential-balances/supply",
+            post(handlers::confidential_supply),
         )
         .route(
             "/confidential-balances/initialize",
@@ -35,12 +65,94 @@ pub fn new(rpc: Arc<RpcClient>) -> Router {
         )
         .route("/confidential-balances/deposit", post(handlers::deposit))
         .route("/confidential-balances/withdraw", post(handlers::withdraw))
+        .route(
+            "/confidential-balances/close",
+            post(handlers::close_account),
+        )
         .route("/confidential-balances/transfer", post(handlers::transfer))
         .route("/confidential-balances/apply", post(handlers::apply))
+        .route(
+            "/confidential-balances/approve-account",
+            post(handlers::approve_account),
+        )
+        .route(
+            "/confidential-balances/pending-approvals",
+            get(handlers::pending_approvals),
+        )
+        .route(
+            "/confidential-balances/enable-confidential-credits",
+            post(handlers::enable_confidential_credits),
+        )
+        .route(
+            "/confidential-balances/disable-confidential-credits",
+            post(handlers::disable_confidential_credits),
+        )
+        .route(
+            "/confidential-balances/enable-non-confidential-credits",
+            post(handlers::enable_non_confidential_credits),
+        )
+        .route(
+            "/confidential-balances/disable-non-confidential-credits",
+            post(handlers::disable_non_confidential_credits),
+        )
+        .route(
+            "/confidential-balances/harvest-withheld-fees",
+            post(handlers::harvest_withheld_fees),
+        )
+        .route(
+            "/confidential-balances/withdraw-withheld-fees",
+            post(handlers::withdraw_withheld_fees),
+        )
+        .route(
+            "/confidential-balances/enable-harvest-to-mint",
+            post(handlers::enable_harvest_to_mint),
+        )
+        .route(
+            "/confidential-balances/disable-harvest-to-mint",
+            post(handlers::disable_harvest_to_mint),
+        )
         .route("/confidential-balances/balances", post(handlers::balances))
+        .route(
+            "/confidential-balances/portfolio",
+            post(handlers::portfolio),
+        )
+        .route("/confidential-balances/history", post(handlers::history))
+        .route(
+            "/confidential-balances/notifications",
+            post(handlers::notifications),
+        )
+        .route(
+            "/private-wrapper/create-wrapped-mint",
+            post(handlers::create_wrapped_mint),
+        )
         .route("/private-wrapper/wrap", post(handlers::wrap_tokens))
         .route("/private-wrapper/unwrap", post(handlers::unwrap_tokens))
-        .with_state(Arc::new(AppState { rpc }))
+        .route(
+            "/private-wrapper/simple-wrap",
+            post(handlers::simple_wrap_tokens),
+        )
+        .route(
+            "/private-wrapper/simple-unwrap",
+            post(handlers::simple_unwrap_tokens),
+        )
+        .route("/private-wrapper/shield", post(handlers::shield))
+        .route("/private-wrapper/unshield", post(handlers::unshield))
+        .route(
+            "/private-wrapper/wrapped-mint",
+            get(handlers::wrapped_mint_info),
+        )
+        .route(
+            "/private-wrapper/wrapped-mints",
+            get(handlers::list_wrapped_mints),
+        )
+        .route("/indexer/status", get(handlers::indexer_status))
+        .route("/indexer/instructions", get(handlers::indexer_instructions))
+        .with_state(Arc::new(AppState {
+            subscriptions: Arc::new(SubscriptionPool::new(rpc.url(), MAX_SUBSCRIPTIONS)),
+            rpc,
+            discrete_log,
+            indexer,
+        }))
         .layer(
             TraceLayer::new_for_http()
                 .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
@@ -21,6 +21,58 @@ pub mod pubkey_string {
     }
 }
 
+pub mod option_pubkey_string {
+    use {
+        serde::{Deserialize, Deserializer, Serializer},
+        solana_sdk::pubkey::Pubkey,
+        std::str::FromStr,
+    };
+
+    pub fn serialize<S>(pubkey: &Option<Pubkey>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match pubkey {
+            Some(pubkey) => serializer.serialize_some(&pubkey.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Pubkey>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: Option<String> = Deserialize::deserialize(deserializer)?;
+        s.map(|s| Pubkey::from_str(&s).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+pub mod pubkey_vec_string {
+    use {
+        serde::{Deserialize, Deserializer, Serializer},
+        solana_sdk::pubkey::Pubkey,
+        std::str::FromStr,
+    };
+
+    pub fn serialize<S>(pubkeys: &[Pubkey], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(pubkeys.iter().map(|pubkey| pubkey.to_string()))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Pubkey>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: Vec<String> = Deserialize::deserialize(deserializer)?;
+        s.iter()
+            .map(|s| Pubkey::from_str(s).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
 pub mod signature_string {
     use {
         serde::{Deserialize, Deserializer, Serializer},
@@ -65,3 +117,102 @@ pub mod keypair_string {
         Ok(Keypair::from_base58_string(&s))
     }
 }
+
+pub mod option_keypair_string {
+    use {
+        serde::{Deserialize, Deserializer, Serializer},
+        solana_sdk::signature::Keypair,
+    };
+
+    pub fn serialize<S>(keypair: &Option<Keypair>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match keypair {
+            Some(keypair) => serializer.serialize_some(&keypair.to_base58_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Keypair>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: Option<String> = Deserialize::deserialize(deserializer)?;
+        Ok(s.map(|s| Keypair::from_base58_string(&s)))
+    }
+}
+
+pub mod u64_string {
+    use {
+        serde::{Deserialize, Deserializer, Serializer},
+        std::str::FromStr,
+    };
+
+    pub fn serialize<S>(amount: &u64, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&amount.to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        u64::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+pub mod i128_string {
+    use {
+        serde::{Deserialize, Deserializer, Serializer},
+        std::str::FromStr,
+    };
+
+    pub fn serialize<S>(amount: &i128, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&amount.to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<i128, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        i128::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Encodes an optional value using its `Display` and `FromStr` implementations
+pub mod option_string {
+    use {
+        serde::{Deserialize, Deserializer, Serializer},
+        std::{fmt::Display, str::FromStr},
+    };
+
+    pub fn serialize<T, S>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Display,
+        S: Serializer,
+    {
+        match value {
+            Some(value) => serializer.serialize_some(&value.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        let s: Option<String> = Deserialize::deserialize(deserializer)?;
+        s.map(|s| T::from_str(&s).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
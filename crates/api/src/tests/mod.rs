@@ -2,32 +2,54 @@ use {
     crate::{
         router,
         types::{
-            ApiBalancesResponse, ApiTransactionResponse, Balances, Deposit, InitializeOrApply,
-            Transfer, Withdraw, WrapTokens,
+            ApiBalancesResponse, ApiConfidentialSupplyResponse, ApiError, ApiHistoryResponse,
+            ApiPendingApprovalsResponse, ApiPortfolioResponse, ApiTransactionResponse,
+            ApiTransferResponse, ApiWithdrawWithheldFeesResponse, ApiWrappedMintResponse,
+            ApiWrappedMintsResponse, ApproveAccount, Balances, CloseAccount, ConfidentialBurn,
+            ConfidentialMint, ConfidentialSupply, CreateConfidentialMint, CreateWrappedMint,
+            Deposit, HarvestWithheldFees, History, InitializeOrApply, Portfolio, Shield, Transfer,
+            Unshield, UpdateCredits, UpdateHarvestToMint, Withdraw, WithdrawWithheldFees,
+            WrapTokens,
         },
     },
     axum_test::TestServer,
-    common::{key_generator::KeypairType, test_helpers::test_key},
+    common::{
+        discrete_log::{DiscreteLogTable, MIN_BABY_STEP_BITS},
+        key_generator::KeypairType,
+        test_helpers::test_key,
+    },
     solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig},
     solana_sdk::{
-        program_pack::Pack, pubkey::Pubkey, signature::Keypair, signer::Signer, system_instruction,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature},
+        signer::Signer,
+        system_instruction,
         transaction::Transaction,
     },
     solana_transaction_status_client_types::UiTransactionEncoding,
-    spl_token_2022::{extension::ExtensionType, state::Mint},
-    spl_token_client::token::ExtensionInitializationParams,
-    spl_token_wrap::{
-        get_wrapped_mint_address, get_wrapped_mint_authority, get_wrapped_mint_backpointer_address,
-        state::Backpointer,
+    spl_token_2022::{
+        extension::ExtensionType, solana_zk_sdk::encryption::elgamal::ElGamalKeypair, state::Mint,
     },
+    spl_token_client::token::ExtensionInitializationParams,
+    spl_token_wrap::get_wrapped_mint_address,
     std::sync::Arc,
 };
 
+pub mod test_approvals;
+pub mod test_close_account;
+pub mod test_credits;
 pub mod test_deposit;
+pub mod test_history;
+pub mod test_indexer;
 pub mod test_initialize;
+pub mod test_mint_burn;
+pub mod test_notifications;
+pub mod test_portfolio;
 pub mod test_private_wrapper;
 pub mod test_transfer;
 pub mod test_withdraw;
+pub mod test_withheld_fees;
 
 struct BlinkTestClient {
     rpc: Arc<RpcClient>,
@@ -53,7 +75,12 @@ impl BlinkTestClient {
         }
         Self {
             rpc: rpc.clone(),
-            server: TestServer::new(router::new(rpc)).unwrap(),
+            server: TestServer::new(router::new(
+                rpc,
+                Arc::new(DiscreteLogTable::new(MIN_BABY_STEP_BITS).unwrap()),
+                None,
+            ))
+            .unwrap(),
         }
     }
     async fn test_initialize(&mut self, key: &Keypair, mint: Pubkey) {
@@ -67,6 +94,7 @@ impl BlinkTestClient {
             token_mint: mint,
             elgamal_signature: elgamal_sig,
             ae_signature: ae_sig,
+            maximum_pending_balance_credit_counter: None,
         };
         let res = self
             .server
@@ -77,6 +105,22 @@ impl BlinkTestClient {
         let response: ApiTransactionResponse = serde_json::from_slice(res.as_bytes()).unwrap();
         self.send_tx(key, response).await;
     }
+    async fn initialize(
+        &mut self,
+        init: &InitializeOrApply,
+    ) -> Result<ApiTransactionResponse, ApiError> {
+        let res = self
+            .server
+            .post("/confidential-balances/initialize")
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(init).unwrap().into())
+            .await;
+        if res.status_code().is_success() {
+            Ok(res.json())
+        } else {
+            Err(res.json())
+        }
+    }
     async fn test_deposit(&mut self, key: &Keypair, mint: Pubkey, amount: u64) {
         println!("depositing to pending balance");
 
@@ -84,6 +128,7 @@ impl BlinkTestClient {
             authority: key.pubkey(),
             token_mint: mint,
             amount,
+            memo: None,
         };
         let res = self
             .server
@@ -120,6 +165,60 @@ impl BlinkTestClient {
         let response: ApiTransactionResponse = serde_json::from_str(&res).unwrap();
         self.send_tx(key, response).await;
     }
+    async fn test_shield(&mut self, key: &Keypair, unwrapped_mint: &Keypair, amount: u64) {
+        println!("shielding tokens");
+        let wrapped_mint =
+            get_wrapped_mint_address(&unwrapped_mint.pubkey(), &spl_token_2022::id());
+        let user_ata = get_user_ata(key, wrapped_mint);
+
+        let shield = Shield {
+            authority: key.pubkey(),
+            unwrapped_token_mint: unwrapped_mint.pubkey(),
+            unwrapped_token_program: spl_token_2022::id(),
+            amount,
+            elgamal_signature: key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata)),
+            ae_signature: key.sign_message(&KeypairType::Ae.message_to_sign(user_ata)),
+        };
+        let res = self
+            .server
+            .post("/private-wrapper/shield")
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(&shield).unwrap().into())
+            .await;
+        let response: ApiTransactionResponse = serde_json::from_slice(res.as_bytes()).unwrap();
+        self.send_tx(key, response).await;
+    }
+    async fn test_unshield(&mut self, key: &Keypair, unwrapped_mint: &Keypair, amount: u64) {
+        println!("unshielding tokens");
+        let wrapped_mint =
+            get_wrapped_mint_address(&unwrapped_mint.pubkey(), &spl_token_2022::id());
+        let user_ata = get_user_ata(key, wrapped_mint);
+        let equality_proof_keypair = Keypair::new();
+        let range_proof_keypair = Keypair::new();
+
+        let unshield = Unshield {
+            authority: key.pubkey(),
+            unwrapped_token_mint: unwrapped_mint.pubkey(),
+            unwrapped_token_program: spl_token_2022::id(),
+            amount,
+            elgamal_signature: key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata)),
+            ae_signature: key.sign_message(&KeypairType::Ae.message_to_sign(user_ata)),
+            equality_proof_keypair: equality_proof_keypair.insecure_clone(),
+            range_proof_keypair: range_proof_keypair.insecure_clone(),
+        };
+        let res = self
+            .server
+            .post("/private-wrapper/unshield")
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(&unshield).unwrap().into())
+            .await;
+        let response: ApiTransactionResponse = serde_json::from_slice(res.as_bytes()).unwrap();
+        self.send_tx_with_signers(
+            response,
+            &[key, &equality_proof_keypair, &range_proof_keypair],
+        )
+        .await;
+    }
     async fn test_unwrap_tokens(
         &mut self,
         key: &Keypair,
@@ -157,6 +256,7 @@ impl BlinkTestClient {
             token_mint: mint,
             elgamal_signature: elgamal_sig,
             ae_signature: ae_sig,
+            maximum_pending_balance_credit_counter: None,
         };
         let res = self
             .server
@@ -184,6 +284,7 @@ impl BlinkTestClient {
             ae_signature: ae_sig,
             equality_proof_keypair: equality_proof_keypair.insecure_clone(),
             range_proof_keypair: range_proof_keypair.insecure_clone(),
+            memo: None,
         };
         let res = self
             .server
@@ -238,6 +339,9 @@ impl BlinkTestClient {
             equality_proof_keypair: equality_proof_keypair.insecure_clone(),
             range_proof_keypair: range_proof_keypair.insecure_clone(),
             ciphertext_validity_proof_keypair: ciphertext_proof_keypair.insecure_clone(),
+            fee_sigma_proof_keypair: None,
+            fee_ciphertext_validity_proof_keypair: None,
+            memo: None,
         };
         let res = self
             .server
@@ -285,6 +389,50 @@ impl BlinkTestClient {
             .await;
         serde_json::from_slice(res.as_bytes()).unwrap()
     }
+    async fn get_history(
+        &mut self,
+        key: &Keypair,
+        mint: Pubkey,
+        before: Option<Signature>,
+        limit: Option<usize>,
+    ) -> ApiHistoryResponse {
+        let user_ata = get_user_ata(key, mint);
+        let elgamal_sig = key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata));
+        let ae_sig = key.sign_message(&KeypairType::Ae.message_to_sign(user_ata));
+
+        let history = History {
+            authority: key.pubkey(),
+            token_mint: mint,
+            elgamal_signature: elgamal_sig,
+            ae_signature: ae_sig,
+            before,
+            limit,
+        };
+        let res = self
+            .server
+            .post("/confidential-balances/history")
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(&history).unwrap().into())
+            .await;
+        serde_json::from_slice(res.as_bytes()).unwrap()
+    }
+    async fn get_portfolio(&mut self, key: &Keypair) -> ApiPortfolioResponse {
+        let elgamal_sig = key.sign_message(&KeypairType::ElGamal.message_to_sign(key.pubkey()));
+        let ae_sig = key.sign_message(&KeypairType::Ae.message_to_sign(key.pubkey()));
+
+        let portfolio = Portfolio {
+            authority: key.pubkey(),
+            elgamal_signature: elgamal_sig,
+            ae_signature: ae_sig,
+        };
+        let res = self
+            .server
+            .post("/confidential-balances/portfolio")
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(&portfolio).unwrap().into())
+            .await;
+        serde_json::from_slice(res.as_bytes()).unwrap()
+    }
     async fn create_confidential_mint(&mut self, key: &Keypair, mint: &Keypair) {
         println!("creating confidential mint");
         let space = ExtensionType::try_calculate_account_len::<Mint>(&[
@@ -335,6 +483,78 @@ impl BlinkTestClient {
 
         self.rpc.send_and_confirm_transaction(&tx).await.unwrap();
     }
+    /// Creates a confidential mint which charges a transfer fee, withheld for
+    /// `withdraw_withheld_authority`
+    async fn create_confidential_transfer_fee_mint(
+        &mut self,
+        key: &Keypair,
+        mint: &Keypair,
+        withdraw_withheld_authority: &ElGamalKeypair,
+        transfer_fee_basis_points: u16,
+        maximum_fee: u64,
+    ) {
+        println!("creating confidential transfer fee mint");
+        let space = ExtensionType::try_calculate_account_len::<Mint>(&[
+            ExtensionType::ConfidentialTransferMint,
+            ExtensionType::TransferFeeConfig,
+            ExtensionType::ConfidentialTransferFeeConfig,
+        ])
+        .unwrap();
+        let rent = self
+            .rpc
+            .get_minimum_balance_for_rent_exemption(space)
+            .await
+            .unwrap();
+
+        let mut instructions = vec![system_instruction::create_account(
+            &key.pubkey(),
+            &mint.pubkey(),
+            rent,
+            space as u64,
+            &spl_token_2022::id(),
+        )];
+        for extension in [
+            ExtensionInitializationParams::ConfidentialTransferMint {
+                authority: Some(key.pubkey()),
+                auto_approve_new_accounts: true,
+                auditor_elgamal_pubkey: None,
+            },
+            ExtensionInitializationParams::TransferFeeConfig {
+                transfer_fee_config_authority: Some(key.pubkey()),
+                withdraw_withheld_authority: Some(key.pubkey()),
+                transfer_fee_basis_points,
+                maximum_fee,
+            },
+            ExtensionInitializationParams::ConfidentialTransferFeeConfig {
+                authority: Some(key.pubkey()),
+                withdraw_withheld_authority_elgamal_pubkey: (*withdraw_withheld_authority.pubkey())
+                    .into(),
+            },
+        ] {
+            instructions.push(
+                extension
+                    .instruction(&spl_token_2022::id(), &mint.pubkey())
+                    .unwrap(),
+            );
+        }
+        instructions.push(
+            spl_token_2022::instruction::initialize_mint2(
+                &spl_token_2022::id(),
+                &mint.pubkey(),
+                &key.pubkey(),
+                None,
+                6,
+            )
+            .unwrap(),
+        );
+
+        let mut tx = Transaction::new_with_payer(&instructions, Some(&key.pubkey()));
+        tx.sign(
+            &vec![key, mint],
+            self.rpc.get_latest_blockhash().await.unwrap(),
+        );
+        self.rpc.send_and_confirm_transaction(&tx).await.unwrap();
+    }
     async fn create_mint(&mut self, key: &Keypair, mint: &Keypair) {
         let create_mint_ix = system_instruction::create_account(
             &key.pubkey(),
@@ -362,6 +582,52 @@ impl BlinkTestClient {
         );
         self.rpc.send_and_confirm_transaction(&tx).await.unwrap();
     }
+    async fn create_transfer_fee_mint(
+        &mut self,
+        key: &Keypair,
+        mint: &Keypair,
+        transfer_fee_basis_points: u16,
+        maximum_fee: u64,
+    ) {
+        let space =
+            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig])
+                .unwrap();
+        let create_mint_ix = system_instruction::create_account(
+            &key.pubkey(),
+            &mint.pubkey(),
+            self.rpc
+                .get_minimum_balance_for_rent_exemption(space)
+                .await
+                .unwrap(),
+            space as u64,
+            &spl_token_2022::id(),
+        );
+        let extension_init_ix = ExtensionInitializationParams::TransferFeeConfig {
+            transfer_fee_config_authority: Some(key.pubkey()),
+            withdraw_withheld_authority: Some(key.pubkey()),
+            transfer_fee_basis_points,
+            maximum_fee,
+        }
+        .instruction(&spl_token_2022::id(), &mint.pubkey())
+        .unwrap();
+        let init_mint_ix = spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::id(),
+            &mint.pubkey(),
+            &key.pubkey(),
+            None,
+            6,
+        )
+        .unwrap();
+        let mut tx = Transaction::new_with_payer(
+            &[create_mint_ix, extension_init_ix, init_mint_ix],
+            Some(&key.pubkey()),
+        );
+        tx.sign(
+            &vec![key, mint],
+            self.rpc.get_latest_blockhash().await.unwrap(),
+        );
+        self.rpc.send_and_confirm_transaction(&tx).await.unwrap();
+    }
     // returns the address of the wrapped mint
     async fn create_confidential_wrapped_mint(
         &mut self,
@@ -369,65 +635,272 @@ impl BlinkTestClient {
         unwrapped_mint: &Keypair,
     ) -> Pubkey {
         println!("creating wrapped confidential mint");
-
-        let wrapped_mint_address =
-            get_wrapped_mint_address(&unwrapped_mint.pubkey(), &spl_token_2022::id());
-
-        let backpoint_rent = self
-            .rpc
-            .get_minimum_balance_for_rent_exemption(std::mem::size_of::<Backpointer>())
+        let res = self
+            .create_wrapped_mint(&CreateWrappedMint {
+                payer: key.pubkey(),
+                unwrapped_token_mint: unwrapped_mint.pubkey(),
+                auto_approve_new_accounts: true,
+                auditor_elgamal_pubkey: None,
+                confidential_transfer_authority: None,
+            })
             .await
             .unwrap();
-        let mint_size = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
-            ExtensionType::ConfidentialTransferMint,
-        ])
-        .unwrap();
-        let mint_rent = self
-            .rpc
-            .get_minimum_balance_for_rent_exemption(mint_size)
+        self.send_tx(key, res).await;
+        get_wrapped_mint_address(&unwrapped_mint.pubkey(), &spl_token_2022::id())
+    }
+    async fn create_wrapped_mint(
+        &mut self,
+        create: &CreateWrappedMint,
+    ) -> Result<ApiTransactionResponse, ApiError> {
+        let res = self
+            .server
+            .post("/private-wrapper/create-wrapped-mint")
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(create).unwrap().into())
+            .await;
+        if res.status_code().is_success() {
+            Ok(res.json())
+        } else {
+            Err(res.json())
+        }
+    }
+    async fn approve_account(
+        &mut self,
+        approve: &ApproveAccount,
+    ) -> Result<ApiTransactionResponse, ApiError> {
+        let res = self
+            .server
+            .post("/confidential-balances/approve-account")
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(approve).unwrap().into())
+            .await;
+        if res.status_code().is_success() {
+            Ok(res.json())
+        } else {
+            Err(res.json())
+        }
+    }
+    async fn close_account(
+        &mut self,
+        close: &CloseAccount,
+    ) -> Result<ApiTransactionResponse, ApiError> {
+        let res = self
+            .server
+            .post("/confidential-balances/close")
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(close).unwrap().into())
+            .await;
+        if res.status_code().is_success() {
+            Ok(res.json())
+        } else {
+            Err(res.json())
+        }
+    }
+    /// Posts to one of the wrap or unwrap routes, ie: `simple-wrap`
+    async fn wrap_tokens<T: serde::Serialize>(
+        &mut self,
+        route: &str,
+        wrap: &T,
+    ) -> Result<ApiTransactionResponse, ApiError> {
+        let res = self
+            .server
+            .post(&format!("/private-wrapper/{route}"))
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(wrap).unwrap().into())
+            .await;
+        if res.status_code().is_success() {
+            Ok(res.json())
+        } else {
+            Err(res.json())
+        }
+    }
+    async fn transfer(&mut self, transfer: &Transfer) -> Result<ApiTransferResponse, ApiError> {
+        let res = self
+            .server
+            .post("/confidential-balances/transfer")
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(transfer).unwrap().into())
+            .await;
+        if res.status_code().is_success() {
+            Ok(res.json())
+        } else {
+            Err(res.json())
+        }
+    }
+    /// Posts to one of the enable or disable credits routes, ie: `enable-confidential-credits`
+    async fn update_credits(
+        &mut self,
+        route: &str,
+        update: &UpdateCredits,
+    ) -> Result<ApiTransactionResponse, ApiError> {
+        let res = self
+            .server
+            .post(&format!("/confidential-balances/{route}"))
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(update).unwrap().into())
+            .await;
+        if res.status_code().is_success() {
+            Ok(res.json())
+        } else {
+            Err(res.json())
+        }
+    }
+    async fn harvest_withheld_fees(
+        &mut self,
+        harvest: &HarvestWithheldFees,
+    ) -> Result<ApiTransactionResponse, ApiError> {
+        let res = self
+            .server
+            .post("/confidential-balances/harvest-withheld-fees")
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(harvest).unwrap().into())
+            .await;
+        if res.status_code().is_success() {
+            Ok(res.json())
+        } else {
+            Err(res.json())
+        }
+    }
+    async fn withdraw_withheld_fees(
+        &mut self,
+        withdraw: &WithdrawWithheldFees,
+    ) -> Result<ApiWithdrawWithheldFeesResponse, ApiError> {
+        let res = self
+            .server
+            .post("/confidential-balances/withdraw-withheld-fees")
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(withdraw).unwrap().into())
+            .await;
+        if res.status_code().is_success() {
+            Ok(res.json())
+        } else {
+            Err(res.json())
+        }
+    }
+    /// Posts to either the `enable-harvest-to-mint` or `disable-harvest-to-mint` route
+    async fn update_harvest_to_mint(
+        &mut self,
+        route: &str,
+        update: &UpdateHarvestToMint,
+    ) -> Result<ApiTransactionResponse, ApiError> {
+        let res = self
+            .server
+            .post(&format!("/confidential-balances/{route}"))
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(update).unwrap().into())
+            .await;
+        if res.status_code().is_success() {
+            Ok(res.json())
+        } else {
+            Err(res.json())
+        }
+    }
+    /// Creates a confidential mint through the api, keeping its supply confidential with the
+    /// supply keys of `key`
+    async fn create_confidential_supply_mint(&mut self, key: &Keypair, mint: &Keypair) {
+        println!("creating confidential supply mint");
+        let res = self
+            .create_native_confidential_mint(&CreateConfidentialMint {
+                authority: key.pubkey(),
+                mint: mint.pubkey(),
+                decimals: 6,
+                auto_approve_new_accounts: true,
+                auditor_elgamal_pubkey: None,
+                confidential_transfer_authority: None,
+                supply_elgamal_signature: Some(
+                    key.sign_message(&KeypairType::SupplyElGamal.message_to_sign(mint.pubkey())),
+                ),
+                supply_ae_signature: Some(
+                    key.sign_message(&KeypairType::SupplyAe.message_to_sign(mint.pubkey())),
+                ),
+            })
             .await
             .unwrap();
-
-        let ix = spl_token_wrap::instruction::create_confidential_mint(
-            &spl_token_wrap::ID,
-            &wrapped_mint_address,
-            &get_wrapped_mint_backpointer_address(&wrapped_mint_address),
-            &unwrapped_mint.pubkey(),
-            &spl_token_2022::id(),
-            true,
-            true,
-            [0u8; 32],
-            [0u8; 32],
-        );
-        let mut ixs = vec![];
-        ixs.append(&mut system_instruction::transfer_many(
-            &key.pubkey(),
-            &[
-                (
-                    get_wrapped_mint_backpointer_address(&wrapped_mint_address),
-                    backpoint_rent,
-                ),
-                (wrapped_mint_address, mint_rent),
-            ],
-        ));
-        ixs.push(ix);
-        let mut tx = Transaction::new_with_payer(&ixs, Some(&key.pubkey()));
-        tx.sign(&vec![key], self.rpc.get_latest_blockhash().await.unwrap());
-
-        self.rpc.send_and_confirm_transaction(&tx).await.unwrap();
-
-        // create the escrow account
-        let ix =
-            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
-                &key.pubkey(),
-                &get_wrapped_mint_authority(&wrapped_mint_address),
-                &unwrapped_mint.pubkey(),
-                &spl_token_2022::id(),
-            );
-        let mut tx = Transaction::new_with_payer(&[ix], Some(&key.pubkey()));
-        tx.sign(&vec![key], self.rpc.get_latest_blockhash().await.unwrap());
-        self.rpc.send_and_confirm_transaction(&tx).await.unwrap();
-        wrapped_mint_address
+        self.send_tx_with_signers(res, &[key, mint]).await;
+    }
+    async fn create_native_confidential_mint(
+        &mut self,
+        create: &CreateConfidentialMint,
+    ) -> Result<ApiTransactionResponse, ApiError> {
+        let res = self
+            .server
+            .post("/confidential-balances/create-confidential-mint")
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(create).unwrap().into())
+            .await;
+        if res.status_code().is_success() {
+            Ok(res.json())
+        } else {
+            Err(res.json())
+        }
+    }
+    async fn confidential_mint(
+        &mut self,
+        mint: &ConfidentialMint,
+    ) -> Result<ApiTransactionResponse, ApiError> {
+        let res = self
+            .server
+            .post("/confidential-balances/mint")
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(mint).unwrap().into())
+            .await;
+        if res.status_code().is_success() {
+            Ok(res.json())
+        } else {
+            Err(res.json())
+        }
+    }
+    async fn confidential_burn(
+        &mut self,
+        burn: &ConfidentialBurn,
+    ) -> Result<ApiTransactionResponse, ApiError> {
+        let res = self
+            .server
+            .post("/confidential-balances/burn")
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(burn).unwrap().into())
+            .await;
+        if res.status_code().is_success() {
+            Ok(res.json())
+        } else {
+            Err(res.json())
+        }
+    }
+    async fn get_supply(
+        &mut self,
+        supply: &ConfidentialSupply,
+    ) -> Result<ApiConfidentialSupplyResponse, ApiError> {
+        let res = self
+            .server
+            .post("/confidential-balances/supply")
+            .add_header("Content-Type", "application/json")
+            .bytes(serde_json::to_string(supply).unwrap().into())
+            .await;
+        if res.status_code().is_success() {
+            Ok(res.json())
+        } else {
+            Err(res.json())
+        }
+    }
+    async fn get_wrapped_mint_info(&mut self, mint: Pubkey) -> ApiWrappedMintResponse {
+        let res = self
+            .server
+            .get("/private-wrapper/wrapped-mint")
+            .add_query_param("token_mint", mint.to_string())
+            .await;
+        res.json()
+    }
+    async fn list_wrapped_mints(&mut self) -> ApiWrappedMintsResponse {
+        let res = self.server.get("/private-wrapper/wrapped-mints").await;
+        res.json()
+    }
+    async fn get_pending_approvals(&mut self, mint: Pubkey) -> ApiPendingApprovalsResponse {
+        let res = self
+            .server
+            .get("/confidential-balances/pending-approvals")
+            .add_query_param("token_mint", mint.to_string())
+            .await;
+        res.json()
     }
     async fn mint_tokens(&mut self, key: &Keypair, mint: Pubkey, amount: u64) {
         let mut tx = Transaction::new_with_payer(
@@ -457,6 +930,20 @@ impl BlinkTestClient {
         tx.sign(&vec![key], self.rpc.get_latest_blockhash().await.unwrap());
         self.rpc.send_and_confirm_transaction(&tx).await.unwrap();
     }
+    /// Signs each transaction with the keypairs from `signers` that it requires
+    async fn send_tx_with_signers(&mut self, res: ApiTransactionResponse, signers: &[&Keypair]) {
+        for mut tx in res.decode_transactions().unwrap() {
+            let required =
+                &tx.message.account_keys[..tx.message.header.num_required_signatures as usize];
+            let tx_signers = signers
+                .iter()
+                .copied()
+                .filter(|signer| required.contains(&signer.pubkey()))
+                .collect::<Vec<_>>();
+            tx.sign(&tx_signers, self.rpc.get_latest_blockhash().await.unwrap());
+            self.rpc.send_and_confirm_transaction(&tx).await.unwrap();
+        }
+    }
     async fn send_tx(&mut self, key: &Keypair, res: ApiTransactionResponse) {
         let transactions = res.decode_transactions().unwrap();
         for mut tx in transactions {
@@ -0,0 +1,72 @@
+use {
+    crate::{
+        tests::{get_user_ata, BlinkTestClient},
+        types::{ApproveAccount, CreateWrappedMint},
+    },
+    common::test_helpers::test_key,
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{signature::Keypair, signer::Signer},
+    spl_token_wrap::get_wrapped_mint_address,
+    std::sync::Arc,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_approve_account() {
+    let key = test_key();
+    let unwrapped_mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    test_client.create_mint(&key, &unwrapped_mint).await;
+
+    // create a wrapped mint which requires token accounts to be approved
+    let res = test_client
+        .create_wrapped_mint(&CreateWrappedMint {
+            payer: key.pubkey(),
+            unwrapped_token_mint: unwrapped_mint.pubkey(),
+            auto_approve_new_accounts: false,
+            auditor_elgamal_pubkey: None,
+            confidential_transfer_authority: Some(key.pubkey()),
+        })
+        .await
+        .unwrap();
+    test_client.send_tx(&key, res).await;
+    let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint.pubkey(), &spl_token_2022::id());
+
+    test_client.test_initialize(&key, wrapped_mint).await;
+    let user_ata = get_user_ata(&key, wrapped_mint);
+
+    let balances = test_client.get_balances(&key, wrapped_mint).await;
+    assert!(!balances.approved);
+
+    let pending = test_client.get_pending_approvals(wrapped_mint).await;
+    assert!(!pending.auto_approve_new_accounts);
+    assert_eq!(pending.confidential_transfer_authority, Some(key.pubkey()));
+    assert_eq!(pending.token_accounts.len(), 1);
+    assert_eq!(pending.token_accounts[0].token_account, user_ata);
+    assert_eq!(pending.token_accounts[0].owner, key.pubkey());
+
+    // only the confidential transfer authority can approve accounts
+    let mut approve = ApproveAccount {
+        authority: Keypair::new().pubkey(),
+        token_mint: wrapped_mint,
+        token_account: user_ata,
+    };
+    assert!(test_client.approve_account(&approve).await.is_err());
+
+    approve.authority = key.pubkey();
+    let res = test_client.approve_account(&approve).await.unwrap();
+    test_client.send_tx(&key, res).await;
+
+    let balances = test_client.get_balances(&key, wrapped_mint).await;
+    assert!(balances.approved);
+    assert!(test_client
+        .get_pending_approvals(wrapped_mint)
+        .await
+        .token_accounts
+        .is_empty());
+
+    // approving twice is rejected
+    assert!(test_client.approve_account(&approve).await.is_err());
+}
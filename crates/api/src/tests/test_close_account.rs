@@ -0,0 +1,91 @@
+use {
+    super::get_user_ata,
+    crate::{tests::BlinkTestClient, types::CloseAccount},
+    common::{key_generator::KeypairType, test_helpers::test_key},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{signature::Keypair, signer::Signer, transaction::Transaction},
+    std::sync::Arc,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_close_account() {
+    let key = test_key();
+    let mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+    let mut test_client = BlinkTestClient::new(rpc).await;
+    test_client.create_confidential_mint(&key, &mint).await;
+
+    test_client.test_initialize(&key, mint.pubkey()).await;
+
+    test_client
+        .mint_tokens(&key, mint.pubkey(), 1_000_000)
+        .await;
+
+    // leave tokens in both the available and pending balances
+    test_client.test_deposit(&key, mint.pubkey(), 200).await;
+    test_client.test_apply(&key, mint.pubkey()).await;
+    test_client.test_deposit(&key, mint.pubkey(), 100).await;
+
+    let user_ata = get_user_ata(&key, mint.pubkey());
+    let equality_proof_keypair = Keypair::new();
+    let range_proof_keypair = Keypair::new();
+    let mut close = CloseAccount {
+        authority: key.pubkey(),
+        token_mint: mint.pubkey(),
+        elgamal_signature: key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata)),
+        ae_signature: key.sign_message(&KeypairType::Ae.message_to_sign(user_ata)),
+        drain: false,
+        close: false,
+        equality_proof_keypair: Some(equality_proof_keypair.insecure_clone()),
+        range_proof_keypair: Some(range_proof_keypair.insecure_clone()),
+    };
+
+    // a non empty confidential balance must be drained first
+    assert!(test_client.close_account(&close).await.is_err());
+
+    // the non confidential balance prevents closing
+    close.drain = true;
+    close.close = true;
+    assert!(test_client.close_account(&close).await.is_err());
+
+    close.close = false;
+    let res = test_client.close_account(&close).await.unwrap();
+    test_client
+        .send_tx_with_signers(res, &[&key, &equality_proof_keypair, &range_proof_keypair])
+        .await;
+
+    let balances = test_client.get_balances(&key, mint.pubkey()).await;
+    assert_eq!(balances.pending_balance_amount, 0);
+    assert_eq!(balances.available_balance_amount, 0);
+    assert_eq!(balances.non_confidential_balance_amount, 1_000_000);
+
+    // burn the non confidential balance so the account can be closed
+    let mut tx = Transaction::new_with_payer(
+        &[spl_token_2022::instruction::burn(
+            &spl_token_2022::id(),
+            &user_ata,
+            &mint.pubkey(),
+            &key.pubkey(),
+            &[],
+            1_000_000,
+        )
+        .unwrap()],
+        Some(&key.pubkey()),
+    );
+    tx.sign(
+        &vec![&key],
+        test_client.rpc.get_latest_blockhash().await.unwrap(),
+    );
+    test_client
+        .rpc
+        .send_and_confirm_transaction(&tx)
+        .await
+        .unwrap();
+
+    close.drain = false;
+    close.close = true;
+    let res = test_client.close_account(&close).await.unwrap();
+    test_client.send_tx(&key, res).await;
+
+    assert!(test_client.rpc.get_account(&user_ata).await.is_err());
+}
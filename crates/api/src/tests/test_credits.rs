@@ -0,0 +1,55 @@
+use {
+    crate::{tests::BlinkTestClient, types::UpdateCredits},
+    common::test_helpers::test_key,
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{signature::Keypair, signer::Signer},
+    std::sync::Arc,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_toggle_credits() {
+    let key = test_key();
+    let mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+    let mut test_client = BlinkTestClient::new(rpc).await;
+    test_client.create_confidential_mint(&key, &mint).await;
+
+    test_client.test_initialize(&key, mint.pubkey()).await;
+
+    let balances = test_client.get_balances(&key, mint.pubkey()).await;
+    assert!(balances.allow_confidential_credits);
+    assert!(balances.allow_non_confidential_credits);
+
+    let update = UpdateCredits {
+        authority: key.pubkey(),
+        token_mint: mint.pubkey(),
+    };
+
+    // credits which are already enabled can't be enabled again
+    assert!(test_client
+        .update_credits("enable-confidential-credits", &update)
+        .await
+        .is_err());
+
+    for route in [
+        "disable-confidential-credits",
+        "disable-non-confidential-credits",
+    ] {
+        let res = test_client.update_credits(route, &update).await.unwrap();
+        test_client.send_tx(&key, res).await;
+    }
+    let balances = test_client.get_balances(&key, mint.pubkey()).await;
+    assert!(!balances.allow_confidential_credits);
+    assert!(!balances.allow_non_confidential_credits);
+
+    for route in [
+        "enable-confidential-credits",
+        "enable-non-confidential-credits",
+    ] {
+        let res = test_client.update_credits(route, &update).await.unwrap();
+        test_client.send_tx(&key, res).await;
+    }
+    let balances = test_client.get_balances(&key, mint.pubkey()).await;
+    assert!(balances.allow_confidential_credits);
+    assert!(balances.allow_non_confidential_credits);
+}
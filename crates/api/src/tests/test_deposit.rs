@@ -43,4 +43,13 @@ async fn test_deposit() {
     assert_eq!(balances.pending_balance, 0.00001);
     assert_eq!(balances.available_balance, 0.0001);
     assert_eq!(balances.non_confidential_balance, 0.99989);
+    assert_eq!(balances.pending_balance_amount, 10);
+    assert_eq!(balances.available_balance_amount, 100);
+    assert_eq!(balances.non_confidential_balance_amount, 999_890);
+    assert_eq!(balances.decimals, 6);
+    assert_eq!(balances.pending_balance_credit_counter, 1);
+    assert_eq!(balances.maximum_pending_balance_credit_counter, 65536);
+    assert!(balances.allow_confidential_credits);
+    assert!(balances.allow_non_confidential_credits);
+    assert!(balances.approved);
 }
@@ -0,0 +1,107 @@
+use {
+    super::get_user_ata,
+    crate::{tests::BlinkTestClient, types::ApiHistoryEntryKind},
+    common::test_helpers::test_key,
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{signature::Keypair, signer::Signer},
+    std::sync::Arc,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_history() {
+    let key = test_key();
+    let key2 = Keypair::new();
+    let mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    test_client.create_confidential_mint(&key, &mint).await;
+    test_client.test_initialize(&key, mint.pubkey()).await;
+    test_client
+        .mint_tokens(&key, mint.pubkey(), 1_000_000)
+        .await;
+    test_client.test_initialize(&key2, mint.pubkey()).await;
+
+    test_client.test_deposit(&key, mint.pubkey(), 100).await;
+    test_client.test_apply(&key, mint.pubkey()).await;
+    test_client
+        .test_transfer(&key, mint.pubkey(), &key2, 10)
+        .await;
+    test_client.test_withdraw(&key, mint.pubkey(), 20).await;
+
+    let history = test_client
+        .get_history(&key, mint.pubkey(), None, None)
+        .await;
+    assert_eq!(history.token_account, get_user_ata(&key, mint.pubkey()));
+    assert_eq!(history.decimals, 6);
+    assert_eq!(history.before, None);
+
+    // entries are returned newest first
+    let kinds = history
+        .entries
+        .iter()
+        .map(|entry| entry.kind)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            ApiHistoryEntryKind::Withdraw,
+            ApiHistoryEntryKind::TransferOut,
+            ApiHistoryEntryKind::ApplyPendingBalance,
+            ApiHistoryEntryKind::Deposit,
+            ApiHistoryEntryKind::ConfigureAccount,
+        ]
+    );
+    let withdraw = &history.entries[0];
+    assert_eq!(withdraw.amount, Some(20));
+    assert_eq!(withdraw.available_balance_amount, Some(70));
+    assert_eq!(withdraw.pending_balance_amount, Some(0));
+    assert_eq!(withdraw.non_confidential_balance_amount, Some(999_920));
+    let transfer = &history.entries[1];
+    assert_eq!(transfer.amount, Some(10));
+    assert_eq!(
+        transfer.counterparty,
+        Some(get_user_ata(&key2, mint.pubkey()))
+    );
+    assert_eq!(transfer.available_balance_amount, Some(90));
+
+    // the receiver decrypts the incoming amount from the ciphertext validity proof
+    let history = test_client
+        .get_history(&key2, mint.pubkey(), None, None)
+        .await;
+    let transfer = &history.entries[0];
+    assert_eq!(transfer.kind, ApiHistoryEntryKind::TransferIn);
+    assert_eq!(transfer.amount, Some(10));
+    assert_eq!(
+        transfer.counterparty,
+        Some(get_user_ata(&key, mint.pubkey()))
+    );
+    assert_eq!(transfer.pending_balance_amount, Some(10));
+
+    // page through the history two transactions at a time
+    let first_page = test_client
+        .get_history(&key, mint.pubkey(), None, Some(2))
+        .await;
+    assert_eq!(first_page.entries.len(), 2);
+    let second_page = test_client
+        .get_history(&key, mint.pubkey(), first_page.before, Some(2))
+        .await;
+    assert_eq!(
+        second_page.entries[0].kind,
+        ApiHistoryEntryKind::ApplyPendingBalance
+    );
+    // the balances at the start of a page are recovered from older transactions
+    assert_eq!(second_page.entries[0].amount, Some(100));
+    assert_eq!(second_page.entries[0].available_balance_amount, Some(100));
+    let third_page = test_client
+        .get_history(&key, mint.pubkey(), second_page.before, Some(2))
+        .await;
+    let kinds = third_page
+        .entries
+        .iter()
+        .map(|entry| entry.kind)
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, vec![ApiHistoryEntryKind::ConfigureAccount]);
+    assert_eq!(third_page.before, None);
+}
@@ -0,0 +1,104 @@
+use {
+    super::get_user_ata,
+    crate::{
+        indexer::{Indexer, IndexerStore},
+        router,
+        tests::BlinkTestClient,
+        types::{
+            ApiIndexedInstructionKind, ApiIndexedInstructionsResponse, ApiIndexerStatusResponse,
+        },
+    },
+    axum_test::TestServer,
+    common::{
+        discrete_log::{DiscreteLogTable, MIN_BABY_STEP_BITS},
+        test_helpers::test_key,
+    },
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{signature::Keypair, signer::Signer},
+    std::{sync::Arc, time::Duration},
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_indexer() {
+    let key = test_key();
+    let key2 = Keypair::new();
+    let mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc.clone()).await;
+
+    test_client.create_confidential_mint(&key, &mint).await;
+    test_client.test_initialize(&key, mint.pubkey()).await;
+    test_client
+        .mint_tokens(&key, mint.pubkey(), 1_000_000)
+        .await;
+    test_client.test_initialize(&key2, mint.pubkey()).await;
+
+    test_client.test_deposit(&key, mint.pubkey(), 100).await;
+    test_client.test_apply(&key, mint.pubkey()).await;
+    test_client
+        .test_transfer(&key, mint.pubkey(), &key2, 10)
+        .await;
+
+    // replay the transactions confirmed by the local validator
+    let store = Arc::new(IndexerStore::open_in_memory(mint.pubkey()).unwrap());
+    let indexer = Indexer::new(rpc.clone(), store.clone(), Duration::from_secs(1));
+    assert!(indexer.index_once().await.unwrap() > 0);
+
+    let server = TestServer::new(router::new(
+        rpc,
+        Arc::new(DiscreteLogTable::new(MIN_BABY_STEP_BITS).unwrap()),
+        Some(store),
+    ))
+    .unwrap();
+
+    let user_ata = get_user_ata(&key, mint.pubkey());
+    let res = server
+        .get("/indexer/instructions")
+        .add_query_param("token_account", user_ata.to_string())
+        .await;
+    res.assert_status_ok();
+    let res: ApiIndexedInstructionsResponse = res.json();
+    let kinds = res
+        .instructions
+        .iter()
+        .map(|instruction| instruction.kind)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            ApiIndexedInstructionKind::ConfigureAccount,
+            ApiIndexedInstructionKind::Deposit,
+            ApiIndexedInstructionKind::ApplyPendingBalance,
+            ApiIndexedInstructionKind::Transfer,
+        ]
+    );
+    let transfer = &res.instructions[3];
+    assert_eq!(
+        transfer.counterparty,
+        Some(get_user_ata(&key2, mint.pubkey()))
+    );
+    assert!(transfer
+        .ciphertexts
+        .contains_key("transfer_amount_auditor_ciphertext_lo"));
+
+    // a second pass only picks up new transactions
+    test_client.test_withdraw(&key, mint.pubkey(), 20).await;
+    assert_eq!(indexer.index_once().await.unwrap(), 1);
+
+    let res = server
+        .get("/indexer/instructions")
+        .add_query_param("kind", "withdraw")
+        .await;
+    res.assert_status_ok();
+    let res: ApiIndexedInstructionsResponse = res.json();
+    assert_eq!(res.instructions.len(), 1);
+    assert_eq!(res.instructions[0].amount, Some(20));
+
+    let res = server.get("/indexer/status").await;
+    res.assert_status_ok();
+    let status: ApiIndexerStatusResponse = res.json();
+    assert_eq!(status.token_mint, mint.pubkey());
+    assert_eq!(status.token_account_count, 2);
+    assert_eq!(status.instruction_count, 6);
+}
@@ -1,6 +1,9 @@
 use {
-    crate::tests::BlinkTestClient,
-    common::test_helpers::test_key,
+    crate::{
+        tests::{get_user_ata, BlinkTestClient},
+        types::InitializeOrApply,
+    },
+    common::{key_generator::KeypairType, test_helpers::test_key},
     solana_client::nonblocking::rpc_client::RpcClient,
     solana_sdk::{signature::Keypair, signer::Signer},
     std::sync::Arc,
@@ -18,3 +21,35 @@ async fn test_initialize() {
 
     test_client.test_initialize(&key, mint.pubkey()).await;
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_initialize_maximum_pending_balance_credit_counter() {
+    let key = test_key();
+    let mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    test_client.create_confidential_mint(&key, &mint).await;
+
+    let user_ata = get_user_ata(&key, mint.pubkey());
+    let mut init = InitializeOrApply {
+        authority: key.pubkey(),
+        token_mint: mint.pubkey(),
+        elgamal_signature: key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata)),
+        ae_signature: key.sign_message(&KeypairType::Ae.message_to_sign(user_ata)),
+        maximum_pending_balance_credit_counter: Some(0),
+    };
+
+    // values outside of the supported bounds are rejected
+    assert!(test_client.initialize(&init).await.is_err());
+    init.maximum_pending_balance_credit_counter = Some(65537);
+    assert!(test_client.initialize(&init).await.is_err());
+
+    init.maximum_pending_balance_credit_counter = Some(16);
+    let res = test_client.initialize(&init).await.unwrap();
+    test_client.send_tx(&key, res).await;
+
+    let balances = test_client.get_balances(&key, mint.pubkey()).await;
+    assert_eq!(balances.maximum_pending_balance_credit_counter, 16);
+}
@@ -0,0 +1,202 @@
+use {
+    crate::{
+        tests::{get_user_ata, BlinkTestClient},
+        types::{ConfidentialBurn, ConfidentialMint, ConfidentialSupply, CreateConfidentialMint},
+    },
+    common::{key_generator::KeypairType, test_helpers::test_key},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer},
+    std::sync::Arc,
+};
+
+/// Mints `amount` confidential tokens of `mint` to `receiver`, with `key` as the mint authority
+async fn mint_confidential(
+    test_client: &mut BlinkTestClient,
+    key: &Keypair,
+    receiver: &Keypair,
+    mint: Pubkey,
+    amount: u64,
+) {
+    let equality_proof_keypair = Keypair::new();
+    let ciphertext_validity_proof_keypair = Keypair::new();
+    let range_proof_keypair = Keypair::new();
+    let res = test_client
+        .confidential_mint(&ConfidentialMint {
+            authority: key.pubkey(),
+            token_mint: mint,
+            supply_elgamal_signature: key
+                .sign_message(&KeypairType::SupplyElGamal.message_to_sign(mint)),
+            supply_ae_signature: key.sign_message(&KeypairType::SupplyAe.message_to_sign(mint)),
+            receiving_token_account: receiver.pubkey(),
+            equality_proof_keypair: equality_proof_keypair.insecure_clone(),
+            ciphertext_validity_proof_keypair: ciphertext_validity_proof_keypair.insecure_clone(),
+            range_proof_keypair: range_proof_keypair.insecure_clone(),
+            amount,
+        })
+        .await
+        .unwrap();
+    test_client
+        .send_tx_with_signers(
+            res,
+            &[
+                key,
+                &equality_proof_keypair,
+                &ciphertext_validity_proof_keypair,
+                &range_proof_keypair,
+            ],
+        )
+        .await;
+}
+
+fn burn_request(key: &Keypair, mint: Pubkey, amount: u64) -> ConfidentialBurn {
+    let user_ata = get_user_ata(key, mint);
+    ConfidentialBurn {
+        authority: key.pubkey(),
+        token_mint: mint,
+        elgamal_signature: key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata)),
+        ae_signature: key.sign_message(&KeypairType::Ae.message_to_sign(user_ata)),
+        equality_proof_keypair: Keypair::new(),
+        ciphertext_validity_proof_keypair: Keypair::new(),
+        range_proof_keypair: Keypair::new(),
+        amount,
+    }
+}
+
+fn supply_request(key: &Keypair, mint: Pubkey) -> ConfidentialSupply {
+    ConfidentialSupply {
+        authority: key.pubkey(),
+        token_mint: mint,
+        supply_elgamal_signature: key
+            .sign_message(&KeypairType::SupplyElGamal.message_to_sign(mint)),
+        supply_ae_signature: key.sign_message(&KeypairType::SupplyAe.message_to_sign(mint)),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_confidential_mint_burn() {
+    let key = test_key();
+    let mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    test_client
+        .create_confidential_supply_mint(&key, &mint)
+        .await;
+    test_client.test_initialize(&key, mint.pubkey()).await;
+
+    let supply = test_client
+        .get_supply(&supply_request(&key, mint.pubkey()))
+        .await
+        .unwrap();
+    assert_eq!(supply.supply_amount, 0);
+    assert_eq!(supply.pending_burn_amount, 0);
+
+    mint_confidential(&mut test_client, &key, &key, mint.pubkey(), 1_000).await;
+    test_client.test_apply(&key, mint.pubkey()).await;
+    let balances = test_client.get_balances(&key, mint.pubkey()).await;
+    assert_eq!(balances.available_balance_amount, 1_000);
+
+    // burns are pending until the next mint applies them to the supply
+    let burn = burn_request(&key, mint.pubkey(), 300);
+    let res = test_client.confidential_burn(&burn).await.unwrap();
+    test_client
+        .send_tx_with_signers(
+            res,
+            &[
+                &key,
+                &burn.equality_proof_keypair,
+                &burn.ciphertext_validity_proof_keypair,
+                &burn.range_proof_keypair,
+            ],
+        )
+        .await;
+    let balances = test_client.get_balances(&key, mint.pubkey()).await;
+    assert_eq!(balances.available_balance_amount, 700);
+    let supply = test_client
+        .get_supply(&supply_request(&key, mint.pubkey()))
+        .await
+        .unwrap();
+    assert_eq!(supply.supply_amount, 700);
+    assert_eq!(supply.pending_burn_amount, 300);
+
+    mint_confidential(&mut test_client, &key, &key, mint.pubkey(), 50).await;
+    let supply = test_client
+        .get_supply(&supply_request(&key, mint.pubkey()))
+        .await
+        .unwrap();
+    assert_eq!(supply.supply_amount, 750);
+    assert_eq!(supply.pending_burn_amount, 0);
+
+    // the available balance can not be exceeded
+    assert!(test_client
+        .confidential_burn(&burn_request(&key, mint.pubkey(), 701))
+        .await
+        .is_err());
+
+    // the supply can only be decrypted by the holder of the supply keys
+    assert!(test_client
+        .get_supply(&supply_request(&Keypair::new(), mint.pubkey()))
+        .await
+        .is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_create_confidential_mint_without_supply() {
+    let key = test_key();
+    let mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    let mut create = CreateConfidentialMint {
+        authority: key.pubkey(),
+        mint: mint.pubkey(),
+        decimals: 6,
+        auto_approve_new_accounts: true,
+        auditor_elgamal_pubkey: None,
+        confidential_transfer_authority: None,
+        supply_elgamal_signature: Some(
+            key.sign_message(&KeypairType::SupplyElGamal.message_to_sign(mint.pubkey())),
+        ),
+        supply_ae_signature: None,
+    };
+
+    // both supply keys are needed to keep the supply confidential
+    assert!(test_client
+        .create_native_confidential_mint(&create)
+        .await
+        .is_err());
+
+    create.supply_elgamal_signature = None;
+    let res = test_client
+        .create_native_confidential_mint(&create)
+        .await
+        .unwrap();
+    test_client.send_tx_with_signers(res, &[&key, &mint]).await;
+    test_client.test_initialize(&key, mint.pubkey()).await;
+
+    // the mint exists now
+    assert!(test_client
+        .create_native_confidential_mint(&create)
+        .await
+        .is_err());
+
+    // confidential mints require the confidential mint burn extension
+    let res = test_client
+        .confidential_mint(&ConfidentialMint {
+            authority: key.pubkey(),
+            token_mint: mint.pubkey(),
+            supply_elgamal_signature: key
+                .sign_message(&KeypairType::SupplyElGamal.message_to_sign(mint.pubkey())),
+            supply_ae_signature: key
+                .sign_message(&KeypairType::SupplyAe.message_to_sign(mint.pubkey())),
+            receiving_token_account: key.pubkey(),
+            equality_proof_keypair: Keypair::new(),
+            ciphertext_validity_proof_keypair: Keypair::new(),
+            range_proof_keypair: Keypair::new(),
+            amount: 100,
+        })
+        .await;
+    assert!(res.is_err());
+}
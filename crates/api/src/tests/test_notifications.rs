@@ -0,0 +1,121 @@
+use {
+    super::get_user_ata,
+    crate::{
+        notifications::{BalanceWatcher, Credentials},
+        tests::BlinkTestClient,
+        types::{ApiBalanceNotificationKind, Notifications},
+    },
+    common::{
+        discrete_log::{DiscreteLogTable, MIN_BABY_STEP_BITS},
+        key_generator::{derive_ae_key, derive_elgamal_key, KeypairType},
+        test_helpers::test_key,
+    },
+    solana_client::nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer},
+    std::{sync::Arc, time::Duration},
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_notifications() {
+    let key = test_key();
+    let mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    test_client.create_confidential_mint(&key, &mint).await;
+    test_client.test_initialize(&key, mint.pubkey()).await;
+    test_client
+        .mint_tokens(&key, mint.pubkey(), 1_000_000)
+        .await;
+
+    let user_ata = get_user_ata(&key, mint.pubkey());
+    let elgamal_sig = key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata));
+    let ae_sig = key.sign_message(&KeypairType::Ae.message_to_sign(user_ata));
+    let credentials = Credentials {
+        elgamal_secret: derive_elgamal_key(elgamal_sig).unwrap().secret().clone(),
+        ae_key: derive_ae_key(ae_sig).unwrap(),
+    };
+
+    let mut notifications = BalanceWatcher::new(
+        Arc::new(PubsubClient::new("ws://localhost:8900").await.unwrap()),
+        user_ata,
+        Some(credentials),
+        Arc::new(DiscreteLogTable::new(MIN_BABY_STEP_BITS).unwrap()),
+    )
+    .subscribe(test_client.rpc.get_account(&user_ata).await.unwrap())
+    .await
+    .unwrap();
+
+    test_client.test_deposit(&key, mint.pubkey(), 100).await;
+
+    let notification = tokio::time::timeout(Duration::from_secs(30), notifications.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(notification.kind, ApiBalanceNotificationKind::Credit);
+    assert_eq!(notification.token_account, user_ata);
+    assert_eq!(notification.token_mint, mint.pubkey());
+    assert_eq!(notification.new_credits, 1);
+    assert_eq!(notification.pending_balance_amount, Some(100));
+    assert_eq!(notification.pending_balance_delta, Some(100));
+    assert_eq!(notification.non_confidential_balance_delta, -100);
+
+    test_client.test_apply(&key, mint.pubkey()).await;
+
+    let notification = tokio::time::timeout(Duration::from_secs(30), notifications.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(notification.kind, ApiBalanceNotificationKind::BalanceChange);
+    assert_eq!(notification.pending_balance_delta, Some(-100));
+    assert_eq!(notification.available_balance_delta, Some(100));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_notifications_request() {
+    let key = test_key();
+    let token_mint = Pubkey::new_unique();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let test_client = BlinkTestClient::new(rpc).await;
+
+    // the credentials are sent in the request body, never in the url
+    let user_ata = get_user_ata(&key, token_mint);
+    let res = test_client
+        .server
+        .post("/confidential-balances/notifications")
+        .add_header("Content-Type", "application/json")
+        .bytes(
+            serde_json::to_string(&Notifications {
+                authority: key.pubkey(),
+                token_mint,
+                elgamal_signature: Some(
+                    key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata)),
+                ),
+                ae_signature: None,
+            })
+            .unwrap()
+            .into(),
+        )
+        .await;
+    assert_eq!(res.status_code(), http::StatusCode::BAD_REQUEST);
+
+    // the token account does not exist
+    let res = test_client
+        .server
+        .post("/confidential-balances/notifications")
+        .add_header("Content-Type", "application/json")
+        .bytes(
+            serde_json::to_string(&Notifications {
+                authority: key.pubkey(),
+                token_mint,
+                elgamal_signature: None,
+                ae_signature: None,
+            })
+            .unwrap()
+            .into(),
+        )
+        .await;
+    assert_eq!(res.status_code(), http::StatusCode::BAD_REQUEST);
+}
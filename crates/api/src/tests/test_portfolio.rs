@@ -0,0 +1,72 @@
+use {
+    crate::tests::BlinkTestClient,
+    common::test_helpers::test_key,
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{signature::Keypair, signer::Signer},
+    std::sync::Arc,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_portfolio() {
+    let key = test_key();
+    let mint = Keypair::new();
+    let unwrapped_mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    // confidential mint which is not a wrapped mint
+    test_client.create_confidential_mint(&key, &mint).await;
+    test_client.test_initialize(&key, mint.pubkey()).await;
+    test_client
+        .mint_tokens(&key, mint.pubkey(), 1_000_000)
+        .await;
+    test_client.test_deposit(&key, mint.pubkey(), 100).await;
+    test_client.test_apply(&key, mint.pubkey()).await;
+    test_client.test_deposit(&key, mint.pubkey(), 10).await;
+
+    // confidential wrapped mint
+    test_client.create_mint(&key, &unwrapped_mint).await;
+    let wrapped_mint = test_client
+        .create_confidential_wrapped_mint(&key, &unwrapped_mint)
+        .await;
+    test_client
+        .create_token_account(&key, &unwrapped_mint)
+        .await;
+    test_client.test_initialize(&key, wrapped_mint).await;
+    test_client
+        .mint_tokens(&key, unwrapped_mint.pubkey(), 1_000_000)
+        .await;
+    test_client
+        .test_wrap_tokens(&key, &unwrapped_mint, wrapped_mint, 500_000)
+        .await;
+    test_client.test_deposit(&key, wrapped_mint, 200_000).await;
+
+    // the test key is shared between tests, so only look at the mints created here
+    let portfolio = test_client.get_portfolio(&key).await;
+
+    let token = portfolio
+        .tokens
+        .iter()
+        .find(|token| token.token_mint == mint.pubkey())
+        .unwrap();
+    assert_eq!(token.unwrapped_token_mint, None);
+    assert_eq!(token.token_accounts.len(), 1);
+    assert_eq!(token.decimals, 6);
+    assert_eq!(token.pending_balance_amount, 10);
+    assert_eq!(token.available_balance_amount, 100);
+    assert_eq!(token.non_confidential_balance_amount, 999_890);
+    assert_eq!(token.total_balance_amount, 1_000_000);
+    assert_eq!(token.total_balance, 1.0);
+
+    let token = portfolio
+        .tokens
+        .iter()
+        .find(|token| token.token_mint == wrapped_mint)
+        .unwrap();
+    assert_eq!(token.unwrapped_token_mint, Some(unwrapped_mint.pubkey()));
+    assert_eq!(token.pending_balance_amount, 200_000);
+    assert_eq!(token.available_balance_amount, 0);
+    assert_eq!(token.non_confidential_balance_amount, 300_000);
+    assert_eq!(token.total_balance, 0.5);
+}
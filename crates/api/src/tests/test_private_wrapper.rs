@@ -1,8 +1,16 @@
 use {
-    crate::tests::BlinkTestClient,
-    common::test_helpers::test_key,
+    crate::{
+        tests::{get_user_ata, BlinkTestClient},
+        types::{CreateWrappedMint, SimpleWrapTokens, WrapTokens},
+    },
+    common::{
+        test_helpers::test_key,
+        wrapped_mint::{escrow_address, WrappedMintConfig},
+    },
     solana_client::nonblocking::rpc_client::RpcClient,
-    solana_sdk::{signature::Keypair, signer::Signer},
+    solana_sdk::{signature::Keypair, signer::Signer, transaction::Transaction},
+    spl_token_2022::solana_zk_sdk::encryption::elgamal::ElGamalKeypair,
+    spl_token_wrap::get_wrapped_mint_address,
     std::sync::Arc,
 };
 
@@ -82,3 +90,524 @@ async fn test_wrap() {
     assert_eq!(balances.available_balance, 0.5);
     assert_eq!(balances.non_confidential_balance, 0.15);
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_create_wrapped_mint() {
+    let key = test_key();
+    let unwrapped_mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc.clone()).await;
+
+    test_client.create_mint(&key, &unwrapped_mint).await;
+
+    let auditor = ElGamalKeypair::new_rand();
+    let mut create = CreateWrappedMint {
+        payer: key.pubkey(),
+        unwrapped_token_mint: unwrapped_mint.pubkey(),
+        auto_approve_new_accounts: false,
+        auditor_elgamal_pubkey: Some((*auditor.pubkey()).into()),
+        confidential_transfer_authority: None,
+    };
+
+    // accounts could never be approved without an authority
+    assert!(test_client.create_wrapped_mint(&create).await.is_err());
+
+    create.confidential_transfer_authority = Some(key.pubkey());
+    let res = test_client.create_wrapped_mint(&create).await.unwrap();
+    test_client.send_tx(&key, res).await;
+
+    let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint.pubkey(), &spl_token_2022::id());
+    let config =
+        WrappedMintConfig::from_mint(&rpc.get_account(&wrapped_mint).await.unwrap()).unwrap();
+    assert_eq!(
+        config,
+        WrappedMintConfig {
+            auto_approve_new_accounts: false,
+            auditor_elgamal_pubkey: create.auditor_elgamal_pubkey,
+            confidential_transfer_authority: Some(key.pubkey()),
+        }
+    );
+
+    // repeating the request is a no-op
+    let res = test_client.create_wrapped_mint(&create).await.unwrap();
+    assert!(res.transactions.is_empty());
+
+    // the settings of an existing mint are not silently changed
+    create.auditor_elgamal_pubkey = None;
+    assert!(test_client.create_wrapped_mint(&create).await.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_shield() {
+    let key = test_key();
+    let unwrapped_mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    test_client.create_mint(&key, &unwrapped_mint).await;
+
+    let wrapped_mint = test_client
+        .create_confidential_wrapped_mint(&key, &unwrapped_mint)
+        .await;
+
+    test_client
+        .create_token_account(&key, &unwrapped_mint)
+        .await;
+    test_client
+        .mint_tokens(
+            &key,
+            unwrapped_mint.pubkey(),
+            spl_token_2022::ui_amount_to_amount(100.0, 6),
+        )
+        .await;
+
+    // the wrapped token account is created and configured as part of the first shield
+    test_client
+        .test_shield(
+            &key,
+            &unwrapped_mint,
+            spl_token_2022::ui_amount_to_amount(1.0, 6),
+        )
+        .await;
+    let balances = test_client.get_balances(&key, wrapped_mint).await;
+    assert_eq!(balances.pending_balance, 0.0);
+    assert_eq!(balances.available_balance, 1.0);
+    assert_eq!(balances.non_confidential_balance, 0.0);
+
+    // shielding into an existing account adds to the available balance
+    test_client
+        .test_shield(
+            &key,
+            &unwrapped_mint,
+            spl_token_2022::ui_amount_to_amount(0.5, 6),
+        )
+        .await;
+    let balances = test_client.get_balances(&key, wrapped_mint).await;
+    assert_eq!(balances.pending_balance, 0.0);
+    assert_eq!(balances.available_balance, 1.5);
+    assert_eq!(balances.pending_balance_credit_counter, 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_unshield() {
+    let key = test_key();
+    let unwrapped_mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    test_client.create_mint(&key, &unwrapped_mint).await;
+
+    let wrapped_mint = test_client
+        .create_confidential_wrapped_mint(&key, &unwrapped_mint)
+        .await;
+
+    test_client
+        .create_token_account(&key, &unwrapped_mint)
+        .await;
+    test_client
+        .mint_tokens(
+            &key,
+            unwrapped_mint.pubkey(),
+            spl_token_2022::ui_amount_to_amount(100.0, 6),
+        )
+        .await;
+
+    test_client
+        .test_shield(
+            &key,
+            &unwrapped_mint,
+            spl_token_2022::ui_amount_to_amount(1.0, 6),
+        )
+        .await;
+
+    // the withdrawn tokens are unwrapped back into the unwrapped token account
+    test_client
+        .test_unshield(
+            &key,
+            &unwrapped_mint,
+            spl_token_2022::ui_amount_to_amount(0.4, 6),
+        )
+        .await;
+    let balances = test_client.get_balances(&key, wrapped_mint).await;
+    assert_eq!(balances.pending_balance, 0.0);
+    assert_eq!(balances.available_balance, 0.6);
+    assert_eq!(balances.non_confidential_balance, 0.0);
+    assert_eq!(
+        test_client
+            .rpc
+            .get_token_account_balance(&get_user_ata(&key, unwrapped_mint.pubkey()))
+            .await
+            .unwrap()
+            .amount
+            .parse::<u64>()
+            .unwrap(),
+        spl_token_2022::ui_amount_to_amount(99.4, 6)
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_wrap_validation() {
+    let key = test_key();
+    let unwrapped_mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    test_client.create_mint(&key, &unwrapped_mint).await;
+    test_client
+        .create_token_account(&key, &unwrapped_mint)
+        .await;
+    test_client
+        .mint_tokens(
+            &key,
+            unwrapped_mint.pubkey(),
+            spl_token_2022::ui_amount_to_amount(1.0, 6),
+        )
+        .await;
+
+    let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint.pubkey(), &spl_token_2022::id());
+    let mut wrap = WrapTokens {
+        authority: key.pubkey(),
+        unwrapped_token_mint: unwrapped_mint.pubkey(),
+        unwrapped_token_program: spl_token_2022::id(),
+        wrapped_token_mint: wrapped_mint,
+        amount: spl_token_2022::ui_amount_to_amount(1.0, 6),
+    };
+
+    // the wrapped mint has not been created yet
+    let err = test_client.wrap_tokens("wrap", &wrap).await.err().unwrap();
+    assert!(err.msg.contains("has not been created"));
+
+    test_client
+        .create_confidential_wrapped_mint(&key, &unwrapped_mint)
+        .await;
+
+    // the wrapped mint must be derived from the unwrapped mint
+    wrap.wrapped_token_mint = Keypair::new().pubkey();
+    let err = test_client.wrap_tokens("wrap", &wrap).await.err().unwrap();
+    assert!(err.msg.contains("is not the wrapped mint of"));
+    wrap.wrapped_token_mint = wrapped_mint;
+
+    // the token program must own the unwrapped mint
+    wrap.unwrapped_token_program = Keypair::new().pubkey();
+    let err = test_client.wrap_tokens("wrap", &wrap).await.err().unwrap();
+    assert!(err.msg.contains("is owned by"));
+    wrap.unwrapped_token_program = spl_token_2022::id();
+
+    // more than the unwrapped balance can't be wrapped
+    wrap.amount = spl_token_2022::ui_amount_to_amount(2.0, 6);
+    let err = test_client.wrap_tokens("wrap", &wrap).await.err().unwrap();
+    assert!(err.msg.contains("insufficient balance"));
+
+    // the wrapped token account is created by the wrap
+    wrap.amount = spl_token_2022::ui_amount_to_amount(1.0, 6);
+    let res = test_client.wrap_tokens("wrap", &wrap).await.unwrap();
+    test_client.send_tx(&key, res).await;
+
+    // more than the wrapped balance can't be unwrapped
+    wrap.amount = spl_token_2022::ui_amount_to_amount(2.0, 6);
+    let err = test_client
+        .wrap_tokens("unwrap", &wrap)
+        .await
+        .err()
+        .unwrap();
+    assert!(err.msg.contains("insufficient balance"));
+
+    wrap.amount = spl_token_2022::ui_amount_to_amount(1.0, 6);
+    let res = test_client.wrap_tokens("unwrap", &wrap).await.unwrap();
+    test_client.send_tx(&key, res).await;
+    assert_eq!(
+        test_client
+            .rpc
+            .get_token_account_balance(&get_user_ata(&key, unwrapped_mint.pubkey()))
+            .await
+            .unwrap()
+            .amount
+            .parse::<u64>()
+            .unwrap(),
+        spl_token_2022::ui_amount_to_amount(1.0, 6)
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_simple_wrap() {
+    let key = test_key();
+    let unwrapped_mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    test_client.create_mint(&key, &unwrapped_mint).await;
+    let wrapped_mint = test_client
+        .create_confidential_wrapped_mint(&key, &unwrapped_mint)
+        .await;
+    test_client
+        .create_token_account(&key, &unwrapped_mint)
+        .await;
+    test_client
+        .mint_tokens(
+            &key,
+            unwrapped_mint.pubkey(),
+            spl_token_2022::ui_amount_to_amount(100.0, 6),
+        )
+        .await;
+
+    // wrapping with the unwrapped mint resolves the wrapped mint and token program
+    let res = test_client
+        .wrap_tokens(
+            "simple-wrap",
+            &SimpleWrapTokens {
+                authority: key.pubkey(),
+                token_mint: unwrapped_mint.pubkey(),
+                ui_amount: "1.5".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    test_client.send_tx(&key, res).await;
+    assert_eq!(
+        test_client
+            .rpc
+            .get_token_account_balance(&get_user_ata(&key, wrapped_mint))
+            .await
+            .unwrap()
+            .amount
+            .parse::<u64>()
+            .unwrap(),
+        spl_token_2022::ui_amount_to_amount(1.5, 6)
+    );
+
+    // unwrapping with the wrapped mint resolves the unwrapped mint through the backpointer
+    let res = test_client
+        .wrap_tokens(
+            "simple-unwrap",
+            &SimpleWrapTokens {
+                authority: key.pubkey(),
+                token_mint: wrapped_mint,
+                ui_amount: "0.5".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    test_client.send_tx(&key, res).await;
+    assert_eq!(
+        test_client
+            .rpc
+            .get_token_account_balance(&get_user_ata(&key, wrapped_mint))
+            .await
+            .unwrap()
+            .amount
+            .parse::<u64>()
+            .unwrap(),
+        spl_token_2022::ui_amount_to_amount(1.0, 6)
+    );
+
+    // amounts must be positive, and fit the decimals of the mint exactly
+    for ui_amount in ["0.0", "0.0000001", "1.2345678", "-1", "1e3", ""] {
+        assert!(
+            test_client
+                .wrap_tokens(
+                    "simple-wrap",
+                    &SimpleWrapTokens {
+                        authority: key.pubkey(),
+                        token_mint: unwrapped_mint.pubkey(),
+                        ui_amount: ui_amount.to_string(),
+                    },
+                )
+                .await
+                .is_err(),
+            "{ui_amount}"
+        );
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_wrapped_mint_info() {
+    let key = test_key();
+    let unwrapped_mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    test_client.create_mint(&key, &unwrapped_mint).await;
+    let wrapped_mint = test_client
+        .create_confidential_wrapped_mint(&key, &unwrapped_mint)
+        .await;
+    test_client
+        .create_token_account(&key, &unwrapped_mint)
+        .await;
+    test_client
+        .mint_tokens(
+            &key,
+            unwrapped_mint.pubkey(),
+            spl_token_2022::ui_amount_to_amount(100.0, 6),
+        )
+        .await;
+    test_client
+        .test_shield(
+            &key,
+            &unwrapped_mint,
+            spl_token_2022::ui_amount_to_amount(2.0, 6),
+        )
+        .await;
+
+    // the same info is returned for either mint
+    for mint in [unwrapped_mint.pubkey(), wrapped_mint] {
+        let info = test_client.get_wrapped_mint_info(mint).await;
+        assert_eq!(info.unwrapped_token_mint, unwrapped_mint.pubkey());
+        assert_eq!(info.unwrapped_token_program, spl_token_2022::id());
+        assert_eq!(info.wrapped_token_mint, wrapped_mint);
+        assert_eq!(
+            info.backpointer_unwrapped_token_mint,
+            Some(unwrapped_mint.pubkey())
+        );
+        assert!(info.escrow_exists);
+        assert_eq!(info.escrow_balance, 2.0);
+        assert_eq!(info.wrapped_supply, 2.0);
+        assert!(!info.supply_mismatch);
+        assert_eq!(info.escrow_surplus_amount, 0);
+    }
+
+    // unwrapped tokens sent to the escrow directly are a surplus, not a mismatch
+    let mut tx = Transaction::new_with_payer(
+        &[spl_token_2022::instruction::mint_to(
+            &spl_token_2022::id(),
+            &unwrapped_mint.pubkey(),
+            &escrow_address(&unwrapped_mint.pubkey(), &spl_token_2022::id()),
+            &key.pubkey(),
+            &[&key.pubkey()],
+            spl_token_2022::ui_amount_to_amount(0.5, 6),
+        )
+        .unwrap()],
+        Some(&key.pubkey()),
+    );
+    tx.sign(
+        &[&key],
+        test_client.rpc.get_latest_blockhash().await.unwrap(),
+    );
+    test_client
+        .rpc
+        .send_and_confirm_transaction(&tx)
+        .await
+        .unwrap();
+    let info = test_client.get_wrapped_mint_info(wrapped_mint).await;
+    assert!(!info.supply_mismatch);
+    assert_eq!(info.escrow_surplus, 0.5);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_list_wrapped_mints() {
+    let key = test_key();
+    let unwrapped_mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    test_client.create_mint(&key, &unwrapped_mint).await;
+    let wrapped_mint = test_client
+        .create_confidential_wrapped_mint(&key, &unwrapped_mint)
+        .await;
+
+    let res = test_client.list_wrapped_mints().await;
+    let listed = res
+        .wrapped_mints
+        .iter()
+        .find(|mint| mint.unwrapped_token_mint == unwrapped_mint.pubkey())
+        .unwrap();
+    assert_eq!(listed.wrapped_token_mint, wrapped_mint);
+    assert_eq!(listed.decimals, 6);
+    assert!(listed.confidential_transfers);
+    assert!(!listed.has_auditor);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_wrap_transfer_fee() {
+    let key = test_key();
+    let unwrapped_mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    // 1% transfer fee on the unwrapped mint
+    test_client
+        .create_transfer_fee_mint(&key, &unwrapped_mint, 100, u64::MAX)
+        .await;
+    let wrapped_mint = test_client
+        .create_confidential_wrapped_mint(&key, &unwrapped_mint)
+        .await;
+    test_client
+        .create_token_account(&key, &unwrapped_mint)
+        .await;
+    test_client
+        .mint_tokens(
+            &key,
+            unwrapped_mint.pubkey(),
+            spl_token_2022::ui_amount_to_amount(100.0, 6),
+        )
+        .await;
+
+    let wrap = WrapTokens {
+        authority: key.pubkey(),
+        unwrapped_token_mint: unwrapped_mint.pubkey(),
+        unwrapped_token_program: spl_token_2022::id(),
+        wrapped_token_mint: wrapped_mint,
+        amount: spl_token_2022::ui_amount_to_amount(1.0, 6),
+    };
+
+    // only the amount received by the escrow after the fee is wrapped
+    let res = test_client.wrap_tokens("wrap", &wrap).await.unwrap();
+    test_client.send_tx(&key, res).await;
+    assert_eq!(
+        test_client
+            .rpc
+            .get_token_account_balance(&get_user_ata(&key, wrapped_mint))
+            .await
+            .unwrap()
+            .amount
+            .parse::<u64>()
+            .unwrap(),
+        spl_token_2022::ui_amount_to_amount(0.99, 6)
+    );
+
+    // the fee is charged again when the escrow releases the unwrapped tokens
+    let res = test_client
+        .wrap_tokens(
+            "unwrap",
+            &WrapTokens {
+                amount: spl_token_2022::ui_amount_to_amount(0.5, 6),
+                ..wrap
+            },
+        )
+        .await
+        .unwrap();
+    test_client.send_tx(&key, res).await;
+    assert_eq!(
+        test_client
+            .rpc
+            .get_token_account_balance(&get_user_ata(&key, wrapped_mint))
+            .await
+            .unwrap()
+            .amount
+            .parse::<u64>()
+            .unwrap(),
+        spl_token_2022::ui_amount_to_amount(0.49, 6)
+    );
+    assert_eq!(
+        test_client
+            .rpc
+            .get_token_account_balance(&get_user_ata(&key, unwrapped_mint.pubkey()))
+            .await
+            .unwrap()
+            .amount
+            .parse::<u64>()
+            .unwrap(),
+        spl_token_2022::ui_amount_to_amount(99.495, 6)
+    );
+
+    // the escrow still backs the full wrapped supply
+    let info = test_client.get_wrapped_mint_info(wrapped_mint).await;
+    assert!(!info.supply_mismatch);
+}
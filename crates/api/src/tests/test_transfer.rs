@@ -1,9 +1,20 @@
 use {
     super::get_user_ata,
-    crate::tests::BlinkTestClient,
-    common::test_helpers::test_key,
+    crate::{
+        tests::BlinkTestClient,
+        types::{ApiTransactionResponse, Transfer},
+    },
+    common::{key_generator::KeypairType, test_helpers::test_key},
     solana_client::nonblocking::rpc_client::RpcClient,
-    solana_sdk::{signature::Keypair, signer::Signer},
+    solana_sdk::{
+        packet::PACKET_DATA_SIZE, pubkey::Pubkey, signature::Keypair, signer::Signer,
+        transaction::Transaction,
+    },
+    spl_token_2022::{
+        extension::{memo_transfer::instruction::enable_required_transfer_memos, ExtensionType},
+        instruction::reallocate,
+        solana_zk_sdk::encryption::elgamal::ElGamalKeypair,
+    },
     std::sync::Arc,
 };
 
@@ -48,3 +59,290 @@ async fn test_transfer() {
 
     test_client.test_apply(&key2, mint.pubkey()).await;
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_transfer_with_fee() {
+    let key = test_key();
+    let key2 = Keypair::new();
+    let mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    // 1% transfer fee
+    test_client
+        .create_confidential_transfer_fee_mint(
+            &key,
+            &mint,
+            &ElGamalKeypair::new_rand(),
+            100,
+            1_000_000,
+        )
+        .await;
+
+    test_client.test_initialize(&key, mint.pubkey()).await;
+    test_client
+        .mint_tokens(&key, mint.pubkey(), 1_000_000)
+        .await;
+    test_client.test_initialize(&key2, mint.pubkey()).await;
+
+    test_client.test_deposit(&key, mint.pubkey(), 100_000).await;
+    test_client.test_apply(&key, mint.pubkey()).await;
+
+    let user_ata = get_user_ata(&key, mint.pubkey());
+    let equality_proof_keypair = Keypair::new();
+    let ciphertext_validity_proof_keypair = Keypair::new();
+    let range_proof_keypair = Keypair::new();
+    let fee_sigma_proof_keypair = Keypair::new();
+    let fee_ciphertext_validity_proof_keypair = Keypair::new();
+    let mut transfer = Transfer {
+        authority: key.pubkey(),
+        token_mint: mint.pubkey(),
+        elgamal_signature: key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata)),
+        ae_signature: key.sign_message(&KeypairType::Ae.message_to_sign(user_ata)),
+        receiving_token_account: key2.pubkey(),
+        equality_proof_keypair: equality_proof_keypair.insecure_clone(),
+        ciphertext_validity_proof_keypair: ciphertext_validity_proof_keypair.insecure_clone(),
+        range_proof_keypair: range_proof_keypair.insecure_clone(),
+        fee_sigma_proof_keypair: None,
+        fee_ciphertext_validity_proof_keypair: None,
+        amount: 10_000,
+        memo: None,
+    };
+
+    // the fee proofs need their own keypairs
+    assert!(test_client.transfer(&transfer).await.is_err());
+
+    transfer.fee_sigma_proof_keypair = Some(fee_sigma_proof_keypair.insecure_clone());
+    transfer.fee_ciphertext_validity_proof_keypair =
+        Some(fee_ciphertext_validity_proof_keypair.insecure_clone());
+    let res = test_client.transfer(&transfer).await.unwrap();
+    assert_eq!(res.transfer_fee_amount, 100);
+    assert_eq!(res.received_amount, 9_900);
+
+    test_client
+        .send_tx_with_signers(
+            ApiTransactionResponse {
+                transactions: res.transactions,
+            },
+            &[
+                &key,
+                &equality_proof_keypair,
+                &ciphertext_validity_proof_keypair,
+                &range_proof_keypair,
+                &fee_sigma_proof_keypair,
+                &fee_ciphertext_validity_proof_keypair,
+            ],
+        )
+        .await;
+
+    // the fee is withheld in the receiving account, only the remainder is credited
+    test_client.test_apply(&key2, mint.pubkey()).await;
+    let balances = test_client.get_balances(&key2, mint.pubkey()).await;
+    assert_eq!(
+        balances.available_balance,
+        spl_token_2022::amount_to_ui_amount(9_900, 6)
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_transfer_with_memo() {
+    let key = test_key();
+    let key2 = Keypair::new();
+    let mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    test_client.create_confidential_mint(&key, &mint).await;
+    test_client.test_initialize(&key, mint.pubkey()).await;
+    test_client
+        .mint_tokens(&key, mint.pubkey(), 1_000_000)
+        .await;
+    test_client.test_initialize(&key2, mint.pubkey()).await;
+
+    test_client.test_deposit(&key, mint.pubkey(), 100).await;
+    test_client.test_apply(&key, mint.pubkey()).await;
+
+    // key2 requires memos on incoming transfers
+    require_transfer_memos(&test_client, &key, &key2, mint.pubkey()).await;
+
+    let user_ata = get_user_ata(&key, mint.pubkey());
+    let equality_proof_keypair = Keypair::new();
+    let ciphertext_validity_proof_keypair = Keypair::new();
+    let range_proof_keypair = Keypair::new();
+    let mut transfer = Transfer {
+        authority: key.pubkey(),
+        token_mint: mint.pubkey(),
+        elgamal_signature: key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata)),
+        ae_signature: key.sign_message(&KeypairType::Ae.message_to_sign(user_ata)),
+        receiving_token_account: key2.pubkey(),
+        equality_proof_keypair: equality_proof_keypair.insecure_clone(),
+        ciphertext_validity_proof_keypair: ciphertext_validity_proof_keypair.insecure_clone(),
+        range_proof_keypair: range_proof_keypair.insecure_clone(),
+        fee_sigma_proof_keypair: None,
+        fee_ciphertext_validity_proof_keypair: None,
+        amount: 10,
+        memo: None,
+    };
+
+    // the transfer would be rejected by the token program without a memo
+    assert!(test_client.transfer(&transfer).await.is_err());
+
+    // memos which don't fit in the transfer transaction are rejected up front
+    transfer.memo = Some("a".repeat(PACKET_DATA_SIZE));
+    let err = test_client.transfer(&transfer).await.unwrap_err();
+    assert!(err.msg.contains("memo"), "{}", err.msg);
+
+    transfer.memo = Some("invoice 42".to_string());
+    let res = test_client.transfer(&transfer).await.unwrap();
+    test_client
+        .send_tx_with_signers(
+            ApiTransactionResponse {
+                transactions: res.transactions,
+            },
+            &[
+                &key,
+                &equality_proof_keypair,
+                &ciphertext_validity_proof_keypair,
+                &range_proof_keypair,
+            ],
+        )
+        .await;
+
+    test_client.test_apply(&key2, mint.pubkey()).await;
+    let balances = test_client.get_balances(&key2, mint.pubkey()).await;
+    assert_eq!(
+        balances.available_balance,
+        spl_token_2022::amount_to_ui_amount(10, 6)
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_transfer_with_fee_and_memo() {
+    let key = test_key();
+    let key2 = Keypair::new();
+    let mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    // 1% transfer fee
+    test_client
+        .create_confidential_transfer_fee_mint(
+            &key,
+            &mint,
+            &ElGamalKeypair::new_rand(),
+            100,
+            1_000_000,
+        )
+        .await;
+
+    test_client.test_initialize(&key, mint.pubkey()).await;
+    test_client
+        .mint_tokens(&key, mint.pubkey(), 1_000_000)
+        .await;
+    test_client.test_initialize(&key2, mint.pubkey()).await;
+
+    test_client.test_deposit(&key, mint.pubkey(), 100_000).await;
+    test_client.test_apply(&key, mint.pubkey()).await;
+
+    // key2 requires memos on incoming transfers
+    require_transfer_memos(&test_client, &key, &key2, mint.pubkey()).await;
+
+    let user_ata = get_user_ata(&key, mint.pubkey());
+    let equality_proof_keypair = Keypair::new();
+    let ciphertext_validity_proof_keypair = Keypair::new();
+    let range_proof_keypair = Keypair::new();
+    let fee_sigma_proof_keypair = Keypair::new();
+    let fee_ciphertext_validity_proof_keypair = Keypair::new();
+    let mut transfer = Transfer {
+        authority: key.pubkey(),
+        token_mint: mint.pubkey(),
+        elgamal_signature: key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata)),
+        ae_signature: key.sign_message(&KeypairType::Ae.message_to_sign(user_ata)),
+        receiving_token_account: key2.pubkey(),
+        equality_proof_keypair: equality_proof_keypair.insecure_clone(),
+        ciphertext_validity_proof_keypair: ciphertext_validity_proof_keypair.insecure_clone(),
+        range_proof_keypair: range_proof_keypair.insecure_clone(),
+        fee_sigma_proof_keypair: Some(fee_sigma_proof_keypair.insecure_clone()),
+        fee_ciphertext_validity_proof_keypair: Some(
+            fee_ciphertext_validity_proof_keypair.insecure_clone(),
+        ),
+        amount: 10_000,
+        memo: None,
+    };
+
+    // the transfer would be rejected by the token program without a memo
+    assert!(test_client.transfer(&transfer).await.is_err());
+
+    // memos which don't fit in the transfer transaction are rejected up front
+    transfer.memo = Some("a".repeat(PACKET_DATA_SIZE));
+    let err = test_client.transfer(&transfer).await.unwrap_err();
+    assert!(err.msg.contains("memo"), "{}", err.msg);
+
+    transfer.memo = Some("invoice 42".to_string());
+    let res = test_client.transfer(&transfer).await.unwrap();
+    assert_eq!(res.received_amount, 9_900);
+    test_client
+        .send_tx_with_signers(
+            ApiTransactionResponse {
+                transactions: res.transactions,
+            },
+            &[
+                &key,
+                &equality_proof_keypair,
+                &ciphertext_validity_proof_keypair,
+                &range_proof_keypair,
+                &fee_sigma_proof_keypair,
+                &fee_ciphertext_validity_proof_keypair,
+            ],
+        )
+        .await;
+
+    test_client.test_apply(&key2, mint.pubkey()).await;
+    let balances = test_client.get_balances(&key2, mint.pubkey()).await;
+    assert_eq!(
+        balances.available_balance,
+        spl_token_2022::amount_to_ui_amount(9_900, 6)
+    );
+}
+
+/// Enables the MemoTransfer extension on the token account of `owner`, requiring memos on
+/// incoming transfers
+async fn require_transfer_memos(
+    test_client: &BlinkTestClient,
+    payer: &Keypair,
+    owner: &Keypair,
+    mint: Pubkey,
+) {
+    let token_account = get_user_ata(owner, mint);
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            reallocate(
+                &spl_token_2022::id(),
+                &token_account,
+                &payer.pubkey(),
+                &owner.pubkey(),
+                &[],
+                &[ExtensionType::MemoTransfer],
+            )
+            .unwrap(),
+            enable_required_transfer_memos(
+                &spl_token_2022::id(),
+                &token_account,
+                &owner.pubkey(),
+                &[],
+            )
+            .unwrap(),
+        ],
+        Some(&payer.pubkey()),
+        &[payer, owner],
+        test_client.rpc.get_latest_blockhash().await.unwrap(),
+    );
+    test_client
+        .rpc
+        .send_and_confirm_transaction(&tx)
+        .await
+        .unwrap();
+}
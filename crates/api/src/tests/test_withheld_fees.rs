@@ -0,0 +1,238 @@
+use {
+    crate::{
+        tests::{get_user_ata, BlinkTestClient},
+        types::{
+            ApiTransactionResponse, HarvestWithheldFees, Transfer, UpdateHarvestToMint,
+            WithdrawWithheldFees,
+        },
+    },
+    common::{
+        key_generator::{derive_elgamal_key, KeypairType},
+        test_helpers::test_key,
+    },
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer},
+    std::sync::Arc,
+};
+
+/// Sends a confidential transfer of `amount` from `key` to `receiver`, withholding the fee in the
+/// token account of `receiver`
+async fn transfer_with_fee(
+    test_client: &mut BlinkTestClient,
+    key: &Keypair,
+    receiver: &Keypair,
+    mint: Pubkey,
+    amount: u64,
+) {
+    let user_ata = get_user_ata(key, mint);
+    let equality_proof_keypair = Keypair::new();
+    let ciphertext_validity_proof_keypair = Keypair::new();
+    let range_proof_keypair = Keypair::new();
+    let fee_sigma_proof_keypair = Keypair::new();
+    let fee_ciphertext_validity_proof_keypair = Keypair::new();
+    let res = test_client
+        .transfer(&Transfer {
+            authority: key.pubkey(),
+            token_mint: mint,
+            elgamal_signature: key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata)),
+            ae_signature: key.sign_message(&KeypairType::Ae.message_to_sign(user_ata)),
+            receiving_token_account: receiver.pubkey(),
+            equality_proof_keypair: equality_proof_keypair.insecure_clone(),
+            ciphertext_validity_proof_keypair: ciphertext_validity_proof_keypair.insecure_clone(),
+            range_proof_keypair: range_proof_keypair.insecure_clone(),
+            fee_sigma_proof_keypair: Some(fee_sigma_proof_keypair.insecure_clone()),
+            fee_ciphertext_validity_proof_keypair: Some(
+                fee_ciphertext_validity_proof_keypair.insecure_clone(),
+            ),
+            amount,
+            memo: None,
+        })
+        .await
+        .unwrap();
+    test_client
+        .send_tx_with_signers(
+            ApiTransactionResponse {
+                transactions: res.transactions,
+            },
+            &[
+                key,
+                &equality_proof_keypair,
+                &ciphertext_validity_proof_keypair,
+                &range_proof_keypair,
+                &fee_sigma_proof_keypair,
+                &fee_ciphertext_validity_proof_keypair,
+            ],
+        )
+        .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_withdraw_withheld_fees() {
+    let key = test_key();
+    let key2 = Keypair::new();
+    let mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+
+    let mut test_client = BlinkTestClient::new(rpc).await;
+
+    // the withheld fees are encrypted for the withdraw withheld key of the mint authority
+    let user_ata = get_user_ata(&key, mint.pubkey());
+    let withdraw_withheld_signature =
+        key.sign_message(&KeypairType::WithdrawWithheld.message_to_sign(user_ata));
+    let withdraw_withheld_key = derive_elgamal_key(withdraw_withheld_signature).unwrap();
+
+    // 1% transfer fee
+    test_client
+        .create_confidential_transfer_fee_mint(&key, &mint, &withdraw_withheld_key, 100, 1_000_000)
+        .await;
+
+    test_client.test_initialize(&key, mint.pubkey()).await;
+    test_client
+        .mint_tokens(&key, mint.pubkey(), 1_000_000)
+        .await;
+    test_client.test_initialize(&key2, mint.pubkey()).await;
+
+    test_client.test_deposit(&key, mint.pubkey(), 100_000).await;
+    test_client.test_apply(&key, mint.pubkey()).await;
+
+    transfer_with_fee(&mut test_client, &key, &key2, mint.pubkey(), 10_000).await;
+
+    let receiver_ata = get_user_ata(&key2, mint.pubkey());
+    let equality_proof_keypair = Keypair::new();
+    let mut withdraw = WithdrawWithheldFees {
+        authority: key.pubkey(),
+        token_mint: mint.pubkey(),
+        withdraw_withheld_signature,
+        elgamal_signature: key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata)),
+        ae_signature: key.sign_message(&KeypairType::Ae.message_to_sign(user_ata)),
+        equality_proof_keypair: equality_proof_keypair.insecure_clone(),
+        sources: vec![receiver_ata],
+    };
+
+    // withdraw the fee withheld in the receiving account
+    let res = test_client.withdraw_withheld_fees(&withdraw).await.unwrap();
+    assert_eq!(res.amount, 100);
+    test_client
+        .send_tx_with_signers(
+            ApiTransactionResponse {
+                transactions: res.transactions,
+            },
+            &[&key, &equality_proof_keypair],
+        )
+        .await;
+    let balances = test_client.get_balances(&key, mint.pubkey()).await;
+    assert_eq!(
+        balances.available_balance,
+        spl_token_2022::amount_to_ui_amount(90_100, 6)
+    );
+
+    // nothing is left to withdraw from the mint until fees are harvested to it
+    withdraw.sources = vec![];
+    assert!(test_client.withdraw_withheld_fees(&withdraw).await.is_err());
+
+    transfer_with_fee(&mut test_client, &key, &key2, mint.pubkey(), 10_000).await;
+    let res = test_client
+        .harvest_withheld_fees(&HarvestWithheldFees {
+            payer: key.pubkey(),
+            token_mint: mint.pubkey(),
+            sources: vec![],
+        })
+        .await
+        .unwrap();
+    test_client.send_tx(&key, res).await;
+
+    let res = test_client.withdraw_withheld_fees(&withdraw).await.unwrap();
+    assert_eq!(res.amount, 100);
+    test_client
+        .send_tx_with_signers(
+            ApiTransactionResponse {
+                transactions: res.transactions,
+            },
+            &[&key, &equality_proof_keypair],
+        )
+        .await;
+    let balances = test_client.get_balances(&key, mint.pubkey()).await;
+    assert_eq!(
+        balances.available_balance,
+        spl_token_2022::amount_to_ui_amount(80_200, 6)
+    );
+
+    // only the withdraw withheld authority can withdraw the fees
+    let user_ata2 = get_user_ata(&key2, mint.pubkey());
+    assert!(test_client
+        .withdraw_withheld_fees(&WithdrawWithheldFees {
+            authority: key2.pubkey(),
+            token_mint: mint.pubkey(),
+            withdraw_withheld_signature: key2
+                .sign_message(&KeypairType::WithdrawWithheld.message_to_sign(user_ata2)),
+            elgamal_signature: key2.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata2)),
+            ae_signature: key2.sign_message(&KeypairType::Ae.message_to_sign(user_ata2)),
+            equality_proof_keypair: Keypair::new(),
+            sources: vec![],
+        })
+        .await
+        .is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_toggle_harvest_to_mint() {
+    let key = test_key();
+    let mint = Keypair::new();
+    let rpc = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+    let mut test_client = BlinkTestClient::new(rpc).await;
+    test_client
+        .create_confidential_transfer_fee_mint(
+            &key,
+            &mint,
+            &derive_elgamal_key(
+                key.sign_message(&KeypairType::WithdrawWithheld.message_to_sign(Pubkey::default())),
+            )
+            .unwrap(),
+            100,
+            1_000_000,
+        )
+        .await;
+    test_client.test_initialize(&key, mint.pubkey()).await;
+
+    let update = UpdateHarvestToMint {
+        authority: key.pubkey(),
+        token_mint: mint.pubkey(),
+    };
+    let harvest = HarvestWithheldFees {
+        payer: key.pubkey(),
+        token_mint: mint.pubkey(),
+        sources: vec![get_user_ata(&key, mint.pubkey())],
+    };
+
+    // harvesting to the mint is enabled when the mint is created
+    assert!(test_client
+        .update_harvest_to_mint("enable-harvest-to-mint", &update)
+        .await
+        .is_err());
+
+    let res = test_client
+        .update_harvest_to_mint("disable-harvest-to-mint", &update)
+        .await
+        .unwrap();
+    test_client.send_tx(&key, res).await;
+    assert!(test_client.harvest_withheld_fees(&harvest).await.is_err());
+
+    let res = test_client
+        .update_harvest_to_mint("enable-harvest-to-mint", &update)
+        .await
+        .unwrap();
+    test_client.send_tx(&key, res).await;
+    assert!(test_client.harvest_withheld_fees(&harvest).await.is_ok());
+
+    // only the confidential transfer fee authority can toggle harvesting
+    assert!(test_client
+        .update_harvest_to_mint(
+            "disable-harvest-to-mint",
+            &UpdateHarvestToMint {
+                authority: Pubkey::new_unique(),
+                token_mint: mint.pubkey(),
+            },
+        )
+        .await
+        .is_err());
+}
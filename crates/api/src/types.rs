@@ -8,6 +8,8 @@ use {
         signature::{Keypair, Signature},
         transaction::Transaction,
     },
+    spl_token_2022::solana_zk_sdk::encryption::pod::elgamal::PodElGamalPubkey,
+    std::collections::BTreeMap,
 };
 
 /// JSON request used to initialize a confidential token account or apply a pending balance
@@ -29,6 +31,11 @@ pub struct InitializeOrApply {
     /// This is used to derive the AE key
     #[serde(with = "serde_utils::signature_string")]
     pub ae_signature: Signature,
+    /// The number of incoming credits after which the pending balance must be applied
+    ///
+    /// Only used when initializing, must be between 1 and 65536 and defaults to 65536
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maximum_pending_balance_credit_counter: Option<u64>,
 }
 
 /// JSON request used to deposit from non-confidential balance to pending balance
@@ -43,6 +50,9 @@ pub struct Deposit {
     pub token_mint: Pubkey,
     /// The amount of tokens to deposit or withdraw in lamports
     pub amount: u64,
+    /// Optional memo recorded with the deposit
+    #[serde(default)]
+    pub memo: Option<String>,
 }
 
 /// JSON request used to deposit from non-confidential balance to pending balance
@@ -73,6 +83,42 @@ pub struct Withdraw {
     /// The keypair to be used for the range proof
     #[serde(with = "serde_utils::keypair_string")]
     pub range_proof_keypair: Keypair,
+    /// Optional memo recorded with the withdrawal
+    #[serde(default)]
+    pub memo: Option<String>,
+}
+
+/// JSON request used to empty a confidential token account, and optionally close it
+#[derive(Serialize, Deserialize)]
+pub struct CloseAccount {
+    /// The public key of the wallet which owns the token account
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub authority: Pubkey,
+    /// The confidential token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+    /// The signed message of [b"ElGamalSecretKey", user_ata]
+    ///
+    /// This is used to derive the ElGamal keypair
+    #[serde(with = "serde_utils::signature_string")]
+    pub elgamal_signature: Signature,
+    /// The signed message of [b"AEKey", user_ata]
+    ///
+    /// This is used to derive the AE key
+    #[serde(with = "serde_utils::signature_string")]
+    pub ae_signature: Signature,
+    /// Apply the pending balance and withdraw the confidential balance before emptying the account
+    #[serde(default)]
+    pub drain: bool,
+    /// Close the token account to reclaim its rent, the non confidential balance must be zero
+    #[serde(default)]
+    pub close: bool,
+    /// The keypair to be used for the equality proof, required to drain a non zero balance
+    #[serde(default, with = "serde_utils::option_keypair_string")]
+    pub equality_proof_keypair: Option<Keypair>,
+    /// The keypair to be used for the range proof, required to drain a non zero balance
+    #[serde(default, with = "serde_utils::option_keypair_string")]
+    pub range_proof_keypair: Option<Keypair>,
 }
 
 /// JSON request used to transfer confidential token balances
@@ -102,8 +148,19 @@ pub struct Transfer {
     pub ciphertext_validity_proof_keypair: Keypair,
     #[serde(with = "serde_utils::keypair_string")]
     pub range_proof_keypair: Keypair,
+    /// The keypair to be used for the fee sigma proof, required for mints with a transfer fee
+    #[serde(default, with = "serde_utils::option_keypair_string")]
+    pub fee_sigma_proof_keypair: Option<Keypair>,
+    /// The keypair to be used for the fee ciphertext validity proof, required for mints with a
+    /// transfer fee
+    #[serde(default, with = "serde_utils::option_keypair_string")]
+    pub fee_ciphertext_validity_proof_keypair: Option<Keypair>,
     /// Amount of tokens to transfer
     pub amount: u64,
+    /// Optional memo recorded with the transfer, required when the receiving token account
+    /// requires incoming transfer memos
+    #[serde(default)]
+    pub memo: Option<String>,
 }
 
 /// JSON request used to display account balances
@@ -127,6 +184,100 @@ pub struct Balances {
     pub ae_signature: Signature,
 }
 
+/// JSON request used to display the balances of every confidential token account owned by a wallet
+#[derive(Serialize, Deserialize)]
+pub struct Portfolio {
+    /// The public key of the wallet which owns the token accounts
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub authority: Pubkey,
+    /// The signed message of [b"ElGamalSecretKey", user_ata]
+    ///
+    /// This is used to derive the ElGamal keypair
+    #[serde(with = "serde_utils::signature_string")]
+    pub elgamal_signature: Signature,
+    /// The signed message of [b"AEKey", user_ata]
+    ///
+    /// This is used to derive the AE key
+    #[serde(with = "serde_utils::signature_string")]
+    pub ae_signature: Signature,
+}
+
+/// JSON request used to subscribe to notifications for a confidential token account
+///
+/// The signatures are optional, when omitted notifications do not include decrypted balances
+#[derive(Serialize, Deserialize)]
+pub struct Notifications {
+    /// The public key of the wallet which owns the token account
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub authority: Pubkey,
+    /// The confidential token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+    /// The signed message of [b"ElGamalSecretKey", user_ata]
+    ///
+    /// This is used to derive the ElGamal keypair
+    #[serde(default, with = "serde_utils::option_string")]
+    pub elgamal_signature: Option<Signature>,
+    /// The signed message of [b"AEKey", user_ata]
+    ///
+    /// This is used to derive the AE key
+    #[serde(default, with = "serde_utils::option_string")]
+    pub ae_signature: Option<Signature>,
+}
+
+/// JSON request used to display the decrypted transaction history of a confidential token account
+#[derive(Serialize, Deserialize)]
+pub struct History {
+    /// The public key of the wallet which owns the token account
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub authority: Pubkey,
+    /// The confidential token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+    /// The signed message of [b"ElGamalSecretKey", user_ata]
+    ///
+    /// This is used to derive the ElGamal keypair
+    #[serde(with = "serde_utils::signature_string")]
+    pub elgamal_signature: Signature,
+    /// The signed message of [b"AEKey", user_ata]
+    ///
+    /// This is used to derive the AE key
+    #[serde(with = "serde_utils::signature_string")]
+    pub ae_signature: Signature,
+    /// Only return transactions older than this signature, used to page through the history
+    #[serde(default, with = "serde_utils::option_string")]
+    pub before: Option<Signature>,
+    /// Maximum number of transactions to return
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Query parameters used to list the instructions recorded by the indexer
+#[derive(Serialize, Deserialize, Default)]
+pub struct IndexedInstructions {
+    /// Only return instructions involving this token account
+    #[serde(default, with = "serde_utils::option_string")]
+    pub token_account: Option<Pubkey>,
+    /// Only return instructions of this kind
+    #[serde(default)]
+    pub kind: Option<ApiIndexedInstructionKind>,
+    /// Only return instructions at or after this slot
+    #[serde(default)]
+    pub min_slot: Option<u64>,
+    /// Only return instructions at or before this slot
+    #[serde(default)]
+    pub max_slot: Option<u64>,
+    /// Only return instructions confirmed at or after this unix timestamp
+    #[serde(default)]
+    pub min_block_time: Option<i64>,
+    /// Only return instructions confirmed at or before this unix timestamp
+    #[serde(default)]
+    pub max_block_time: Option<i64>,
+    /// Maximum number of instructions to return
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
 /// JSON request used to wrap or unwrap tokens
 #[derive(Serialize, Deserialize)]
 pub struct WrapTokens {
@@ -146,8 +297,317 @@ pub struct WrapTokens {
     pub amount: u64,
 }
 
-/// JSON response indicating an error message
+/// JSON request used to wrap or unwrap tokens, resolving the other mint and token program
+#[derive(Serialize, Deserialize)]
+pub struct SimpleWrapTokens {
+    /// The public key of the wallet which is wrapping or unwrapping tokens
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub authority: Pubkey,
+    /// Either the unwrapped token mint, or its confidential wrapped mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+    /// Amount of tokens to wrap or unwrap as a decimal string, in ui units using the decimals of
+    /// the mint
+    pub ui_amount: String,
+}
+
+/// JSON request used to wrap tokens, and deposit them into the confidential balance
+#[derive(Serialize, Deserialize)]
+pub struct Shield {
+    /// The public key of the wallet which is shielding tokens
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub authority: Pubkey,
+    /// The unwrapped token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub unwrapped_token_mint: Pubkey,
+    /// The token program of the unwrapped mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub unwrapped_token_program: Pubkey,
+    /// Amount of unwrapped tokens to shield
+    pub amount: u64,
+    /// The signed message of [b"ElGamalSecretKey", user_ata] for the wrapped mint ATA
+    ///
+    /// This is used to derive the ElGamal keypair
+    #[serde(with = "serde_utils::signature_string")]
+    pub elgamal_signature: Signature,
+    /// The signed message of [b"AEKey", user_ata] for the wrapped mint ATA
+    ///
+    /// This is used to derive the AE key
+    #[serde(with = "serde_utils::signature_string")]
+    pub ae_signature: Signature,
+}
+
+/// JSON request used to withdraw tokens from the confidential balance, and unwrap them
 #[derive(Serialize, Deserialize)]
+pub struct Unshield {
+    /// The public key of the wallet which is unshielding tokens
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub authority: Pubkey,
+    /// The unwrapped token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub unwrapped_token_mint: Pubkey,
+    /// The token program of the unwrapped mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub unwrapped_token_program: Pubkey,
+    /// Amount of wrapped tokens to unshield
+    pub amount: u64,
+    /// The signed message of [b"ElGamalSecretKey", user_ata] for the wrapped mint ATA
+    ///
+    /// This is used to derive the ElGamal keypair
+    #[serde(with = "serde_utils::signature_string")]
+    pub elgamal_signature: Signature,
+    /// The signed message of [b"AEKey", user_ata] for the wrapped mint ATA
+    ///
+    /// This is used to derive the AE key
+    #[serde(with = "serde_utils::signature_string")]
+    pub ae_signature: Signature,
+    /// The keypair to be used for the equality proof
+    #[serde(with = "serde_utils::keypair_string")]
+    pub equality_proof_keypair: Keypair,
+    /// The keypair to be used for the range proof
+    #[serde(with = "serde_utils::keypair_string")]
+    pub range_proof_keypair: Keypair,
+}
+
+/// JSON request used to create the confidential wrapped mint of an unwrapped mint
+#[derive(Serialize, Deserialize)]
+pub struct CreateWrappedMint {
+    /// The public key of the wallet paying for the wrapped mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub payer: Pubkey,
+    /// The unwrapped token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub unwrapped_token_mint: Pubkey,
+    /// Whether token accounts can make confidential transfers without being approved
+    #[serde(default = "default_auto_approve_new_accounts")]
+    pub auto_approve_new_accounts: bool,
+    /// The base64 encoded ElGamal pubkey of the auditor, which can decrypt every transfer amount
+    #[serde(default, with = "serde_utils::option_string")]
+    pub auditor_elgamal_pubkey: Option<PodElGamalPubkey>,
+    /// Authority which can approve token accounts and update the confidential transfer settings
+    #[serde(default, with = "serde_utils::option_pubkey_string")]
+    pub confidential_transfer_authority: Option<Pubkey>,
+}
+
+fn default_auto_approve_new_accounts() -> bool {
+    true
+}
+
+/// JSON request used to create a natively issued confidential mint
+///
+/// The confidential mint burn extension is added when both supply signatures are provided, which
+/// keeps the supply of the mint confidential
+#[derive(Serialize, Deserialize)]
+pub struct CreateConfidentialMint {
+    /// The public key of the wallet paying for the mint, which becomes its mint authority
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub authority: Pubkey,
+    /// The public key of the new mint account, which must sign the transaction
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub mint: Pubkey,
+    /// The number of decimals of the mint
+    pub decimals: u8,
+    /// Whether token accounts can make confidential transfers without being approved
+    #[serde(default = "default_auto_approve_new_accounts")]
+    pub auto_approve_new_accounts: bool,
+    /// The base64 encoded ElGamal pubkey of the auditor, which can decrypt every transfer amount
+    #[serde(default, with = "serde_utils::option_string")]
+    pub auditor_elgamal_pubkey: Option<PodElGamalPubkey>,
+    /// Authority which can approve token accounts and update the confidential transfer settings
+    #[serde(default, with = "serde_utils::option_pubkey_string")]
+    pub confidential_transfer_authority: Option<Pubkey>,
+    /// The signed message of [b"SupplyElGamalSecretKey"]
+    ///
+    /// This is used to derive the ElGamal keypair the supply is encrypted for
+    #[serde(default, with = "serde_utils::option_string")]
+    pub supply_elgamal_signature: Option<Signature>,
+    /// The signed message of [b"SupplyAEKey"]
+    ///
+    /// This is used to derive the AE key the decryptable supply is encrypted with
+    #[serde(default, with = "serde_utils::option_string")]
+    pub supply_ae_signature: Option<Signature>,
+}
+
+/// JSON request used by the mint authority of a mint with a confidential supply to mint tokens
+/// into the pending balance of a confidential token account
+#[derive(Serialize, Deserialize)]
+pub struct ConfidentialMint {
+    /// The mint authority of the token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub authority: Pubkey,
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+    /// The signed message of [b"SupplyElGamalSecretKey"]
+    ///
+    /// This is used to derive the ElGamal keypair the supply is encrypted for
+    #[serde(with = "serde_utils::signature_string")]
+    pub supply_elgamal_signature: Signature,
+    /// The signed message of [b"SupplyAEKey"]
+    ///
+    /// This is used to derive the AE key the decryptable supply is encrypted with
+    #[serde(with = "serde_utils::signature_string")]
+    pub supply_ae_signature: Signature,
+    /// Token account receiving the tokens
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub receiving_token_account: Pubkey,
+    #[serde(with = "serde_utils::keypair_string")]
+    pub equality_proof_keypair: Keypair,
+    #[serde(with = "serde_utils::keypair_string")]
+    pub ciphertext_validity_proof_keypair: Keypair,
+    #[serde(with = "serde_utils::keypair_string")]
+    pub range_proof_keypair: Keypair,
+    /// Amount of tokens to mint
+    pub amount: u64,
+}
+
+/// JSON request used to burn tokens from the available balance of a confidential token account
+/// of a mint with a confidential supply
+#[derive(Serialize, Deserialize)]
+pub struct ConfidentialBurn {
+    /// Authority burning tokens
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub authority: Pubkey,
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+    /// The signed message of [b"ElGamalSecretKey", user_ata]
+    ///
+    /// This is used to derive the ElGamal keypair
+    #[serde(with = "serde_utils::signature_string")]
+    pub elgamal_signature: Signature,
+    /// The signed message of [b"AEKey", user_ata]
+    ///
+    /// This is used to derive the AE key
+    #[serde(with = "serde_utils::signature_string")]
+    pub ae_signature: Signature,
+    #[serde(with = "serde_utils::keypair_string")]
+    pub equality_proof_keypair: Keypair,
+    #[serde(with = "serde_utils::keypair_string")]
+    pub ciphertext_validity_proof_keypair: Keypair,
+    #[serde(with = "serde_utils::keypair_string")]
+    pub range_proof_keypair: Keypair,
+    /// Amount of tokens to burn
+    pub amount: u64,
+}
+
+/// JSON request used by the holder of the supply keys of a mint to decrypt its confidential supply
+#[derive(Serialize, Deserialize)]
+pub struct ConfidentialSupply {
+    /// The wallet the supply keys are derived from
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub authority: Pubkey,
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+    /// The signed message of [b"SupplyElGamalSecretKey"]
+    #[serde(with = "serde_utils::signature_string")]
+    pub supply_elgamal_signature: Signature,
+    /// The signed message of [b"SupplyAEKey"]
+    #[serde(with = "serde_utils::signature_string")]
+    pub supply_ae_signature: Signature,
+}
+
+/// JSON request used by the confidential transfer authority of a mint to approve a token account
+///
+/// Only needed for mints which do not auto approve new accounts
+#[derive(Serialize, Deserialize)]
+pub struct ApproveAccount {
+    /// The confidential transfer authority of the token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub authority: Pubkey,
+    /// The confidential token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+    /// The confidential token account to approve
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_account: Pubkey,
+}
+
+/// JSON request used to enable or disable confidential or non confidential credits of a token account
+#[derive(Serialize, Deserialize)]
+pub struct UpdateCredits {
+    /// The public key of the wallet which owns the token account
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub authority: Pubkey,
+    /// The confidential token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+}
+
+/// JSON request used to harvest the withheld confidential transfer fees of token accounts to the mint
+#[derive(Serialize, Deserialize)]
+pub struct HarvestWithheldFees {
+    /// The public key of the wallet paying for the harvest, which anyone can do
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub payer: Pubkey,
+    /// The confidential token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+    /// The token accounts to harvest from, every confidential token account of the mint if empty
+    #[serde(default, with = "serde_utils::pubkey_vec_string")]
+    pub sources: Vec<Pubkey>,
+}
+
+/// JSON request used by the withdraw withheld authority of a mint to withdraw withheld
+/// confidential transfer fees into its own confidential token account
+#[derive(Serialize, Deserialize)]
+pub struct WithdrawWithheldFees {
+    /// The withdraw withheld authority of the token mint, which owns the destination token account
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub authority: Pubkey,
+    /// The confidential token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+    /// The signed message of [b"WithdrawWithheldElGamalSecretKey"]
+    ///
+    /// This is used to derive the ElGamal keypair the withheld fees are encrypted for
+    #[serde(with = "serde_utils::signature_string")]
+    pub withdraw_withheld_signature: Signature,
+    /// The signed message of [b"ElGamalSecretKey", user_ata]
+    ///
+    /// This is used to derive the ElGamal keypair
+    #[serde(with = "serde_utils::signature_string")]
+    pub elgamal_signature: Signature,
+    /// The signed message of [b"AEKey", user_ata]
+    ///
+    /// This is used to derive the AE key
+    #[serde(with = "serde_utils::signature_string")]
+    pub ae_signature: Signature,
+    /// The keypair to be used for the ciphertext equality proof
+    #[serde(with = "serde_utils::keypair_string")]
+    pub equality_proof_keypair: Keypair,
+    /// The token accounts to withdraw from, the fees harvested to the mint are withdrawn if empty
+    #[serde(default, with = "serde_utils::pubkey_vec_string")]
+    pub sources: Vec<Pubkey>,
+}
+
+/// JSON request used to enable or disable harvesting withheld confidential transfer fees to a mint
+#[derive(Serialize, Deserialize)]
+pub struct UpdateHarvestToMint {
+    /// The confidential transfer fee authority of the token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub authority: Pubkey,
+    /// The confidential token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+}
+
+/// Query parameters used to list the confidential token accounts of a mint awaiting approval
+#[derive(Serialize, Deserialize)]
+pub struct PendingApprovals {
+    /// The confidential token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+}
+
+/// Query parameters used to look up a wrapped mint and its backing
+#[derive(Serialize, Deserialize)]
+pub struct WrappedMintInfo {
+    /// Either the unwrapped token mint, or its confidential wrapped mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+}
+
+/// JSON response indicating an error message
+#[derive(Serialize, Deserialize, Debug)]
 pub struct ApiError {
     pub msg: String,
 }
@@ -161,6 +621,62 @@ pub struct ApiTransactionResponse {
     pub transactions: Vec<String>,
 }
 
+/// JSON response containing the confidential transfer transactions
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiTransferResponse {
+    /// The bincode serialized, base64 encoded transactions to sign and send in order
+    pub transactions: Vec<String>,
+    /// The confidential transfer fee withheld in the receiving token account in base units,
+    /// encoded as a string
+    #[serde(with = "serde_utils::u64_string")]
+    pub transfer_fee_amount: u64,
+    /// The amount credited to the pending balance of the receiving token account in base units,
+    /// encoded as a string
+    #[serde(with = "serde_utils::u64_string")]
+    pub received_amount: u64,
+}
+
+/// JSON response containing the transactions which withdraw withheld confidential transfer fees
+#[derive(Serialize, Deserialize)]
+pub struct ApiWithdrawWithheldFeesResponse {
+    /// The base64 encoded transactions
+    pub transactions: Vec<String>,
+    /// The amount of withheld fees withdrawn in lamports
+    #[serde(with = "serde_utils::u64_string")]
+    pub amount: u64,
+}
+
+/// JSON response containing the decrypted confidential supply of a mint
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiConfidentialSupplyResponse {
+    /// The amount of tokens in circulation, once every burn is applied to the supply
+    pub supply: f64,
+    /// The amount of tokens burned since the pending burn was last applied to the supply
+    pub pending_burn: f64,
+    /// The supply in base units, encoded as a string to avoid loss of precision
+    #[serde(with = "serde_utils::u64_string")]
+    pub supply_amount: u64,
+    /// The pending burn in base units, encoded as a string
+    #[serde(with = "serde_utils::u64_string")]
+    pub pending_burn_amount: u64,
+    /// The number of decimals of the token mint
+    pub decimals: u8,
+}
+
+/// JSON response containing the wrap or unwrap transaction
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiWrapTokensResponse {
+    /// The bincode serialized, base64 encoded transactions to sign and send
+    pub transactions: Vec<String>,
+    /// The transfer fee withheld by the unwrapped mint in base units, encoded as a string
+    #[serde(with = "serde_utils::u64_string")]
+    pub transfer_fee_amount: u64,
+    /// The amount of wrapped tokens minted when wrapping, or of unwrapped tokens received when
+    /// unwrapping, in base units and encoded as a string
+    #[serde(with = "serde_utils::u64_string")]
+    pub received_amount: u64,
+}
+
 /// JSON response containing account balances
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ApiBalancesResponse {
@@ -170,6 +686,429 @@ pub struct ApiBalancesResponse {
     pub available_balance: f64,
     // The amount of non confidential tokens
     pub non_confidential_balance: f64,
+    /// The pending balance in base units, encoded as a string to avoid loss of precision
+    #[serde(with = "serde_utils::u64_string")]
+    pub pending_balance_amount: u64,
+    /// The available confidential balance in base units, encoded as a string
+    #[serde(with = "serde_utils::u64_string")]
+    pub available_balance_amount: u64,
+    /// The non confidential balance in base units, encoded as a string
+    #[serde(with = "serde_utils::u64_string")]
+    pub non_confidential_balance_amount: u64,
+    /// The number of decimals of the token mint
+    pub decimals: u8,
+    /// The number of credits made to the pending balance since it was last applied
+    pub pending_balance_credit_counter: u64,
+    /// The number of credits after which the pending balance must be applied
+    pub maximum_pending_balance_credit_counter: u64,
+    /// Whether the account accepts incoming confidential transfers
+    pub allow_confidential_credits: bool,
+    /// Whether the account accepts incoming non confidential transfers
+    pub allow_non_confidential_credits: bool,
+    /// Whether the account has been approved by the mint's confidential transfer authority
+    pub approved: bool,
+    /// The base64 encoded ElGamal public key of the account
+    pub elgamal_pubkey: String,
+}
+
+/// JSON response containing the balances of every confidential token account owned by a wallet
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiPortfolioResponse {
+    /// Balances aggregated per token mint
+    pub tokens: Vec<ApiPortfolioToken>,
+}
+
+/// Balances of all confidential token accounts for a single token mint
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiPortfolioToken {
+    /// The confidential token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+    /// If `token_mint` was created by the spl token wrap program, the mint it wraps
+    #[serde(with = "serde_utils::option_pubkey_string")]
+    pub unwrapped_token_mint: Option<Pubkey>,
+    /// The token accounts included in the totals
+    pub token_accounts: Vec<String>,
+    /// The number of decimals of the token mint
+    pub decimals: u8,
+    /// The amount of tokens pending application to the available confidential balance
+    pub pending_balance: f64,
+    /// The amount of tokens available as confidential balance
+    pub available_balance: f64,
+    /// The amount of non confidential tokens
+    pub non_confidential_balance: f64,
+    /// The sum of all balances
+    pub total_balance: f64,
+    /// The pending balance in base units, encoded as a string
+    #[serde(with = "serde_utils::u64_string")]
+    pub pending_balance_amount: u64,
+    /// The available confidential balance in base units, encoded as a string
+    #[serde(with = "serde_utils::u64_string")]
+    pub available_balance_amount: u64,
+    /// The non confidential balance in base units, encoded as a string
+    #[serde(with = "serde_utils::u64_string")]
+    pub non_confidential_balance_amount: u64,
+    /// The sum of all balances in base units, encoded as a string
+    #[serde(with = "serde_utils::u64_string")]
+    pub total_balance_amount: u64,
+    /// The token accounts which can't transact until approved by the mint's confidential transfer authority
+    #[serde(default)]
+    pub unapproved_token_accounts: Vec<String>,
+    /// The token accounts whose confidential balances could not be decrypted, they are counted
+    /// as zero in the totals
+    #[serde(default)]
+    pub undecryptable_token_accounts: Vec<String>,
+}
+
+/// JSON response listing the confidential token accounts of a mint awaiting approval
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiPendingApprovalsResponse {
+    /// The confidential token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+    /// Whether the mint approves new accounts automatically, in which case none should be pending
+    pub auto_approve_new_accounts: bool,
+    /// The authority which can approve the token accounts
+    #[serde(with = "serde_utils::option_pubkey_string")]
+    pub confidential_transfer_authority: Option<Pubkey>,
+    /// The token accounts awaiting approval
+    pub token_accounts: Vec<ApiPendingApproval>,
+}
+
+/// JSON response describing a wrapped mint, and whether its supply is backed by the escrow
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiWrappedMintResponse {
+    /// The unwrapped token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub unwrapped_token_mint: Pubkey,
+    /// The token program of the unwrapped mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub unwrapped_token_program: Pubkey,
+    /// The confidential wrapped mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub wrapped_token_mint: Pubkey,
+    /// The token wrap backpointer of the wrapped mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub backpointer: Pubkey,
+    /// The unwrapped mint stored in the backpointer, if it exists and matches the wrapped mint
+    #[serde(with = "serde_utils::option_pubkey_string")]
+    pub backpointer_unwrapped_token_mint: Option<Pubkey>,
+    /// The PDA which is the mint authority of the wrapped mint, and owner of the escrow
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub wrapped_mint_authority: Pubkey,
+    /// The token account holding the unwrapped tokens backing the wrapped supply
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub escrow: Pubkey,
+    /// Whether the escrow token account exists
+    pub escrow_exists: bool,
+    /// The escrow balance
+    pub escrow_balance: f64,
+    /// The escrow balance in base units, encoded as a string
+    #[serde(with = "serde_utils::u64_string")]
+    pub escrow_balance_amount: u64,
+    /// The wrapped supply
+    pub wrapped_supply: f64,
+    /// The wrapped supply in base units, encoded as a string
+    #[serde(with = "serde_utils::u64_string")]
+    pub wrapped_supply_amount: u64,
+    /// The number of decimals of both mints
+    pub decimals: u8,
+    /// Whether the escrow balance is less than the wrapped supply
+    pub supply_mismatch: bool,
+    /// The escrow balance exceeding the wrapped supply
+    pub escrow_surplus: f64,
+    /// The escrow balance exceeding the wrapped supply in base units, encoded as a string
+    #[serde(with = "serde_utils::u64_string")]
+    pub escrow_surplus_amount: u64,
+}
+
+/// JSON response listing every wrapped mint created by the token wrap program
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiWrappedMintsResponse {
+    pub wrapped_mints: Vec<ApiWrappedMint>,
+}
+
+/// A wrapped mint created by the token wrap program
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiWrappedMint {
+    /// The unwrapped token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub unwrapped_token_mint: Pubkey,
+    /// The wrapped mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub wrapped_token_mint: Pubkey,
+    /// The number of decimals of both mints
+    pub decimals: u8,
+    /// Whether the wrapped mint supports confidential transfers
+    pub confidential_transfers: bool,
+    /// Whether the wrapped mint has an auditor which can decrypt every transfer amount
+    pub has_auditor: bool,
+}
+
+/// A confidential token account awaiting approval
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiPendingApproval {
+    /// The confidential token account
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_account: Pubkey,
+    /// The wallet which owns the token account
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub owner: Pubkey,
+    /// The base64 encoded ElGamal public key of the account
+    pub elgamal_pubkey: String,
+}
+
+/// The type of change reported by an [`ApiBalanceNotification`]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiBalanceNotificationKind {
+    /// The pending balance was credited by an incoming transfer or deposit
+    Credit,
+    /// Any other change to the token account balances
+    BalanceChange,
+}
+
+impl ApiBalanceNotificationKind {
+    /// Returns the name used for the server sent event
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Credit => "credit",
+            Self::BalanceChange => "balance_change",
+        }
+    }
+}
+
+/// JSON notification emitted when the balances of a confidential token account change
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ApiBalanceNotification {
+    /// The type of change
+    pub kind: ApiBalanceNotificationKind,
+    /// The token account which changed
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_account: Pubkey,
+    /// The confidential token mint
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+    /// The slot at which the change was observed
+    pub slot: u64,
+    /// The number of credits made to the pending balance since it was last applied
+    pub pending_balance_credit_counter: u64,
+    /// The number of credits made since the previous notification
+    pub new_credits: u64,
+    /// The non confidential balance in base units
+    #[serde(with = "serde_utils::u64_string")]
+    pub non_confidential_balance_amount: u64,
+    /// The change in non confidential balance since the previous notification
+    #[serde(with = "serde_utils::i128_string")]
+    pub non_confidential_balance_delta: i128,
+    /// The decrypted pending balance in base units, only present if credentials were provided
+    #[serde(with = "serde_utils::option_string")]
+    pub pending_balance_amount: Option<u64>,
+    /// The change in pending balance since the previous notification
+    #[serde(with = "serde_utils::option_string")]
+    pub pending_balance_delta: Option<i128>,
+    /// The decrypted available balance in base units, only present if credentials were provided
+    #[serde(with = "serde_utils::option_string")]
+    pub available_balance_amount: Option<u64>,
+    /// The change in available balance since the previous notification
+    #[serde(with = "serde_utils::option_string")]
+    pub available_balance_delta: Option<i128>,
+}
+
+/// JSON response containing the decrypted transaction history of a confidential token account
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiHistoryResponse {
+    /// The confidential token account
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_account: Pubkey,
+    /// The number of decimals of the token mint
+    pub decimals: u8,
+    /// History entries, newest first
+    pub entries: Vec<ApiHistoryEntry>,
+    /// Pass as `before` to fetch the next page, `None` once the oldest transaction was returned
+    #[serde(with = "serde_utils::option_string")]
+    pub before: Option<Signature>,
+}
+
+/// The confidential transfer instruction recorded by an [`ApiHistoryEntry`]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiHistoryEntryKind {
+    /// The token account was configured for confidential transfers
+    ConfigureAccount,
+    /// Non confidential tokens were deposited into the pending balance
+    Deposit,
+    /// The pending balance was applied to the available balance
+    ApplyPendingBalance,
+    /// Confidential tokens were sent to another token account
+    TransferOut,
+    /// Confidential tokens were received into the pending balance
+    TransferIn,
+    /// Confidential tokens were withdrawn into the non confidential balance
+    Withdraw,
+    /// Confidential tokens were minted into the pending balance by the mint authority
+    Mint,
+    /// Confidential tokens were burned from the available balance
+    Burn,
+    /// The available balance was emptied so the token account can be closed
+    EmptyAccount,
+}
+
+impl ApiHistoryEntryKind {
+    /// Returns the name used in csv exports
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ConfigureAccount => "configure_account",
+            Self::Deposit => "deposit",
+            Self::ApplyPendingBalance => "apply_pending_balance",
+            Self::TransferOut => "transfer_out",
+            Self::TransferIn => "transfer_in",
+            Self::Withdraw => "withdraw",
+            Self::Mint => "mint",
+            Self::Burn => "burn",
+            Self::EmptyAccount => "empty_account",
+        }
+    }
+}
+
+/// A single confidential transfer instruction in the history of a token account
+///
+/// Balances are the running balances after the instruction, and are omitted when they
+/// can not be determined from the history available on the rpc node
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ApiHistoryEntry {
+    /// The transaction containing the instruction
+    #[serde(with = "serde_utils::signature_string")]
+    pub signature: Signature,
+    /// The slot the transaction was confirmed in
+    pub slot: u64,
+    /// Estimated unix timestamp of the transaction
+    pub block_time: Option<i64>,
+    /// The type of instruction
+    pub kind: ApiHistoryEntryKind,
+    /// The decrypted amount moved by the instruction in base units
+    #[serde(with = "serde_utils::option_string")]
+    pub amount: Option<u64>,
+    /// The other token account of a transfer
+    #[serde(with = "serde_utils::option_pubkey_string")]
+    pub counterparty: Option<Pubkey>,
+    /// The pending balance in base units
+    #[serde(with = "serde_utils::option_string")]
+    pub pending_balance_amount: Option<u64>,
+    /// The available confidential balance in base units
+    #[serde(with = "serde_utils::option_string")]
+    pub available_balance_amount: Option<u64>,
+    /// The non confidential balance in base units
+    #[serde(with = "serde_utils::option_string")]
+    pub non_confidential_balance_amount: Option<u64>,
+}
+
+/// The instruction recorded by an [`ApiIndexedInstruction`]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiIndexedInstructionKind {
+    ConfigureAccount,
+    Deposit,
+    Withdraw,
+    ApplyPendingBalance,
+    Transfer,
+    EmptyAccount,
+    /// Confidential tokens were minted by the mint authority
+    Mint,
+    /// Confidential tokens were burned
+    Burn,
+    /// Unwrapped tokens were wrapped into the confidential mint by the token wrap program
+    Wrap,
+    /// Wrapped tokens were unwrapped by the token wrap program
+    Unwrap,
+}
+
+impl ApiIndexedInstructionKind {
+    /// Returns the name stored in the indexer database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ConfigureAccount => "configure_account",
+            Self::Deposit => "deposit",
+            Self::Withdraw => "withdraw",
+            Self::ApplyPendingBalance => "apply_pending_balance",
+            Self::Transfer => "transfer",
+            Self::EmptyAccount => "empty_account",
+            Self::Mint => "mint",
+            Self::Burn => "burn",
+            Self::Wrap => "wrap",
+            Self::Unwrap => "unwrap",
+        }
+    }
+}
+
+impl std::str::FromStr for ApiIndexedInstructionKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "configure_account" => Self::ConfigureAccount,
+            "deposit" => Self::Deposit,
+            "withdraw" => Self::Withdraw,
+            "apply_pending_balance" => Self::ApplyPendingBalance,
+            "transfer" => Self::Transfer,
+            "empty_account" => Self::EmptyAccount,
+            "mint" => Self::Mint,
+            "burn" => Self::Burn,
+            "wrap" => Self::Wrap,
+            "unwrap" => Self::Unwrap,
+            _ => anyhow::bail!("unknown instruction kind {s}"),
+        })
+    }
+}
+
+/// A confidential transfer or token wrap instruction recorded by the indexer
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ApiIndexedInstruction {
+    /// The transaction containing the instruction
+    #[serde(with = "serde_utils::signature_string")]
+    pub signature: Signature,
+    /// The position of the instruction in the transaction
+    pub instruction_index: u32,
+    /// The slot the transaction was confirmed in
+    pub slot: u64,
+    /// Estimated unix timestamp of the transaction
+    pub block_time: Option<i64>,
+    /// The type of instruction
+    pub kind: ApiIndexedInstructionKind,
+    /// The confidential token account the instruction operates on, the source of transfers
+    /// and unwraps, and the recipient of wraps
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_account: Pubkey,
+    /// The destination token account of a transfer
+    #[serde(with = "serde_utils::option_pubkey_string")]
+    pub counterparty: Option<Pubkey>,
+    /// The amount of deposits, withdrawals, wraps and unwraps in base units
+    #[serde(with = "serde_utils::option_string")]
+    pub amount: Option<u64>,
+    /// Base64 encoded ciphertexts included in the instruction, keyed by their field name
+    pub ciphertexts: BTreeMap<String, String>,
+    /// Every account passed to the instruction
+    pub accounts: Vec<String>,
+}
+
+/// JSON response containing instructions recorded by the indexer, oldest first
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiIndexedInstructionsResponse {
+    pub instructions: Vec<ApiIndexedInstruction>,
+}
+
+/// JSON response describing the progress of the indexer
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiIndexerStatusResponse {
+    /// The confidential token mint being indexed
+    #[serde(with = "serde_utils::pubkey_string")]
+    pub token_mint: Pubkey,
+    /// The most recent slot containing an indexed transaction
+    pub last_indexed_slot: Option<u64>,
+    /// The number of recorded instructions
+    pub instruction_count: u64,
+    /// The number of token accounts configured for the mint
+    pub token_account_count: u64,
 }
 
 impl ApiTransactionResponse {
@@ -220,4 +1159,88 @@ mod test {
         assert_eq!(init_msg.ae_signature, expected_ae_signature);
         assert_eq!(init_msg.token_mint, mint);
     }
+
+    #[test]
+    fn test_balances_response_serialization() {
+        let balances = ApiBalancesResponse {
+            pending_balance: 0.0,
+            available_balance: 18446744073.709553,
+            non_confidential_balance: 0.000000001,
+            pending_balance_amount: 0,
+            available_balance_amount: u64::MAX,
+            non_confidential_balance_amount: 1,
+            decimals: 9,
+            pending_balance_credit_counter: 1,
+            maximum_pending_balance_credit_counter: 65536,
+            allow_confidential_credits: true,
+            allow_non_confidential_credits: true,
+            approved: true,
+            elgamal_pubkey: "yK6ZeLGATEB+S/gR2xcNWotmym2AXeaM+1U0exawHB0=".to_string(),
+        };
+
+        let value = serde_json::to_value(&balances).unwrap();
+        // raw amounts are encoded as strings so they survive javascript number parsing
+        assert_eq!(value["available_balance_amount"], "18446744073709551615");
+        assert_eq!(value["non_confidential_balance_amount"], "1");
+
+        let decoded: ApiBalancesResponse = serde_json::from_value(value).unwrap();
+        assert_eq!(decoded.available_balance_amount, u64::MAX);
+        assert_eq!(decoded.pending_balance_amount, 0);
+        assert_eq!(decoded.decimals, 9);
+    }
+
+    #[test]
+    fn test_portfolio_response_serialization() {
+        let wrapped_mint = Pubkey::new_unique();
+        let unwrapped_mint = Pubkey::new_unique();
+        let portfolio = ApiPortfolioResponse {
+            tokens: vec![
+                ApiPortfolioToken {
+                    token_mint: wrapped_mint,
+                    unwrapped_token_mint: Some(unwrapped_mint),
+                    token_accounts: vec![Pubkey::new_unique().to_string()],
+                    decimals: 6,
+                    pending_balance: 0.00001,
+                    available_balance: 0.0001,
+                    non_confidential_balance: 0.0,
+                    total_balance: 0.00011,
+                    pending_balance_amount: 10,
+                    available_balance_amount: 100,
+                    non_confidential_balance_amount: 0,
+                    total_balance_amount: 110,
+                    unapproved_token_accounts: vec![],
+                    undecryptable_token_accounts: vec![],
+                },
+                ApiPortfolioToken {
+                    token_mint: Pubkey::new_unique(),
+                    unwrapped_token_mint: None,
+                    token_accounts: vec![],
+                    decimals: 9,
+                    pending_balance: 0.0,
+                    available_balance: 0.0,
+                    non_confidential_balance: 0.0,
+                    total_balance: 0.0,
+                    pending_balance_amount: 0,
+                    available_balance_amount: 0,
+                    non_confidential_balance_amount: 0,
+                    total_balance_amount: 0,
+                    unapproved_token_accounts: vec![],
+                    undecryptable_token_accounts: vec![],
+                },
+            ],
+        };
+
+        let value = serde_json::to_value(&portfolio).unwrap();
+        assert_eq!(value["tokens"][0]["token_mint"], wrapped_mint.to_string());
+        assert_eq!(
+            value["tokens"][0]["unwrapped_token_mint"],
+            unwrapped_mint.to_string()
+        );
+        assert_eq!(value["tokens"][0]["total_balance_amount"], "110");
+        assert!(value["tokens"][1]["unwrapped_token_mint"].is_null());
+
+        let decoded: ApiPortfolioResponse = serde_json::from_value(value).unwrap();
+        assert_eq!(decoded.tokens[0].unwrapped_token_mint, Some(unwrapped_mint));
+        assert_eq!(decoded.tokens[1].unwrapped_token_mint, None);
+    }
 }
@@ -1,4 +1,17 @@
-pub async fn start_api(listen_url: String, rpc_endpoint: String) -> anyhow::Result<()> {
+pub async fn start_api(
+    listen_url: String,
+    rpc_endpoint: String,
+    discrete_log_table: String,
+    baby_step_bits: u32,
+    indexer_database: Option<String>,
+) -> anyhow::Result<()> {
     log::info!("starting api");
-    api::start_api(&listen_url, rpc_endpoint).await
+    api::start_api(
+        &listen_url,
+        rpc_endpoint,
+        discrete_log_table,
+        baby_step_bits,
+        indexer_database,
+    )
+    .await
 }
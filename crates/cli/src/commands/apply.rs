@@ -17,22 +17,35 @@ pub async fn apply(
     let unwrapped_mint: Pubkey = unwrapped_mint.parse().unwrap();
     let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint, &spl_token_2022::id());
     let key = Keypair::read_from_file(keypair).unwrap();
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    apply_pending_balance(&client, &rpc, &api_url, &key, wrapped_mint).await?;
+
+    Ok(())
+}
 
+/// Requests an apply transaction from the api for the user's ata of `token_mint`, then signs and sends it
+pub async fn apply_pending_balance(
+    client: &reqwest::Client,
+    rpc: &RpcClient,
+    api_url: &str,
+    key: &Keypair,
+    token_mint: Pubkey,
+) -> anyhow::Result<()> {
     let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
         &key.pubkey(),
-        &wrapped_mint,
+        &token_mint,
         &spl_token_2022::id(),
     );
     let elgamal_sig = key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata));
     let ae_sig = key.sign_message(&KeypairType::Ae.message_to_sign(user_ata));
 
-    let client = reqwest::ClientBuilder::new().build()?;
-
     let payload = InitializeOrApply {
         authority: key.pubkey(),
-        token_mint: wrapped_mint,
+        token_mint,
         elgamal_signature: elgamal_sig,
         ae_signature: ae_sig,
+        maximum_pending_balance_credit_counter: None,
     };
 
     log::info!("{}", serde_json::to_string_pretty(&payload).unwrap());
@@ -49,7 +62,7 @@ pub async fn apply(
 
     let txs = res.decode_transactions()?;
     for mut tx in txs {
-        tx.sign(&vec![&key], rpc.get_latest_blockhash().await?);
+        tx.sign(&vec![key], rpc.get_latest_blockhash().await?);
         log::info!("sending apply tx");
         let sig = rpc.send_and_confirm_transaction(&tx).await?;
         log::info!("sent apply tx {sig}");
@@ -0,0 +1,109 @@
+use api::types::{ApiError, ApiPendingApprovalsResponse, ApiTransactionResponse, ApproveAccount};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::{EncodableKey, Signer},
+};
+use spl_token_wrap::get_wrapped_mint_address;
+
+/// Approves confidential token accounts of a wrapped mint as its confidential transfer authority
+///
+/// Every account awaiting approval is approved if neither a token account nor an owner is given
+pub async fn approve_account(
+    api_url: String,
+    rpc_url: String,
+    keypair: String,
+    unwrapped_mint: String,
+    token_account: Option<String>,
+    owner: Option<String>,
+) -> anyhow::Result<()> {
+    let unwrapped_mint: Pubkey = unwrapped_mint.parse()?;
+    let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint, &spl_token_2022::id());
+    let rpc = RpcClient::new(rpc_url);
+    let key = Keypair::read_from_file(&keypair)
+        .map_err(|err| anyhow::anyhow!("failed to read keypair {keypair} {err}"))?;
+
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let token_accounts = match (token_account, owner) {
+        (Some(_), Some(_)) => anyhow::bail!("only one of --token-account and --owner can be set"),
+        (Some(token_account), None) => vec![token_account.parse()?],
+        (None, Some(owner)) => vec![
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &owner.parse()?,
+                &wrapped_mint,
+                &spl_token_2022::id(),
+            ),
+        ],
+        (None, None) => get_pending_approvals(&client, &api_url, wrapped_mint)
+            .await?
+            .token_accounts
+            .into_iter()
+            .map(|pending| pending.token_account)
+            .collect(),
+    };
+    if token_accounts.is_empty() {
+        log::info!("no token accounts of {wrapped_mint} are awaiting approval");
+        return Ok(());
+    }
+
+    for token_account in token_accounts {
+        let payload = ApproveAccount {
+            authority: key.pubkey(),
+            token_mint: wrapped_mint,
+            token_account,
+        };
+        let req = client
+            .post(format!("{api_url}/confidential-balances/approve-account"))
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .build()?;
+        let res = client.execute(req).await?;
+        if !res.status().is_success() {
+            let err: ApiError = res.json().await?;
+            anyhow::bail!("failed to approve {token_account} {}", err.msg);
+        }
+        let res: ApiTransactionResponse = res.json().await?;
+
+        for mut tx in res.decode_transactions()? {
+            tx.sign(&vec![&key], rpc.get_latest_blockhash().await?);
+            let sig = rpc.send_and_confirm_transaction(&tx).await?;
+            log::info!("approved {token_account} in tx {sig}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Displays the confidential token accounts of a wrapped mint awaiting approval
+pub async fn pending_approvals(api_url: String, unwrapped_mint: String) -> anyhow::Result<()> {
+    let unwrapped_mint: Pubkey = unwrapped_mint.parse()?;
+    let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint, &spl_token_2022::id());
+
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let res = get_pending_approvals(&client, &api_url, wrapped_mint).await?;
+
+    log::info!("{}", serde_json::to_string_pretty(&res).unwrap());
+
+    Ok(())
+}
+
+/// Requests the confidential token accounts of `token_mint` awaiting approval from the api
+pub async fn get_pending_approvals(
+    client: &reqwest::Client,
+    api_url: &str,
+    token_mint: Pubkey,
+) -> anyhow::Result<ApiPendingApprovalsResponse> {
+    let req = client
+        .get(format!("{api_url}/confidential-balances/pending-approvals"))
+        .query(&[("token_mint", token_mint.to_string())])
+        .build()?;
+    let res = client.execute(req).await?;
+    if !res.status().is_success() {
+        let err: ApiError = res.json().await?;
+        anyhow::bail!("failed to list pending approvals {}", err.msg);
+    }
+    Ok(res.json().await?)
+}
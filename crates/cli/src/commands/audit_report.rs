@@ -0,0 +1,151 @@
+use api::{
+    audit::{build_report, AccountTotals, MintSupply},
+    indexer::{Indexer, IndexerStore},
+    types::IndexedInstructions,
+};
+use common::discrete_log::DiscreteLogTable;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::{
+    amount_to_ui_amount_string_trimmed,
+    extension::{
+        confidential_transfer::ConfidentialTransferMint, BaseStateWithExtensions,
+        StateWithExtensions,
+    },
+    solana_zk_sdk::encryption::pod::elgamal::PodElGamalPubkey,
+    state::{Account, Mint},
+};
+use spl_token_wrap::{get_wrapped_mint_address, get_wrapped_mint_authority};
+use std::{sync::Arc, time::Duration};
+
+use super::auditor::read_auditor_keypair;
+
+const CSV_HEADER: &str = "token_account,inflow,outflow,deposited,withdrawn,transferred_in,transferred_out,wrapped,unwrapped";
+
+#[allow(clippy::too_many_arguments)]
+pub async fn audit_report(
+    rpc_url: String,
+    unwrapped_mint: String,
+    auditor_keypair: String,
+    passphrase_env: String,
+    indexer_database: Option<String>,
+    min_slot: Option<u64>,
+    max_slot: Option<u64>,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    format: String,
+    discrete_log_table: String,
+    baby_step_bits: u32,
+) -> anyhow::Result<()> {
+    let unwrapped_mint: Pubkey = unwrapped_mint.parse()?;
+    let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint, &spl_token_2022::id());
+    let auditor = read_auditor_keypair(&auditor_keypair, &passphrase_env)?;
+    let rpc = Arc::new(RpcClient::new(rpc_url));
+
+    let mint_account = rpc.get_account(&wrapped_mint).await?;
+    let mint = StateWithExtensions::<Mint>::unpack(&mint_account.data)?;
+    let mint_auditor = Option::<PodElGamalPubkey>::from(
+        mint.get_extension::<ConfidentialTransferMint>()?
+            .auditor_elgamal_pubkey,
+    );
+    match mint_auditor {
+        Some(mint_auditor) if mint_auditor == PodElGamalPubkey::from(*auditor.pubkey()) => {}
+        Some(mint_auditor) => log::warn!(
+            "auditor keypair does not match the current auditor {mint_auditor} of {wrapped_mint}"
+        ),
+        None => anyhow::bail!("wrapped mint {wrapped_mint} does not have an auditor"),
+    }
+
+    // bring the index up to date before reading from it
+    let store = Arc::new(match indexer_database {
+        Some(indexer_database) => IndexerStore::open(indexer_database, wrapped_mint)?,
+        None => IndexerStore::open_in_memory(wrapped_mint)?,
+    });
+    log::info!("indexing {wrapped_mint}");
+    Indexer::new(rpc.clone(), store.clone(), Duration::from_secs(1))
+        .index_once()
+        .await?;
+    let instructions = store.instructions(
+        &IndexedInstructions {
+            min_slot,
+            max_slot,
+            min_block_time: start_time,
+            max_block_time: end_time,
+            ..Default::default()
+        },
+        None,
+    )?;
+    log::info!("auditing {} instructions", instructions.len());
+
+    // the supply only describes the end of the report when it extends to the latest slot
+    let supply = if max_slot.is_none() && end_time.is_none() {
+        let unwrapped_mint_program = rpc.get_account(&unwrapped_mint).await?.owner;
+        let escrow = spl_associated_token_account::get_associated_token_address_with_program_id(
+            &get_wrapped_mint_authority(&wrapped_mint),
+            &unwrapped_mint,
+            &unwrapped_mint_program,
+        );
+        let escrow = rpc.get_account(&escrow).await?;
+        Some(MintSupply {
+            supply: mint.base.supply,
+            escrow_balance: StateWithExtensions::<Account>::unpack(&escrow.data)?
+                .base
+                .amount,
+        })
+    } else {
+        None
+    };
+    let decimals = mint.base.decimals;
+    let from_genesis = min_slot.is_none() && start_time.is_none();
+
+    let report = tokio::task::spawn_blocking(move || {
+        let discrete_log = DiscreteLogTable::load_or_build(discrete_log_table, baby_step_bits)?;
+        build_report(
+            wrapped_mint,
+            &instructions,
+            auditor.secret(),
+            &discrete_log,
+            from_genesis,
+            supply,
+        )
+    })
+    .await??;
+
+    for check in &report.checks {
+        if check.passed {
+            log::info!("check {} passed, {}", check.name, check.detail);
+        } else {
+            log::warn!("check {} failed, {}", check.name, check.detail);
+        }
+    }
+
+    // printed to stdout rather than logged so the output can be redirected to a file
+    match format.as_str() {
+        "csv" => {
+            println!("{CSV_HEADER}");
+            for totals in &report.accounts {
+                println!("{}", csv_row(totals, decimals));
+            }
+        }
+        _ => println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+    }
+
+    Ok(())
+}
+
+/// Formats the totals of an account as a csv row, with amounts in ui units
+fn csv_row(totals: &AccountTotals, decimals: u8) -> String {
+    let amount = |amount: u64| amount_to_ui_amount_string_trimmed(amount, decimals);
+    [
+        totals.token_account.to_string(),
+        amount(totals.inflow),
+        amount(totals.outflow),
+        amount(totals.deposited),
+        amount(totals.withdrawn),
+        amount(totals.transferred_in),
+        amount(totals.transferred_out),
+        amount(totals.wrapped),
+        amount(totals.unwrapped),
+    ]
+    .join(",")
+}
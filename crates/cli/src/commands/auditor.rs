@@ -0,0 +1,170 @@
+use common::key_generator::{derive_elgamal_key, derive_elgamal_key_from_seed, KeypairType};
+use common::keystore::{is_encrypted_keystore, EncryptedKeystore, DEFAULT_PBKDF2_ROUNDS};
+use common::wrapped_mint::{update_wrapped_mint_instruction, WrappedMintConfig};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::{EncodableKey, Signer},
+    transaction::Transaction,
+};
+use spl_token_2022::solana_zk_sdk::encryption::{
+    elgamal::ElGamalKeypair, pod::elgamal::PodElGamalPubkey,
+};
+use spl_token_wrap::get_wrapped_mint_address;
+use std::path::Path;
+
+/// Generates an auditor ElGamal keypair, randomly or derived from a seed file or wallet signature
+pub async fn keygen(
+    output: String,
+    seed_file: Option<String>,
+    keypair: Option<String>,
+    encrypt: bool,
+    passphrase_env: String,
+    force: bool,
+) -> anyhow::Result<()> {
+    if !force && Path::new(&output).exists() {
+        anyhow::bail!("{output} already exists, pass --force to overwrite it");
+    }
+    let auditor = match (seed_file, keypair) {
+        (Some(_), Some(_)) => anyhow::bail!("only one of --seed-file and --keypair can be set"),
+        (Some(seed_file), None) => derive_elgamal_key_from_seed(&std::fs::read(&seed_file)?)?,
+        (None, Some(keypair)) => {
+            let key = Keypair::read_from_file(&keypair)
+                .map_err(|err| anyhow::anyhow!("failed to read keypair {keypair} {err}"))?;
+            // the ata is ignored by the message, the auditor key is tied to the wallet only
+            derive_elgamal_key(
+                key.sign_message(&KeypairType::Auditor.message_to_sign(Pubkey::default())),
+            )?
+        }
+        (None, None) => ElGamalKeypair::new_rand(),
+    };
+
+    if encrypt {
+        let passphrase = read_passphrase(&passphrase_env, true)?;
+        EncryptedKeystore::encrypt(&auditor, &passphrase, DEFAULT_PBKDF2_ROUNDS)?
+            .write_to_file(&output)?;
+    } else {
+        auditor
+            .write_to_file(&output)
+            .map_err(|err| anyhow::anyhow!("failed to save auditor keypair {output} {err}"))?;
+    }
+    log::info!("saved auditor keypair {output}");
+    println!("{}", auditor.pubkey());
+    Ok(())
+}
+
+/// Displays the ElGamal pubkey of an auditor keypair, encrypted keystores don't need the passphrase
+pub async fn show_pubkey(auditor_keypair: String) -> anyhow::Result<()> {
+    println!("{}", read_auditor_pubkey(&auditor_keypair)?);
+    Ok(())
+}
+
+/// Updates the auditor and auto approve setting of a wrapped mint with its confidential
+/// transfer authority
+#[allow(clippy::too_many_arguments)]
+pub async fn rotate(
+    rpc_url: String,
+    keypair: String,
+    authority: Option<String>,
+    unwrapped_mint: String,
+    auditor_keypair: Option<String>,
+    auditor_elgamal_pubkey: Option<String>,
+    remove_auditor: bool,
+    approve_policy: Option<String>,
+) -> anyhow::Result<()> {
+    let unwrapped_mint: Pubkey = unwrapped_mint.parse()?;
+    let rpc = RpcClient::new(rpc_url);
+    let key = Keypair::read_from_file(&keypair)
+        .map_err(|err| anyhow::anyhow!("failed to read keypair {keypair} {err}"))?;
+    let authority = match authority {
+        Some(authority) => Keypair::read_from_file(&authority)
+            .map_err(|err| anyhow::anyhow!("failed to read authority {authority} {err}"))?,
+        None => key.insecure_clone(),
+    };
+    let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint, &spl_token_2022::id());
+
+    let existing = WrappedMintConfig::from_mint(&rpc.get_account(&wrapped_mint).await?)?;
+    if existing.confidential_transfer_authority != Some(authority.pubkey()) {
+        anyhow::bail!(
+            "{} is not the confidential transfer authority of {wrapped_mint}",
+            authority.pubkey()
+        );
+    }
+
+    let auditor_elgamal_pubkey = match (auditor_keypair, auditor_elgamal_pubkey, remove_auditor) {
+        (None, None, false) => existing.auditor_elgamal_pubkey,
+        (None, None, true) => None,
+        (Some(auditor_keypair), None, false) => Some(read_auditor_pubkey(&auditor_keypair)?),
+        (None, Some(auditor_elgamal_pubkey), false) => Some(
+            auditor_elgamal_pubkey
+                .parse::<PodElGamalPubkey>()
+                .map_err(|err| anyhow::anyhow!("invalid auditor elgamal pubkey {err}"))?,
+        ),
+        _ => anyhow::bail!(
+            "only one of --auditor-keypair, --auditor-elgamal-pubkey and --remove-auditor can be set"
+        ),
+    };
+    let config = WrappedMintConfig {
+        auto_approve_new_accounts: approve_policy
+            .map(|policy| policy == "auto")
+            .unwrap_or(existing.auto_approve_new_accounts),
+        auditor_elgamal_pubkey,
+        ..existing
+    };
+    if config == existing {
+        log::info!("wrapped mint {wrapped_mint} already uses these settings");
+        return Ok(());
+    }
+    let ix = update_wrapped_mint_instruction(&wrapped_mint, &existing, &config)?;
+
+    let mut tx = Transaction::new_with_payer(&[ix], Some(&key.pubkey()));
+    let mut signers = vec![&key];
+    if authority.pubkey() != key.pubkey() {
+        signers.push(&authority);
+    }
+    tx.sign(&signers, rpc.get_latest_blockhash().await?);
+
+    log::info!("updating wrapped mint {wrapped_mint} from {existing:?} to {config:?}");
+    let sig = rpc.send_and_confirm_transaction(&tx).await?;
+    log::info!("sent tx {sig}");
+    Ok(())
+}
+
+/// Reads an auditor keypair from either a json keypair or an encrypted keystore, the passphrase
+/// of which is read from `passphrase_env` or stdin
+pub fn read_auditor_keypair(path: &str, passphrase_env: &str) -> anyhow::Result<ElGamalKeypair> {
+    if is_encrypted_keystore(path) {
+        let passphrase = read_passphrase(passphrase_env, false)?;
+        return EncryptedKeystore::read_from_file(path)?.decrypt(&passphrase);
+    }
+    ElGamalKeypair::read_from_file(path)
+        .map_err(|err| anyhow::anyhow!("failed to read auditor keypair {path} {err}"))
+}
+
+/// Reads the ElGamal pubkey of an auditor keypair without decrypting it
+pub fn read_auditor_pubkey(path: &str) -> anyhow::Result<PodElGamalPubkey> {
+    if is_encrypted_keystore(path) {
+        return EncryptedKeystore::read_from_file(path)?.pubkey();
+    }
+    let auditor = ElGamalKeypair::read_from_file(path)
+        .map_err(|err| anyhow::anyhow!("failed to read auditor keypair {path} {err}"))?;
+    Ok((*auditor.pubkey()).into())
+}
+
+pub fn read_passphrase(passphrase_env: &str, confirm: bool) -> anyhow::Result<String> {
+    let passphrase = match std::env::var(passphrase_env) {
+        Ok(passphrase) => passphrase,
+        Err(_) => {
+            let passphrase = rpassword::prompt_password("keystore passphrase: ")?;
+            if confirm && rpassword::prompt_password("confirm passphrase: ")? != passphrase {
+                anyhow::bail!("passphrases do not match");
+            }
+            passphrase
+        }
+    };
+    if passphrase.is_empty() {
+        anyhow::bail!("passphrase can't be empty");
+    }
+    Ok(passphrase)
+}
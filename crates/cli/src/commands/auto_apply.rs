@@ -0,0 +1,293 @@
+use api::types::ApiBalancesResponse;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::{EncodableKey, Signer},
+};
+use spl_token_wrap::get_wrapped_mint_address;
+use std::time::{Duration, Instant};
+
+use super::{apply::apply_pending_balance, balances::get_balances};
+
+/// Conditions under which the pending balance of an account is applied
+struct ApplyPolicy {
+    /// Apply once the pending balance credit counter reaches this value
+    counter_threshold: u64,
+    /// Apply once the pending balance reaches this amount in base units
+    amount_threshold: Option<u64>,
+    /// Apply any non zero pending balance once this much time passed since the last apply
+    interval: Option<Duration>,
+}
+
+impl ApplyPolicy {
+    /// Returns the reason the pending balance should be applied, or `None` if it should not be
+    fn apply_reason(
+        &self,
+        balances: &ApiBalancesResponse,
+        since_last_apply: Duration,
+    ) -> Option<String> {
+        if balances.pending_balance_credit_counter == 0 {
+            return None;
+        }
+        // apply one credit before the maximum, as the account stops accepting credits once it
+        // reaches it
+        let counter_threshold = self.counter_threshold.min(
+            balances
+                .maximum_pending_balance_credit_counter
+                .saturating_sub(1)
+                .max(1),
+        );
+        if balances.pending_balance_credit_counter >= counter_threshold {
+            return Some(format!(
+                "pending balance credit counter {} reached threshold {counter_threshold}",
+                balances.pending_balance_credit_counter
+            ));
+        }
+        if let Some(amount_threshold) = self.amount_threshold {
+            if balances.pending_balance_amount >= amount_threshold {
+                return Some(format!(
+                    "pending balance {} reached threshold {amount_threshold}",
+                    balances.pending_balance_amount
+                ));
+            }
+        }
+        if let Some(interval) = self.interval {
+            if since_last_apply >= interval {
+                return Some(format!("{}s elapsed since last apply", interval.as_secs()));
+            }
+        }
+        None
+    }
+}
+
+/// A confidential token account watched by the daemon
+struct WatchedAccount {
+    token_mint: Pubkey,
+    user_ata: Pubkey,
+    last_apply: Instant,
+    failures: u32,
+    retry_at: Instant,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn auto_apply(
+    api_url: String,
+    rpc_url: String,
+    keypair: String,
+    unwrapped_mints: Vec<String>,
+    counter_threshold: u64,
+    amount_threshold: Option<u64>,
+    apply_interval: Option<u64>,
+    poll_interval: u64,
+    max_backoff: u64,
+) -> anyhow::Result<()> {
+    let rpc = RpcClient::new(rpc_url);
+    let key = Keypair::read_from_file(keypair).unwrap();
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let policy = ApplyPolicy {
+        counter_threshold,
+        amount_threshold,
+        interval: apply_interval.map(Duration::from_secs),
+    };
+    let poll_interval = Duration::from_secs(poll_interval);
+    let max_backoff = Duration::from_secs(max_backoff);
+
+    let now = Instant::now();
+    let mut accounts = Vec::with_capacity(unwrapped_mints.len());
+    for unwrapped_mint in unwrapped_mints {
+        let unwrapped_mint: Pubkey = unwrapped_mint.parse()?;
+        let token_mint = get_wrapped_mint_address(&unwrapped_mint, &spl_token_2022::id());
+        let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+            &key.pubkey(),
+            &token_mint,
+            &spl_token_2022::id(),
+        );
+        log::info!("watching {user_ata} (wrapped mint {token_mint}) for pending balances");
+        accounts.push(WatchedAccount {
+            token_mint,
+            user_ata,
+            last_apply: now,
+            failures: 0,
+            retry_at: now,
+        });
+    }
+
+    loop {
+        for account in accounts.iter_mut() {
+            if Instant::now() < account.retry_at {
+                continue;
+            }
+
+            let result = async {
+                let balances = get_balances(&client, &api_url, &key, account.token_mint).await?;
+                log::debug!(
+                    "{} pending balance {} credit counter {}/{}",
+                    account.user_ata,
+                    balances.pending_balance_amount,
+                    balances.pending_balance_credit_counter,
+                    balances.maximum_pending_balance_credit_counter
+                );
+                let Some(reason) = policy.apply_reason(&balances, account.last_apply.elapsed())
+                else {
+                    return Ok(());
+                };
+                log::info!("applying pending balance of {}, {reason}", account.user_ata);
+                apply_pending_balance(&client, &rpc, &api_url, &key, account.token_mint).await?;
+                log::info!(
+                    "applied pending balance of {} ({} tokens)",
+                    account.user_ata,
+                    balances.pending_balance_amount
+                );
+                account.last_apply = Instant::now();
+                anyhow::Ok(())
+            }
+            .await;
+
+            match result {
+                Ok(()) => account.failures = 0,
+                Err(err) => {
+                    account.failures = account.failures.saturating_add(1);
+                    let backoff = poll_interval
+                        .saturating_mul(2u32.saturating_pow(account.failures))
+                        .min(max_backoff);
+                    account.retry_at = Instant::now() + backoff;
+                    log::error!(
+                        "failed to auto apply {} (attempt {}), retrying in {}s {err:#}",
+                        account.user_ata,
+                        account.failures,
+                        backoff.as_secs()
+                    );
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                log::info!("stopping auto apply");
+                return Ok(());
+            }
+            _ = tokio::time::sleep(poll_interval) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn balances(counter: u64, maximum_counter: u64, pending: u64) -> ApiBalancesResponse {
+        ApiBalancesResponse {
+            pending_balance: 0.0,
+            available_balance: 0.0,
+            non_confidential_balance: 0.0,
+            pending_balance_amount: pending,
+            available_balance_amount: 0,
+            non_confidential_balance_amount: 0,
+            decimals: 6,
+            pending_balance_credit_counter: counter,
+            maximum_pending_balance_credit_counter: maximum_counter,
+            allow_confidential_credits: true,
+            allow_non_confidential_credits: true,
+            approved: true,
+            elgamal_pubkey: String::new(),
+        }
+    }
+
+    fn policy(
+        counter_threshold: u64,
+        amount_threshold: Option<u64>,
+        interval: Option<u64>,
+    ) -> ApplyPolicy {
+        ApplyPolicy {
+            counter_threshold,
+            amount_threshold,
+            interval: interval.map(Duration::from_secs),
+        }
+    }
+
+    #[test]
+    fn test_apply_reason_empty_pending_balance() {
+        // nothing is applied without credits, even once every threshold is reached
+        let policy = policy(0, Some(0), Some(0));
+        assert!(policy
+            .apply_reason(&balances(0, 65536, 0), Duration::from_secs(60))
+            .is_none());
+    }
+
+    #[test]
+    fn test_apply_reason_counter_threshold() {
+        let policy = policy(10, None, None);
+        assert!(policy
+            .apply_reason(&balances(9, 65536, 100), Duration::ZERO)
+            .is_none());
+        assert!(policy
+            .apply_reason(&balances(10, 65536, 100), Duration::ZERO)
+            .unwrap()
+            .contains("credit counter 10 reached threshold 10"));
+        assert!(policy
+            .apply_reason(&balances(11, 65536, 100), Duration::ZERO)
+            .is_some());
+    }
+
+    #[test]
+    fn test_apply_reason_maximum_counter() {
+        // the threshold is capped one below the maximum counter of the account
+        let policy = policy(1_000, None, None);
+        assert!(policy
+            .apply_reason(&balances(6, 8, 100), Duration::ZERO)
+            .is_none());
+        assert!(policy
+            .apply_reason(&balances(7, 8, 100), Duration::ZERO)
+            .unwrap()
+            .contains("credit counter 7 reached threshold 7"));
+
+        // but never below a single credit
+        assert!(policy
+            .apply_reason(&balances(1, 1, 100), Duration::ZERO)
+            .unwrap()
+            .contains("credit counter 1 reached threshold 1"));
+    }
+
+    #[test]
+    fn test_apply_reason_amount_threshold() {
+        let policy = policy(10, Some(500), None);
+        assert!(policy
+            .apply_reason(&balances(1, 65536, 499), Duration::ZERO)
+            .is_none());
+        assert!(policy
+            .apply_reason(&balances(1, 65536, 500), Duration::ZERO)
+            .unwrap()
+            .contains("pending balance 500 reached threshold 500"));
+
+        // the counter threshold takes precedence
+        assert!(policy
+            .apply_reason(&balances(10, 65536, 500), Duration::ZERO)
+            .unwrap()
+            .contains("credit counter"));
+    }
+
+    #[test]
+    fn test_apply_reason_interval() {
+        let policy = policy(10, Some(500), Some(3600));
+        assert!(policy
+            .apply_reason(&balances(1, 65536, 100), Duration::from_secs(3599))
+            .is_none());
+        assert_eq!(
+            policy
+                .apply_reason(&balances(1, 65536, 100), Duration::from_secs(3600))
+                .unwrap(),
+            "3600s elapsed since last apply"
+        );
+
+        // without an interval the elapsed time is ignored
+        let policy = ApplyPolicy {
+            interval: None,
+            ..policy
+        };
+        assert!(policy
+            .apply_reason(&balances(1, 65536, 100), Duration::from_secs(u64::MAX))
+            .is_none());
+    }
+}
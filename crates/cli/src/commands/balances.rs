@@ -1,4 +1,4 @@
-use api::types::{ApiBalancesResponse, Balances};
+use api::types::{ApiBalancesResponse, ApiError, Balances};
 use common::key_generator::KeypairType;
 use solana_sdk::{
     pubkey::Pubkey,
@@ -16,24 +16,43 @@ pub async fn balances(
     let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint, &spl_token_2022::id());
     let key = Keypair::read_from_file(keypair).unwrap();
 
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let res = get_balances(&client, &api_url, &key, wrapped_mint).await?;
+
+    log::info!("{}", serde_json::to_string_pretty(&res).unwrap());
+    if !res.approved {
+        log::warn!(
+            "token account can't transact until approved by the confidential transfer authority of {wrapped_mint}"
+        );
+    }
+
+    Ok(())
+}
+
+/// Requests the balances of the user's ata of `token_mint` from the api
+pub async fn get_balances(
+    client: &reqwest::Client,
+    api_url: &str,
+    key: &Keypair,
+    token_mint: Pubkey,
+) -> anyhow::Result<ApiBalancesResponse> {
     let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
         &key.pubkey(),
-        &wrapped_mint,
+        &token_mint,
         &spl_token_2022::id(),
     );
     let elgamal_sig = key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata));
     let ae_sig = key.sign_message(&KeypairType::Ae.message_to_sign(user_ata));
 
-    let client = reqwest::ClientBuilder::new().build()?;
-
     let payload = Balances {
         authority: key.pubkey(),
-        token_mint: wrapped_mint,
+        token_mint,
         elgamal_signature: elgamal_sig,
         ae_signature: ae_sig,
     };
 
-    log::info!("{}", serde_json::to_string_pretty(&payload).unwrap());
+    log::debug!("{}", serde_json::to_string_pretty(&payload).unwrap());
 
     let req = client
         .post(format!("{api_url}/confidential-balances/balances"))
@@ -41,9 +60,9 @@ pub async fn balances(
         .json(&payload)
         .build()?;
     let res = client.execute(req).await?;
-    let res: ApiBalancesResponse = res.json().await?;
-
-    log::info!("{}", serde_json::to_string_pretty(&res).unwrap());
-
-    Ok(())
+    if !res.status().is_success() {
+        let err: ApiError = res.json().await?;
+        anyhow::bail!("failed to get balances {}", err.msg);
+    }
+    Ok(res.json().await?)
 }
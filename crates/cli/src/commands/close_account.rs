@@ -0,0 +1,88 @@
+use api::types::{ApiError, ApiTransactionResponse, CloseAccount};
+use common::key_generator::KeypairType;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::{EncodableKey, Signer},
+};
+use spl_token_wrap::get_wrapped_mint_address;
+
+/// Empties the confidential balance of the wrapped mint token account, closing it if requested
+pub async fn close_account(
+    api_url: String,
+    rpc_url: String,
+    keypair: String,
+    unwrapped_mint: String,
+    drain: bool,
+    close: bool,
+) -> anyhow::Result<()> {
+    let rpc = RpcClient::new(rpc_url);
+    let unwrapped_mint: Pubkey = unwrapped_mint.parse()?;
+    let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint, &spl_token_2022::id());
+    let key = Keypair::read_from_file(&keypair)
+        .map_err(|err| anyhow::anyhow!("failed to read keypair {keypair} {err}"))?;
+
+    let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &key.pubkey(),
+        &wrapped_mint,
+        &spl_token_2022::id(),
+    );
+    let elgamal_sig = key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata));
+    let ae_sig = key.sign_message(&KeypairType::Ae.message_to_sign(user_ata));
+
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let equality_proof_keypair = Keypair::new();
+    let range_proof_keypair = Keypair::new();
+
+    let payload = CloseAccount {
+        authority: key.pubkey(),
+        token_mint: wrapped_mint,
+        elgamal_signature: elgamal_sig,
+        ae_signature: ae_sig,
+        drain,
+        close,
+        equality_proof_keypair: Some(equality_proof_keypair.insecure_clone()),
+        range_proof_keypair: Some(range_proof_keypair.insecure_clone()),
+    };
+
+    let req = client
+        .post(format!("{api_url}/confidential-balances/close"))
+        .header("Content-Type", "application/json")
+        .json(&payload)
+        .build()?;
+    let res = client.execute(req).await?;
+    if !res.status().is_success() {
+        let err: ApiError = res.json().await?;
+        anyhow::bail!("failed to close {user_ata} {}", err.msg);
+    }
+    let res: ApiTransactionResponse = res.json().await?;
+
+    let txs = res.decode_transactions()?;
+    if txs.is_empty() {
+        log::info!("{user_ata} is already empty");
+        return Ok(());
+    }
+    for (idx, mut tx) in txs.into_iter().enumerate() {
+        // the proof context accounts are only created by the first withdraw transaction
+        let required =
+            &tx.message.account_keys[..tx.message.header.num_required_signatures as usize];
+        let signers = [&key, &equality_proof_keypair, &range_proof_keypair]
+            .into_iter()
+            .filter(|signer| required.contains(&signer.pubkey()))
+            .collect::<Vec<_>>();
+        tx.sign(&signers, rpc.get_latest_blockhash().await?);
+        log::info!("sending close account tx({idx})");
+        let sig = rpc.send_and_confirm_transaction(&tx).await?;
+        log::info!("sent close account tx({idx}) {sig}");
+    }
+
+    if close {
+        log::info!("closed {user_ata}");
+    } else {
+        log::info!("emptied {user_ata}");
+    }
+
+    Ok(())
+}
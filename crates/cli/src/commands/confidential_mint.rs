@@ -0,0 +1,265 @@
+use api::types::{
+    ApiConfidentialSupplyResponse, ApiError, ApiTransactionResponse, ConfidentialBurn,
+    ConfidentialMint, ConfidentialSupply, CreateConfidentialMint,
+};
+use common::key_generator::KeypairType;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::{EncodableKey, Signer},
+};
+use spl_token_2022::solana_zk_sdk::encryption::pod::elgamal::PodElGamalPubkey;
+
+/// Creates a natively issued confidential mint, keeping its supply confidential with the supply
+/// keys of the mint authority if `confidential_supply` is set
+#[allow(clippy::too_many_arguments)]
+pub async fn create(
+    api_url: String,
+    rpc_url: String,
+    keypair: String,
+    mint_keypair: Option<String>,
+    decimals: u8,
+    confidential_supply: bool,
+    approve_policy: String,
+    auditor_elgamal_pubkey: Option<String>,
+    confidential_transfer_authority: Option<String>,
+) -> anyhow::Result<()> {
+    let rpc = RpcClient::new(rpc_url);
+    let key = Keypair::read_from_file(&keypair)
+        .map_err(|err| anyhow::anyhow!("failed to read keypair {keypair} {err}"))?;
+    let mint = match mint_keypair {
+        Some(mint_keypair) => Keypair::read_from_file(&mint_keypair)
+            .map_err(|err| anyhow::anyhow!("failed to read keypair {mint_keypair} {err}"))?,
+        None => Keypair::new(),
+    };
+    let auditor_elgamal_pubkey = auditor_elgamal_pubkey
+        .map(|auditor_elgamal_pubkey| {
+            auditor_elgamal_pubkey
+                .parse::<PodElGamalPubkey>()
+                .map_err(|err| anyhow::anyhow!("invalid auditor elgamal pubkey {err}"))
+        })
+        .transpose()?;
+    let confidential_transfer_authority = confidential_transfer_authority
+        .map(|authority| authority.parse())
+        .transpose()?;
+
+    // the supply keys are tied to the mint authority
+    let (supply_elgamal_signature, supply_ae_signature) = if confidential_supply {
+        (
+            Some(key.sign_message(&KeypairType::SupplyElGamal.message_to_sign(mint.pubkey()))),
+            Some(key.sign_message(&KeypairType::SupplyAe.message_to_sign(mint.pubkey()))),
+        )
+    } else {
+        (None, None)
+    };
+
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let req = client
+        .post(format!(
+            "{api_url}/confidential-balances/create-confidential-mint"
+        ))
+        .header("Content-Type", "application/json")
+        .json(&CreateConfidentialMint {
+            authority: key.pubkey(),
+            mint: mint.pubkey(),
+            decimals,
+            auto_approve_new_accounts: approve_policy == "auto",
+            auditor_elgamal_pubkey,
+            confidential_transfer_authority,
+            supply_elgamal_signature,
+            supply_ae_signature,
+        })
+        .build()?;
+    let res = client.execute(req).await?;
+    if !res.status().is_success() {
+        let err: ApiError = res.json().await?;
+        anyhow::bail!("failed to create confidential mint {}", err.msg);
+    }
+    let res: ApiTransactionResponse = res.json().await?;
+
+    for mut tx in res.decode_transactions()? {
+        tx.sign(&vec![&key, &mint], rpc.get_latest_blockhash().await?);
+        let sig = rpc.send_and_confirm_transaction(&tx).await?;
+        log::info!("sent create confidential mint tx {sig}");
+    }
+    log::info!("created confidential mint {}", mint.pubkey());
+
+    Ok(())
+}
+
+/// Mints confidential tokens into the pending balance of the recipient's token account
+pub async fn mint(
+    api_url: String,
+    rpc_url: String,
+    keypair: String,
+    token_mint: String,
+    recipient: String,
+    amount: u64,
+) -> anyhow::Result<()> {
+    let rpc = RpcClient::new(rpc_url);
+    let token_mint: Pubkey = token_mint.parse()?;
+    let recipient: Pubkey = recipient.parse()?;
+    let key = Keypair::read_from_file(&keypair)
+        .map_err(|err| anyhow::anyhow!("failed to read keypair {keypair} {err}"))?;
+
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let equality_proof_keypair = Keypair::new();
+    let ciphertext_validity_proof_keypair = Keypair::new();
+    let range_proof_keypair = Keypair::new();
+
+    let req = client
+        .post(format!("{api_url}/confidential-balances/mint"))
+        .header("Content-Type", "application/json")
+        .json(&ConfidentialMint {
+            authority: key.pubkey(),
+            token_mint,
+            supply_elgamal_signature: key
+                .sign_message(&KeypairType::SupplyElGamal.message_to_sign(token_mint)),
+            supply_ae_signature: key
+                .sign_message(&KeypairType::SupplyAe.message_to_sign(token_mint)),
+            receiving_token_account: recipient,
+            equality_proof_keypair: equality_proof_keypair.insecure_clone(),
+            ciphertext_validity_proof_keypair: ciphertext_validity_proof_keypair.insecure_clone(),
+            range_proof_keypair: range_proof_keypair.insecure_clone(),
+            amount,
+        })
+        .build()?;
+    let res = client.execute(req).await?;
+    if !res.status().is_success() {
+        let err: ApiError = res.json().await?;
+        anyhow::bail!("failed to mint confidential tokens {}", err.msg);
+    }
+    let res: ApiTransactionResponse = res.json().await?;
+
+    send_transactions(
+        &rpc,
+        res,
+        &[
+            &key,
+            &equality_proof_keypair,
+            &ciphertext_validity_proof_keypair,
+            &range_proof_keypair,
+        ],
+        "mint",
+    )
+    .await
+}
+
+/// Burns confidential tokens from the available balance of the wallet's token account
+pub async fn burn(
+    api_url: String,
+    rpc_url: String,
+    keypair: String,
+    token_mint: String,
+    amount: u64,
+) -> anyhow::Result<()> {
+    let rpc = RpcClient::new(rpc_url);
+    let token_mint: Pubkey = token_mint.parse()?;
+    let key = Keypair::read_from_file(&keypair)
+        .map_err(|err| anyhow::anyhow!("failed to read keypair {keypair} {err}"))?;
+
+    let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &key.pubkey(),
+        &token_mint,
+        &spl_token_2022::id(),
+    );
+
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let equality_proof_keypair = Keypair::new();
+    let ciphertext_validity_proof_keypair = Keypair::new();
+    let range_proof_keypair = Keypair::new();
+
+    let req = client
+        .post(format!("{api_url}/confidential-balances/burn"))
+        .header("Content-Type", "application/json")
+        .json(&ConfidentialBurn {
+            authority: key.pubkey(),
+            token_mint,
+            elgamal_signature: key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata)),
+            ae_signature: key.sign_message(&KeypairType::Ae.message_to_sign(user_ata)),
+            equality_proof_keypair: equality_proof_keypair.insecure_clone(),
+            ciphertext_validity_proof_keypair: ciphertext_validity_proof_keypair.insecure_clone(),
+            range_proof_keypair: range_proof_keypair.insecure_clone(),
+            amount,
+        })
+        .build()?;
+    let res = client.execute(req).await?;
+    if !res.status().is_success() {
+        let err: ApiError = res.json().await?;
+        anyhow::bail!("failed to burn confidential tokens {}", err.msg);
+    }
+    let res: ApiTransactionResponse = res.json().await?;
+
+    send_transactions(
+        &rpc,
+        res,
+        &[
+            &key,
+            &equality_proof_keypair,
+            &ciphertext_validity_proof_keypair,
+            &range_proof_keypair,
+        ],
+        "burn",
+    )
+    .await
+}
+
+/// Displays the decrypted confidential supply of a mint
+pub async fn supply(api_url: String, keypair: String, token_mint: String) -> anyhow::Result<()> {
+    let token_mint: Pubkey = token_mint.parse()?;
+    let key = Keypair::read_from_file(&keypair)
+        .map_err(|err| anyhow::anyhow!("failed to read keypair {keypair} {err}"))?;
+
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let req = client
+        .post(format!("{api_url}/confidential-balances/supply"))
+        .header("Content-Type", "application/json")
+        .json(&ConfidentialSupply {
+            authority: key.pubkey(),
+            token_mint,
+            supply_elgamal_signature: key
+                .sign_message(&KeypairType::SupplyElGamal.message_to_sign(token_mint)),
+            supply_ae_signature: key
+                .sign_message(&KeypairType::SupplyAe.message_to_sign(token_mint)),
+        })
+        .build()?;
+    let res = client.execute(req).await?;
+    if !res.status().is_success() {
+        let err: ApiError = res.json().await?;
+        anyhow::bail!("failed to decrypt confidential supply {}", err.msg);
+    }
+    let res: ApiConfidentialSupplyResponse = res.json().await?;
+
+    log::info!("{}", serde_json::to_string_pretty(&res).unwrap());
+
+    Ok(())
+}
+
+/// Signs each transaction with the signers it requires, and sends them in order
+async fn send_transactions(
+    rpc: &RpcClient,
+    res: ApiTransactionResponse,
+    signers: &[&Keypair],
+    name: &str,
+) -> anyhow::Result<()> {
+    for (idx, mut tx) in res.decode_transactions()?.into_iter().enumerate() {
+        // the proof keypairs only sign the transaction creating the proof accounts
+        let tx_signers = signers
+            .iter()
+            .copied()
+            .filter(|signer| {
+                tx.message.account_keys[..tx.message.header.num_required_signatures as usize]
+                    .contains(&signer.pubkey())
+            })
+            .collect::<Vec<_>>();
+        tx.sign(&tx_signers, rpc.get_latest_blockhash().await?);
+        let sig = rpc.send_and_confirm_transaction(&tx).await?;
+        log::info!("sent {name} tx({idx}) {sig}");
+    }
+    Ok(())
+}
@@ -1,22 +1,35 @@
+use common::keystore::{EncryptedKeystore, DEFAULT_PBKDF2_ROUNDS};
+use common::wrapped_mint::{
+    backpointer_len, create_wrapped_mint_instructions, escrow_address, wrapped_mint_len,
+    WrappedMintAccounts, WrappedMintConfig,
+};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
     pubkey::Pubkey,
     signature::Keypair,
     signer::{EncodableKey, Signer},
-    system_instruction,
     transaction::Transaction,
 };
-use spl_token_2022::extension::ExtensionType;
-use spl_token_wrap::{
-    get_wrapped_mint_address, get_wrapped_mint_authority, get_wrapped_mint_backpointer_address,
-    state::Backpointer,
+use spl_token_2022::solana_zk_sdk::encryption::{
+    elgamal::ElGamalKeypair, pod::elgamal::PodElGamalPubkey,
 };
+use spl_token_wrap::{get_wrapped_mint_address, get_wrapped_mint_backpointer_address};
+use std::path::Path;
+
+use super::auditor::{read_auditor_pubkey, read_passphrase};
 
+#[allow(clippy::too_many_arguments)]
 pub async fn create_token_mint(
     rpc_url: String,
     keypair: String,
     unwrapped_mint: String,
     unwrapped_mint_program: String,
+    approve_policy: String,
+    auditor_elgamal_pubkey: Option<String>,
+    auditor_keypair: Option<String>,
+    generate_auditor_keypair: bool,
+    passphrase_env: String,
+    confidential_transfer_authority: Option<String>,
 ) -> anyhow::Result<()> {
     let unwrapped_mint: Pubkey = unwrapped_mint.parse().unwrap();
     let unwrapped_mint_program: Pubkey = unwrapped_mint_program.parse().unwrap();
@@ -24,55 +37,91 @@ pub async fn create_token_mint(
     let key = Keypair::read_from_file(keypair).unwrap();
     let wrapped_mint_address = get_wrapped_mint_address(&unwrapped_mint, &spl_token_2022::id());
 
-    let backpoint_rent = rpc
-        .get_minimum_balance_for_rent_exemption(std::mem::size_of::<Backpointer>())
-        .await
-        .unwrap();
-    let mint_size = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
-        ExtensionType::ConfidentialTransferMint,
-    ])
-    .unwrap();
-    let mint_rent = rpc
-        .get_minimum_balance_for_rent_exemption(mint_size)
-        .await
-        .unwrap();
+    let auditor_elgamal_pubkey = match (auditor_elgamal_pubkey, auditor_keypair) {
+        (Some(_), Some(_)) => {
+            anyhow::bail!("only one of --auditor-elgamal-pubkey and --auditor-keypair can be set")
+        }
+        (Some(auditor_elgamal_pubkey), None) => Some(
+            auditor_elgamal_pubkey
+                .parse::<PodElGamalPubkey>()
+                .map_err(|err| anyhow::anyhow!("invalid auditor elgamal pubkey {err}"))?,
+        ),
+        (None, Some(auditor_keypair)) => Some(load_auditor_pubkey(
+            &auditor_keypair,
+            generate_auditor_keypair,
+            &passphrase_env,
+        )?),
+        (None, None) if generate_auditor_keypair => {
+            anyhow::bail!("--generate-auditor-keypair requires --auditor-keypair")
+        }
+        (None, None) => None,
+    };
+    let config = WrappedMintConfig {
+        auto_approve_new_accounts: approve_policy == "auto",
+        auditor_elgamal_pubkey,
+        confidential_transfer_authority: confidential_transfer_authority
+            .map(|authority| authority.parse())
+            .transpose()?,
+    };
 
-    let ix = spl_token_wrap::instruction::create_confidential_mint(
-        &spl_token_wrap::ID,
-        &wrapped_mint_address,
-        &get_wrapped_mint_backpointer_address(&wrapped_mint_address),
-        &unwrapped_mint,
-        &spl_token_2022::id(),
-        true,
-        true,
-        [0u8; 32],
-        [0u8; 32],
-    );
-    let mut ixs = vec![];
-    ixs.append(&mut system_instruction::transfer_many(
+    // check which accounts already exist so the command can be safely repeated
+    let accounts = rpc
+        .get_multiple_accounts(&[
+            wrapped_mint_address,
+            get_wrapped_mint_backpointer_address(&wrapped_mint_address),
+            escrow_address(&unwrapped_mint, &unwrapped_mint_program),
+        ])
+        .await?;
+    let [wrapped_mint, backpointer, escrow] = <[_; 3]>::try_from(accounts)
+        .map_err(|_| anyhow::anyhow!("failed to query wrapped mint accounts"))?;
+    let ixs = create_wrapped_mint_instructions(
         &key.pubkey(),
-        &[
-            (
-                get_wrapped_mint_backpointer_address(&wrapped_mint_address),
-                backpoint_rent,
-            ),
-            (wrapped_mint_address, mint_rent),
-        ],
-    ));
-    ixs.push(
-        spl_associated_token_account::instruction::create_associated_token_account(
-            &key.pubkey(),
-            &get_wrapped_mint_authority(&wrapped_mint_address),
-            &unwrapped_mint,
-            &unwrapped_mint_program,
-        ),
-    );
-    ixs.push(ix);
+        &unwrapped_mint,
+        &unwrapped_mint_program,
+        &config,
+        &WrappedMintAccounts {
+            wrapped_mint,
+            backpointer,
+            escrow,
+            mint_rent: rpc
+                .get_minimum_balance_for_rent_exemption(wrapped_mint_len()?)
+                .await?,
+            backpointer_rent: rpc
+                .get_minimum_balance_for_rent_exemption(backpointer_len())
+                .await?,
+        },
+    )?;
+    if ixs.is_empty() {
+        log::info!("confidential wrapped mint {wrapped_mint_address} already exists");
+        return Ok(());
+    }
+
     let mut tx = Transaction::new_with_payer(&ixs, Some(&key.pubkey()));
     tx.sign(&vec![key], rpc.get_latest_blockhash().await.unwrap());
 
-    log::info!("creating confidential wrapped mint");
+    log::info!("creating confidential wrapped mint {wrapped_mint_address}");
     let sig = rpc.send_and_confirm_transaction(&tx).await.unwrap();
     log::info!("sent tx {sig}");
     Ok(())
 }
+
+/// Reads the auditor pubkey, generating and saving a new encrypted keystore if requested and missing
+fn load_auditor_pubkey(
+    path: &str,
+    generate: bool,
+    passphrase_env: &str,
+) -> anyhow::Result<PodElGamalPubkey> {
+    if generate && !Path::new(path).exists() {
+        let auditor = ElGamalKeypair::new_rand();
+        let passphrase = read_passphrase(passphrase_env, true)?;
+        EncryptedKeystore::encrypt(&auditor, &passphrase, DEFAULT_PBKDF2_ROUNDS)?
+            .write_to_file(path)
+            .map_err(|err| anyhow::anyhow!("failed to save auditor keystore {path} {err}"))?;
+        log::info!(
+            "generated auditor keystore {path} with pubkey {}",
+            auditor.pubkey()
+        );
+        return Ok((*auditor.pubkey()).into());
+    }
+    read_auditor_pubkey(path)
+}
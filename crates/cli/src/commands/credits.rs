@@ -0,0 +1,68 @@
+use api::types::{ApiError, ApiTransactionResponse, UpdateCredits};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::{EncodableKey, Signer},
+};
+use spl_token_wrap::get_wrapped_mint_address;
+
+/// Enables or disables incoming credits of the wrapped mint token account
+pub async fn update_credits(
+    api_url: String,
+    rpc_url: String,
+    keypair: String,
+    unwrapped_mint: String,
+    confidential: Option<bool>,
+    non_confidential: Option<bool>,
+) -> anyhow::Result<()> {
+    let mut routes = vec![];
+    match confidential {
+        Some(true) => routes.push("enable-confidential-credits"),
+        Some(false) => routes.push("disable-confidential-credits"),
+        None => {}
+    }
+    match non_confidential {
+        Some(true) => routes.push("enable-non-confidential-credits"),
+        Some(false) => routes.push("disable-non-confidential-credits"),
+        None => {}
+    }
+    if routes.is_empty() {
+        anyhow::bail!("at least one of --confidential and --non-confidential must be set");
+    }
+
+    let rpc = RpcClient::new(rpc_url);
+    let unwrapped_mint: Pubkey = unwrapped_mint.parse()?;
+    let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint, &spl_token_2022::id());
+    let key = Keypair::read_from_file(&keypair)
+        .map_err(|err| anyhow::anyhow!("failed to read keypair {keypair} {err}"))?;
+
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let payload = UpdateCredits {
+        authority: key.pubkey(),
+        token_mint: wrapped_mint,
+    };
+
+    for route in routes {
+        let req = client
+            .post(format!("{api_url}/confidential-balances/{route}"))
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .build()?;
+        let res = client.execute(req).await?;
+        if !res.status().is_success() {
+            let err: ApiError = res.json().await?;
+            anyhow::bail!("failed to {route} {}", err.msg);
+        }
+        let res: ApiTransactionResponse = res.json().await?;
+
+        for mut tx in res.decode_transactions()? {
+            tx.sign(&vec![&key], rpc.get_latest_blockhash().await?);
+            let sig = rpc.send_and_confirm_transaction(&tx).await?;
+            log::info!("sent {route} tx {sig}");
+        }
+    }
+
+    Ok(())
+}
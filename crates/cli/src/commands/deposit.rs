@@ -12,6 +12,7 @@ pub async fn deposit(
     keypair: String,
     unwrapped_mint: String,
     amount: u64,
+    memo: Option<String>,
 ) -> anyhow::Result<()> {
     let rpc = RpcClient::new(rpc_url);
     let unwrapped_mint: Pubkey = unwrapped_mint.parse().unwrap();
@@ -23,6 +24,7 @@ pub async fn deposit(
         authority: key.pubkey(),
         token_mint: wrapped_mint,
         amount,
+        memo,
     };
 
     log::info!("{}", serde_json::to_string_pretty(&payload).unwrap());
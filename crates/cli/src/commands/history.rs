@@ -0,0 +1,116 @@
+use api::types::{ApiHistoryEntry, ApiHistoryResponse, History};
+use common::key_generator::KeypairType;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::{EncodableKey, Signer},
+};
+use spl_token_2022::amount_to_ui_amount_string_trimmed;
+use spl_token_wrap::get_wrapped_mint_address;
+
+const CSV_HEADER: &str = "signature,slot,block_time,kind,amount,counterparty,pending_balance,available_balance,non_confidential_balance";
+
+#[allow(clippy::too_many_arguments)]
+pub async fn history(
+    api_url: String,
+    keypair: String,
+    unwrapped_mint: String,
+    format: String,
+    limit: Option<usize>,
+    before: Option<String>,
+    all: bool,
+) -> anyhow::Result<()> {
+    let unwrapped_mint: Pubkey = unwrapped_mint.parse().unwrap();
+    let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint, &spl_token_2022::id());
+    let key = Keypair::read_from_file(keypair).unwrap();
+    let mut before: Option<Signature> = before.map(|before| before.parse()).transpose()?;
+
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let mut history = get_history(&client, &api_url, &key, wrapped_mint, before, limit).await?;
+    before = history.before;
+    while all && before.is_some() {
+        let mut page = get_history(&client, &api_url, &key, wrapped_mint, before, limit).await?;
+        history.entries.append(&mut page.entries);
+        before = page.before;
+    }
+    history.before = before;
+
+    // printed to stdout rather than logged so the output can be redirected to a file
+    match format.as_str() {
+        "csv" => {
+            println!("{CSV_HEADER}");
+            for entry in &history.entries {
+                println!("{}", csv_row(entry, history.decimals));
+            }
+        }
+        _ => println!("{}", serde_json::to_string_pretty(&history).unwrap()),
+    }
+
+    Ok(())
+}
+
+/// Requests a page of the history of the user's ata of `token_mint` from the api
+async fn get_history(
+    client: &reqwest::Client,
+    api_url: &str,
+    key: &Keypair,
+    token_mint: Pubkey,
+    before: Option<Signature>,
+    limit: Option<usize>,
+) -> anyhow::Result<ApiHistoryResponse> {
+    let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &key.pubkey(),
+        &token_mint,
+        &spl_token_2022::id(),
+    );
+    let elgamal_sig = key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata));
+    let ae_sig = key.sign_message(&KeypairType::Ae.message_to_sign(user_ata));
+
+    let payload = History {
+        authority: key.pubkey(),
+        token_mint,
+        elgamal_signature: elgamal_sig,
+        ae_signature: ae_sig,
+        before,
+        limit,
+    };
+
+    let req = client
+        .post(format!("{api_url}/confidential-balances/history"))
+        .header("Content-Type", "application/json")
+        .json(&payload)
+        .build()?;
+    let res = client.execute(req).await?;
+    if !res.status().is_success() {
+        anyhow::bail!("failed to get history {}", res.text().await?);
+    }
+    Ok(res.json().await?)
+}
+
+/// Formats an entry as a csv row, with amounts in ui units and unknown values left empty
+fn csv_row(entry: &ApiHistoryEntry, decimals: u8) -> String {
+    let amount = |amount: Option<u64>| {
+        amount
+            .map(|amount| amount_to_ui_amount_string_trimmed(amount, decimals))
+            .unwrap_or_default()
+    };
+    [
+        entry.signature.to_string(),
+        entry.slot.to_string(),
+        entry
+            .block_time
+            .map(|block_time| block_time.to_string())
+            .unwrap_or_default(),
+        entry.kind.as_str().to_string(),
+        amount(entry.amount),
+        entry
+            .counterparty
+            .map(|counterparty| counterparty.to_string())
+            .unwrap_or_default(),
+        amount(entry.pending_balance_amount),
+        amount(entry.available_balance_amount),
+        amount(entry.non_confidential_balance_amount),
+    ]
+    .join(",")
+}
@@ -0,0 +1,26 @@
+use api::indexer::{Indexer, IndexerStore};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use spl_token_wrap::get_wrapped_mint_address;
+use std::{sync::Arc, time::Duration};
+
+pub async fn index(
+    rpc_url: String,
+    unwrapped_mint: String,
+    database: String,
+    poll_interval: u64,
+) -> anyhow::Result<()> {
+    let unwrapped_mint: Pubkey = unwrapped_mint.parse()?;
+    let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint, &spl_token_2022::id());
+
+    log::info!("opening indexer database {database}");
+    let store = Arc::new(IndexerStore::open(database, wrapped_mint)?);
+
+    Indexer::new(
+        Arc::new(RpcClient::new(rpc_url)),
+        store,
+        Duration::from_secs(poll_interval),
+    )
+    .run()
+    .await
+}
@@ -12,6 +12,7 @@ pub async fn initialize(
     rpc_url: String,
     keypair: String,
     unwrapped_mint: String,
+    maximum_pending_balance_credit_counter: Option<u64>,
 ) -> anyhow::Result<()> {
     let rpc = RpcClient::new(rpc_url);
     let unwrapped_mint: Pubkey = unwrapped_mint.parse().unwrap();
@@ -33,6 +34,7 @@ pub async fn initialize(
         token_mint: wrapped_mint,
         elgamal_signature: elgamal_sig,
         ae_signature: ae_sig,
+        maximum_pending_balance_credit_counter,
     };
 
     log::info!("{}", serde_json::to_string_pretty(&payload).unwrap());
@@ -0,0 +1,20 @@
+use api::types::{ApiError, ApiWrappedMintsResponse};
+
+/// Displays every wrapped mint created by the token wrap program
+pub async fn list_wrapped_mints(api_url: String) -> anyhow::Result<()> {
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let req = client
+        .get(format!("{api_url}/private-wrapper/wrapped-mints"))
+        .build()?;
+    let res = client.execute(req).await?;
+    if !res.status().is_success() {
+        let err: ApiError = res.json().await?;
+        anyhow::bail!("failed to list wrapped mints {}", err.msg);
+    }
+    let res: ApiWrappedMintsResponse = res.json().await?;
+
+    log::info!("{}", serde_json::to_string_pretty(&res).unwrap());
+
+    Ok(())
+}
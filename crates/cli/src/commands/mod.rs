@@ -1,12 +1,28 @@
 pub mod api;
 pub mod apply;
+pub mod approvals;
+pub mod audit_report;
+pub mod auditor;
+pub mod auto_apply;
 pub mod balances;
+pub mod close_account;
+pub mod confidential_mint;
 pub mod create_confidential_wrapped_mint;
+pub mod credits;
 pub mod deposit;
+pub mod history;
+pub mod index;
 pub mod initialize;
+pub mod list_wrapped_mints;
+pub mod portfolio;
+pub mod shield;
 pub mod transfer;
+pub mod unshield;
 pub mod unwrap;
+pub mod verify_backing;
+pub mod watch;
 pub mod withdraw;
+pub mod withheld_fees;
 pub mod wrap;
 
 use clap::{Parser, Subcommand};
@@ -37,6 +53,23 @@ pub enum Commands {
             default_value = "https://api.devnet.solana.com/"
         )]
         rpc_url: String,
+        #[arg(
+            long,
+            help = "path to the precomputed discrete log table, built on first start if missing",
+            default_value = "discrete_log_table.bin"
+        )]
+        discrete_log_table: String,
+        #[arg(
+            long,
+            help = "size of the discrete log table as a power of two (16-24)",
+            default_value = "20"
+        )]
+        baby_step_bits: u32,
+        #[arg(
+            long,
+            help = "path to a database populated by the index command, enables the /indexer endpoints"
+        )]
+        indexer_database: Option<String>,
     },
     #[command(
         about = "initialize a wrapped mint with the spl token wrap program that supports confidential transfers"
@@ -62,6 +95,36 @@ pub enum Commands {
             default_value = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
         )]
         unwrapped_mint_program: String,
+        #[arg(
+            long,
+            help = "whether token accounts are approved automatically, or by the confidential transfer authority",
+            default_value = "auto",
+            value_parser = ["auto", "manual"]
+        )]
+        approve_policy: String,
+        #[arg(long, help = "base64 encoded ElGamal pubkey of the auditor")]
+        auditor_elgamal_pubkey: Option<String>,
+        #[arg(
+            long,
+            help = "path to the json ElGamal keypair or encrypted keystore of the auditor"
+        )]
+        auditor_keypair: Option<String>,
+        #[arg(
+            long,
+            help = "generate and save the auditor keypair as an encrypted keystore if --auditor-keypair does not exist"
+        )]
+        generate_auditor_keypair: bool,
+        #[arg(
+            long,
+            help = "environment variable holding the passphrase of the generated keystore, prompted for if unset",
+            default_value = "AUDITOR_KEYSTORE_PASSPHRASE"
+        )]
+        passphrase_env: String,
+        #[arg(
+            long,
+            help = "authority which can approve token accounts and update the confidential transfer settings"
+        )]
+        confidential_transfer_authority: Option<String>,
     },
     #[command(
         about = "wrap tokens with the spl token wrap program, the wrapped tokens will support confidential transfers"
@@ -89,13 +152,76 @@ pub enum Commands {
         unwrapped_mint: String,
         #[arg(
             long,
-            help = "token program address for the unwrapped mint",
-            default_value = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+            help = "token program address for the unwrapped mint, looked up from the mint account if omitted"
         )]
-        unwrapped_mint_program: String,
+        unwrapped_mint_program: Option<String>,
         #[arg(long, help = "amount of tokens to wrap in lamports")]
         amount: u64,
     },
+    #[command(
+        about = "wrap tokens and move them into the confidential balance, initializing the token account if needed"
+    )]
+    Shield {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(long, help = "path to a json keypair")]
+        keypair: String,
+        #[arg(
+            long,
+            help = "unwrapped token mint",
+            default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
+        )]
+        unwrapped_mint: String,
+        #[arg(
+            long,
+            help = "token program address for the unwrapped mint, looked up from the mint account if omitted"
+        )]
+        unwrapped_mint_program: Option<String>,
+        #[arg(long, help = "amount of tokens to shield in lamports")]
+        amount: u64,
+    },
+    #[command(
+        about = "withdraw tokens from the confidential balance and unwrap them, applying the pending balance if needed"
+    )]
+    Unshield {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(long, help = "path to a json keypair")]
+        keypair: String,
+        #[arg(
+            long,
+            help = "unwrapped token mint",
+            default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
+        )]
+        unwrapped_mint: String,
+        #[arg(
+            long,
+            help = "token program address for the unwrapped mint, looked up from the mint account if omitted"
+        )]
+        unwrapped_mint_program: Option<String>,
+        #[arg(long, help = "amount of tokens to unshield in lamports")]
+        amount: u64,
+    },
     #[command(about = "initialize a confidential transfer account")]
     Initialize {
         #[arg(
@@ -118,6 +244,11 @@ pub enum Commands {
             default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
         )]
         unwrapped_mint: String,
+        #[arg(
+            long,
+            help = "number of incoming transfers after which the pending balance must be applied, defaults to 65536"
+        )]
+        maximum_pending_balance_credit_counter: Option<u64>,
     },
     #[command(about = "deposit tokens from the non confidential balance into pending balance")]
     Deposit {
@@ -143,6 +274,8 @@ pub enum Commands {
         unwrapped_mint: String,
         #[arg(long, help = "amount of tokens to deposit in lamports")]
         amount: u64,
+        #[arg(long, help = "optional memo recorded with the deposit")]
+        memo: Option<String>,
     },
     #[command(about = "apply tokens from the pending balance into confidential available balance")]
     Apply {
@@ -167,15 +300,26 @@ pub enum Commands {
         )]
         unwrapped_mint: String,
     },
-    #[command(about = "display confidential and non confidential balances")]
-    Balances {
+    #[command(
+        about = "approve confidential token accounts as the confidential transfer authority of the wrapped mint"
+    )]
+    ApproveAccount {
         #[arg(
             long,
             help = "api endpoint for the private wrapper",
             default_value = "http://127.0.0.1:1337"
         )]
         api_url: String,
-        #[arg(long, help = "path to a json keypair")]
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(
+            long,
+            help = "path to the json keypair of the confidential transfer authority"
+        )]
         keypair: String,
         #[arg(
             long,
@@ -183,9 +327,59 @@ pub enum Commands {
             default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
         )]
         unwrapped_mint: String,
+        #[arg(
+            long,
+            help = "token account to approve, every pending account is approved if neither this nor --owner is set"
+        )]
+        token_account: Option<String>,
+        #[arg(long, help = "wallet whose associated token account is approved")]
+        owner: Option<String>,
     },
-    #[command(about = "confidentially transfers tokens")]
-    Transfer {
+    #[command(about = "list every wrapped mint created by the token wrap program")]
+    ListWrappedMints {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+    },
+    #[command(
+        about = "verify the wrapped supply of a mint is backed by its escrow, exiting with an error on mismatch"
+    )]
+    VerifyBacking {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(
+            long,
+            help = "unwrapped or wrapped token mint",
+            default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
+        )]
+        token_mint: String,
+    },
+    #[command(about = "list the confidential token accounts of a wrapped mint awaiting approval")]
+    PendingApprovals {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(
+            long,
+            help = "unwrapped token mint",
+            default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
+        )]
+        unwrapped_mint: String,
+    },
+    #[command(
+        about = "daemon which applies pending balances once a threshold is reached or on a schedule"
+    )]
+    AutoApply {
         #[arg(
             long,
             help = "api endpoint for the private wrapper",
@@ -200,48 +394,206 @@ pub enum Commands {
         rpc_url: String,
         #[arg(long, help = "path to a json keypair")]
         keypair: String,
+        #[arg(
+            long,
+            help = "unwrapped token mint, can be provided multiple times to watch several accounts",
+            default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
+        )]
+        unwrapped_mint: Vec<String>,
+        #[arg(
+            long,
+            help = "apply once the pending balance credit counter reaches this value",
+            default_value = "50000"
+        )]
+        counter_threshold: u64,
+        #[arg(
+            long,
+            help = "apply once the pending balance reaches this amount in lamports"
+        )]
+        amount_threshold: Option<u64>,
+        #[arg(
+            long,
+            help = "apply any pending balance once this many seconds passed since the last apply"
+        )]
+        apply_interval: Option<u64>,
+        #[arg(
+            long,
+            help = "number of seconds between balance checks",
+            default_value = "30"
+        )]
+        poll_interval: u64,
+        #[arg(
+            long,
+            help = "maximum number of seconds to back off after failures",
+            default_value = "600"
+        )]
+        max_backoff: u64,
+    },
+    #[command(about = "display confidential and non confidential balances")]
+    Balances {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(long, help = "path to a json keypair")]
+        keypair: String,
         #[arg(
             long,
             help = "unwrapped token mint",
             default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
         )]
         unwrapped_mint: String,
+    },
+    #[command(
+        about = "display the balances of every confidential token account owned by a wallet"
+    )]
+    Portfolio {
         #[arg(
             long,
-            help = "public key of user to transfer funds to",
-            default_value = "BYuf1dG4YecRxCzkykK5tgBnNJo2SVdbedAzuFXgWy9y"
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
         )]
-        recipient: String,
-        #[arg(long, help = "amount of tokens to transfer in lamports")]
-        amount: u64,
+        api_url: String,
+        #[arg(long, help = "path to a json keypair")]
+        keypair: String,
     },
-    #[command(about = "withdraw tokens from the confidential balance to non confidential balance")]
-    Withdraw {
+    #[command(about = "display the decrypted confidential transaction history")]
+    History {
         #[arg(
             long,
             help = "api endpoint for the private wrapper",
             default_value = "http://127.0.0.1:1337"
         )]
         api_url: String,
+        #[arg(long, help = "path to a json keypair")]
+        keypair: String,
+        #[arg(
+            long,
+            help = "unwrapped token mint",
+            default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
+        )]
+        unwrapped_mint: String,
+        #[arg(
+            long,
+            help = "output format",
+            default_value = "json",
+            value_parser = ["json", "csv"]
+        )]
+        format: String,
+        #[arg(long, help = "maximum number of transactions to display")]
+        limit: Option<usize>,
+        #[arg(long, help = "only display transactions older than this signature")]
+        before: Option<String>,
+        #[arg(long, help = "page through and display the entire history")]
+        all: bool,
+    },
+    #[command(about = "decrypt the confidential transfers of a wrapped mint as its auditor")]
+    AuditReport {
         #[arg(
             long,
             help = "solana rpc url",
             default_value = "https://api.devnet.solana.com/"
         )]
         rpc_url: String,
-        #[arg(long, help = "path to a json keypair")]
-        keypair: String,
         #[arg(
             long,
             help = "unwrapped token mint",
             default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
         )]
         unwrapped_mint: String,
-        #[arg(long, help = "amount of tokens to withdraw in lamports")]
-        amount: u64,
+        #[arg(
+            long,
+            help = "path to the json ElGamal keypair or encrypted keystore of the mint auditor"
+        )]
+        auditor_keypair: String,
+        #[arg(
+            long,
+            help = "environment variable holding the keystore passphrase, prompted for if unset",
+            default_value = "AUDITOR_KEYSTORE_PASSPHRASE"
+        )]
+        passphrase_env: String,
+        #[arg(
+            long,
+            help = "path to a database populated by the index command, indexes into memory if omitted"
+        )]
+        indexer_database: Option<String>,
+        #[arg(long, help = "only audit transactions at or after this slot")]
+        min_slot: Option<u64>,
+        #[arg(long, help = "only audit transactions at or before this slot")]
+        max_slot: Option<u64>,
+        #[arg(long, help = "only audit transactions at or after this unix timestamp")]
+        start_time: Option<i64>,
+        #[arg(
+            long,
+            help = "only audit transactions at or before this unix timestamp"
+        )]
+        end_time: Option<i64>,
+        #[arg(
+            long,
+            help = "output format",
+            default_value = "json",
+            value_parser = ["json", "csv"]
+        )]
+        format: String,
+        #[arg(
+            long,
+            help = "path to the precomputed discrete log table, built on first start if missing",
+            default_value = "discrete_log_table.bin"
+        )]
+        discrete_log_table: String,
+        #[arg(
+            long,
+            help = "size of the discrete log table as a power of two (16-24)",
+            default_value = "20"
+        )]
+        baby_step_bits: u32,
     },
-    #[command(about = "unwrap tokens with the spl token wrap program")]
-    UnwrapTokens {
+    #[command(about = "manage the ElGamal keypair of a wrapped mint auditor")]
+    Auditor {
+        #[command(subcommand)]
+        command: AuditorCommands,
+    },
+    #[command(about = "harvest and withdraw the withheld confidential transfer fees of a mint")]
+    WithheldFees {
+        #[command(subcommand)]
+        command: WithheldFeesCommands,
+    },
+    #[command(about = "create natively issued confidential mints and mint or burn their tokens")]
+    ConfidentialMint {
+        #[command(subcommand)]
+        command: ConfidentialMintCommands,
+    },
+    #[command(about = "index the confidential transfer and wrap instructions of a wrapped mint")]
+    Index {
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(
+            long,
+            help = "unwrapped token mint",
+            default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
+        )]
+        unwrapped_mint: String,
+        #[arg(
+            long,
+            help = "path to the sqlite database, created if missing",
+            default_value = "indexer.db"
+        )]
+        database: String,
+        #[arg(
+            long,
+            help = "seconds to wait between indexing passes",
+            default_value = "10"
+        )]
+        poll_interval: u64,
+    },
+    #[command(about = "confidentially transfers tokens")]
+    Transfer {
         #[arg(
             long,
             help = "api endpoint for the private wrapper",
@@ -264,11 +616,482 @@ pub enum Commands {
         unwrapped_mint: String,
         #[arg(
             long,
-            help = "token program address for the unwrapped mint",
-            default_value = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+            help = "public key of user to transfer funds to",
+            default_value = "BYuf1dG4YecRxCzkykK5tgBnNJo2SVdbedAzuFXgWy9y"
         )]
-        unwrapped_mint_program: String,
-        #[arg(long, help = "amount of tokens to wrap in lamports")]
+        recipient: String,
+        #[arg(long, help = "amount of tokens to transfer in lamports")]
         amount: u64,
+        #[arg(
+            long,
+            help = "optional memo recorded with the transfer, required when the recipient requires incoming transfer memos"
+        )]
+        memo: Option<String>,
+    },
+    #[command(about = "withdraw tokens from the confidential balance to non confidential balance")]
+    Withdraw {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(long, help = "path to a json keypair")]
+        keypair: String,
+        #[arg(
+            long,
+            help = "unwrapped token mint",
+            default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
+        )]
+        unwrapped_mint: String,
+        #[arg(long, help = "amount of tokens to withdraw in lamports")]
+        amount: u64,
+        #[arg(long, help = "optional memo recorded with the withdrawal")]
+        memo: Option<String>,
+    },
+    #[command(
+        about = "empty the confidential balance of a token account, and optionally close it to reclaim rent"
+    )]
+    CloseAccount {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(long, help = "path to a json keypair")]
+        keypair: String,
+        #[arg(
+            long,
+            help = "unwrapped token mint",
+            default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
+        )]
+        unwrapped_mint: String,
+        #[arg(
+            long,
+            help = "apply the pending balance and withdraw the confidential balance before emptying the account"
+        )]
+        drain: bool,
+        #[arg(
+            long,
+            help = "close the token account once emptied, the non confidential balance must be zero"
+        )]
+        close: bool,
+    },
+    #[command(
+        about = "enable or disable incoming confidential and non confidential transfers to a token account"
+    )]
+    UpdateCredits {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(long, help = "path to a json keypair")]
+        keypair: String,
+        #[arg(
+            long,
+            help = "unwrapped token mint",
+            default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
+        )]
+        unwrapped_mint: String,
+        #[arg(long, help = "whether to accept incoming confidential transfers")]
+        confidential: Option<bool>,
+        #[arg(long, help = "whether to accept incoming non confidential transfers")]
+        non_confidential: Option<bool>,
+    },
+    #[command(about = "unwrap tokens with the spl token wrap program")]
+    UnwrapTokens {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(long, help = "path to a json keypair")]
+        keypair: String,
+        #[arg(
+            long,
+            help = "unwrapped token mint",
+            default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
+        )]
+        unwrapped_mint: String,
+        #[arg(
+            long,
+            help = "token program address for the unwrapped mint, looked up from the mint account if omitted"
+        )]
+        unwrapped_mint_program: Option<String>,
+        #[arg(long, help = "amount of tokens to wrap in lamports")]
+        amount: u64,
+    },
+    #[command(
+        about = "watch a confidential token account for incoming transfers and balance changes"
+    )]
+    Watch {
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(
+            long,
+            help = "solana websocket url, derived from the rpc url if not provided"
+        )]
+        ws_url: Option<String>,
+        #[arg(long, help = "path to a json keypair")]
+        keypair: String,
+        #[arg(
+            long,
+            help = "unwrapped token mint",
+            default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
+        )]
+        unwrapped_mint: String,
+        #[arg(
+            long,
+            help = "url to post notifications to, notifications are logged if not provided"
+        )]
+        webhook_url: Option<String>,
+        #[arg(
+            long,
+            help = "number of times a failed webhook delivery is retried",
+            default_value = "5"
+        )]
+        max_retries: u32,
+        #[arg(
+            long,
+            help = "path to the precomputed discrete log table, built on first start if missing",
+            default_value = "discrete_log_table.bin"
+        )]
+        discrete_log_table: String,
+        #[arg(
+            long,
+            help = "size of the discrete log table as a power of two (16-24)",
+            default_value = "20"
+        )]
+        baby_step_bits: u32,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuditorCommands {
+    #[command(
+        about = "generate an auditor keypair, randomly or derived from a seed file or wallet signature"
+    )]
+    Keygen {
+        #[arg(long, help = "path to save the auditor keypair to")]
+        output: String,
+        #[arg(
+            long,
+            help = "derive the keypair from the contents of this file, at least 32 bytes"
+        )]
+        seed_file: Option<String>,
+        #[arg(
+            long,
+            help = "derive the keypair from a signature of this json wallet keypair"
+        )]
+        keypair: Option<String>,
+        #[arg(long, help = "save the keypair as a passphrase encrypted keystore")]
+        encrypt: bool,
+        #[arg(
+            long,
+            help = "environment variable holding the keystore passphrase, prompted for if unset",
+            default_value = "AUDITOR_KEYSTORE_PASSPHRASE"
+        )]
+        passphrase_env: String,
+        #[arg(long, help = "overwrite the output file if it exists")]
+        force: bool,
+    },
+    #[command(about = "display the ElGamal pubkey of an auditor keypair or keystore")]
+    ShowPubkey {
+        #[arg(
+            long,
+            help = "path to the json ElGamal keypair or encrypted keystore of the auditor"
+        )]
+        auditor_keypair: String,
+    },
+    #[command(
+        about = "update the auditor and approve policy of a wrapped mint with its confidential transfer authority"
+    )]
+    Rotate {
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(long, help = "path to a json keypair paying for the transaction")]
+        keypair: String,
+        #[arg(
+            long,
+            help = "path to the json keypair of the confidential transfer authority, defaults to --keypair"
+        )]
+        authority: Option<String>,
+        #[arg(
+            long,
+            help = "unwrapped token mint",
+            default_value = "GqxbzHAZrSaTGEqXcTCUiMR7bLUPrSCb4nZdqcKEkahv"
+        )]
+        unwrapped_mint: String,
+        #[arg(
+            long,
+            help = "path to the json ElGamal keypair or encrypted keystore of the new auditor"
+        )]
+        auditor_keypair: Option<String>,
+        #[arg(long, help = "base64 encoded ElGamal pubkey of the new auditor")]
+        auditor_elgamal_pubkey: Option<String>,
+        #[arg(long, help = "remove the auditor of the wrapped mint")]
+        remove_auditor: bool,
+        #[arg(
+            long,
+            help = "whether token accounts are approved automatically, or by the confidential transfer authority, unchanged if omitted",
+            value_parser = ["auto", "manual"]
+        )]
+        approve_policy: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum WithheldFeesCommands {
+    #[command(
+        about = "display the withdraw withheld ElGamal pubkey derived from a wallet, used when creating a mint with confidential transfer fees"
+    )]
+    ShowPubkey {
+        #[arg(
+            long,
+            help = "path to the json keypair of the withdraw withheld authority"
+        )]
+        keypair: String,
+    },
+    #[command(
+        about = "harvest the withheld confidential transfer fees of token accounts to the mint"
+    )]
+    Harvest {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(long, help = "path to a json keypair paying for the transactions")]
+        keypair: String,
+        #[arg(long, help = "confidential token mint with a transfer fee")]
+        token_mint: String,
+        #[arg(
+            long,
+            help = "token account to harvest from, can be provided multiple times, every token account of the mint if omitted"
+        )]
+        source: Vec<String>,
+    },
+    #[command(
+        about = "withdraw withheld confidential transfer fees into the token account of the withdraw withheld authority"
+    )]
+    Withdraw {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(
+            long,
+            help = "path to the json keypair of the withdraw withheld authority"
+        )]
+        keypair: String,
+        #[arg(long, help = "confidential token mint with a transfer fee")]
+        token_mint: String,
+        #[arg(
+            long,
+            help = "token account to withdraw from, can be provided multiple times, the fees harvested to the mint are withdrawn if omitted"
+        )]
+        source: Vec<String>,
+    },
+    #[command(about = "allow harvesting withheld confidential transfer fees to the mint")]
+    EnableHarvestToMint {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(
+            long,
+            help = "path to the json keypair of the confidential transfer fee authority"
+        )]
+        keypair: String,
+        #[arg(long, help = "confidential token mint with a transfer fee")]
+        token_mint: String,
+    },
+    #[command(about = "reject harvesting withheld confidential transfer fees to the mint")]
+    DisableHarvestToMint {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(
+            long,
+            help = "path to the json keypair of the confidential transfer fee authority"
+        )]
+        keypair: String,
+        #[arg(long, help = "confidential token mint with a transfer fee")]
+        token_mint: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfidentialMintCommands {
+    #[command(about = "create a confidential mint, optionally keeping its supply confidential")]
+    Create {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(
+            long,
+            help = "path to the json keypair of the mint authority paying for the mint"
+        )]
+        keypair: String,
+        #[arg(
+            long,
+            help = "path to the json keypair of the mint, generated if omitted"
+        )]
+        mint_keypair: Option<String>,
+        #[arg(long, help = "number of decimals of the mint", default_value = "6")]
+        decimals: u8,
+        #[arg(
+            long,
+            help = "keep the supply confidential, encrypted for the supply keys of the mint authority"
+        )]
+        confidential_supply: bool,
+        #[arg(
+            long,
+            help = "whether token accounts are approved automatically, or by the confidential transfer authority",
+            default_value = "auto",
+            value_parser = ["auto", "manual"]
+        )]
+        approve_policy: String,
+        #[arg(long, help = "base64 encoded ElGamal pubkey of the auditor")]
+        auditor_elgamal_pubkey: Option<String>,
+        #[arg(
+            long,
+            help = "authority which can approve token accounts and update the confidential transfer settings"
+        )]
+        confidential_transfer_authority: Option<String>,
+    },
+    #[command(about = "mint tokens into the pending balance of a confidential token account")]
+    Mint {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(long, help = "path to the json keypair of the mint authority")]
+        keypair: String,
+        #[arg(long, help = "confidential token mint with a confidential supply")]
+        token_mint: String,
+        #[arg(long, help = "public key of the user to mint tokens to")]
+        recipient: String,
+        #[arg(long, help = "amount of tokens to mint in lamports")]
+        amount: u64,
+    },
+    #[command(about = "burn tokens from the available confidential balance")]
+    Burn {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(
+            long,
+            help = "solana rpc url",
+            default_value = "https://api.devnet.solana.com/"
+        )]
+        rpc_url: String,
+        #[arg(long, help = "path to a json keypair")]
+        keypair: String,
+        #[arg(long, help = "confidential token mint with a confidential supply")]
+        token_mint: String,
+        #[arg(long, help = "amount of tokens to burn in lamports")]
+        amount: u64,
+    },
+    #[command(about = "display the decrypted confidential supply of a mint")]
+    Supply {
+        #[arg(
+            long,
+            help = "api endpoint for the private wrapper",
+            default_value = "http://127.0.0.1:1337"
+        )]
+        api_url: String,
+        #[arg(long, help = "path to the json keypair of the mint authority")]
+        keypair: String,
+        #[arg(long, help = "confidential token mint with a confidential supply")]
+        token_mint: String,
     },
 }
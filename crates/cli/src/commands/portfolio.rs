@@ -0,0 +1,33 @@
+use api::types::{ApiPortfolioResponse, Portfolio};
+use common::key_generator::KeypairType;
+use solana_sdk::{
+    signature::Keypair,
+    signer::{EncodableKey, Signer},
+};
+
+pub async fn portfolio(api_url: String, keypair: String) -> anyhow::Result<()> {
+    let key = Keypair::read_from_file(keypair).unwrap();
+
+    let elgamal_sig = key.sign_message(&KeypairType::ElGamal.message_to_sign(key.pubkey()));
+    let ae_sig = key.sign_message(&KeypairType::Ae.message_to_sign(key.pubkey()));
+
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let payload = Portfolio {
+        authority: key.pubkey(),
+        elgamal_signature: elgamal_sig,
+        ae_signature: ae_sig,
+    };
+
+    let req = client
+        .post(format!("{api_url}/confidential-balances/portfolio"))
+        .header("Content-Type", "application/json")
+        .json(&payload)
+        .build()?;
+    let res = client.execute(req).await?;
+    let res: ApiPortfolioResponse = res.json().await?;
+
+    log::info!("{}", serde_json::to_string_pretty(&res).unwrap());
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use api::types::{ApiTransactionResponse, Transfer};
+use api::types::{ApiTransactionResponse, ApiTransferResponse, Transfer};
 use common::key_generator::KeypairType;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
@@ -15,6 +15,7 @@ pub async fn transfer(
     recipient: String,
     unwrapped_mint: String,
     amount: u64,
+    memo: Option<String>,
 ) -> anyhow::Result<()> {
     let rpc = RpcClient::new(rpc_url);
     let unwrapped_mint: Pubkey = unwrapped_mint.parse().unwrap();
@@ -40,6 +41,9 @@ pub async fn transfer(
     let equality_proof_keypair = Keypair::new();
     let range_proof_keypair = Keypair::new();
     let ciphertext_proof_keypair = Keypair::new();
+    // only used by mints with a transfer fee
+    let fee_sigma_proof_keypair = Keypair::new();
+    let fee_ciphertext_proof_keypair = Keypair::new();
 
     let payload = Transfer {
         authority: key.pubkey(),
@@ -50,7 +54,10 @@ pub async fn transfer(
         equality_proof_keypair: equality_proof_keypair.insecure_clone(),
         range_proof_keypair: range_proof_keypair.insecure_clone(),
         ciphertext_validity_proof_keypair: ciphertext_proof_keypair.insecure_clone(),
+        fee_sigma_proof_keypair: Some(fee_sigma_proof_keypair.insecure_clone()),
+        fee_ciphertext_validity_proof_keypair: Some(fee_ciphertext_proof_keypair.insecure_clone()),
         amount,
+        memo,
     };
 
     log::info!("{}", serde_json::to_string_pretty(&payload).unwrap());
@@ -61,29 +68,41 @@ pub async fn transfer(
         .json(&payload)
         .build()?;
     let res = client.execute(req).await?;
-    let res: ApiTransactionResponse = res.json().await?;
+    let res: ApiTransferResponse = res.json().await?;
 
     log::info!("{}", serde_json::to_string_pretty(&res).unwrap());
+    log::info!(
+        "recipient receives {} tokens after a transfer fee of {}",
+        res.received_amount,
+        res.transfer_fee_amount
+    );
 
-    let txs = res.decode_transactions()?;
+    let signers = [
+        &key,
+        &equality_proof_keypair,
+        &range_proof_keypair,
+        &ciphertext_proof_keypair,
+        &fee_sigma_proof_keypair,
+        &fee_ciphertext_proof_keypair,
+    ];
+    let txs = ApiTransactionResponse {
+        transactions: res.transactions,
+    }
+    .decode_transactions()?;
     for (idx, mut tx) in txs.into_iter().enumerate() {
+        // the proof keypairs only sign the transaction creating the proof accounts
+        let tx_signers = signers
+            .into_iter()
+            .filter(|signer| {
+                tx.message.account_keys[..tx.message.header.num_required_signatures as usize]
+                    .contains(&signer.pubkey())
+            })
+            .collect::<Vec<_>>();
         loop {
             let Ok(blockhash) = rpc.get_latest_blockhash().await else {
                 continue;
             };
-            if idx == 0 {
-                tx.sign(
-                    &vec![
-                        &key,
-                        &equality_proof_keypair,
-                        &range_proof_keypair,
-                        &ciphertext_proof_keypair,
-                    ],
-                    blockhash,
-                );
-            } else {
-                tx.sign(&vec![&key], blockhash);
-            }
+            tx.sign(&tx_signers, blockhash);
             log::info!("sending transfer tx({idx})");
             let sig = match rpc.send_and_confirm_transaction(&tx).await {
                 Ok(sig) => sig,
@@ -0,0 +1,82 @@
+use api::types::{ApiError, ApiTransactionResponse, Unshield};
+use common::key_generator::KeypairType;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::{EncodableKey, Signer},
+};
+use spl_token_wrap::get_wrapped_mint_address;
+
+/// Withdraws tokens from the confidential balance and unwraps them
+pub async fn unshield(
+    api_url: String,
+    rpc_url: String,
+    keypair: String,
+    unwrapped_mint: String,
+    unwrapped_mint_program: Option<String>,
+    amount: u64,
+) -> anyhow::Result<()> {
+    let unwrapped_mint: Pubkey = unwrapped_mint.parse()?;
+    let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint, &spl_token_2022::id());
+    let rpc = RpcClient::new(rpc_url);
+    let unwrapped_mint_program: Pubkey = match unwrapped_mint_program {
+        Some(unwrapped_mint_program) => unwrapped_mint_program.parse()?,
+        None => rpc.get_account(&unwrapped_mint).await?.owner,
+    };
+    let key = Keypair::read_from_file(&keypair)
+        .map_err(|err| anyhow::anyhow!("failed to read keypair {keypair} {err}"))?;
+
+    let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &key.pubkey(),
+        &wrapped_mint,
+        &spl_token_2022::id(),
+    );
+    let elgamal_sig = key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata));
+    let ae_sig = key.sign_message(&KeypairType::Ae.message_to_sign(user_ata));
+
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let equality_proof_keypair = Keypair::new();
+    let range_proof_keypair = Keypair::new();
+
+    let payload = Unshield {
+        authority: key.pubkey(),
+        unwrapped_token_mint: unwrapped_mint,
+        unwrapped_token_program: unwrapped_mint_program,
+        amount,
+        elgamal_signature: elgamal_sig,
+        ae_signature: ae_sig,
+        equality_proof_keypair: equality_proof_keypair.insecure_clone(),
+        range_proof_keypair: range_proof_keypair.insecure_clone(),
+    };
+
+    let req = client
+        .post(format!("{api_url}/private-wrapper/unshield"))
+        .header("Content-Type", "application/json")
+        .json(&payload)
+        .build()?;
+    let res = client.execute(req).await?;
+    if !res.status().is_success() {
+        let err: ApiError = res.json().await?;
+        anyhow::bail!("failed to unshield tokens {}", err.msg);
+    }
+    let res: ApiTransactionResponse = res.json().await?;
+
+    // the transactions depend on each other, so each must confirm before the next is sent
+    for (idx, mut tx) in res.decode_transactions()?.into_iter().enumerate() {
+        // the proof context accounts are only created by the first withdraw transaction
+        let required =
+            &tx.message.account_keys[..tx.message.header.num_required_signatures as usize];
+        let signers = [&key, &equality_proof_keypair, &range_proof_keypair]
+            .into_iter()
+            .filter(|signer| required.contains(&signer.pubkey()))
+            .collect::<Vec<_>>();
+        tx.sign(&signers, rpc.get_latest_blockhash().await?);
+        log::info!("sending unshield tx({idx})");
+        let sig = rpc.send_and_confirm_transaction(&tx).await?;
+        log::info!("sent unshield tx({idx}) {sig}");
+    }
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use api::types::{ApiTransactionResponse, WrapTokens};
+use api::types::{ApiTransactionResponse, ApiWrapTokensResponse, WrapTokens};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
     pubkey::Pubkey,
@@ -12,12 +12,15 @@ pub async fn unwrap(
     rpc_url: String,
     keypair: String,
     unwrapped_mint: String,
-    unwrapped_mint_program: String,
+    unwrapped_mint_program: Option<String>,
     amount: u64,
 ) -> anyhow::Result<()> {
     let unwrapped_mint: Pubkey = unwrapped_mint.parse().unwrap();
-    let unwrapped_mint_program: Pubkey = unwrapped_mint_program.parse().unwrap();
     let rpc = RpcClient::new(rpc_url);
+    let unwrapped_mint_program: Pubkey = match unwrapped_mint_program {
+        Some(unwrapped_mint_program) => unwrapped_mint_program.parse()?,
+        None => rpc.get_account(&unwrapped_mint).await?.owner,
+    };
     let key = Keypair::read_from_file(keypair).unwrap();
 
     let client = reqwest::ClientBuilder::new().build()?;
@@ -38,11 +41,19 @@ pub async fn unwrap(
         .json(&payload)
         .build()?;
     let res = client.execute(req).await?;
-    let res: ApiTransactionResponse = res.json().await?;
+    let res: ApiWrapTokensResponse = res.json().await?;
 
     log::info!("{}", serde_json::to_string_pretty(&res).unwrap());
+    log::info!(
+        "receiving {} tokens after a transfer fee of {}",
+        res.received_amount,
+        res.transfer_fee_amount
+    );
 
-    let txs = res.decode_transactions()?;
+    let txs = ApiTransactionResponse {
+        transactions: res.transactions,
+    }
+    .decode_transactions()?;
     for mut tx in txs {
         tx.sign(&vec![&key], rpc.get_latest_blockhash().await?);
         log::info!("sending wrap tx");
@@ -0,0 +1,53 @@
+use api::types::{ApiError, ApiWrappedMintResponse};
+use solana_sdk::pubkey::Pubkey;
+
+/// Verifies the wrapped supply of a mint is backed by its escrow, failing if the escrow holds
+/// less than the wrapped supply
+pub async fn verify_backing(api_url: String, token_mint: String) -> anyhow::Result<()> {
+    let token_mint: Pubkey = token_mint.parse()?;
+
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let req = client
+        .get(format!("{api_url}/private-wrapper/wrapped-mint"))
+        .query(&[("token_mint", token_mint.to_string())])
+        .build()?;
+    let res = client.execute(req).await?;
+    if !res.status().is_success() {
+        let err: ApiError = res.json().await?;
+        anyhow::bail!("failed to get wrapped mint info {}", err.msg);
+    }
+    let res: ApiWrappedMintResponse = res.json().await?;
+
+    log::info!("{}", serde_json::to_string_pretty(&res).unwrap());
+
+    if res.backpointer_unwrapped_token_mint != Some(res.unwrapped_token_mint) {
+        anyhow::bail!(
+            "backpointer {} of wrapped mint {} does not point to {}",
+            res.backpointer,
+            res.wrapped_token_mint,
+            res.unwrapped_token_mint
+        );
+    }
+    if res.supply_mismatch {
+        anyhow::bail!(
+            "escrow balance of {} does not cover wrapped supply of {}",
+            res.escrow_balance_amount,
+            res.wrapped_supply_amount
+        );
+    }
+    if res.escrow_surplus_amount > 0 {
+        log::warn!(
+            "escrow {} holds a surplus of {} over the wrapped supply",
+            res.escrow,
+            res.escrow_surplus_amount
+        );
+    }
+    log::info!(
+        "wrapped supply of {} is backed by escrow {}",
+        res.wrapped_token_mint,
+        res.escrow
+    );
+
+    Ok(())
+}
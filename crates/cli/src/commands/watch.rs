@@ -0,0 +1,82 @@
+use api::notifications::{websocket_url, BalanceWatcher, Credentials, WebhookDispatcher};
+use common::{
+    discrete_log::DiscreteLogTable,
+    key_generator::{derive_ae_key, derive_elgamal_key, KeypairType},
+};
+use solana_client::nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::{EncodableKey, Signer},
+};
+use spl_token_wrap::get_wrapped_mint_address;
+use std::{sync::Arc, time::Duration};
+
+#[allow(clippy::too_many_arguments)]
+pub async fn watch(
+    rpc_url: String,
+    ws_url: Option<String>,
+    keypair: String,
+    unwrapped_mint: String,
+    webhook_url: Option<String>,
+    max_retries: u32,
+    discrete_log_table: String,
+    baby_step_bits: u32,
+) -> anyhow::Result<()> {
+    let unwrapped_mint: Pubkey = unwrapped_mint.parse().unwrap();
+    let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint, &spl_token_2022::id());
+    let key = Keypair::read_from_file(keypair).unwrap();
+
+    let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &key.pubkey(),
+        &wrapped_mint,
+        &spl_token_2022::id(),
+    );
+    let elgamal_sig = key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata));
+    let ae_sig = key.sign_message(&KeypairType::Ae.message_to_sign(user_ata));
+    let credentials = Credentials {
+        elgamal_secret: derive_elgamal_key(elgamal_sig)?.secret().clone(),
+        ae_key: derive_ae_key(ae_sig)?,
+    };
+
+    let discrete_log = tokio::task::spawn_blocking(move || {
+        DiscreteLogTable::load_or_build(discrete_log_table, baby_step_bits)
+    })
+    .await??;
+
+    let rpc = RpcClient::new(rpc_url.clone());
+    let token_account = rpc.get_account(&user_ata).await?;
+
+    let ws_url = match ws_url {
+        Some(ws_url) => ws_url,
+        None => websocket_url(&rpc_url)?,
+    };
+    let client = PubsubClient::new(&ws_url)
+        .await
+        .map_err(|err| anyhow::anyhow!("failed to connect to {ws_url} {err}"))?;
+    let mut notifications = BalanceWatcher::new(
+        Arc::new(client),
+        user_ata,
+        Some(credentials),
+        Arc::new(discrete_log),
+    )
+    .subscribe(token_account)
+    .await?;
+
+    log::info!("watching {user_ata} for balance changes");
+
+    match webhook_url {
+        Some(webhook_url) => {
+            WebhookDispatcher::new(webhook_url, max_retries, Duration::from_secs(1))
+                .run(notifications)
+                .await
+        }
+        None => {
+            while let Some(notification) = notifications.recv().await {
+                log::info!("{}", serde_json::to_string_pretty(&notification).unwrap());
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -14,6 +14,7 @@ pub async fn withdraw(
     keypair: String,
     unwrapped_mint: String,
     amount: u64,
+    memo: Option<String>,
 ) -> anyhow::Result<()> {
     let rpc = RpcClient::new(rpc_url);
     let unwrapped_mint: Pubkey = unwrapped_mint.parse().unwrap();
@@ -41,6 +42,7 @@ pub async fn withdraw(
         equality_proof_keypair: equality_proof_keypair.insecure_clone(),
         range_proof_keypair: range_proof_keypair.insecure_clone(),
         amount,
+        memo,
     };
 
     log::info!("{}", serde_json::to_string_pretty(&payload).unwrap());
@@ -0,0 +1,193 @@
+use api::types::{
+    ApiError, ApiTransactionResponse, ApiWithdrawWithheldFeesResponse, HarvestWithheldFees,
+    UpdateHarvestToMint, WithdrawWithheldFees,
+};
+use common::key_generator::{derive_elgamal_key, KeypairType};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::{EncodableKey, Signer},
+};
+use spl_token_2022::solana_zk_sdk::encryption::pod::elgamal::PodElGamalPubkey;
+
+/// Displays the withdraw withheld ElGamal pubkey derived from a wallet, which is configured on
+/// mints with confidential transfer fees
+pub async fn show_pubkey(keypair: String) -> anyhow::Result<()> {
+    let key = Keypair::read_from_file(&keypair)
+        .map_err(|err| anyhow::anyhow!("failed to read keypair {keypair} {err}"))?;
+    // the ata is ignored by the message, the withdraw withheld key is tied to the wallet only
+    let withdraw_withheld_key = derive_elgamal_key(
+        key.sign_message(&KeypairType::WithdrawWithheld.message_to_sign(Pubkey::default())),
+    )?;
+    println!(
+        "{}",
+        PodElGamalPubkey::from(*withdraw_withheld_key.pubkey())
+    );
+    Ok(())
+}
+
+/// Harvests the withheld confidential transfer fees of token accounts to the mint
+pub async fn harvest(
+    api_url: String,
+    rpc_url: String,
+    keypair: String,
+    token_mint: String,
+    sources: Vec<String>,
+) -> anyhow::Result<()> {
+    let rpc = RpcClient::new(rpc_url);
+    let token_mint: Pubkey = token_mint.parse()?;
+    let sources = sources
+        .iter()
+        .map(|source| source.parse())
+        .collect::<Result<Vec<Pubkey>, _>>()?;
+    let key = Keypair::read_from_file(&keypair)
+        .map_err(|err| anyhow::anyhow!("failed to read keypair {keypair} {err}"))?;
+
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let req = client
+        .post(format!(
+            "{api_url}/confidential-balances/harvest-withheld-fees"
+        ))
+        .header("Content-Type", "application/json")
+        .json(&HarvestWithheldFees {
+            payer: key.pubkey(),
+            token_mint,
+            sources,
+        })
+        .build()?;
+    let res = client.execute(req).await?;
+    if !res.status().is_success() {
+        let err: ApiError = res.json().await?;
+        anyhow::bail!("failed to harvest withheld fees {}", err.msg);
+    }
+    let res: ApiTransactionResponse = res.json().await?;
+
+    for mut tx in res.decode_transactions()? {
+        tx.sign(&vec![&key], rpc.get_latest_blockhash().await?);
+        let sig = rpc.send_and_confirm_transaction(&tx).await?;
+        log::info!("sent harvest tx {sig}");
+    }
+
+    Ok(())
+}
+
+/// Withdraws withheld confidential transfer fees from the mint, or from token accounts, into the
+/// confidential token account of the withdraw withheld authority
+pub async fn withdraw(
+    api_url: String,
+    rpc_url: String,
+    keypair: String,
+    token_mint: String,
+    sources: Vec<String>,
+) -> anyhow::Result<()> {
+    let rpc = RpcClient::new(rpc_url);
+    let token_mint: Pubkey = token_mint.parse()?;
+    let sources = sources
+        .iter()
+        .map(|source| source.parse())
+        .collect::<Result<Vec<Pubkey>, _>>()?;
+    let key = Keypair::read_from_file(&keypair)
+        .map_err(|err| anyhow::anyhow!("failed to read keypair {keypair} {err}"))?;
+
+    let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &key.pubkey(),
+        &token_mint,
+        &spl_token_2022::id(),
+    );
+
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let equality_proof_keypair = Keypair::new();
+
+    let req = client
+        .post(format!(
+            "{api_url}/confidential-balances/withdraw-withheld-fees"
+        ))
+        .header("Content-Type", "application/json")
+        .json(&WithdrawWithheldFees {
+            authority: key.pubkey(),
+            token_mint,
+            withdraw_withheld_signature: key
+                .sign_message(&KeypairType::WithdrawWithheld.message_to_sign(user_ata)),
+            elgamal_signature: key.sign_message(&KeypairType::ElGamal.message_to_sign(user_ata)),
+            ae_signature: key.sign_message(&KeypairType::Ae.message_to_sign(user_ata)),
+            equality_proof_keypair: equality_proof_keypair.insecure_clone(),
+            sources,
+        })
+        .build()?;
+    let res = client.execute(req).await?;
+    if !res.status().is_success() {
+        let err: ApiError = res.json().await?;
+        anyhow::bail!("failed to withdraw withheld fees {}", err.msg);
+    }
+    let res: ApiWithdrawWithheldFeesResponse = res.json().await?;
+    log::info!("withdrawing {} withheld tokens", res.amount);
+
+    let signers = [&key, &equality_proof_keypair];
+    let txs = ApiTransactionResponse {
+        transactions: res.transactions,
+    }
+    .decode_transactions()?;
+    for (idx, mut tx) in txs.into_iter().enumerate() {
+        // the proof keypair only signs the transaction creating the proof account
+        let tx_signers = signers
+            .into_iter()
+            .filter(|signer| {
+                tx.message.account_keys[..tx.message.header.num_required_signatures as usize]
+                    .contains(&signer.pubkey())
+            })
+            .collect::<Vec<_>>();
+        tx.sign(&tx_signers, rpc.get_latest_blockhash().await?);
+        let sig = rpc.send_and_confirm_transaction(&tx).await?;
+        log::info!("sent withdraw withheld fees tx({idx}) {sig}");
+    }
+
+    Ok(())
+}
+
+/// Enables or disables harvesting withheld confidential transfer fees to the mint
+pub async fn update_harvest_to_mint(
+    api_url: String,
+    rpc_url: String,
+    keypair: String,
+    token_mint: String,
+    enable: bool,
+) -> anyhow::Result<()> {
+    let route = if enable {
+        "enable-harvest-to-mint"
+    } else {
+        "disable-harvest-to-mint"
+    };
+
+    let rpc = RpcClient::new(rpc_url);
+    let token_mint: Pubkey = token_mint.parse()?;
+    let key = Keypair::read_from_file(&keypair)
+        .map_err(|err| anyhow::anyhow!("failed to read keypair {keypair} {err}"))?;
+
+    let client = reqwest::ClientBuilder::new().build()?;
+
+    let req = client
+        .post(format!("{api_url}/confidential-balances/{route}"))
+        .header("Content-Type", "application/json")
+        .json(&UpdateHarvestToMint {
+            authority: key.pubkey(),
+            token_mint,
+        })
+        .build()?;
+    let res = client.execute(req).await?;
+    if !res.status().is_success() {
+        let err: ApiError = res.json().await?;
+        anyhow::bail!("failed to {route} {}", err.msg);
+    }
+    let res: ApiTransactionResponse = res.json().await?;
+
+    for mut tx in res.decode_transactions()? {
+        tx.sign(&vec![&key], rpc.get_latest_blockhash().await?);
+        let sig = rpc.send_and_confirm_transaction(&tx).await?;
+        log::info!("sent {route} tx {sig}");
+    }
+
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use std::str::FromStr;
 
 use clap::Parser;
-use commands::Commands;
+use commands::{AuditorCommands, Commands, ConfidentialMintCommands, WithheldFeesCommands};
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
@@ -16,18 +16,42 @@ pub async fn main() -> anyhow::Result<()> {
         Commands::StartAPI {
             listen_url,
             rpc_url,
-        } => commands::api::start_api(listen_url, rpc_url).await,
+            discrete_log_table,
+            baby_step_bits,
+            indexer_database,
+        } => {
+            commands::api::start_api(
+                listen_url,
+                rpc_url,
+                discrete_log_table,
+                baby_step_bits,
+                indexer_database,
+            )
+            .await
+        }
         Commands::CreateConfidentialWrappedMint {
             rpc_url,
             keypair,
             unwrapped_mint,
             unwrapped_mint_program,
+            approve_policy,
+            auditor_elgamal_pubkey,
+            auditor_keypair,
+            generate_auditor_keypair,
+            passphrase_env,
+            confidential_transfer_authority,
         } => {
             commands::create_confidential_wrapped_mint::create_token_mint(
                 rpc_url,
                 keypair,
                 unwrapped_mint,
                 unwrapped_mint_program,
+                approve_policy,
+                auditor_elgamal_pubkey,
+                auditor_keypair,
+                generate_auditor_keypair,
+                passphrase_env,
+                confidential_transfer_authority,
             )
             .await
         }
@@ -49,30 +73,323 @@ pub async fn main() -> anyhow::Result<()> {
             )
             .await
         }
+        Commands::Shield {
+            api_url,
+            rpc_url,
+            keypair,
+            unwrapped_mint,
+            unwrapped_mint_program,
+            amount,
+        } => {
+            commands::shield::shield(
+                api_url,
+                rpc_url,
+                keypair,
+                unwrapped_mint,
+                unwrapped_mint_program,
+                amount,
+            )
+            .await
+        }
+        Commands::Unshield {
+            api_url,
+            rpc_url,
+            keypair,
+            unwrapped_mint,
+            unwrapped_mint_program,
+            amount,
+        } => {
+            commands::unshield::unshield(
+                api_url,
+                rpc_url,
+                keypair,
+                unwrapped_mint,
+                unwrapped_mint_program,
+                amount,
+            )
+            .await
+        }
         Commands::Initialize {
             api_url,
             rpc_url,
             keypair,
             unwrapped_mint,
-        } => commands::initialize::initialize(api_url, rpc_url, keypair, unwrapped_mint).await,
+            maximum_pending_balance_credit_counter,
+        } => {
+            commands::initialize::initialize(
+                api_url,
+                rpc_url,
+                keypair,
+                unwrapped_mint,
+                maximum_pending_balance_credit_counter,
+            )
+            .await
+        }
         Commands::Deposit {
             api_url,
             rpc_url,
             keypair,
             unwrapped_mint,
             amount,
-        } => commands::deposit::deposit(api_url, rpc_url, keypair, unwrapped_mint, amount).await,
+            memo,
+        } => {
+            commands::deposit::deposit(api_url, rpc_url, keypair, unwrapped_mint, amount, memo)
+                .await
+        }
         Commands::Apply {
             api_url,
             rpc_url,
             keypair,
             unwrapped_mint,
         } => commands::apply::apply(api_url, rpc_url, keypair, unwrapped_mint).await,
+        Commands::ApproveAccount {
+            api_url,
+            rpc_url,
+            keypair,
+            unwrapped_mint,
+            token_account,
+            owner,
+        } => {
+            commands::approvals::approve_account(
+                api_url,
+                rpc_url,
+                keypair,
+                unwrapped_mint,
+                token_account,
+                owner,
+            )
+            .await
+        }
+        Commands::PendingApprovals {
+            api_url,
+            unwrapped_mint,
+        } => commands::approvals::pending_approvals(api_url, unwrapped_mint).await,
+        Commands::AutoApply {
+            api_url,
+            rpc_url,
+            keypair,
+            unwrapped_mint,
+            counter_threshold,
+            amount_threshold,
+            apply_interval,
+            poll_interval,
+            max_backoff,
+        } => {
+            commands::auto_apply::auto_apply(
+                api_url,
+                rpc_url,
+                keypair,
+                unwrapped_mint,
+                counter_threshold,
+                amount_threshold,
+                apply_interval,
+                poll_interval,
+                max_backoff,
+            )
+            .await
+        }
         Commands::Balances {
             api_url,
             keypair,
             unwrapped_mint,
         } => commands::balances::balances(api_url, keypair, unwrapped_mint).await,
+        Commands::Portfolio { api_url, keypair } => {
+            commands::portfolio::portfolio(api_url, keypair).await
+        }
+        Commands::History {
+            api_url,
+            keypair,
+            unwrapped_mint,
+            format,
+            limit,
+            before,
+            all,
+        } => {
+            commands::history::history(api_url, keypair, unwrapped_mint, format, limit, before, all)
+                .await
+        }
+        Commands::AuditReport {
+            rpc_url,
+            unwrapped_mint,
+            auditor_keypair,
+            passphrase_env,
+            indexer_database,
+            min_slot,
+            max_slot,
+            start_time,
+            end_time,
+            format,
+            discrete_log_table,
+            baby_step_bits,
+        } => {
+            commands::audit_report::audit_report(
+                rpc_url,
+                unwrapped_mint,
+                auditor_keypair,
+                passphrase_env,
+                indexer_database,
+                min_slot,
+                max_slot,
+                start_time,
+                end_time,
+                format,
+                discrete_log_table,
+                baby_step_bits,
+            )
+            .await
+        }
+        Commands::WithheldFees { command } => match command {
+            WithheldFeesCommands::ShowPubkey { keypair } => {
+                commands::withheld_fees::show_pubkey(keypair).await
+            }
+            WithheldFeesCommands::Harvest {
+                api_url,
+                rpc_url,
+                keypair,
+                token_mint,
+                source,
+            } => {
+                commands::withheld_fees::harvest(api_url, rpc_url, keypair, token_mint, source)
+                    .await
+            }
+            WithheldFeesCommands::Withdraw {
+                api_url,
+                rpc_url,
+                keypair,
+                token_mint,
+                source,
+            } => {
+                commands::withheld_fees::withdraw(api_url, rpc_url, keypair, token_mint, source)
+                    .await
+            }
+            WithheldFeesCommands::EnableHarvestToMint {
+                api_url,
+                rpc_url,
+                keypair,
+                token_mint,
+            } => {
+                commands::withheld_fees::update_harvest_to_mint(
+                    api_url, rpc_url, keypair, token_mint, true,
+                )
+                .await
+            }
+            WithheldFeesCommands::DisableHarvestToMint {
+                api_url,
+                rpc_url,
+                keypair,
+                token_mint,
+            } => {
+                commands::withheld_fees::update_harvest_to_mint(
+                    api_url, rpc_url, keypair, token_mint, false,
+                )
+                .await
+            }
+        },
+        Commands::ConfidentialMint { command } => match command {
+            ConfidentialMintCommands::Create {
+                api_url,
+                rpc_url,
+                keypair,
+                mint_keypair,
+                decimals,
+                confidential_supply,
+                approve_policy,
+                auditor_elgamal_pubkey,
+                confidential_transfer_authority,
+            } => {
+                commands::confidential_mint::create(
+                    api_url,
+                    rpc_url,
+                    keypair,
+                    mint_keypair,
+                    decimals,
+                    confidential_supply,
+                    approve_policy,
+                    auditor_elgamal_pubkey,
+                    confidential_transfer_authority,
+                )
+                .await
+            }
+            ConfidentialMintCommands::Mint {
+                api_url,
+                rpc_url,
+                keypair,
+                token_mint,
+                recipient,
+                amount,
+            } => {
+                commands::confidential_mint::mint(
+                    api_url, rpc_url, keypair, token_mint, recipient, amount,
+                )
+                .await
+            }
+            ConfidentialMintCommands::Burn {
+                api_url,
+                rpc_url,
+                keypair,
+                token_mint,
+                amount,
+            } => {
+                commands::confidential_mint::burn(api_url, rpc_url, keypair, token_mint, amount)
+                    .await
+            }
+            ConfidentialMintCommands::Supply {
+                api_url,
+                keypair,
+                token_mint,
+            } => commands::confidential_mint::supply(api_url, keypair, token_mint).await,
+        },
+        Commands::Auditor { command } => match command {
+            AuditorCommands::Keygen {
+                output,
+                seed_file,
+                keypair,
+                encrypt,
+                passphrase_env,
+                force,
+            } => {
+                commands::auditor::keygen(
+                    output,
+                    seed_file,
+                    keypair,
+                    encrypt,
+                    passphrase_env,
+                    force,
+                )
+                .await
+            }
+            AuditorCommands::ShowPubkey { auditor_keypair } => {
+                commands::auditor::show_pubkey(auditor_keypair).await
+            }
+            AuditorCommands::Rotate {
+                rpc_url,
+                keypair,
+                authority,
+                unwrapped_mint,
+                auditor_keypair,
+                auditor_elgamal_pubkey,
+                remove_auditor,
+                approve_policy,
+            } => {
+                commands::auditor::rotate(
+                    rpc_url,
+                    keypair,
+                    authority,
+                    unwrapped_mint,
+                    auditor_keypair,
+                    auditor_elgamal_pubkey,
+                    remove_auditor,
+                    approve_policy,
+                )
+                .await
+            }
+        },
+        Commands::Index {
+            rpc_url,
+            unwrapped_mint,
+            database,
+            poll_interval,
+        } => commands::index::index(rpc_url, unwrapped_mint, database, poll_interval).await,
         Commands::Transfer {
             api_url,
             rpc_url,
@@ -80,6 +397,7 @@ pub async fn main() -> anyhow::Result<()> {
             unwrapped_mint,
             recipient,
             amount,
+            memo,
         } => {
             commands::transfer::transfer(
                 api_url,
@@ -88,6 +406,7 @@ pub async fn main() -> anyhow::Result<()> {
                 recipient,
                 unwrapped_mint,
                 amount,
+                memo,
             )
             .await
         }
@@ -97,7 +416,54 @@ pub async fn main() -> anyhow::Result<()> {
             keypair,
             unwrapped_mint,
             amount,
-        } => commands::withdraw::withdraw(api_url, rpc_url, keypair, unwrapped_mint, amount).await,
+            memo,
+        } => {
+            commands::withdraw::withdraw(api_url, rpc_url, keypair, unwrapped_mint, amount, memo)
+                .await
+        }
+        Commands::CloseAccount {
+            api_url,
+            rpc_url,
+            keypair,
+            unwrapped_mint,
+            drain,
+            close,
+        } => {
+            commands::close_account::close_account(
+                api_url,
+                rpc_url,
+                keypair,
+                unwrapped_mint,
+                drain,
+                close,
+            )
+            .await
+        }
+        Commands::UpdateCredits {
+            api_url,
+            rpc_url,
+            keypair,
+            unwrapped_mint,
+            confidential,
+            non_confidential,
+        } => {
+            commands::credits::update_credits(
+                api_url,
+                rpc_url,
+                keypair,
+                unwrapped_mint,
+                confidential,
+                non_confidential,
+            )
+            .await
+        }
+        Commands::ListWrappedMints { api_url } => {
+            commands::list_wrapped_mints::list_wrapped_mints(api_url).await
+        }
+        Commands::VerifyBacking {
+            api_url,
+            token_mint,
+        } => commands::verify_backing::verify_backing(api_url, token_mint).await,
         Commands::UnwrapTokens {
             api_url,
             rpc_url,
@@ -116,6 +482,28 @@ pub async fn main() -> anyhow::Result<()> {
             )
             .await
         }
+        Commands::Watch {
+            rpc_url,
+            ws_url,
+            keypair,
+            unwrapped_mint,
+            webhook_url,
+            max_retries,
+            discrete_log_table,
+            baby_step_bits,
+        } => {
+            commands::watch::watch(
+                rpc_url,
+                ws_url,
+                keypair,
+                unwrapped_mint,
+                webhook_url,
+                max_retries,
+                discrete_log_table,
+                baby_step_bits,
+            )
+            .await
+        }
     }
 }
 
@@ -0,0 +1,40 @@
+//! Compares pending balance decryption using the zk sdk against the precomputed tables
+//!
+//! Run with `cargo bench -p common`
+
+use {
+    common::discrete_log::{default_num_threads, DiscreteLogTable, DEFAULT_BABY_STEP_BITS},
+    criterion::{criterion_group, criterion_main, BenchmarkId, Criterion},
+    solana_zk_sdk::encryption::elgamal::ElGamalKeypair,
+};
+
+fn bench_decrypt_u32(c: &mut Criterion) {
+    let keypair = ElGamalKeypair::new_rand();
+    let table = DiscreteLogTable::new(DEFAULT_BABY_STEP_BITS).unwrap();
+    let num_threads = default_num_threads();
+
+    let mut group = c.benchmark_group("decrypt_u32");
+    group.sample_size(10);
+    // a small pending_balance_lo and a large pending_balance_hi value
+    for amount in [1_000u64, u32::MAX as u64 - 1] {
+        let ciphertext = keypair.pubkey().encrypt(amount);
+        group.bench_with_input(BenchmarkId::new("zk_sdk", amount), &ciphertext, |b, ct| {
+            b.iter(|| keypair.secret().decrypt_u32(ct).unwrap())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("precomputed_table", amount),
+            &ciphertext,
+            |b, ct| {
+                b.iter(|| {
+                    table
+                        .decrypt_u32(keypair.secret(), ct, num_threads)
+                        .unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_decrypt_u32);
+criterion_main!(benches);
@@ -1,13 +1,51 @@
 //! Utilities for working with solana accounts in the context of confidential transfers
 
 use {
-    solana_sdk::account::Account,
+    crate::discrete_log::{default_num_threads, DiscreteLogTable},
+    anyhow::{anyhow, Result},
+    bytemuck::Zeroable,
+    solana_sdk::{account::Account, pubkey::Pubkey},
+    solana_zk_sdk::{
+        encryption::{
+            auth_encryption::{AeCiphertext, AeKey},
+            elgamal::{ElGamalCiphertext, ElGamalKeypair, ElGamalPubkey, ElGamalSecretKey},
+            grouped_elgamal::GroupedElGamalCiphertext,
+            pedersen::PedersenOpening,
+            pod::{
+                auth_encryption::PodAeCiphertext,
+                elgamal::PodElGamalCiphertext,
+                grouped_elgamal::{
+                    PodGroupedElGamalCiphertext2Handles, PodGroupedElGamalCiphertext3Handles,
+                },
+            },
+        },
+        zk_elgamal_proof_program::proof_data::CiphertextCiphertextEqualityProofData,
+    },
     spl_token_2022::{
-        extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+        extension::{
+            confidential_transfer::{
+                account_info::combine_balances, ConfidentialTransferAccount, EncryptedBalance,
+            },
+            confidential_transfer_fee::{ConfidentialTransferFeeAmount, EncryptedWithheldAmount},
+            memo_transfer::memo_required,
+            BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+        },
         state::{Account as TokenAccount, Mint},
     },
+    spl_token_confidential_transfer_ciphertext_arithmetic as ciphertext_arithmetic,
+    spl_token_wrap::{get_wrapped_mint_address, state::Backpointer},
 };
 
+/// The number of incoming credits after which the pending balance must be applied, used when
+/// initializing token accounts unless another value is requested
+pub const DEFAULT_MAXIMUM_PENDING_BALANCE_CREDIT_COUNTER: u64 = 65536;
+
+/// The largest supported maximum pending balance credit counter
+///
+/// Each credit adds at most 16 bits to the low bits of the pending balance, so capping the
+/// counter at 2^16 keeps the pending balance decryptable with the discrete log table
+pub const MAX_PENDING_BALANCE_CREDIT_COUNTER: u64 = 65536;
+
 /// Checks to see if the specified account is a valid token mint for confidential transfers
 ///
 /// Validates that:
@@ -31,6 +69,16 @@ pub fn is_valid_mint(mint: &Account) -> bool {
         .is_some()
 }
 
+/// Checks to see if the specified token mint has the TransferFeeConfig extension
+///
+/// Token accounts of these mints need space for the ConfidentialTransferFeeAmount extension
+/// when they are configured for confidential transfers
+pub fn mint_has_transfer_fee(mint: &Account) -> bool {
+    StateWithExtensions::<Mint>::unpack(&mint.data)
+        .and_then(|state| state.get_extension_types())
+        .is_ok_and(|extensions| extensions.contains(&ExtensionType::TransferFeeConfig))
+}
+
 /// Checks to see if the token account is already configured for confidential transfers
 ///
 /// Validates that:
@@ -51,19 +99,266 @@ pub fn token_account_already_configured(account: &Account) -> bool {
         .is_some()
 }
 
+/// Checks to see if the token account requires a memo on incoming transfers, which is the case
+/// when its MemoTransfer extension is enabled
+pub fn requires_incoming_transfer_memos(account: &Account) -> bool {
+    StateWithExtensions::<TokenAccount>::unpack(&account.data)
+        .is_ok_and(|state| memo_required(&state))
+}
+
+/// Decrypts the pending balance of a confidential token account
+///
+/// Both halves of the pending balance are decrypted with the precomputed discrete log table,
+/// this is cpu bound and should not be called from an async context
+pub fn decrypt_pending_balance(
+    discrete_log: &DiscreteLogTable,
+    elgamal_secret: &ElGamalSecretKey,
+    account: &ConfidentialTransferAccount,
+) -> Result<u64> {
+    let pending_balance_lo = TryInto::<ElGamalCiphertext>::try_into(account.pending_balance_lo)
+        .map_err(|e| anyhow!("failed to parse pending_balance_lo {e:#?}"))?;
+    let pending_balance_hi = TryInto::<ElGamalCiphertext>::try_into(account.pending_balance_hi)
+        .map_err(|e| anyhow!("failed to parse pending_balance_hi {e:#?}"))?;
+
+    let num_threads = default_num_threads();
+    let pending_balance_lo = discrete_log
+        .decrypt_u32(elgamal_secret, &pending_balance_lo, num_threads)
+        .ok_or_else(|| anyhow!("failed to decrypt pending_balance_lo"))?;
+    let pending_balance_hi = discrete_log
+        .decrypt_u32(elgamal_secret, &pending_balance_hi, num_threads)
+        .ok_or_else(|| anyhow!("failed to decrypt pending_balance_hi"))?;
+
+    combine_balances(pending_balance_lo, pending_balance_hi)
+        .ok_or_else(|| anyhow!("failed to combine pending_balance_lo and pending_balance_hi"))
+}
+
+/// Decrypts the available balance of a confidential token account
+pub fn decrypt_available_balance(
+    ae_key: &AeKey,
+    account: &ConfidentialTransferAccount,
+) -> Result<u64> {
+    let decryptable_available_balance =
+        TryInto::<AeCiphertext>::try_into(account.decryptable_available_balance)
+            .map_err(|e| anyhow!("failed to parse decryptable_available_balance {e:#?}"))?;
+    ae_key
+        .decrypt(&decryptable_available_balance)
+        .ok_or_else(|| anyhow!("failed to decrypt available balance"))
+}
+
+/// Decrypts a transfer amount from the grouped ciphertexts of a transfer
+///
+/// `handle_index` selects the decryption handle of the key holder, `0` for the source,
+/// `1` for the destination and `2` for the auditor
+pub fn decrypt_transfer_amount(
+    discrete_log: &DiscreteLogTable,
+    elgamal_secret: &ElGamalSecretKey,
+    transfer_amount_lo: PodGroupedElGamalCiphertext3Handles,
+    transfer_amount_hi: PodGroupedElGamalCiphertext3Handles,
+    handle_index: usize,
+) -> Result<u64> {
+    let transfer_amount_lo = GroupedElGamalCiphertext::<3>::try_from(transfer_amount_lo)
+        .map_err(|e| anyhow!("failed to parse transfer_amount_lo {e:#?}"))?
+        .to_elgamal_ciphertext(handle_index)
+        .map_err(|e| anyhow!("failed to extract transfer_amount_lo {e:#?}"))?;
+    let transfer_amount_hi = GroupedElGamalCiphertext::<3>::try_from(transfer_amount_hi)
+        .map_err(|e| anyhow!("failed to parse transfer_amount_hi {e:#?}"))?
+        .to_elgamal_ciphertext(handle_index)
+        .map_err(|e| anyhow!("failed to extract transfer_amount_hi {e:#?}"))?;
+
+    let num_threads = default_num_threads();
+    let transfer_amount_lo = discrete_log
+        .decrypt_u32(elgamal_secret, &transfer_amount_lo, num_threads)
+        .ok_or_else(|| anyhow!("failed to decrypt transfer_amount_lo"))?;
+    let transfer_amount_hi = discrete_log
+        .decrypt_u32(elgamal_secret, &transfer_amount_hi, num_threads)
+        .ok_or_else(|| anyhow!("failed to decrypt transfer_amount_hi"))?;
+
+    combine_balances(transfer_amount_lo, transfer_amount_hi)
+        .ok_or_else(|| anyhow!("failed to combine transfer_amount_lo and transfer_amount_hi"))
+}
+
+/// Decrypts a transfer fee from the grouped fee ciphertexts of a transfer with fee
+///
+/// `handle_index` selects the decryption handle of the key holder, `0` for the destination and
+/// `1` for the withdraw withheld authority
+pub fn decrypt_transfer_fee(
+    discrete_log: &DiscreteLogTable,
+    elgamal_secret: &ElGamalSecretKey,
+    fee_lo: PodGroupedElGamalCiphertext2Handles,
+    fee_hi: PodGroupedElGamalCiphertext2Handles,
+    handle_index: usize,
+) -> Result<u64> {
+    let fee_lo = GroupedElGamalCiphertext::<2>::try_from(fee_lo)
+        .map_err(|e| anyhow!("failed to parse fee_lo {e:#?}"))?
+        .to_elgamal_ciphertext(handle_index)
+        .map_err(|e| anyhow!("failed to extract fee_lo {e:#?}"))?;
+    let fee_hi = GroupedElGamalCiphertext::<2>::try_from(fee_hi)
+        .map_err(|e| anyhow!("failed to parse fee_hi {e:#?}"))?
+        .to_elgamal_ciphertext(handle_index)
+        .map_err(|e| anyhow!("failed to extract fee_hi {e:#?}"))?;
+
+    // fees are split into a 16 bit lo and 32 bit hi part, like pending balances
+    let num_threads = default_num_threads();
+    let fee_lo = discrete_log
+        .decrypt_u32(elgamal_secret, &fee_lo, num_threads)
+        .ok_or_else(|| anyhow!("failed to decrypt fee_lo"))?;
+    let fee_hi = discrete_log
+        .decrypt_u32(elgamal_secret, &fee_hi, num_threads)
+        .ok_or_else(|| anyhow!("failed to decrypt fee_hi"))?;
+
+    combine_balances(fee_lo, fee_hi).ok_or_else(|| anyhow!("failed to combine fee_lo and fee_hi"))
+}
+
+/// Decrypts a transfer amount from the auditor ciphertexts included in a transfer instruction
+pub fn decrypt_auditor_transfer_amount(
+    discrete_log: &DiscreteLogTable,
+    auditor_secret: &ElGamalSecretKey,
+    transfer_amount_auditor_ciphertext_lo: PodElGamalCiphertext,
+    transfer_amount_auditor_ciphertext_hi: PodElGamalCiphertext,
+) -> Result<u64> {
+    let transfer_amount_lo = ElGamalCiphertext::try_from(transfer_amount_auditor_ciphertext_lo)
+        .map_err(|e| anyhow!("failed to parse transfer_amount_auditor_ciphertext_lo {e:#?}"))?;
+    let transfer_amount_hi = ElGamalCiphertext::try_from(transfer_amount_auditor_ciphertext_hi)
+        .map_err(|e| anyhow!("failed to parse transfer_amount_auditor_ciphertext_hi {e:#?}"))?;
+
+    let num_threads = default_num_threads();
+    let transfer_amount_lo = discrete_log
+        .decrypt_u32(auditor_secret, &transfer_amount_lo, num_threads)
+        .ok_or_else(|| anyhow!("failed to decrypt transfer_amount_auditor_ciphertext_lo"))?;
+    let transfer_amount_hi = discrete_log
+        .decrypt_u32(auditor_secret, &transfer_amount_hi, num_threads)
+        .ok_or_else(|| anyhow!("failed to decrypt transfer_amount_auditor_ciphertext_hi"))?;
+
+    combine_balances(transfer_amount_lo, transfer_amount_hi)
+        .ok_or_else(|| anyhow!("failed to combine the auditor transfer amounts"))
+}
+
+/// Returns the confidential transfer account as it will be once its pending balance is applied
+///
+/// Mirrors the token program, so proofs for instructions sent after the apply can be generated
+/// before it lands
+pub fn apply_pending_balance_locally(
+    account: &ConfidentialTransferAccount,
+    new_decryptable_available_balance: PodAeCiphertext,
+) -> Result<ConfidentialTransferAccount> {
+    let mut account = *account;
+    account.available_balance = ciphertext_arithmetic::add_with_lo_hi(
+        &account.available_balance,
+        &account.pending_balance_lo,
+        &account.pending_balance_hi,
+    )
+    .ok_or_else(|| anyhow!("failed to add the pending balance to the available balance"))?;
+    account.decryptable_available_balance = new_decryptable_available_balance;
+    account.pending_balance_lo = EncryptedBalance::zeroed();
+    account.pending_balance_hi = EncryptedBalance::zeroed();
+    account.expected_pending_balance_credit_counter = account.pending_balance_credit_counter;
+    account.actual_pending_balance_credit_counter = account.pending_balance_credit_counter;
+    account.pending_balance_credit_counter = 0.into();
+    Ok(account)
+}
+
+/// Returns the confidential transfer account as it will be once `amount` is withdrawn from its
+/// available balance
+pub fn withdraw_locally(
+    account: &ConfidentialTransferAccount,
+    amount: u64,
+    new_decryptable_available_balance: PodAeCiphertext,
+) -> Result<ConfidentialTransferAccount> {
+    let mut account = *account;
+    account.available_balance =
+        ciphertext_arithmetic::subtract_from(&account.available_balance, amount)
+            .ok_or_else(|| anyhow!("failed to subtract the amount from the available balance"))?;
+    account.decryptable_available_balance = new_decryptable_available_balance;
+    Ok(account)
+}
+
+/// Returns the sum of the withheld confidential transfer fees of token accounts
+///
+/// Every account must have the ConfidentialTransferFeeAmount extension
+pub fn aggregate_withheld_amounts(accounts: &[Account]) -> Result<EncryptedWithheldAmount> {
+    accounts
+        .iter()
+        .try_fold(EncryptedWithheldAmount::zeroed(), |aggregate, account| {
+            let token_account = StateWithExtensions::<TokenAccount>::unpack(&account.data)
+                .map_err(|e| anyhow!("failed to unpack token account {e:#?}"))?;
+            let extension = token_account
+                .get_extension::<ConfidentialTransferFeeAmount>()
+                .map_err(|e| anyhow!("token account has no withheld confidential fees {e:#?}"))?;
+            ciphertext_arithmetic::add(&aggregate, &extension.withheld_amount)
+                .ok_or_else(|| anyhow!("failed to add withheld amounts"))
+        })
+}
+
+/// Decrypts a withheld fee amount, and generates the proof that a new ciphertext for
+/// `destination_pubkey` encrypts the same amount
+///
+/// Withheld amounts are decrypted with the precomputed discrete log table, so they must fit in
+/// 32 bits. Returns the decrypted amount along with the proof data
+pub fn withdraw_withheld_proof_data(
+    discrete_log: &DiscreteLogTable,
+    withdraw_withheld_authority: &ElGamalKeypair,
+    destination_pubkey: &ElGamalPubkey,
+    withheld_amount: EncryptedWithheldAmount,
+) -> Result<(u64, CiphertextCiphertextEqualityProofData)> {
+    let withheld_amount = ElGamalCiphertext::try_from(withheld_amount)
+        .map_err(|e| anyhow!("failed to parse withheld_amount {e:#?}"))?;
+    let amount = discrete_log
+        .decrypt_u32(
+            withdraw_withheld_authority.secret(),
+            &withheld_amount,
+            default_num_threads(),
+        )
+        .ok_or_else(|| anyhow!("failed to decrypt withheld_amount"))?;
+
+    let opening = PedersenOpening::new_rand();
+    let destination_ciphertext = destination_pubkey.encrypt_with(amount, &opening);
+    let proof_data = CiphertextCiphertextEqualityProofData::new(
+        withdraw_withheld_authority,
+        destination_pubkey,
+        &withheld_amount,
+        &destination_ciphertext,
+        &opening,
+        amount,
+    )
+    .map_err(|e| anyhow!("failed to generate ciphertext equality proof {e:#?}"))?;
+    Ok((amount, proof_data))
+}
+
+/// Returns the unwrapped mint recorded in a token wrap backpointer account
+///
+/// Validates that:
+/// * The account is owned by the spl token wrap program
+/// * The backpointer data is well formed
+/// * The unwrapped mint derives `wrapped_mint` for the spl_token_2022 program
+pub fn unwrapped_mint_from_backpointer(wrapped_mint: &Pubkey, account: &Account) -> Option<Pubkey> {
+    if account.owner != spl_token_wrap::id() {
+        return None;
+    }
+    let backpointer = bytemuck::try_from_bytes::<Backpointer>(&account.data).ok()?;
+    if get_wrapped_mint_address(&backpointer.unwrapped_mint, &spl_token_2022::id()) != *wrapped_mint
+    {
+        return None;
+    }
+    Some(backpointer.unwrapped_mint)
+}
+
 #[cfg(test)]
 mod test {
     use {
         super::*,
-        bytemuck::Zeroable,
         solana_sdk::{program_pack::Pack, pubkey::Pubkey},
-        solana_zk_sdk::encryption::{auth_encryption::AeKey, elgamal::ElGamalKeypair},
+        solana_zk_sdk::{
+            encryption::{
+                auth_encryption::AeKey, elgamal::ElGamalKeypair, grouped_elgamal::GroupedElGamal,
+            },
+            zk_elgamal_proof_program::proof_data::{ZeroCiphertextProofData, ZkProofData},
+        },
         spl_pod::{optional_keys::OptionalNonZeroPubkey, primitives::PodBool},
         spl_token_2022::{
             extension::{
-                confidential_transfer::{
-                    ConfidentialTransferAccount, ConfidentialTransferMint, EncryptedBalance,
-                },
+                confidential_transfer::{ConfidentialTransferAccount, ConfidentialTransferMint},
+                memo_transfer::MemoTransfer,
+                transfer_fee::TransferFeeConfig,
                 BaseStateWithExtensionsMut, StateWithExtensionsMut,
             },
             state::AccountState,
@@ -126,6 +421,48 @@ mod test {
         }))
     }
 
+    #[test]
+    fn test_mint_has_transfer_fee() {
+        let account_size =
+            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig])
+                .unwrap();
+        let mut account_data = vec![0; account_size];
+
+        let mut state =
+            StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut account_data).unwrap();
+        state.init_extension::<TransferFeeConfig>(false).unwrap();
+        state.base = Mint {
+            is_initialized: true,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+
+        assert!(mint_has_transfer_fee(&Account {
+            data: account_data,
+            ..Default::default()
+        }));
+
+        // mints without extensions, and other accounts have no transfer fee
+        let mut account_data = vec![0; Mint::LEN];
+        Mint::pack(
+            Mint {
+                is_initialized: true,
+                ..Default::default()
+            },
+            &mut account_data,
+        )
+        .unwrap();
+        assert!(!mint_has_transfer_fee(&Account {
+            data: account_data,
+            ..Default::default()
+        }));
+        assert!(!mint_has_transfer_fee(&Account {
+            data: vec![1, 2, 3, 4],
+            ..Default::default()
+        }));
+    }
+
     #[test]
     fn test_token_account_already_configured_not_a_token() {
         assert!(!token_account_already_configured(&Account {
@@ -199,4 +536,257 @@ mod test {
             ..Default::default()
         }))
     }
+
+    #[test]
+    fn test_requires_incoming_transfer_memos() {
+        let account_size = ExtensionType::try_calculate_account_len::<TokenAccount>(&[
+            ExtensionType::MemoTransfer,
+        ])
+        .unwrap();
+        let mut account_data = vec![0; account_size];
+
+        let mut state =
+            StateWithExtensionsMut::<TokenAccount>::unpack_uninitialized(&mut account_data)
+                .unwrap();
+        state
+            .init_extension::<MemoTransfer>(false)
+            .unwrap()
+            .require_incoming_transfer_memos = PodBool::from_bool(true);
+        state.base = TokenAccount {
+            mint: Pubkey::new_unique(),
+            state: AccountState::Initialized,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+
+        assert!(requires_incoming_transfer_memos(&Account {
+            data: account_data,
+            ..Default::default()
+        }));
+
+        // accounts without the extension, and other accounts don't require memos
+        let mut account_data = vec![0; TokenAccount::LEN];
+        TokenAccount::pack(
+            TokenAccount {
+                mint: Pubkey::new_unique(),
+                state: AccountState::Initialized,
+                ..Default::default()
+            },
+            &mut account_data,
+        )
+        .unwrap();
+        assert!(!requires_incoming_transfer_memos(&Account {
+            data: account_data,
+            ..Default::default()
+        }));
+        assert!(!requires_incoming_transfer_memos(&Account {
+            data: vec![1, 2, 3],
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn test_decrypt_transfer_amount() {
+        let discrete_log = DiscreteLogTable::new(crate::discrete_log::MIN_BABY_STEP_BITS).unwrap();
+        let source = ElGamalKeypair::new_rand();
+        let destination = ElGamalKeypair::new_rand();
+        let auditor = ElGamalKeypair::new_rand();
+        let pubkeys = [source.pubkey(), destination.pubkey(), auditor.pubkey()];
+
+        // transfer amounts are split into a 16 bit lo and 32 bit hi part
+        let amount: u64 = (1_234 << 16) + 5_678;
+        let transfer_amount_lo = GroupedElGamal::<3>::encrypt(pubkeys, 5_678u64).into();
+        let transfer_amount_hi = GroupedElGamal::<3>::encrypt(pubkeys, 1_234u64).into();
+
+        for (handle_index, keypair) in [&source, &destination, &auditor].into_iter().enumerate() {
+            assert_eq!(
+                decrypt_transfer_amount(
+                    &discrete_log,
+                    keypair.secret(),
+                    transfer_amount_lo,
+                    transfer_amount_hi,
+                    handle_index,
+                )
+                .unwrap(),
+                amount
+            );
+        }
+
+        // the auditor ciphertexts of a transfer are the auditor components of the grouped ciphertexts
+        let auditor_ciphertext = |ciphertext: PodGroupedElGamalCiphertext3Handles| {
+            GroupedElGamalCiphertext::<3>::try_from(ciphertext)
+                .unwrap()
+                .to_elgamal_ciphertext(2)
+                .unwrap()
+                .into()
+        };
+        assert_eq!(
+            decrypt_auditor_transfer_amount(
+                &discrete_log,
+                auditor.secret(),
+                auditor_ciphertext(transfer_amount_lo),
+                auditor_ciphertext(transfer_amount_hi),
+            )
+            .unwrap(),
+            amount
+        );
+
+        // the wrong handle does not decrypt to the transfer amount
+        assert_ne!(
+            decrypt_transfer_amount(
+                &discrete_log,
+                destination.secret(),
+                transfer_amount_lo,
+                transfer_amount_hi,
+                0,
+            )
+            .ok(),
+            Some(amount)
+        );
+    }
+
+    #[test]
+    fn test_decrypt_transfer_fee() {
+        let discrete_log = DiscreteLogTable::new(crate::discrete_log::MIN_BABY_STEP_BITS).unwrap();
+        let destination = ElGamalKeypair::new_rand();
+        let withdraw_withheld_authority = ElGamalKeypair::new_rand();
+        let pubkeys = [destination.pubkey(), withdraw_withheld_authority.pubkey()];
+
+        let fee: u64 = (12 << 16) + 34;
+        let fee_lo = GroupedElGamal::<2>::encrypt(pubkeys, 34u64).into();
+        let fee_hi = GroupedElGamal::<2>::encrypt(pubkeys, 12u64).into();
+        for (handle_index, keypair) in [&destination, &withdraw_withheld_authority]
+            .into_iter()
+            .enumerate()
+        {
+            assert_eq!(
+                decrypt_transfer_fee(
+                    &discrete_log,
+                    keypair.secret(),
+                    fee_lo,
+                    fee_hi,
+                    handle_index
+                )
+                .unwrap(),
+                fee
+            );
+        }
+    }
+
+    #[test]
+    fn test_drain_locally() {
+        let discrete_log = DiscreteLogTable::new(crate::discrete_log::MIN_BABY_STEP_BITS).unwrap();
+        let elgamal_keypair = ElGamalKeypair::new_rand();
+        let ae_key = AeKey::new_rand();
+
+        let mut account = ConfidentialTransferAccount::zeroed();
+        account.elgamal_pubkey = elgamal_keypair.pubkey_owned().into();
+        account.available_balance = elgamal_keypair.pubkey().encrypt(10u64).into();
+        account.decryptable_available_balance = ae_key.encrypt(10).into();
+        account.pending_balance_lo = elgamal_keypair.pubkey().encrypt(5u64).into();
+        account.pending_balance_hi = elgamal_keypair.pubkey().encrypt(2u64).into();
+        account.pending_balance_credit_counter = 3.into();
+        let total = 10 + 5 + (2 << 16);
+
+        let applied =
+            apply_pending_balance_locally(&account, ae_key.encrypt(total).into()).unwrap();
+        let available_balance: ElGamalCiphertext = applied.available_balance.try_into().unwrap();
+        assert_eq!(
+            discrete_log.decrypt_u32(
+                elgamal_keypair.secret(),
+                &available_balance,
+                default_num_threads()
+            ),
+            Some(total)
+        );
+        assert_eq!(decrypt_available_balance(&ae_key, &applied).unwrap(), total);
+        assert_eq!(applied.pending_balance_lo, EncryptedBalance::zeroed());
+        assert_eq!(u64::from(applied.pending_balance_credit_counter), 0);
+        assert_eq!(
+            u64::from(applied.expected_pending_balance_credit_counter),
+            3
+        );
+
+        // only a fully drained account can prove its available balance is zero
+        let partial = withdraw_locally(&applied, total - 1, ae_key.encrypt(1).into()).unwrap();
+        let available_balance = partial.available_balance.try_into().unwrap();
+        assert!(
+            ZeroCiphertextProofData::new(&elgamal_keypair, &available_balance)
+                .map_or(true, |proof| proof.verify_proof().is_err())
+        );
+
+        let drained = withdraw_locally(&applied, total, ae_key.encrypt(0).into()).unwrap();
+        let available_balance = drained.available_balance.try_into().unwrap();
+        ZeroCiphertextProofData::new(&elgamal_keypair, &available_balance)
+            .unwrap()
+            .verify_proof()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_withdraw_withheld_amounts() {
+        let discrete_log = DiscreteLogTable::new(crate::discrete_log::MIN_BABY_STEP_BITS).unwrap();
+        let withdraw_withheld_authority = ElGamalKeypair::new_rand();
+        let destination = ElGamalKeypair::new_rand();
+
+        let fee_account = |withheld_amount: u64| {
+            let account_size = ExtensionType::try_calculate_account_len::<TokenAccount>(&[
+                ExtensionType::ConfidentialTransferFeeAmount,
+            ])
+            .unwrap();
+            let mut account_data = vec![0; account_size];
+            let mut state =
+                StateWithExtensionsMut::<TokenAccount>::unpack_uninitialized(&mut account_data)
+                    .unwrap();
+            let extension = state
+                .init_extension::<ConfidentialTransferFeeAmount>(false)
+                .unwrap();
+            extension.withheld_amount = withdraw_withheld_authority
+                .pubkey()
+                .encrypt(withheld_amount)
+                .into();
+            state.base = TokenAccount {
+                mint: Pubkey::new_unique(),
+                state: AccountState::Initialized,
+                ..Default::default()
+            };
+            state.pack_base();
+            state.init_account_type().unwrap();
+            Account {
+                data: account_data,
+                ..Default::default()
+            }
+        };
+
+        let withheld_amount =
+            aggregate_withheld_amounts(&[fee_account(100), fee_account(250)]).unwrap();
+        let (amount, proof_data) = withdraw_withheld_proof_data(
+            &discrete_log,
+            &withdraw_withheld_authority,
+            destination.pubkey(),
+            withheld_amount,
+        )
+        .unwrap();
+        assert_eq!(amount, 350);
+        proof_data.verify_proof().unwrap();
+
+        // the proven destination ciphertext decrypts to the withheld amount
+        let destination_ciphertext: ElGamalCiphertext = proof_data
+            .context_data()
+            .second_ciphertext
+            .try_into()
+            .unwrap();
+        assert_eq!(
+            discrete_log.decrypt_u32(
+                destination.secret(),
+                &destination_ciphertext,
+                default_num_threads()
+            ),
+            Some(350)
+        );
+
+        // accounts without withheld fees can not be aggregated
+        assert!(aggregate_withheld_amounts(&[Account::default()]).is_err());
+    }
 }
@@ -0,0 +1,262 @@
+//! Precomputed baby-step giant-step tables used to speed up ElGamal decryption
+//!
+//! `ElGamalSecretKey::decrypt_u32` solves the discrete log with a fixed 2^16 entry table and
+//! up to 2^16 single threaded giant steps. [`DiscreteLogTable`] trades memory for latency by
+//! using a larger, configurable baby-step table which is built once (or loaded from disk) and
+//! then shared between requests, splitting the remaining giant steps across the shared rayon
+//! thread pool.
+
+use {
+    anyhow::{anyhow, Context, Result},
+    curve25519_dalek::{
+        constants::RISTRETTO_BASEPOINT_POINT as G, ristretto::RistrettoPoint, scalar::Scalar,
+    },
+    rayon::prelude::*,
+    solana_zk_sdk::encryption::elgamal::{ElGamalCiphertext, ElGamalSecretKey},
+    std::{
+        collections::HashMap,
+        num::NonZeroUsize,
+        path::Path,
+        sync::atomic::{AtomicBool, Ordering},
+    },
+};
+
+/// Default number of baby-step bits, resulting in a table of 2^20 points
+pub const DEFAULT_BABY_STEP_BITS: u32 = 20;
+
+/// Smallest supported table size, equivalent to the table bundled with the zk sdk
+pub const MIN_BABY_STEP_BITS: u32 = 16;
+
+/// Largest supported table size
+pub const MAX_BABY_STEP_BITS: u32 = 24;
+
+/// Upper bound (exclusive) of values that can be decoded, matching `decrypt_u32`
+const DECODE_BOUND: u64 = 1 << 32;
+
+/// Baby-step table mapping `x * G` to `x` for all `x < 2^baby_step_bits`
+///
+/// Points are keyed by the first 8 bytes of their compressed encoding which keeps the table
+/// small, candidate solutions are verified before being returned so prefix collisions are harmless.
+pub struct DiscreteLogTable {
+    baby_step_bits: u32,
+    table: HashMap<u64, Vec<u32>>,
+}
+
+impl DiscreteLogTable {
+    /// Builds a new table containing `2^baby_step_bits` points
+    pub fn new(baby_step_bits: u32) -> Result<Self> {
+        validate_baby_step_bits(baby_step_bits)?;
+
+        let table_size = 1u64 << baby_step_bits;
+        let num_threads = default_num_threads().get() as u64;
+        let chunk_size = table_size.div_ceil(num_threads);
+
+        // each thread computes a contiguous range of baby steps
+        let chunks = std::thread::scope(|scope| {
+            (0..num_threads)
+                .map(|i| {
+                    let start = i * chunk_size;
+                    let end = table_size.min(start + chunk_size);
+                    scope.spawn(move || {
+                        let mut point = Scalar::from(start) * G;
+                        let mut entries = Vec::with_capacity(end.saturating_sub(start) as usize);
+                        for x in start..end {
+                            entries.push((point_key(&point), x as u32));
+                            point += G;
+                        }
+                        entries
+                    })
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|handle| handle.join())
+                .collect::<Vec<_>>()
+        });
+
+        let mut table: HashMap<u64, Vec<u32>> = HashMap::with_capacity(table_size as usize);
+        for chunk in chunks {
+            let chunk = chunk.map_err(|_| anyhow!("failed to build discrete log table"))?;
+            for (key, x) in chunk {
+                table.entry(key).or_default().push(x);
+            }
+        }
+
+        Ok(Self {
+            baby_step_bits,
+            table,
+        })
+    }
+
+    /// Loads a table previously written with [`DiscreteLogTable::save`]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let data = std::fs::read(path.as_ref())
+            .with_context(|| format!("failed to read {}", path.as_ref().display()))?;
+        let (baby_step_bits, table): (u32, HashMap<u64, Vec<u32>>) =
+            bincode::deserialize(&data).with_context(|| "failed to deserialize table")?;
+        validate_baby_step_bits(baby_step_bits)?;
+        if table.values().map(Vec::len).sum::<usize>() != 1 << baby_step_bits {
+            return Err(anyhow!("discrete log table is incomplete"));
+        }
+        Ok(Self {
+            baby_step_bits,
+            table,
+        })
+    }
+
+    /// Writes the table to disk so it can be reused by [`DiscreteLogTable::load`]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let data = bincode::serialize(&(self.baby_step_bits, &self.table))
+            .with_context(|| "failed to serialize table")?;
+        std::fs::write(path.as_ref(), data)
+            .with_context(|| format!("failed to write {}", path.as_ref().display()))
+    }
+
+    /// Loads the table from `path` if it exists with the requested size, otherwise builds it
+    /// and writes it to `path`
+    pub fn load_or_build(path: impl AsRef<Path>, baby_step_bits: u32) -> Result<Self> {
+        if path.as_ref().exists() {
+            match Self::load(path.as_ref()) {
+                Ok(table) if table.baby_step_bits == baby_step_bits => return Ok(table),
+                Ok(_) => log::warn!("discrete log table size changed, rebuilding"),
+                Err(err) => log::warn!("failed to load discrete log table, rebuilding {err:#?}"),
+            }
+        }
+        let table = Self::new(baby_step_bits)?;
+        table.save(path)?;
+        Ok(table)
+    }
+
+    /// Returns the number of baby-step bits this table was built with
+    pub fn baby_step_bits(&self) -> u32 {
+        self.baby_step_bits
+    }
+
+    /// Decrypts a ciphertext encrypting a value smaller than 2^32
+    ///
+    /// This is a drop in replacement for `ElGamalSecretKey::decrypt_u32`
+    pub fn decrypt_u32(
+        &self,
+        secret: &ElGamalSecretKey,
+        ciphertext: &ElGamalCiphertext,
+        num_threads: NonZeroUsize,
+    ) -> Option<u64> {
+        let target =
+            ciphertext.commitment.get_point() - secret.get_scalar() * ciphertext.handle.get_point();
+        self.decode_u32(target, num_threads)
+    }
+
+    /// Finds `x < 2^32` such that `x * G == target`
+    ///
+    /// The giant steps are split into `num_threads` chunks searched on the shared rayon thread
+    /// pool, which bounds the number of threads across concurrent decryptions
+    pub fn decode_u32(&self, target: RistrettoPoint, num_threads: NonZeroUsize) -> Option<u64> {
+        let giant_steps = DECODE_BOUND >> self.baby_step_bits;
+        let num_threads = (num_threads.get() as u64).min(giant_steps);
+        let chunk_size = giant_steps.div_ceil(num_threads);
+        let giant_step = Scalar::from(1u64 << self.baby_step_bits) * G;
+        // stops every chunk as soon as one of them finds the solution
+        let found = AtomicBool::new(false);
+
+        (0..num_threads).into_par_iter().find_map_any(|i| {
+            let start = i * chunk_size;
+            let end = giant_steps.min(start + chunk_size);
+            // target - j * 2^bits * G for j in start..end
+            let mut point = target - Scalar::from(start) * giant_step;
+            for j in start..end {
+                if found.load(Ordering::Relaxed) {
+                    return None;
+                }
+                if let Some(x) = self.lookup(&point) {
+                    found.store(true, Ordering::Relaxed);
+                    return Some((j << self.baby_step_bits) + x);
+                }
+                point -= giant_step;
+            }
+            None
+        })
+    }
+
+    /// Returns the baby step `x` such that `x * G == point`
+    fn lookup(&self, point: &RistrettoPoint) -> Option<u64> {
+        self.table
+            .get(&point_key(point))?
+            .iter()
+            .find(|x| Scalar::from(**x) * G == *point)
+            .map(|x| *x as u64)
+    }
+}
+
+/// Returns the number of threads available for decryption
+pub fn default_num_threads() -> NonZeroUsize {
+    std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN)
+}
+
+fn validate_baby_step_bits(baby_step_bits: u32) -> Result<()> {
+    if !(MIN_BABY_STEP_BITS..=MAX_BABY_STEP_BITS).contains(&baby_step_bits) {
+        return Err(anyhow!(
+            "baby step bits must be between {MIN_BABY_STEP_BITS} and {MAX_BABY_STEP_BITS}"
+        ));
+    }
+    Ok(())
+}
+
+fn point_key(point: &RistrettoPoint) -> u64 {
+    let compressed = point.compress();
+    u64::from_le_bytes(compressed.as_bytes()[..8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, solana_zk_sdk::encryption::elgamal::ElGamalKeypair};
+
+    #[test]
+    fn test_invalid_baby_step_bits() {
+        assert!(DiscreteLogTable::new(MIN_BABY_STEP_BITS - 1).is_err());
+        assert!(DiscreteLogTable::new(MAX_BABY_STEP_BITS + 1).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_u32() {
+        let table = DiscreteLogTable::new(MIN_BABY_STEP_BITS).unwrap();
+        let keypair = ElGamalKeypair::new_rand();
+
+        for amount in [0u64, 1, 65535, 65536, 1_000_000, u32::MAX as u64] {
+            let ciphertext = keypair.pubkey().encrypt(amount);
+            assert_eq!(
+                table.decrypt_u32(keypair.secret(), &ciphertext, default_num_threads()),
+                Some(amount)
+            );
+            assert_eq!(
+                table.decrypt_u32(keypair.secret(), &ciphertext, NonZeroUsize::MIN),
+                keypair.secret().decrypt_u32(&ciphertext)
+            );
+        }
+
+        // values outside of the u32 range can not be decoded
+        let ciphertext = keypair.pubkey().encrypt(u32::MAX as u64 + 1);
+        assert_eq!(
+            table.decrypt_u32(keypair.secret(), &ciphertext, default_num_threads()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("discrete_log_table_{}.bin", std::process::id()));
+        let table = DiscreteLogTable::load_or_build(&path, MIN_BABY_STEP_BITS).unwrap();
+        assert!(path.exists());
+
+        let loaded = DiscreteLogTable::load(&path).unwrap();
+        assert_eq!(loaded.baby_step_bits(), table.baby_step_bits());
+
+        let keypair = ElGamalKeypair::new_rand();
+        let ciphertext = keypair.pubkey().encrypt(123_456_789u64);
+        assert_eq!(
+            loaded.decrypt_u32(keypair.secret(), &ciphertext, default_num_threads()),
+            Some(123_456_789)
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
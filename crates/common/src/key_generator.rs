@@ -2,15 +2,21 @@
 
 use {
     anyhow::{anyhow, Result},
-    solana_sdk::{pubkey::Pubkey, signature::Signature},
+    solana_sdk::{pubkey::Pubkey, signature::Signature, signer::SeedDerivable},
     solana_zk_sdk::encryption::{auth_encryption::AeKey, elgamal::ElGamalKeypair},
 };
 
-/// Defines the two types of keypairs that are required by confidential transactions
+/// Defines the two types of keypairs that are required by confidential transactions, along with
+/// the ElGamal keypairs of a mint auditor and of the withdraw withheld authority of a mint, and
+/// the supply keys of a mint with a confidential supply
 #[derive(Clone, Copy)]
 pub enum KeypairType {
     ElGamal,
     Ae,
+    Auditor,
+    WithdrawWithheld,
+    SupplyElGamal,
+    SupplyAe,
 }
 
 impl KeypairType {
@@ -19,6 +25,10 @@ impl KeypairType {
         match self {
             Self::ElGamal => [b"ElGamalSecretKey", &b""[..]].concat(),
             Self::Ae => [b"AEKey", &b""[..]].concat(),
+            Self::Auditor => [b"AuditorElGamalSecretKey", &b""[..]].concat(),
+            Self::WithdrawWithheld => [b"WithdrawWithheldElGamalSecretKey", &b""[..]].concat(),
+            Self::SupplyElGamal => [b"SupplyElGamalSecretKey", &b""[..]].concat(),
+            Self::SupplyAe => [b"SupplyAEKey", &b""[..]].concat(),
         }
     }
 }
@@ -29,6 +39,11 @@ pub fn derive_elgamal_key(signature: Signature) -> Result<ElGamalKeypair> {
         .map_err(|e| anyhow!("failed to derive elgamal keypair {e:#?}"))
 }
 
+/// Derives an ElGamal key from a seed of at least 32 bytes, such as the contents of a seed file
+pub fn derive_elgamal_key_from_seed(seed: &[u8]) -> Result<ElGamalKeypair> {
+    ElGamalKeypair::from_seed(seed).map_err(|e| anyhow!("failed to derive elgamal keypair {e:#?}"))
+}
+
 /// Derives an Ae key from a signature
 pub fn derive_ae_key(signature: Signature) -> Result<AeKey> {
     AeKey::new_from_signature(&signature).map_err(|e| anyhow!("failed to derive ae keypair {e:#?}"))
@@ -62,6 +77,41 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_derive_auditor_keypair() {
+        let user_key = test_key();
+
+        let msg = KeypairType::Auditor.message_to_sign(Pubkey::new_unique());
+
+        let auditor_key = derive_elgamal_key(user_key.sign_message(&msg)).unwrap();
+
+        // the auditor key must never be the same as the token account key of the wallet
+        let elgamal_msg = KeypairType::ElGamal.message_to_sign(Pubkey::new_unique());
+        let elgamal_key = derive_elgamal_key(user_key.sign_message(&elgamal_msg)).unwrap();
+        assert_ne!(auditor_key.pubkey(), elgamal_key.pubkey());
+
+        // nor the same as the withdraw withheld authority key of the wallet
+        let withdraw_withheld_msg =
+            KeypairType::WithdrawWithheld.message_to_sign(Pubkey::new_unique());
+        let withdraw_withheld_key =
+            derive_elgamal_key(user_key.sign_message(&withdraw_withheld_msg)).unwrap();
+        assert_ne!(auditor_key.pubkey(), withdraw_withheld_key.pubkey());
+        assert_ne!(elgamal_key.pubkey(), withdraw_withheld_key.pubkey());
+
+        // nor the same as the supply key of the mints of the wallet
+        let supply_msg = KeypairType::SupplyElGamal.message_to_sign(Pubkey::new_unique());
+        let supply_key = derive_elgamal_key(user_key.sign_message(&supply_msg)).unwrap();
+        assert_ne!(auditor_key.pubkey(), supply_key.pubkey());
+        assert_ne!(elgamal_key.pubkey(), supply_key.pubkey());
+
+        // seeds shorter than 32 bytes are rejected
+        assert!(derive_elgamal_key_from_seed(&[1; 31]).is_err());
+        assert_eq!(
+            derive_elgamal_key_from_seed(&[1; 32]).unwrap().pubkey(),
+            derive_elgamal_key_from_seed(&[1; 32]).unwrap().pubkey()
+        );
+    }
+
     #[test]
     fn test_derive_ae_key() {
         let user_key = test_key();
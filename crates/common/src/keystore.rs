@@ -0,0 +1,174 @@
+//! Passphrase encrypted storage for ElGamal keypairs, such as the auditor key of a wrapped mint
+
+use {
+    aes_gcm_siv::{
+        aead::{Aead, KeyInit},
+        Aes256GcmSiv, Nonce,
+    },
+    anyhow::{anyhow, Result},
+    hmac::Hmac,
+    rand::RngCore,
+    serde::{Deserialize, Serialize},
+    sha2::Sha256,
+    solana_zk_sdk::encryption::{
+        elgamal::{ElGamalKeypair, ElGamalSecretKey},
+        pod::elgamal::PodElGamalPubkey,
+    },
+    std::path::Path,
+    zeroize::Zeroizing,
+};
+
+/// Current version of the keystore format
+pub const KEYSTORE_VERSION: u8 = 1;
+
+/// Number of pbkdf2 rounds used when encrypting new keystores
+pub const DEFAULT_PBKDF2_ROUNDS: u32 = 600_000;
+
+/// Range of pbkdf2 rounds accepted in a keystore, too few rounds make the passphrase easy to brute
+/// force and too many make decryption hang
+pub const PBKDF2_ROUNDS: std::ops::RangeInclusive<u32> = 100_000..=10_000_000;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// An ElGamal keypair encrypted with aes-gcm-siv under a key derived from a passphrase
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EncryptedKeystore {
+    pub version: u8,
+    /// base64 encoded ElGamal pubkey, readable without the passphrase
+    pub pubkey: String,
+    pub pbkdf2_rounds: u32,
+    pub salt: Vec<u8>,
+    pub nonce: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+impl EncryptedKeystore {
+    /// Encrypts the secret key of `keypair` with `passphrase`
+    pub fn encrypt(keypair: &ElGamalKeypair, passphrase: &str, pbkdf2_rounds: u32) -> Result<Self> {
+        check_pbkdf2_rounds(pbkdf2_rounds)?;
+        let mut rng = rand::thread_rng();
+        let mut salt = vec![0; SALT_LEN];
+        rng.fill_bytes(&mut salt);
+        let mut nonce = vec![0; NONCE_LEN];
+        rng.fill_bytes(&mut nonce);
+
+        let ciphertext = cipher(passphrase, &salt, pbkdf2_rounds)
+            .encrypt(Nonce::from_slice(&nonce), &keypair.secret().as_bytes()[..])
+            .map_err(|e| anyhow!("failed to encrypt keypair {e:#?}"))?;
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            pubkey: keypair.pubkey().to_string(),
+            pbkdf2_rounds,
+            salt,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    /// Decrypts the keypair, failing if the passphrase is wrong or the keystore was modified
+    pub fn decrypt(&self, passphrase: &str) -> Result<ElGamalKeypair> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(anyhow!("unsupported keystore version {}", self.version));
+        }
+        if self.nonce.len() != NONCE_LEN {
+            return Err(anyhow!("invalid keystore nonce"));
+        }
+        if self.salt.len() != SALT_LEN {
+            return Err(anyhow!("invalid keystore salt"));
+        }
+        check_pbkdf2_rounds(self.pbkdf2_rounds)?;
+        let secret = Zeroizing::new(
+            cipher(passphrase, &self.salt, self.pbkdf2_rounds)
+                .decrypt(Nonce::from_slice(&self.nonce), &self.ciphertext[..])
+                .map_err(|_| anyhow!("failed to decrypt keystore, is the passphrase correct?"))?,
+        );
+        let secret = ElGamalSecretKey::try_from(&secret[..])
+            .map_err(|e| anyhow!("invalid keystore secret key {e:#?}"))?;
+        let keypair = ElGamalKeypair::new(secret);
+        if keypair.pubkey().to_string() != self.pubkey {
+            return Err(anyhow!("keystore pubkey does not match its secret key"));
+        }
+        Ok(keypair)
+    }
+
+    /// Returns the ElGamal pubkey of the keystore without decrypting it
+    pub fn pubkey(&self) -> Result<PodElGamalPubkey> {
+        self.pubkey
+            .parse()
+            .map_err(|e| anyhow!("invalid keystore pubkey {e:#?}"))
+    }
+
+    pub fn read_from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let contents = std::fs::read(path)?;
+        Ok(serde_json::from_slice(&contents)?)
+    }
+
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        Ok(std::fs::write(path, serde_json::to_vec_pretty(self)?)?)
+    }
+}
+
+/// Returns true if the file at `path` is an encrypted keystore rather than a plain json keypair
+pub fn is_encrypted_keystore(path: impl AsRef<Path>) -> bool {
+    EncryptedKeystore::read_from_file(path).is_ok()
+}
+
+fn check_pbkdf2_rounds(rounds: u32) -> Result<()> {
+    if !PBKDF2_ROUNDS.contains(&rounds) {
+        return Err(anyhow!(
+            "pbkdf2 rounds must be between {} and {}, got {rounds}",
+            PBKDF2_ROUNDS.start(),
+            PBKDF2_ROUNDS.end()
+        ));
+    }
+    Ok(())
+}
+
+fn cipher(passphrase: &str, salt: &[u8], rounds: u32) -> Aes256GcmSiv {
+    let mut key = Zeroizing::new([0u8; 32]);
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase.as_bytes(), salt, rounds, &mut key[..]);
+    Aes256GcmSiv::new_from_slice(&key[..]).expect("key is 32 bytes")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_keystore_roundtrip() {
+        let keypair = ElGamalKeypair::new_rand();
+        let keystore =
+            EncryptedKeystore::encrypt(&keypair, "hunter2", *PBKDF2_ROUNDS.start()).unwrap();
+        assert_eq!(keystore.pubkey().unwrap(), (*keypair.pubkey()).into());
+
+        let decrypted = keystore.decrypt("hunter2").unwrap();
+        assert_eq!(decrypted.secret(), keypair.secret());
+        assert!(keystore.decrypt("hunter3").is_err());
+
+        // tampering with the ciphertext is detected
+        let mut tampered = keystore.clone();
+        tampered.ciphertext[0] ^= 1;
+        assert!(tampered.decrypt("hunter2").is_err());
+
+        // the kdf parameters read from the file are validated
+        for rounds in [0, *PBKDF2_ROUNDS.start() - 1, u32::MAX] {
+            let weakened = EncryptedKeystore {
+                pbkdf2_rounds: rounds,
+                ..keystore.clone()
+            };
+            assert!(weakened.decrypt("hunter2").is_err());
+        }
+        let mut truncated = keystore.clone();
+        truncated.salt.truncate(4);
+        assert!(truncated.decrypt("hunter2").is_err());
+        assert!(EncryptedKeystore::encrypt(&keypair, "hunter2", 1).is_err());
+
+        // the keystore survives a round trip through a file
+        let path = std::env::temp_dir().join(format!("keystore-{}.json", rand::random::<u64>()));
+        keystore.write_to_file(&path).unwrap();
+        assert!(is_encrypted_keystore(&path));
+        assert_eq!(EncryptedKeystore::read_from_file(&path).unwrap(), keystore);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
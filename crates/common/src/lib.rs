@@ -1,8 +1,13 @@
 //! Common utilities for working with the confidential blink specification
 
 pub mod accounts;
+pub mod discrete_log;
 pub mod key_generator;
+pub mod keystore;
+pub mod mint_burn;
 pub mod proofs;
+pub mod transactions;
+pub mod wrapped_mint;
 
 #[cfg(any(test, feature = "test-helpers"))]
 pub mod test_helpers;
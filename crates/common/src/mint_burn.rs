@@ -0,0 +1,325 @@
+//! Utilities for natively issued confidential mints whose supply is kept confidential with the
+//! confidential mint burn extension
+
+use {
+    crate::{
+        discrete_log::{default_num_threads, DiscreteLogTable},
+        wrapped_mint::WrappedMintConfig,
+    },
+    anyhow::{anyhow, Result},
+    solana_sdk::{instruction::Instruction, pubkey::Pubkey, system_instruction},
+    solana_zk_sdk::encryption::{
+        auth_encryption::{AeCiphertext, AeKey},
+        elgamal::{ElGamalCiphertext, ElGamalKeypair, ElGamalPubkey},
+        pod::{auth_encryption::PodAeCiphertext, elgamal::PodElGamalCiphertext},
+    },
+    spl_token_2022::{
+        extension::{
+            confidential_mint_burn::{self, ConfidentialMintBurn},
+            ExtensionType,
+        },
+        instruction::initialize_mint2,
+        state::Mint,
+    },
+    spl_token_confidential_transfer_ciphertext_arithmetic as ciphertext_arithmetic,
+    spl_token_confidential_transfer_proof_generation::mint::{
+        mint_split_proof_data, MintProofData,
+    },
+};
+
+/// Confidential transfer settings of a natively issued confidential mint, the same settings as
+/// a wrapped mint
+pub type ConfidentialMintConfig = WrappedMintConfig;
+
+/// Size of a confidential mint, with room for the confidential mint burn extension if `mint_burn`
+pub fn confidential_mint_len(mint_burn: bool) -> Result<usize> {
+    let mut extensions = vec![ExtensionType::ConfidentialTransferMint];
+    if mint_burn {
+        extensions.push(ExtensionType::ConfidentialMintBurn);
+    }
+    ExtensionType::try_calculate_account_len::<Mint>(&extensions)
+        .map_err(|e| anyhow!("failed to calculate confidential mint size {e:#?}"))
+}
+
+/// Returns the instructions which create `mint` as a confidential mint of `payer`
+///
+/// The confidential mint burn extension is added when `supply` keys are provided, starting from
+/// a decryptable supply of zero
+pub fn create_confidential_mint_instructions(
+    payer: &Pubkey,
+    mint: &Pubkey,
+    decimals: u8,
+    rent: u64,
+    config: &ConfidentialMintConfig,
+    supply: Option<(&ElGamalPubkey, &AeKey)>,
+) -> Result<Vec<Instruction>> {
+    config.validate()?;
+
+    let mint_len = confidential_mint_len(supply.is_some())?;
+    let mut ixs = vec![
+        system_instruction::create_account(
+            payer,
+            mint,
+            rent,
+            mint_len as u64,
+            &spl_token_2022::id(),
+        ),
+        spl_token_2022::extension::confidential_transfer::instruction::initialize_mint(
+            &spl_token_2022::id(),
+            mint,
+            config.confidential_transfer_authority,
+            config.auto_approve_new_accounts,
+            config.auditor_elgamal_pubkey,
+        )
+        .map_err(|e| anyhow!("failed to create confidential transfer mint instruction {e:#?}"))?,
+    ];
+    if let Some((supply_elgamal_pubkey, supply_ae_key)) = supply {
+        ixs.push(
+            confidential_mint_burn::instruction::initialize_mint(
+                &spl_token_2022::id(),
+                mint,
+                &(*supply_elgamal_pubkey).into(),
+                &supply_ae_key.encrypt(0).into(),
+            )
+            .map_err(|e| anyhow!("failed to create confidential mint burn instruction {e:#?}"))?,
+        );
+    }
+    // the mint must be initialized after its extensions
+    ixs.push(
+        initialize_mint2(&spl_token_2022::id(), mint, payer, None, decimals)
+            .map_err(|e| anyhow!("failed to create initialize mint instruction {e:#?}"))?,
+    );
+    Ok(ixs)
+}
+
+/// Returns the extension as it will be once its pending burn is applied to the supply
+pub fn apply_pending_burn(extension: &ConfidentialMintBurn) -> Result<ConfidentialMintBurn> {
+    let confidential_supply =
+        ciphertext_arithmetic::subtract(&extension.confidential_supply, &extension.pending_burn)
+            .ok_or_else(|| anyhow!("failed to apply pending burn"))?;
+    Ok(ConfidentialMintBurn {
+        confidential_supply,
+        pending_burn: PodElGamalCiphertext::default(),
+        ..*extension
+    })
+}
+
+/// Decrypts the confidential supply of a mint, excluding its pending burn
+///
+/// Only the difference between the decryptable supply and the confidential supply, which are
+/// the burns applied since the last mint, is decrypted with the precomputed discrete log table.
+/// That difference must fit in 32 bits
+pub fn decrypt_supply(
+    discrete_log: &DiscreteLogTable,
+    supply_elgamal_keypair: &ElGamalKeypair,
+    supply_ae_key: &AeKey,
+    extension: &ConfidentialMintBurn,
+) -> Result<u64> {
+    let decryptable_supply =
+        decrypt_decryptable_supply(supply_ae_key, extension.decryptable_supply)?;
+    let confidential_supply = ElGamalCiphertext::try_from(extension.confidential_supply)
+        .map_err(|e| anyhow!("failed to parse confidential_supply {e:#?}"))?;
+    let supply_delta =
+        supply_elgamal_keypair.pubkey().encrypt(decryptable_supply) - confidential_supply;
+    let burned = discrete_log
+        .decrypt_u32(
+            supply_elgamal_keypair.secret(),
+            &supply_delta,
+            default_num_threads(),
+        )
+        .ok_or_else(|| anyhow!("failed to decrypt confidential_supply"))?;
+    decryptable_supply
+        .checked_sub(burned)
+        .ok_or_else(|| anyhow!("confidential supply is larger than the decryptable supply"))
+}
+
+/// Decrypts the amount burned since the pending burn of a mint was last applied
+///
+/// The pending burn is decrypted with the precomputed discrete log table, so it must fit in 32
+/// bits
+pub fn decrypt_pending_burn(
+    discrete_log: &DiscreteLogTable,
+    supply_elgamal_keypair: &ElGamalKeypair,
+    extension: &ConfidentialMintBurn,
+) -> Result<u64> {
+    let pending_burn = ElGamalCiphertext::try_from(extension.pending_burn)
+        .map_err(|e| anyhow!("failed to parse pending_burn {e:#?}"))?;
+    discrete_log
+        .decrypt_u32(
+            supply_elgamal_keypair.secret(),
+            &pending_burn,
+            default_num_threads(),
+        )
+        .ok_or_else(|| anyhow!("failed to decrypt pending_burn"))
+}
+
+/// Generates the proofs needed to mint `amount` tokens to `destination_pubkey`, along with the
+/// new decryptable supply
+///
+/// The pending burn of the mint must already be applied to `extension`
+pub fn mint_proof_data(
+    discrete_log: &DiscreteLogTable,
+    supply_elgamal_keypair: &ElGamalKeypair,
+    supply_ae_key: &AeKey,
+    extension: &ConfidentialMintBurn,
+    amount: u64,
+    destination_pubkey: &ElGamalPubkey,
+    auditor_pubkey: Option<&ElGamalPubkey>,
+) -> Result<(MintProofData, PodAeCiphertext)> {
+    let current_supply = decrypt_supply(
+        discrete_log,
+        supply_elgamal_keypair,
+        supply_ae_key,
+        extension,
+    )?;
+    let new_supply = current_supply
+        .checked_add(amount)
+        .ok_or_else(|| anyhow!("confidential supply overflows"))?;
+    let current_supply_ciphertext = ElGamalCiphertext::try_from(extension.confidential_supply)
+        .map_err(|e| anyhow!("failed to parse confidential_supply {e:#?}"))?;
+    let proof_data = mint_split_proof_data(
+        &current_supply_ciphertext,
+        amount,
+        current_supply,
+        supply_elgamal_keypair,
+        destination_pubkey,
+        auditor_pubkey,
+    )
+    .map_err(|e| anyhow!("failed to generate mint proofs {e:#?}"))?;
+    Ok((proof_data, supply_ae_key.encrypt(new_supply).into()))
+}
+
+fn decrypt_decryptable_supply(
+    supply_ae_key: &AeKey,
+    decryptable_supply: PodAeCiphertext,
+) -> Result<u64> {
+    AeCiphertext::try_from(decryptable_supply)
+        .map_err(|e| anyhow!("failed to parse decryptable_supply {e:#?}"))?
+        .decrypt(supply_ae_key)
+        .ok_or_else(|| anyhow!("failed to decrypt decryptable_supply"))
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, crate::discrete_log::MIN_BABY_STEP_BITS};
+
+    #[test]
+    fn test_create_confidential_mint_instructions() {
+        let payer = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let supply_elgamal_keypair = ElGamalKeypair::new_rand();
+        let supply_ae_key = AeKey::new_rand();
+
+        let ixs = create_confidential_mint_instructions(
+            &payer,
+            &mint,
+            6,
+            1,
+            &ConfidentialMintConfig::default(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(ixs.len(), 3);
+
+        let ixs = create_confidential_mint_instructions(
+            &payer,
+            &mint,
+            6,
+            1,
+            &ConfidentialMintConfig::default(),
+            Some((supply_elgamal_keypair.pubkey(), &supply_ae_key)),
+        )
+        .unwrap();
+        assert_eq!(ixs.len(), 4);
+        assert!(ixs[1..]
+            .iter()
+            .all(|ix| ix.program_id == spl_token_2022::id()));
+        assert!(confidential_mint_len(true).unwrap() > confidential_mint_len(false).unwrap());
+
+        // token accounts could never be approved
+        assert!(create_confidential_mint_instructions(
+            &payer,
+            &mint,
+            6,
+            1,
+            &ConfidentialMintConfig {
+                auto_approve_new_accounts: false,
+                ..Default::default()
+            },
+            None,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_decrypt_supply() {
+        let discrete_log = DiscreteLogTable::new(MIN_BABY_STEP_BITS).unwrap();
+        let supply_elgamal_keypair = ElGamalKeypair::new_rand();
+        let supply_ae_key = AeKey::new_rand();
+        let supply_pubkey = supply_elgamal_keypair.pubkey();
+
+        // 1000 tokens were minted, of which 200 were burned and applied, and 300 are pending
+        let extension = ConfidentialMintBurn {
+            confidential_supply: supply_pubkey.encrypt(800_u64).into(),
+            decryptable_supply: supply_ae_key.encrypt(1000).into(),
+            supply_elgamal_pubkey: (*supply_pubkey).into(),
+            pending_burn: supply_pubkey.encrypt(300_u64).into(),
+        };
+        assert_eq!(
+            decrypt_supply(
+                &discrete_log,
+                &supply_elgamal_keypair,
+                &supply_ae_key,
+                &extension
+            )
+            .unwrap(),
+            800
+        );
+        assert_eq!(
+            decrypt_pending_burn(&discrete_log, &supply_elgamal_keypair, &extension).unwrap(),
+            300
+        );
+
+        let applied = apply_pending_burn(&extension).unwrap();
+        assert_eq!(
+            decrypt_supply(
+                &discrete_log,
+                &supply_elgamal_keypair,
+                &supply_ae_key,
+                &applied
+            )
+            .unwrap(),
+            500
+        );
+        assert_eq!(
+            decrypt_pending_burn(&discrete_log, &supply_elgamal_keypair, &applied).unwrap(),
+            0
+        );
+
+        // minting builds on the supply once the pending burn is applied
+        let destination = ElGamalKeypair::new_rand();
+        let (_, new_decryptable_supply) = mint_proof_data(
+            &discrete_log,
+            &supply_elgamal_keypair,
+            &supply_ae_key,
+            &applied,
+            50,
+            destination.pubkey(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            decrypt_decryptable_supply(&supply_ae_key, new_decryptable_supply).unwrap(),
+            550
+        );
+
+        // the supply can not be decrypted with another key
+        assert!(decrypt_supply(
+            &discrete_log,
+            &ElGamalKeypair::new_rand(),
+            &supply_ae_key,
+            &extension
+        )
+        .is_err());
+    }
+}
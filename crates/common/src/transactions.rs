@@ -0,0 +1,234 @@
+//! Utilities for building confidential transfer transactions
+
+use {
+    anyhow::{anyhow, Context, Result},
+    base64::{prelude::BASE64_STANDARD, Engine},
+    solana_sdk::{
+        instruction::Instruction, packet::PACKET_DATA_SIZE, pubkey::Pubkey,
+        transaction::Transaction,
+    },
+    solana_zk_sdk::encryption::{auth_encryption::AeKey, elgamal::ElGamalKeypair},
+    spl_token_2022::extension::{
+        confidential_transfer::instruction::{configure_account, PubkeyValidityProofData},
+        ExtensionType,
+    },
+    spl_token_confidential_transfer_proof_extraction::instruction::{ProofData, ProofLocation},
+};
+
+/// Returns the instructions which create the ATA of `authority` for `token_mint` if needed,
+/// and configure it for confidential transfers
+///
+/// `transfer_fee` must be set for mints with the TransferFeeConfig extension, see
+/// [`crate::accounts::mint_has_transfer_fee`]
+pub fn configure_account_instructions(
+    authority: &Pubkey,
+    token_mint: &Pubkey,
+    elgamal_key: &ElGamalKeypair,
+    ae_key: &AeKey,
+    maximum_pending_balance_credit_counter: u64,
+    transfer_fee: bool,
+) -> Result<Vec<Instruction>> {
+    let user_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        authority,
+        token_mint,
+        &spl_token_2022::id(),
+    );
+
+    let proof_data = PubkeyValidityProofData::new(elgamal_key)
+        .map_err(|err| anyhow!("failed to generate proof data {err:#?}"))?;
+
+    // the proof is verified by the instruction directly following the configure instruction
+    let mut configure_instructions = configure_account(
+        &spl_token_2022::id(),
+        &user_ata,
+        token_mint,
+        &ae_key.encrypt(0).into(),
+        maximum_pending_balance_credit_counter,
+        authority,
+        &[],
+        ProofLocation::InstructionOffset(
+            1.try_into().unwrap(),
+            ProofData::InstructionData(&proof_data),
+        ),
+    )
+    .map_err(|err| anyhow!("failed to generate configure instructions {err:#?}"))?;
+
+    // confidential transfers of mints with a fee also track the encrypted withheld fee
+    let mut extensions = vec![ExtensionType::ConfidentialTransferAccount];
+    if transfer_fee {
+        extensions.push(ExtensionType::ConfidentialTransferFeeAmount);
+    }
+
+    let mut instructions = vec![
+        spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            authority,
+            authority,
+            token_mint,
+            &spl_token_2022::id(),
+        ),
+        // the only possible error for rellocate is if the token program is not spl_token_2022
+        spl_token_2022::instruction::reallocate(
+            &spl_token_2022::id(),
+            &user_ata,
+            authority,
+            authority,
+            &[],
+            &extensions,
+        )
+        .unwrap(),
+    ];
+    instructions.append(&mut configure_instructions);
+    Ok(instructions)
+}
+
+/// Prepends an SPL Memo instruction signed by `signer` to `instructions` when a memo is given
+///
+/// The memo must directly precede the token instruction it annotates, which is required for
+/// transfers to token accounts with the MemoTransfer extension, so fails if the memo and
+/// `instructions` don't fit in a single transaction
+pub fn with_memo(
+    memo: Option<&str>,
+    signer: &Pubkey,
+    instructions: Vec<Instruction>,
+) -> Result<Vec<Instruction>> {
+    let Some(memo) = memo else {
+        return Ok(instructions);
+    };
+    let instructions = std::iter::once(spl_memo::build_memo(memo.as_bytes(), &[signer]))
+        .chain(instructions)
+        .collect::<Vec<_>>();
+    if !fits_in_packet(&instructions, signer)? {
+        return Err(anyhow!(
+            "memo of {} bytes is too long to fit in the transaction",
+            memo.len()
+        ));
+    }
+    Ok(instructions)
+}
+
+/// Packs groups of instructions into as few transactions as possible, keeping their order
+///
+/// Instructions within a group are never split across transactions, as they may depend on
+/// each other (ie: proof verification at an instruction offset)
+pub fn pack_instructions(
+    groups: Vec<Vec<Instruction>>,
+    payer: &Pubkey,
+) -> Result<Vec<Transaction>> {
+    let mut txs = vec![];
+    let mut pending: Vec<Instruction> = vec![];
+    for group in groups {
+        if !fits_in_packet(&group, payer)? {
+            return Err(anyhow!("instructions do not fit in a single transaction"));
+        }
+        let mut candidate = pending.clone();
+        candidate.extend(group.iter().cloned());
+        if fits_in_packet(&candidate, payer)? {
+            pending = candidate;
+            continue;
+        }
+        txs.push(Transaction::new_with_payer(&pending, Some(payer)));
+        pending = group;
+    }
+    if !pending.is_empty() {
+        txs.push(Transaction::new_with_payer(&pending, Some(payer)));
+    }
+    Ok(txs)
+}
+
+/// Encodes unsigned transactions as base64 for the client to sign and send
+pub fn encode_transactions(txs: &[Transaction]) -> Result<Vec<String>> {
+    txs.iter()
+        .map(|tx| {
+            Ok(BASE64_STANDARD
+                .encode(bincode::serialize(tx).with_context(|| "failed to serialize transaction")?))
+        })
+        .collect()
+}
+
+/// Checks if a transaction containing `instructions` fits within a single packet once signed
+fn fits_in_packet(instructions: &[Instruction], payer: &Pubkey) -> Result<bool> {
+    let tx = Transaction::new_with_payer(instructions, Some(payer));
+    Ok(bincode::serialized_size(&tx)? as usize <= PACKET_DATA_SIZE)
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, solana_sdk::instruction::AccountMeta};
+
+    fn instruction(data_len: usize) -> Instruction {
+        Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &vec![0; data_len],
+            vec![AccountMeta::new(Pubkey::new_unique(), false)],
+        )
+    }
+
+    #[test]
+    fn test_pack_instructions() {
+        let payer = Pubkey::new_unique();
+
+        // small groups are packed into a single transaction
+        let txs =
+            pack_instructions(vec![vec![instruction(10)], vec![instruction(10)]], &payer).unwrap();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].message.instructions.len(), 2);
+
+        // groups which don't fit together are split, preserving their order
+        let groups = vec![
+            vec![instruction(600)],
+            vec![instruction(600), instruction(10)],
+            vec![instruction(10)],
+        ];
+        let txs = pack_instructions(groups, &payer).unwrap();
+        assert_eq!(txs.len(), 2);
+        assert_eq!(txs[0].message.instructions.len(), 1);
+        assert_eq!(txs[1].message.instructions.len(), 3);
+        assert_eq!(txs[1].message.instructions[0].data.len(), 600);
+
+        // a group larger than a packet can't be packed
+        assert!(pack_instructions(vec![vec![instruction(PACKET_DATA_SIZE)]], &payer).is_err());
+    }
+
+    #[test]
+    fn test_encode_transactions() {
+        let payer = Pubkey::new_unique();
+        let txs =
+            pack_instructions(vec![vec![instruction(10)], vec![instruction(600)]], &payer).unwrap();
+
+        let encoded = encode_transactions(&txs).unwrap();
+        assert_eq!(encoded.len(), txs.len());
+        let decoded: Transaction =
+            bincode::deserialize(&BASE64_STANDARD.decode(&encoded[0]).unwrap()).unwrap();
+        assert_eq!(decoded, txs[0]);
+    }
+
+    #[test]
+    fn test_with_memo() {
+        let signer = Pubkey::new_unique();
+
+        assert_eq!(
+            with_memo(None, &signer, vec![instruction(10)])
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let ixs = with_memo(Some("invoice 42"), &signer, vec![instruction(10)]).unwrap();
+        assert_eq!(ixs.len(), 2);
+        assert_eq!(ixs[0].program_id, spl_memo::id());
+        assert_eq!(ixs[0].data, b"invoice 42");
+        assert_eq!(ixs[0].accounts[0].pubkey, signer);
+        assert!(ixs[0].accounts[0].is_signer);
+        assert_eq!(ixs[1].data.len(), 10);
+
+        // the memo must fit in the transaction of the instructions it annotates
+        let memo = "a".repeat(PACKET_DATA_SIZE / 2);
+        assert!(with_memo(Some(&memo), &signer, vec![instruction(10)]).is_ok());
+        assert!(with_memo(
+            Some(&memo),
+            &signer,
+            vec![instruction(PACKET_DATA_SIZE / 2)]
+        )
+        .is_err());
+    }
+}
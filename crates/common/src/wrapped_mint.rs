@@ -0,0 +1,594 @@
+//! Utilities for creating confidential wrapped mints with the spl token wrap program
+
+use {
+    anyhow::{anyhow, Result},
+    solana_sdk::{account::Account, instruction::Instruction, pubkey::Pubkey, system_instruction},
+    solana_zk_sdk::encryption::pod::elgamal::PodElGamalPubkey,
+    spl_token_2022::{
+        extension::{
+            confidential_transfer::ConfidentialTransferMint, non_transferable::NonTransferable,
+            pausable::PausableConfig, transfer_fee::TransferFeeConfig, transfer_hook,
+            BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+        },
+        state::{Account as TokenAccount, Mint},
+    },
+    spl_token_wrap::{
+        get_wrapped_mint_address, get_wrapped_mint_authority, get_wrapped_mint_backpointer_address,
+        state::Backpointer,
+    },
+};
+
+/// Confidential transfer settings of a wrapped mint
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WrappedMintConfig {
+    /// Whether token accounts can make confidential transfers without being approved
+    pub auto_approve_new_accounts: bool,
+    /// ElGamal pubkey of the auditor, which can decrypt every transfer amount
+    pub auditor_elgamal_pubkey: Option<PodElGamalPubkey>,
+    /// Authority which can approve token accounts and update the confidential transfer settings
+    pub confidential_transfer_authority: Option<Pubkey>,
+}
+
+impl Default for WrappedMintConfig {
+    fn default() -> Self {
+        Self {
+            auto_approve_new_accounts: true,
+            auditor_elgamal_pubkey: None,
+            confidential_transfer_authority: None,
+        }
+    }
+}
+
+impl WrappedMintConfig {
+    /// Reads the confidential transfer settings of an existing wrapped mint
+    pub fn from_mint(mint: &Account) -> Result<Self> {
+        let mint = StateWithExtensions::<Mint>::unpack(&mint.data)
+            .map_err(|e| anyhow!("failed to unpack wrapped mint {e:#?}"))?;
+        let extension = mint
+            .get_extension::<ConfidentialTransferMint>()
+            .map_err(|e| anyhow!("wrapped mint does not support confidential transfers {e:#?}"))?;
+        Ok(Self {
+            auto_approve_new_accounts: extension.auto_approve_new_accounts.into(),
+            auditor_elgamal_pubkey: extension.auditor_elgamal_pubkey.into(),
+            confidential_transfer_authority: extension.authority.into(),
+        })
+    }
+
+    /// Ensures token accounts can still be approved when auto approval is disabled
+    pub fn validate(&self) -> Result<()> {
+        if !self.auto_approve_new_accounts && self.confidential_transfer_authority.is_none() {
+            return Err(anyhow!(
+                "a confidential transfer authority is required to approve token accounts"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The accounts of a wrapped mint which already exist, along with the rent needed to create them
+pub struct WrappedMintAccounts {
+    pub wrapped_mint: Option<Account>,
+    pub backpointer: Option<Account>,
+    pub escrow: Option<Account>,
+    pub mint_rent: u64,
+    pub backpointer_rent: u64,
+}
+
+/// The accounts a wrap or unwrap request is validated against
+pub struct WrapAccounts {
+    pub unwrapped_mint: Option<Account>,
+    pub backpointer: Option<Account>,
+    pub escrow: Option<Account>,
+    /// The token account the tokens are taken from, which is the unwrapped token account when
+    /// wrapping and the wrapped token account when unwrapping
+    pub source: Option<Account>,
+}
+
+/// Size of a wrapped mint with the confidential transfer extension
+pub fn wrapped_mint_len() -> Result<usize> {
+    ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::ConfidentialTransferMint])
+        .map_err(|e| anyhow!("failed to calculate wrapped mint size {e:#?}"))
+}
+
+/// Size of the backpointer from a wrapped mint to its unwrapped mint
+pub fn backpointer_len() -> usize {
+    std::mem::size_of::<Backpointer>()
+}
+
+/// Returns the token account of the wrapped mint authority which escrows the unwrapped tokens
+pub fn escrow_address(unwrapped_mint: &Pubkey, unwrapped_token_program: &Pubkey) -> Pubkey {
+    let wrapped_mint = get_wrapped_mint_address(unwrapped_mint, &spl_token_2022::id());
+    spl_associated_token_account::get_associated_token_address_with_program_id(
+        &get_wrapped_mint_authority(&wrapped_mint),
+        unwrapped_mint,
+        unwrapped_token_program,
+    )
+}
+
+/// Ensures wrapping or unwrapping `amount` tokens between `unwrapped_mint` and `wrapped_mint`
+/// won't fail on chain, returning the reason it would otherwise
+pub fn validate_wrap_accounts(
+    unwrapped_mint: &Pubkey,
+    unwrapped_token_program: &Pubkey,
+    wrapped_mint: &Pubkey,
+    amount: u64,
+    accounts: &WrapAccounts,
+) -> Result<()> {
+    let expected_wrapped_mint = get_wrapped_mint_address(unwrapped_mint, &spl_token_2022::id());
+    if *wrapped_mint != expected_wrapped_mint {
+        return Err(anyhow!(
+            "wrapped mint {wrapped_mint} is not the wrapped mint of {unwrapped_mint}, expected {expected_wrapped_mint}"
+        ));
+    }
+    if accounts.backpointer.is_none() {
+        return Err(anyhow!(
+            "wrapped mint {wrapped_mint} has not been created for {unwrapped_mint}"
+        ));
+    }
+    let Some(mint) = &accounts.unwrapped_mint else {
+        return Err(anyhow!("unwrapped mint {unwrapped_mint} does not exist"));
+    };
+    if mint.owner != *unwrapped_token_program {
+        return Err(anyhow!(
+            "unwrapped mint {unwrapped_mint} is owned by {}, not {unwrapped_token_program}",
+            mint.owner
+        ));
+    }
+    if accounts.escrow.is_none() {
+        return Err(anyhow!(
+            "escrow {} of wrapped mint {wrapped_mint} does not exist",
+            escrow_address(unwrapped_mint, unwrapped_token_program)
+        ));
+    }
+    let Some(source) = &accounts.source else {
+        return Err(anyhow!("source token account does not exist"));
+    };
+    let balance = StateWithExtensions::<TokenAccount>::unpack(&source.data)
+        .map_err(|e| anyhow!("failed to unpack source token account {e:#?}"))?
+        .base
+        .amount;
+    if balance < amount {
+        return Err(anyhow!(
+            "insufficient balance of {balance} in the source token account"
+        ));
+    }
+    Ok(())
+}
+
+/// The effect of the unwrapped mint extensions on moving unwrapped tokens to or from the escrow
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnwrappedTransfer {
+    /// The transfer fee withheld from the moved tokens
+    pub fee: u64,
+    /// The amount left after the transfer fee, which is the amount of wrapped tokens minted when
+    /// wrapping, and the amount of unwrapped tokens received when unwrapping
+    pub received_amount: u64,
+    /// The program invoked by the transfer hook of the unwrapped mint, whose extra accounts must
+    /// be added to the wrap or unwrap instruction
+    pub transfer_hook_program: Option<Pubkey>,
+}
+
+/// Ensures the extensions of `unwrapped_mint` allow moving `amount` tokens to or from the escrow
+/// during `epoch`, returning the effect they have on the transfer
+///
+/// Mints of the legacy token program have no extensions, so nothing is withheld from them
+pub fn unwrapped_transfer(
+    unwrapped_mint: &Account,
+    amount: u64,
+    epoch: u64,
+) -> Result<UnwrappedTransfer> {
+    let mint = StateWithExtensions::<Mint>::unpack(&unwrapped_mint.data)
+        .map_err(|e| anyhow!("failed to unpack unwrapped mint {e:#?}"))?;
+    if mint.get_extension::<NonTransferable>().is_ok() {
+        return Err(anyhow!(
+            "unwrapped mint is non transferable, so it can't be wrapped"
+        ));
+    }
+    if let Ok(pausable) = mint.get_extension::<PausableConfig>() {
+        if bool::from(pausable.paused) {
+            return Err(anyhow!("unwrapped mint is paused"));
+        }
+    }
+    let fee = match mint.get_extension::<TransferFeeConfig>() {
+        Ok(transfer_fee) => transfer_fee
+            .calculate_epoch_fee(epoch, amount)
+            .ok_or_else(|| anyhow!("failed to calculate transfer fee"))?,
+        Err(_) => 0,
+    };
+    let received_amount = amount.saturating_sub(fee);
+    if received_amount == 0 {
+        return Err(anyhow!(
+            "amount of {amount} doesn't cover the transfer fee of {fee}"
+        ));
+    }
+    Ok(UnwrappedTransfer {
+        fee,
+        received_amount,
+        transfer_hook_program: transfer_hook::get_program_id(&mint),
+    })
+}
+
+/// Returns the instructions still required to create the confidential wrapped mint of
+/// `unwrapped_mint` and its escrow, which are none if everything already exists
+///
+/// Fails if the wrapped mint exists with settings other than `config`
+pub fn create_wrapped_mint_instructions(
+    payer: &Pubkey,
+    unwrapped_mint: &Pubkey,
+    unwrapped_token_program: &Pubkey,
+    config: &WrappedMintConfig,
+    accounts: &WrappedMintAccounts,
+) -> Result<Vec<Instruction>> {
+    config.validate()?;
+    let wrapped_mint = get_wrapped_mint_address(unwrapped_mint, &spl_token_2022::id());
+    let backpointer = get_wrapped_mint_backpointer_address(&wrapped_mint);
+
+    let mut ixs = vec![];
+    match (&accounts.wrapped_mint, &accounts.backpointer) {
+        (Some(existing), Some(_)) => {
+            let existing = WrappedMintConfig::from_mint(existing)?;
+            if existing != *config {
+                return Err(anyhow!(
+                    "wrapped mint {wrapped_mint} already exists with different settings {existing:?}"
+                ));
+            }
+        }
+        (None, None) => {
+            // the token wrap program expects both accounts to be funded before creating them
+            ixs.append(&mut system_instruction::transfer_many(
+                payer,
+                &[
+                    (backpointer, accounts.backpointer_rent),
+                    (wrapped_mint, accounts.mint_rent),
+                ],
+            ));
+            ixs.push(spl_token_wrap::instruction::create_confidential_mint(
+                &spl_token_wrap::id(),
+                &wrapped_mint,
+                &backpointer,
+                unwrapped_mint,
+                &spl_token_2022::id(),
+                true,
+                config.auto_approve_new_accounts,
+                config
+                    .auditor_elgamal_pubkey
+                    .map(bytemuck::cast)
+                    .unwrap_or_default(),
+                config
+                    .confidential_transfer_authority
+                    .map(|authority| authority.to_bytes())
+                    .unwrap_or_default(),
+            ));
+        }
+        _ => {
+            return Err(anyhow!(
+                "only one of wrapped mint {wrapped_mint} and backpointer {backpointer} exists"
+            ))
+        }
+    }
+
+    if accounts.escrow.is_none() {
+        ixs.push(
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                payer,
+                &get_wrapped_mint_authority(&wrapped_mint),
+                unwrapped_mint,
+                unwrapped_token_program,
+            ),
+        );
+    }
+    Ok(ixs)
+}
+
+/// Returns the instruction updating the auto approve setting and auditor of an existing wrapped
+/// mint to `config`, which must be signed by its confidential transfer authority
+///
+/// The confidential transfer authority itself can't be changed by this instruction
+pub fn update_wrapped_mint_instruction(
+    wrapped_mint: &Pubkey,
+    existing: &WrappedMintConfig,
+    config: &WrappedMintConfig,
+) -> Result<Instruction> {
+    config.validate()?;
+    let authority = existing.confidential_transfer_authority.ok_or_else(|| {
+        anyhow!("wrapped mint {wrapped_mint} has no confidential transfer authority")
+    })?;
+    if config.confidential_transfer_authority != existing.confidential_transfer_authority {
+        return Err(anyhow!(
+            "the confidential transfer authority of {wrapped_mint} can't be changed by an update"
+        ));
+    }
+    spl_token_2022::extension::confidential_transfer::instruction::update_mint(
+        &spl_token_2022::id(),
+        wrapped_mint,
+        &authority,
+        &[],
+        config.auto_approve_new_accounts,
+        config.auditor_elgamal_pubkey,
+    )
+    .map_err(|e| anyhow!("failed to build update mint instruction {e:#?}"))
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        solana_sdk::program_pack::Pack,
+        solana_zk_sdk::encryption::elgamal::ElGamalKeypair,
+        spl_pod::optional_keys::{OptionalNonZeroElGamalPubkey, OptionalNonZeroPubkey},
+        spl_token_2022::extension::{BaseStateWithExtensionsMut, StateWithExtensionsMut},
+    };
+
+    fn wrapped_mint(config: &WrappedMintConfig) -> Account {
+        let mut data = vec![0; wrapped_mint_len().unwrap()];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        let extension = state
+            .init_extension::<ConfidentialTransferMint>(false)
+            .unwrap();
+        extension.auto_approve_new_accounts = config.auto_approve_new_accounts.into();
+        extension.authority =
+            OptionalNonZeroPubkey::try_from(config.confidential_transfer_authority).unwrap();
+        extension.auditor_elgamal_pubkey =
+            OptionalNonZeroElGamalPubkey::try_from(config.auditor_elgamal_pubkey).unwrap();
+        state.base = Mint {
+            is_initialized: true,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        Account {
+            data,
+            owner: spl_token_2022::id(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_create_wrapped_mint_instructions() {
+        let payer = Pubkey::new_unique();
+        let unwrapped_mint = Pubkey::new_unique();
+        let config = WrappedMintConfig {
+            auto_approve_new_accounts: false,
+            auditor_elgamal_pubkey: Some((*ElGamalKeypair::new_rand().pubkey()).into()),
+            confidential_transfer_authority: Some(Pubkey::new_unique()),
+        };
+        let mut accounts = WrappedMintAccounts {
+            wrapped_mint: None,
+            backpointer: None,
+            escrow: None,
+            mint_rent: 2,
+            backpointer_rent: 1,
+        };
+
+        // funding both accounts, creating the mint and the escrow
+        let ixs = create_wrapped_mint_instructions(
+            &payer,
+            &unwrapped_mint,
+            &spl_token_2022::id(),
+            &config,
+            &accounts,
+        )
+        .unwrap();
+        assert_eq!(ixs.len(), 4);
+        assert_eq!(ixs[2].program_id, spl_token_wrap::id());
+        assert_eq!(ixs[3].program_id, spl_associated_token_account::id());
+
+        // nothing left to do once every account exists with the same settings
+        accounts.wrapped_mint = Some(wrapped_mint(&config));
+        accounts.backpointer = Some(Account::default());
+        accounts.escrow = Some(Account::default());
+        assert_eq!(
+            WrappedMintConfig::from_mint(accounts.wrapped_mint.as_ref().unwrap()).unwrap(),
+            config
+        );
+        assert!(create_wrapped_mint_instructions(
+            &payer,
+            &unwrapped_mint,
+            &spl_token_2022::id(),
+            &config,
+            &accounts,
+        )
+        .unwrap()
+        .is_empty());
+
+        // the existing mint is never silently reconfigured
+        assert!(create_wrapped_mint_instructions(
+            &payer,
+            &unwrapped_mint,
+            &spl_token_2022::id(),
+            &WrappedMintConfig::default(),
+            &accounts,
+        )
+        .is_err());
+
+        // a partially created mint is reported
+        accounts.backpointer = None;
+        assert!(create_wrapped_mint_instructions(
+            &payer,
+            &unwrapped_mint,
+            &spl_token_2022::id(),
+            &config,
+            &accounts,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_update_wrapped_mint_instruction() {
+        let wrapped_mint = Pubkey::new_unique();
+        let existing = WrappedMintConfig {
+            confidential_transfer_authority: Some(Pubkey::new_unique()),
+            ..Default::default()
+        };
+        let rotated = WrappedMintConfig {
+            auditor_elgamal_pubkey: Some((*ElGamalKeypair::new_rand().pubkey()).into()),
+            ..existing
+        };
+        let ix = update_wrapped_mint_instruction(&wrapped_mint, &existing, &rotated).unwrap();
+        assert_eq!(ix.program_id, spl_token_2022::id());
+        assert_eq!(ix.accounts[0].pubkey, wrapped_mint);
+        assert_eq!(
+            Some(ix.accounts[1].pubkey),
+            existing.confidential_transfer_authority
+        );
+        assert!(ix.accounts[1].is_signer);
+
+        // mints without an authority can't be updated
+        assert!(update_wrapped_mint_instruction(
+            &wrapped_mint,
+            &WrappedMintConfig::default(),
+            &WrappedMintConfig::default()
+        )
+        .is_err());
+        // and the authority itself is not changed by an update
+        assert!(update_wrapped_mint_instruction(
+            &wrapped_mint,
+            &existing,
+            &WrappedMintConfig {
+                confidential_transfer_authority: Some(Pubkey::new_unique()),
+                ..existing
+            }
+        )
+        .is_err());
+    }
+
+    fn token_account(amount: u64) -> Account {
+        let mut data = vec![0; TokenAccount::LEN];
+        TokenAccount::pack(
+            TokenAccount {
+                amount,
+                state: spl_token_2022::state::AccountState::Initialized,
+                ..Default::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        Account {
+            data,
+            owner: spl_token_2022::id(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_wrap_accounts() {
+        let unwrapped_mint = Pubkey::new_unique();
+        let wrapped_mint = get_wrapped_mint_address(&unwrapped_mint, &spl_token_2022::id());
+        let mut accounts = WrapAccounts {
+            unwrapped_mint: Some(Account {
+                owner: spl_token_2022::id(),
+                ..Default::default()
+            }),
+            backpointer: Some(Account::default()),
+            escrow: Some(Account::default()),
+            source: Some(token_account(10)),
+        };
+        let validate = |accounts: &WrapAccounts, wrapped_mint: &Pubkey, amount: u64| {
+            validate_wrap_accounts(
+                &unwrapped_mint,
+                &spl_token_2022::id(),
+                wrapped_mint,
+                amount,
+                accounts,
+            )
+        };
+        assert!(validate(&accounts, &wrapped_mint, 10).is_ok());
+
+        // each failure is reported with its own reason
+        let err = validate(&accounts, &Pubkey::new_unique(), 10).unwrap_err();
+        assert!(err.to_string().contains("is not the wrapped mint of"));
+        let err = validate(&accounts, &wrapped_mint, 11).unwrap_err();
+        assert!(err.to_string().contains("insufficient balance"));
+
+        accounts.escrow = None;
+        let err = validate(&accounts, &wrapped_mint, 10).unwrap_err();
+        assert!(err.to_string().contains("escrow"));
+
+        accounts.unwrapped_mint.as_mut().unwrap().owner = Pubkey::new_unique();
+        let err = validate(&accounts, &wrapped_mint, 10).unwrap_err();
+        assert!(err.to_string().contains("is owned by"));
+
+        accounts.backpointer = None;
+        let err = validate(&accounts, &wrapped_mint, 10).unwrap_err();
+        assert!(err.to_string().contains("has not been created"));
+    }
+
+    fn unwrapped_mint(
+        extensions: &[ExtensionType],
+        init: impl FnOnce(&mut StateWithExtensionsMut<Mint>),
+    ) -> Account {
+        let len = ExtensionType::try_calculate_account_len::<Mint>(extensions).unwrap();
+        let mut data = vec![0; len];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        init(&mut state);
+        state.base = Mint {
+            is_initialized: true,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        Account {
+            data,
+            owner: spl_token_2022::id(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_unwrapped_transfer() {
+        // mints without extensions, including legacy mints, withhold nothing
+        let mint = unwrapped_mint(&[], |_| {});
+        assert_eq!(
+            unwrapped_transfer(&mint, 100, 0).unwrap(),
+            UnwrappedTransfer {
+                fee: 0,
+                received_amount: 100,
+                transfer_hook_program: None,
+            }
+        );
+
+        // the fee of the current epoch is withheld, and the hook program is reported
+        let hook_program = Pubkey::new_unique();
+        let mint = unwrapped_mint(
+            &[
+                ExtensionType::TransferFeeConfig,
+                ExtensionType::TransferHook,
+            ],
+            |state| {
+                let transfer_fee = state.init_extension::<TransferFeeConfig>(false).unwrap();
+                transfer_fee.newer_transfer_fee.epoch = 10.into();
+                transfer_fee.newer_transfer_fee.transfer_fee_basis_points = 100.into();
+                transfer_fee.newer_transfer_fee.maximum_fee = 5.into();
+                state
+                    .init_extension::<transfer_hook::TransferHook>(false)
+                    .unwrap()
+                    .program_id = OptionalNonZeroPubkey::try_from(Some(hook_program)).unwrap();
+            },
+        );
+        assert_eq!(unwrapped_transfer(&mint, 100, 9).unwrap().fee, 0);
+        let transfer = unwrapped_transfer(&mint, 100, 10).unwrap();
+        assert_eq!(transfer.fee, 1);
+        assert_eq!(transfer.received_amount, 99);
+        assert_eq!(transfer.transfer_hook_program, Some(hook_program));
+        assert_eq!(unwrapped_transfer(&mint, 1000, 10).unwrap().fee, 5);
+        // the whole amount would be withheld
+        assert!(unwrapped_transfer(&mint, 1, 10).is_err());
+
+        // non transferable mints can't be wrapped
+        let mint = unwrapped_mint(&[ExtensionType::NonTransferable], |state| {
+            state.init_extension::<NonTransferable>(false).unwrap();
+        });
+        assert!(unwrapped_transfer(&mint, 100, 0).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(WrappedMintConfig::default().validate().is_ok());
+        assert!(WrappedMintConfig {
+            auto_approve_new_accounts: false,
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+}